@@ -0,0 +1,179 @@
+// src/archive.rs
+
+use crate::file_reader::BINARY_SNIFF_BYTES;
+use crate::types::ResolvedFile;
+use flate2::read::GzDecoder;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// The file entries of an archive, by their path inside it.
+type Entries = BTreeMap<PathBuf, Vec<u8>>;
+
+/// Archives read so far, by canonical path. A tar file can only be read from
+/// the start, so each archive is read once and its entries kept in memory.
+static LOADED: LazyLock<Mutex<HashMap<PathBuf, Arc<Entries>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// A source archive used in place of the working directory (`--archive`).
+#[derive(Debug, Clone)]
+pub struct Archive {
+    /// The archive's canonical path.
+    pub path: PathBuf,
+    /// The archive's file name, which prefixes every display path.
+    pub name: PathBuf,
+    /// Every file in the archive, by its path inside it, sorted.
+    pub paths: Vec<PathBuf>,
+}
+
+/// The archive formats `--archive` reads, going by the file name.
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+impl Archive {
+    /// Reads the archive at `path` (`.zip`, `.tar`, `.tar.gz` or `.tgz`).
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| format!("Failed to open archive {:?}: {}", path, e))?;
+        let entries = load_entries(&canonical)?;
+        Ok(Self {
+            name: PathBuf::from(canonical.file_name().unwrap_or_default()),
+            paths: entries.keys().cloned().collect(),
+            path: canonical,
+        })
+    }
+
+    /// The file at `entry`, a path inside the archive.
+    pub fn resolve(&self, entry: &Path) -> ResolvedFile {
+        ResolvedFile::new(self.name.join(entry), self.path.join(entry)).in_archive(&self.path)
+    }
+}
+
+/// Returns the entries of the archive at canonical `path`, reading it if
+/// this process hasn't yet.
+fn load_entries(path: &Path) -> Result<Arc<Entries>, String> {
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(entries) = loaded.get(path) {
+        return Ok(Arc::clone(entries));
+    }
+    let format = Format::of(path).ok_or_else(|| {
+        format!(
+            "Unsupported archive {:?}: expected a .zip, .tar, .tar.gz or .tgz file",
+            path
+        )
+    })?;
+    let file = File::open(path).map_err(|e| format!("Failed to open archive {:?}: {}", path, e))?;
+    let entries = match format {
+        Format::Zip => read_zip(file),
+        Format::Tar => read_tar(file),
+        Format::TarGz => read_tar(GzDecoder::new(file)),
+    }
+    .map_err(|e| format!("Failed to read archive {:?}: {}", path, e))?;
+    let entries = Arc::new(entries);
+    loaded.insert(path.to_path_buf(), Arc::clone(&entries));
+    Ok(entries)
+}
+
+fn read_tar(reader: impl Read) -> io::Result<Entries> {
+    let mut entries = Entries::new();
+    for entry in tar::Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let Some(path) = relative_entry_path(&entry.path()?) else {
+            continue;
+        };
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        entries.insert(path, content);
+    }
+    Ok(entries)
+}
+
+fn read_zip(file: File) -> io::Result<Entries> {
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut entries = Entries::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let Some(path) = entry
+            .enclosed_name()
+            .and_then(|path| relative_entry_path(&path))
+        else {
+            continue;
+        };
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        entries.insert(path, content);
+    }
+    Ok(entries)
+}
+
+/// Normalizes an entry's path, dropping `./` components. Entries that would
+/// land outside the archive's root (absolute, or with `..`) are skipped.
+fn relative_entry_path(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// Reads the entry at `path`, the archive's canonical path joined with the
+/// entry's path inside it, as UTF-8 text.
+pub fn read_entry(archive: &Path, path: &Path) -> Result<String, String> {
+    with_entry(archive, path, |bytes| {
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| format!("{} is not valid UTF-8", path.display()))
+    })?
+}
+
+/// Returns true if the entry at `path` looks binary, by the same test as
+/// `file_reader::is_binary`. Entries that can't be read aren't.
+pub fn is_binary_entry(archive: &Path, path: &Path) -> bool {
+    with_entry(archive, path, |bytes| {
+        bytes
+            .iter()
+            .take(BINARY_SNIFF_BYTES as usize)
+            .any(|&byte| byte == 0)
+    })
+    .unwrap_or(false)
+}
+
+/// Calls `f` with the content of the entry at `path`.
+fn with_entry<T>(archive: &Path, path: &Path, f: impl FnOnce(&[u8]) -> T) -> Result<T, String> {
+    let entries = load_entries(archive)?;
+    path.strip_prefix(archive)
+        .ok()
+        .and_then(|entry| entries.get(entry))
+        .map(|bytes| f(bytes))
+        .ok_or_else(|| format!("{} is not in {}", path.display(), archive.display()))
+}
@@ -0,0 +1,315 @@
+// src/clipboard.rs
+
+use arboard::Clipboard;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Which clipboard ctx-pick copies to (`--clipboard-target`).
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClipboardTarget {
+    /// The clipboard pasted with Ctrl-V (the CLIPBOARD selection on X11).
+    #[default]
+    Clipboard,
+    /// The X11 PRIMARY selection, pasted with a middle click.
+    Primary,
+}
+
+/// Replaces the text on `target`.
+///
+/// PRIMARY is written by `xclip` (or `xsel`) rather than through `clipboard`:
+/// X11 selections are served by the process that owns them, and unlike
+/// CLIPBOARD, no clipboard manager takes PRIMARY over when ctx-pick exits.
+/// Both tools fork a process that keeps serving it afterwards.
+pub fn set_text(
+    clipboard: &mut Clipboard,
+    target: ClipboardTarget,
+    text: String,
+) -> Result<(), arboard::Error> {
+    match target {
+        ClipboardTarget::Clipboard => clipboard.set_text(text),
+        ClipboardTarget::Primary if primary::AVAILABLE => set_primary_text(&text),
+        ClipboardTarget::Primary => Err(arboard::Error::ClipboardNotSupported),
+    }
+}
+
+/// Hands `text` to the first of `xclip` and `xsel` that is installed.
+fn set_primary_text(text: &str) -> Result<(), arboard::Error> {
+    let helpers: [(&str, &[&str]); 2] = [
+        ("xclip", &["-selection", "primary"]),
+        ("xsel", &["--primary", "--input"]),
+    ];
+    for (program, args) in helpers {
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(helper_error(program, e)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| helper_error(program, e))?;
+        }
+        let status = child.wait().map_err(|e| helper_error(program, e))?;
+        return if status.success() {
+            Ok(())
+        } else {
+            Err(arboard::Error::Unknown {
+                description: format!("{} exited with {}", program, status),
+            })
+        };
+    }
+    Err(arboard::Error::Unknown {
+        description: "writing the PRIMARY selection needs xclip or xsel installed".to_string(),
+    })
+}
+
+fn helper_error(program: &str, error: io::Error) -> arboard::Error {
+    arboard::Error::Unknown {
+        description: format!("could not run {}: {}", program, error),
+    }
+}
+
+/// Reads the text on `target`.
+fn get_text(clipboard: &mut Clipboard, target: ClipboardTarget) -> Result<String, arboard::Error> {
+    match target {
+        ClipboardTarget::Clipboard => clipboard.get_text(),
+        ClipboardTarget::Primary => primary::get_text(clipboard),
+    }
+}
+
+/// Clears `target`.
+fn clear(clipboard: &mut Clipboard, target: ClipboardTarget) -> Result<(), arboard::Error> {
+    match target {
+        ClipboardTarget::Clipboard => clipboard.clear(),
+        ClipboardTarget::Primary => primary::clear(clipboard),
+    }
+}
+
+/// Access to the PRIMARY selection, which only X11 (and Wayland compositors
+/// that emulate it) has.
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+mod primary {
+    use arboard::{ClearExtLinux, Clipboard, GetExtLinux, LinuxClipboardKind};
+
+    pub const AVAILABLE: bool = true;
+
+    pub fn get_text(clipboard: &mut Clipboard) -> Result<String, arboard::Error> {
+        clipboard
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+    }
+
+    pub fn clear(clipboard: &mut Clipboard) -> Result<(), arboard::Error> {
+        clipboard
+            .clear_with()
+            .clipboard(LinuxClipboardKind::Primary)
+    }
+}
+
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+)))]
+mod primary {
+    use arboard::Clipboard;
+
+    pub const AVAILABLE: bool = false;
+
+    pub fn get_text(_clipboard: &mut Clipboard) -> Result<String, arboard::Error> {
+        Err(arboard::Error::ClipboardNotSupported)
+    }
+
+    pub fn clear(_clipboard: &mut Clipboard) -> Result<(), arboard::Error> {
+        Err(arboard::Error::ClipboardNotSupported)
+    }
+}
+
+/// Hashes clipboard text so a later process can tell whether the clipboard
+/// still holds what ctx-pick wrote, without passing the content itself around.
+///
+/// `DefaultHasher::new()` uses fixed keys, so the hash is stable between the
+/// main process and the background process it spawns from the same binary.
+pub fn content_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Parses a duration such as `30s`, `5m`, `1h`, or a bare number of seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let (number, multiplier) = match input.char_indices().last() {
+        Some((i, 's')) => (&input[..i], 1),
+        Some((i, 'm')) => (&input[..i], 60),
+        Some((i, 'h')) => (&input[..i], 60 * 60),
+        _ => (input, 1),
+    };
+    let invalid = || format!("invalid duration '{}' (expected e.g. 30s, 5m, 1h)", input);
+    let value: u64 = number.trim().parse().map_err(|_| invalid())?;
+    let seconds = value.checked_mul(multiplier).ok_or_else(invalid)?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Clears `target` immediately.
+pub fn clear_now(target: ClipboardTarget) -> Result<(), arboard::Error> {
+    clear(&mut Clipboard::new()?, target)
+}
+
+/// Spawns a detached copy of this executable that clears the clipboard after
+/// `delay`, but only if it still contains `text`.
+///
+/// A separate process is used because the clear has to happen after ctx-pick
+/// itself has exited. On Linux, clipboard contents are served by the owning
+/// process (or handed to a clipboard manager on exit), so the helper re-reads
+/// whatever is current at clear time rather than relying on our own ownership.
+pub fn spawn_delayed_clear(delay: Duration, text: &str, target: ClipboardTarget) -> io::Result<()> {
+    let exe = std::env::current_exe()?;
+    let mut command = Command::new(exe);
+    command
+        .arg("--clear-watch")
+        .arg(delay.as_secs().to_string())
+        .arg(content_hash(text).to_string());
+    if target == ClipboardTarget::Primary {
+        command.args(["--clipboard-target", "primary"]);
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // Put the helper in its own process group so Ctrl-C in the terminal
+    // that launched ctx-pick doesn't take it down with the parent.
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    command.spawn().map(|_| ())
+}
+
+/// The body of the background helper spawned by `spawn_delayed_clear`.
+///
+/// Sleeps for `delay_secs`, then clears `target` if its text still hashes
+/// to `expected_hash`. Anything the user copied in the meantime is left alone.
+pub fn run_clear_watch(
+    delay_secs: u64,
+    expected_hash: u64,
+    target: ClipboardTarget,
+) -> Result<(), arboard::Error> {
+    std::thread::sleep(Duration::from_secs(delay_secs));
+
+    let mut clipboard = Clipboard::new()?;
+    let current = get_text(&mut clipboard, target).ok();
+    if still_holds(current.as_deref(), expected_hash) {
+        clear(&mut clipboard, target)
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether the clipboard text read by the clear-watch helper is still what
+/// hashed to `expected_hash`. Text that can't be read doesn't count: either
+/// something else was copied, or the content is already gone (e.g. no
+/// clipboard manager took ownership on Linux).
+pub fn still_holds(current: Option<&str>, expected_hash: u64) -> bool {
+    current.is_some_and(|text| content_hash(text) == expected_hash)
+}
+
+/// The text written and read back by `--clipboard-check`.
+const SELF_TEST_TEXT: &str = "ctx-pick clipboard self-test";
+
+/// What is on the clipboard before ctx-pick writes to it (`--confirm-clipboard`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardContents {
+    /// Nothing, so there's nothing to clobber.
+    Empty,
+    Text {
+        chars: usize,
+        lines: usize,
+    },
+    Image {
+        width: usize,
+        height: usize,
+    },
+    /// Something other than plain text or an image, such as rich text or
+    /// files, or possibly nothing: `arboard` reports an empty clipboard and an
+    /// unreadable format the same way.
+    Unreadable,
+}
+
+/// Looks at what `target` holds without changing it.
+pub fn current_contents(clipboard: &mut Clipboard, target: ClipboardTarget) -> ClipboardContents {
+    match get_text(clipboard, target) {
+        Ok(text) if text.is_empty() => ClipboardContents::Empty,
+        Ok(text) => ClipboardContents::Text {
+            chars: text.chars().count(),
+            lines: text.lines().count(),
+        },
+        // Only CLIPBOARD is checked for an image: PRIMARY holds selected text.
+        Err(_) if target == ClipboardTarget::Primary => ClipboardContents::Unreadable,
+        Err(_) => match clipboard.get_image() {
+            Ok(image) => ClipboardContents::Image {
+                width: image.width,
+                height: image.height,
+            },
+            Err(_) => ClipboardContents::Unreadable,
+        },
+    }
+}
+
+/// Describes which clipboard backend `arboard` uses on this platform.
+///
+/// `arboard` doesn't report its backend at runtime, but the choice is fixed
+/// per platform for the features we enable. On Linux that is X11, which also
+/// covers Wayland sessions via XWayland.
+pub fn backend_name() -> String {
+    if cfg!(target_os = "macos") {
+        "macOS NSPasteboard".to_string()
+    } else if cfg!(target_os = "windows") {
+        "Windows clipboard (Win32)".to_string()
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        "X11 (via XWayland; Wayland session detected)".to_string()
+    } else {
+        "X11".to_string()
+    }
+}
+
+/// Writes a test string to the clipboard, reads it back, and checks that it
+/// round-tripped. Whatever text was on the clipboard beforehand is restored.
+pub fn self_test() -> Result<(), String> {
+    let mut clipboard = Clipboard::new().map_err(|e| format!("could not open clipboard: {}", e))?;
+    let previous = clipboard.get_text().ok();
+
+    clipboard
+        .set_text(SELF_TEST_TEXT)
+        .map_err(|e| format!("could not write to clipboard: {}", e))?;
+    let read_back = clipboard
+        .get_text()
+        .map_err(|e| format!("could not read from clipboard: {}", e));
+
+    if let Some(previous) = previous {
+        let _ = clipboard.set_text(previous);
+    }
+
+    match read_back? {
+        text if text == SELF_TEST_TEXT => Ok(()),
+        text => Err(format!(
+            "read back different text than was written ({} characters)",
+            text.chars().count()
+        )),
+    }
+}
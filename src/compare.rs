@@ -0,0 +1,73 @@
+// src/compare.rs
+
+use crate::types::FileContext;
+use similar::TextDiff;
+
+/// How `--compare` presents a file's two versions.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompareFormat {
+    /// The current version and the older one as two consecutive blocks.
+    #[default]
+    Blocks,
+    /// A single unified diff from the older version to the current one.
+    Diff,
+}
+
+/// Pairs each current file context with its version at `revision` (`None` if
+/// the file didn't exist there) and returns the contexts to render.
+///
+/// Files that are identical in both versions collapse to a single block, so
+/// unchanged code isn't paid for twice.
+pub fn combine(
+    current: Vec<FileContext>,
+    previous: Vec<Option<FileContext>>,
+    revision: &str,
+    format: CompareFormat,
+) -> Vec<FileContext> {
+    let mut combined = Vec::new();
+    for (current, previous) in current.into_iter().zip(previous) {
+        let Some(previous) = previous else {
+            combined.push(relabel(current, &format!("new since {}", revision)));
+            continue;
+        };
+        if previous.content == current.content {
+            combined.push(relabel(current, &format!("unchanged since {}", revision)));
+            continue;
+        }
+
+        match format {
+            CompareFormat::Blocks => {
+                let label = current.display_path.clone();
+                combined.push(relabel(current, "current"));
+                combined.push(FileContext::new(
+                    format!("{} (at {})", label, revision),
+                    previous.content,
+                    previous.language,
+                    previous.depth,
+                ));
+            }
+            CompareFormat::Diff => {
+                let old_name = format!("{} @ {}", current.display_path, revision);
+                let diff = TextDiff::from_lines(&previous.content, &current.content)
+                    .unified_diff()
+                    .header(&old_name, &current.display_path)
+                    .to_string();
+                combined.push(FileContext::new(
+                    format!("{} (diff against {})", current.display_path, revision),
+                    diff,
+                    Some("diff".to_string()),
+                    current.depth,
+                ));
+            }
+        }
+    }
+    combined
+}
+
+/// Returns `context` with `note` appended to its header in parentheses.
+fn relabel(context: FileContext, note: &str) -> FileContext {
+    FileContext {
+        display_path: format!("{} ({})", context.display_path, note),
+        ..context
+    }
+}
@@ -1,10 +1,107 @@
+use crate::archive::Archive;
 use crate::error::AppError;
+use crate::git::GitTree;
 use std::env;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The default cap on how many files one directory or glob input may expand to.
+pub const DEFAULT_MAX_FILES_PER_INPUT: usize = 100;
+
+/// The default cap on how many files and directories one glob or fuzzy
+/// search may visit before it's stopped.
+pub const DEFAULT_SEARCH_ENTRY_LIMIT: usize = 1_000_000;
+
+/// The default cap on how long one glob or fuzzy search may run.
+pub const DEFAULT_SEARCH_TIME_LIMIT: Duration = Duration::from_secs(30);
+
+/// The default share of the total output above which a single file triggers
+/// the balance warning.
+pub const DEFAULT_BALANCE_WARNING_THRESHOLD: f64 = 0.6;
+
+/// File name patterns left out of directory and glob expansions unless
+/// `--no-default-excludes` is given: lockfiles, minified bundles, and files
+/// marked as generated. Deliberately short; anything a person might edit by
+/// hand doesn't belong here. Files named directly are never excluded.
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "poetry.lock",
+    "Pipfile.lock",
+    "uv.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+    "*.min.js",
+    "*.min.css",
+    "*.generated.*",
+];
+
+/// Returns the first directory in `path` whose name matches a `DEFAULT_EXCLUDES`
+/// pattern, with that pattern. Running from inside such a directory would
+/// otherwise have the defaults exclude everything in it.
+pub fn default_exclude_covering(path: &Path) -> Option<(String, &'static str)> {
+    path.components().find_map(|component| {
+        let name = component.as_os_str().to_str()?;
+        DEFAULT_EXCLUDES
+            .iter()
+            .find(|pattern| glob::Pattern::new(pattern).is_ok_and(|p| p.matches(name)))
+            .map(|pattern| (name.to_string(), *pattern))
+    })
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
+    /// The root that inputs resolve against and fuzzy search walks.
     pub working_dir: PathBuf,
+    /// The directory ctx-pick was run from. Ambiguous fuzzy candidates close
+    /// to it are listed first, even if `working_dir` is elsewhere.
+    pub invocation_dir: PathBuf,
+    /// The directories fuzzy search is confined to (`--scope-to-inputs`),
+    /// in the order they were given. Empty means all of `working_dir`.
+    pub search_roots: Vec<PathBuf>,
+    /// The most files a single directory or glob input may expand to.
+    /// `None` disables the limit.
+    pub max_files_per_input: Option<usize>,
+    /// The most files and directories one glob or fuzzy search may visit
+    /// (`--search-entry-limit`). `None` disables the limit.
+    pub search_entry_limit: Option<usize>,
+    /// The longest one glob or fuzzy search may run (`--search-time-limit`).
+    /// `None` disables the limit.
+    pub search_time_limit: Option<Duration>,
+    /// How many levels below a directory input its expansion descends
+    /// (`--dir-depth`); 1 takes only the files directly inside it. `None`
+    /// means no limit.
+    pub dir_depth: Option<usize>,
+    /// Whether to expand `~` and `$VAR` references in inputs.
+    pub expand_inputs: bool,
+    /// Warn when one file makes up more than this share (0.0-1.0) of the output.
+    /// `None` disables the warning.
+    pub balance_warning_threshold: Option<f64>,
+    /// When set (`--at`), inputs resolve against this git revision's files
+    /// instead of the working directory.
+    pub git_tree: Option<GitTree>,
+    /// When set (`--archive`), inputs resolve against the archive's files
+    /// instead of the working directory.
+    pub archive: Option<Archive>,
+    /// Whether directory and glob expansions skip `DEFAULT_EXCLUDES`.
+    pub default_excludes: bool,
+    /// Whether fuzzy search and directory expansion include hidden files
+    /// and directories (`--hidden`). Inputs starting with a hidden component
+    /// always do.
+    pub hidden: bool,
+    /// Whether expansions, fuzzy and symbol matches and the picker may include
+    /// files outside `working_dir` (`--allow-outside`).
+    pub allow_outside: bool,
+    /// Whether directory, glob and fuzzy walks descend into symlinked
+    /// directories (off with `--no-follow-symlinks`). Loops are skipped
+    /// either way.
+    pub follow_symlinks: bool,
     // We can add other configuration options here later if needed
     // e.g., verbosity, ignored patterns, etc.
 }
@@ -12,14 +109,69 @@ pub struct Config {
 impl Config {
     /// Creates a new Config instance.
     ///
-    /// Initializes the working directory based on the current environment.
-    pub fn new() -> Result<Self, AppError> {
-        let working_dir = env::current_dir().map_err(|io_err| {
-            AppError::IoError(format!(
-                "Failed to determine current working directory: {}",
-                io_err
-            ))
-        })?;
-        Ok(Config { working_dir })
+    /// The working directory is `root` (`--cwd`) if given, and otherwise the
+    /// process's current directory.
+    pub fn new(root: Option<&Path>) -> Result<Self, AppError> {
+        Self::with_current_dir(root, env::current_dir())
+    }
+
+    /// Creates a Config as `new` does, given the result of looking up the
+    /// current directory. An absolute `root` doesn't need one, so it still
+    /// works after the current directory has been deleted.
+    pub(crate) fn with_current_dir(
+        root: Option<&Path>,
+        current_dir: io::Result<PathBuf>,
+    ) -> Result<Self, AppError> {
+        let working_dir = match (root, &current_dir) {
+            (Some(root), _) if root.is_absolute() => root.to_path_buf(),
+            (Some(root), Ok(current_dir)) => current_dir.join(root),
+            (None, Ok(current_dir)) => current_dir.clone(),
+            (_, Err(io_err)) if io_err.kind() == io::ErrorKind::NotFound => {
+                return Err(AppError::WorkingDirectoryMissing(
+                    env::var_os("PWD").map(PathBuf::from),
+                ));
+            }
+            (_, Err(io_err)) => {
+                return Err(AppError::IoError(format!(
+                    "Failed to determine current working directory: {}",
+                    io_err
+                )));
+            }
+        };
+        if root.is_some() && !working_dir.is_dir() {
+            return Err(AppError::IoError(format!(
+                "--cwd {:?} is not a directory",
+                working_dir
+            )));
+        }
+        Ok(Config {
+            invocation_dir: current_dir.unwrap_or_else(|_| working_dir.clone()),
+            working_dir,
+            search_roots: Vec::new(),
+            max_files_per_input: Some(DEFAULT_MAX_FILES_PER_INPUT),
+            search_entry_limit: Some(DEFAULT_SEARCH_ENTRY_LIMIT),
+            search_time_limit: Some(DEFAULT_SEARCH_TIME_LIMIT),
+            dir_depth: None,
+            expand_inputs: true,
+            balance_warning_threshold: Some(DEFAULT_BALANCE_WARNING_THRESHOLD),
+            git_tree: None,
+            archive: None,
+            default_excludes: true,
+            hidden: false,
+            allow_outside: false,
+            follow_symlinks: true,
+        })
+    }
+
+    /// Fails if the working directory has disappeared since the run started,
+    /// which would otherwise surface as a confusing error for every file.
+    pub fn ensure_working_dir_exists(&self) -> Result<(), AppError> {
+        if self.working_dir.is_dir() {
+            Ok(())
+        } else {
+            Err(AppError::WorkingDirectoryMissing(Some(
+                self.working_dir.clone(),
+            )))
+        }
     }
 }
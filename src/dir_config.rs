@@ -0,0 +1,321 @@
+// src/dir_config.rs
+
+use crate::language::{self, LanguageOverride};
+use crate::types::{InputResolution, ResolvedFile};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of a per-directory settings file.
+pub const DIR_CONFIG_FILE_NAME: &str = ".ctx-pick.toml";
+
+/// Settings read from a `.ctx-pick.toml`, applying to every file beneath the
+/// directory that contains it.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DirConfig {
+    /// The skeleton depth for files beneath this directory.
+    pub depth: Option<usize>,
+    /// Glob patterns, relative to this directory, for files to leave out.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// `GLOB=LANG` language overrides, with globs relative to this directory.
+    #[serde(default)]
+    pub lang_override: Vec<String>,
+}
+
+/// Parses the text of a `.ctx-pick.toml`, with errors on a single line.
+fn parse(text: &str) -> Result<DirConfig, String> {
+    toml::from_str(text).map_err(|e| describe_toml_error(text, &e))
+}
+
+/// Describes an error parsing the TOML `text` on a single line, e.g.
+/// `line 3: invalid type: string "x", expected usize`.
+pub fn describe_toml_error(text: &str, e: &toml::de::Error) -> String {
+    match e.span() {
+        Some(span) => format!(
+            "line {}: {}",
+            text[..span.start].matches('\n').count() + 1,
+            e.message()
+        ),
+        None => e.message().to_string(),
+    }
+}
+
+/// Finds every `.ctx-pick.toml` beneath `root`, skipping what git ignores, sorted.
+pub fn discover(root: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name() == DIR_CONFIG_FILE_NAME)
+        .map(ignore::DirEntry::into_path)
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Reads the `.ctx-pick.toml` at `path` and checks it as `DirConfigs` would,
+/// returning the first problem found.
+pub fn check_file(path: &Path) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let dir_config = parse(&text)?;
+    for pattern in &dir_config.exclude {
+        glob::Pattern::new(pattern)
+            .map_err(|e| format!("invalid exclude pattern '{}': {}", pattern, e))?;
+    }
+    for value in &dir_config.lang_override {
+        language::parse_override(value)
+            .map_err(|e| format!("invalid lang_override '{}': {}", value, e))?;
+    }
+    Ok(())
+}
+
+/// Where a file's effective skeleton depth came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DepthSource {
+    /// The `--depth` flag.
+    Cli,
+    /// The `.ctx-pick.toml` at this path.
+    DirConfig(PathBuf),
+    /// A line of an `--input-file`, as `FILE:LINE`.
+    InputFile(String),
+}
+
+/// The settings that apply to one resolved file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileSettings {
+    /// The skeleton depth, or `None` for full content.
+    pub depth: Option<usize>,
+    /// Where `depth` came from, if set.
+    pub depth_source: Option<DepthSource>,
+    /// The `.ctx-pick.toml` whose `exclude` list matched this file, if any.
+    pub excluded_by: Option<PathBuf>,
+}
+
+/// Loads `.ctx-pick.toml` files on demand, caching them per directory so each
+/// is read at most once however many files live beneath it.
+pub struct DirConfigs {
+    root: PathBuf,
+    loaded: HashMap<PathBuf, Option<DirConfig>>,
+}
+
+impl DirConfigs {
+    /// Creates a loader for configs between files and `root` (the working directory).
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()),
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Returns the config in `dir`, if it has one. A config that can't be
+    /// read or parsed is reported once and then treated as absent.
+    fn load(&mut self, dir: &Path) -> Option<&DirConfig> {
+        self.loaded
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let path = dir.join(DIR_CONFIG_FILE_NAME);
+                let text = fs::read_to_string(&path).ok()?;
+                let mut dir_config = parse(&text)
+                    .map_err(|e| eprintln!("Warning: Ignoring invalid {:?}: {}", path, e))
+                    .ok()?;
+                dir_config
+                    .exclude
+                    .retain(|pattern| match glob::Pattern::new(pattern) {
+                        Ok(_) => true,
+                        Err(e) => {
+                            eprintln!(
+                                "Warning: Ignoring invalid exclude pattern '{}' in {:?}: {}",
+                                pattern, path, e
+                            );
+                            false
+                        }
+                    });
+                dir_config
+                    .lang_override
+                    .retain(|value| match language::parse_override(value) {
+                        Ok(_) => true,
+                        Err(e) => {
+                            eprintln!(
+                                "Warning: Ignoring invalid lang_override '{}' in {:?}: {}",
+                                value, path, e
+                            );
+                            false
+                        }
+                    });
+                Some(dir_config)
+            })
+            .as_ref()
+    }
+
+    /// The configs consulted so far that exist and parsed, relative to the
+    /// working directory and sorted.
+    pub fn applied(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .loaded
+            .iter()
+            .filter(|(_, dir_config)| dir_config.is_some())
+            .map(|(dir, _)| {
+                let path = dir.join(DIR_CONFIG_FILE_NAME);
+                path.strip_prefix(&self.root)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(path)
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Works out the settings for `file` given the `--depth` flag.
+    ///
+    /// Configs are looked up from the file's directory towards the working
+    /// directory. The nearest config that sets `depth` wins, but `--depth`
+    /// overrides them all. Every config along the way may exclude the file.
+    pub fn settings_for(&mut self, file: &ResolvedFile, cli_depth: Option<usize>) -> FileSettings {
+        let mut settings = FileSettings {
+            depth: cli_depth,
+            depth_source: cli_depth.map(|_| DepthSource::Cli),
+            excluded_by: None,
+        };
+
+        let file_path = file.canonical_path();
+        // Files outside the working directory only get the CLI settings.
+        if !file_path.starts_with(&self.root) {
+            return settings;
+        }
+
+        let root = self.root.clone();
+        for dir in file_path.ancestors().skip(1) {
+            if let Some(dir_config) = self.load(dir) {
+                let config_path = dir.join(DIR_CONFIG_FILE_NAME);
+                if settings.depth_source.is_none()
+                    && let Some(depth) = dir_config.depth
+                {
+                    settings.depth = Some(depth);
+                    settings.depth_source = Some(DepthSource::DirConfig(config_path.clone()));
+                }
+                if settings.excluded_by.is_none() && is_excluded(dir_config, dir, file_path) {
+                    settings.excluded_by = Some(config_path);
+                }
+            }
+            if dir == root {
+                break;
+            }
+        }
+        settings
+    }
+
+    /// Collects the `lang_override` entries of every config between `files`
+    /// and the working directory, with their globs anchored at the config's
+    /// directory. Farther configs come first, so that with `language_for`'s
+    /// last-match-wins rule the nearest config's override applies, and any
+    /// `--lang-override` placed after them applies over all of them.
+    pub fn language_overrides<'f>(
+        &mut self,
+        files: impl IntoIterator<Item = &'f ResolvedFile>,
+    ) -> Vec<LanguageOverride> {
+        let root = self.root.clone();
+        let mut dirs: BTreeSet<(usize, PathBuf)> = BTreeSet::new();
+        for file in files {
+            let file_path = file.canonical_path();
+            if !file_path.starts_with(&root) {
+                continue;
+            }
+            for dir in file_path.ancestors().skip(1) {
+                dirs.insert((dir.components().count(), dir.to_path_buf()));
+                if dir == root {
+                    break;
+                }
+            }
+        }
+
+        let mut overrides = Vec::new();
+        for (_, dir) in dirs {
+            let Some(dir_config) = self.load(&dir) else {
+                continue;
+            };
+            let relative = dir.strip_prefix(&root).unwrap_or(&dir);
+            for value in &dir_config.lang_override {
+                let Some((pattern, name)) = value.rsplit_once('=') else {
+                    continue;
+                };
+                // Like `exclude`, a pattern without a `/` matches at any depth.
+                let anchored = if relative.as_os_str().is_empty() {
+                    pattern.to_string()
+                } else if pattern.contains('/') {
+                    format!("{}/{}", glob_prefix(relative), pattern)
+                } else {
+                    format!("{}/**/{}", glob_prefix(relative), pattern)
+                };
+                if let Ok(language_override) =
+                    language::parse_override(&format!("{}={}", anchored, name))
+                {
+                    overrides.push(language_override);
+                }
+            }
+        }
+        overrides
+    }
+
+    /// Turns a successful resolution of `input` into `Excluded` when every
+    /// file it matched is excluded by a config, so the report can say the
+    /// input was filtered out rather than silently contributing nothing.
+    pub fn check_input<'a>(
+        &mut self,
+        input: &'a str,
+        resolution: InputResolution<'a>,
+    ) -> InputResolution<'a> {
+        let InputResolution::Success(files) = &resolution else {
+            return resolution;
+        };
+        let mut config_paths: Vec<PathBuf> = Vec::new();
+        for file in files {
+            let Some(config_path) = self.settings_for(file, None).excluded_by else {
+                return resolution;
+            };
+            if !config_paths.contains(&config_path) {
+                config_paths.push(config_path);
+            }
+        }
+        if config_paths.is_empty() {
+            return resolution;
+        }
+        let config_list: Vec<String> = config_paths
+            .iter()
+            .map(|path| {
+                pathdiff::diff_paths(path, &self.root)
+                    .unwrap_or_else(|| path.clone())
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        InputResolution::Excluded {
+            input_string: input,
+            file_count: files.len(),
+            rule: format!("the exclude list in {}", config_list.join(", ")),
+        }
+    }
+}
+
+/// `dir` as the start of a glob pattern, with `/` separators and any glob
+/// metacharacters in its name escaped.
+fn glob_prefix(dir: &Path) -> String {
+    dir.components()
+        .map(|component| glob::Pattern::escape(&component.as_os_str().to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns true if one of `dir_config`'s exclude patterns matches `file_path`,
+/// relative to `dir`.
+fn is_excluded(dir_config: &DirConfig, dir: &Path, file_path: &Path) -> bool {
+    let Ok(relative) = file_path.strip_prefix(dir) else {
+        return false;
+    };
+    dir_config
+        .exclude
+        .iter()
+        .any(|pattern| glob::Pattern::new(pattern).is_ok_and(|p| p.matches_path(relative)))
+}
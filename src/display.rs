@@ -1,7 +1,19 @@
-use crate::types::{FileContext, InputResolution, ResolvedFile};
-use arboard;
+use crate::clipboard::ClipboardContents;
+use crate::dir_config::{DepthSource, FileSettings};
+use crate::doctor::{Check, CheckStatus};
+use crate::manifest::FileSetDiff;
+use crate::stats::Stats;
+use crate::symbol_extractor;
+use crate::types::{
+    ContextMode, ContextTotals, DirectMatch, FileContext, FuzzyCandidate, InputResolution,
+    ResolutionReport, ResolutionTrace, ResolvedFile, SearchStop,
+};
 use console::{Style, Term};
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
 
 /// Manages all terminal output to stderr, such as status messages,
 /// progress, and error reports. It uses the `console` crate for styling.
@@ -13,6 +25,57 @@ pub struct DisplayManager {
     pub filename_style: Style,
     pub metadata_style: Style,
     pub ambiguous_style: Style,
+    /// How many candidates to list for an ambiguous input.
+    max_ambiguous: usize,
+    /// Whether the summary shows each file's permissions.
+    show_permissions: bool,
+    /// Whether the summary shows each file's detected language.
+    show_languages: bool,
+    /// Headers that different files shared, each with the headers shown
+    /// in its place, to point out in the summary.
+    header_collisions: Vec<(String, Vec<String>)>,
+    /// Files that were deleted between resolving and reading them.
+    removed_files: Vec<String>,
+    /// Zero-length files that were left out.
+    empty_files: Vec<String>,
+    /// Included files with editor artifacts next to them, each with the
+    /// artifacts' descriptions.
+    unsaved_edits: Vec<(String, Vec<String>)>,
+    /// The `--max-total-lines` limit, with the files left out to keep within
+    /// it and their line counts.
+    over_line_budget: Option<(usize, Vec<(String, usize)>)>,
+    /// The lines and characters of the `--footer`, counted in the summary's
+    /// totals but not as a file.
+    footer_size: Option<(usize, usize)>,
+}
+
+/// An answer to the `--confirm-clipboard` question.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// Copy, replacing what's on the clipboard.
+    Overwrite,
+    /// Copy nothing.
+    Cancel,
+    /// Leave some files out, then ask again.
+    EditSelection,
+}
+
+/// How many candidates of an ambiguous input are listed by default.
+pub const DEFAULT_MAX_AMBIGUOUS: usize = 8;
+
+/// A file's detected language key as `--annotate-language` shows it, with
+/// what skeleton mode would do with the file.
+fn language_annotation(language: Option<&str>) -> String {
+    match language {
+        None => "unknown language (no skeleton; included in full)".to_string(),
+        Some(key) if symbol_extractor::is_always_full(key) => {
+            format!("{} (always included in full)", key)
+        }
+        Some(key) if symbol_extractor::is_supported_extension(key) => {
+            format!("{} (skeleton supported)", key)
+        }
+        Some(key) => format!("{} (no skeleton; included in full)", key),
+    }
 }
 
 // --- Public API ---
@@ -28,20 +91,199 @@ impl DisplayManager {
             filename_style: Style::new().cyan().bold(),
             metadata_style: Style::new().dim(),
             ambiguous_style: Style::new().magenta().bold(),
+            max_ambiguous: DEFAULT_MAX_AMBIGUOUS,
+            show_permissions: false,
+            show_languages: false,
+            header_collisions: Vec::new(),
+            removed_files: Vec::new(),
+            empty_files: Vec::new(),
+            unsaved_edits: Vec::new(),
+            over_line_budget: None,
+            footer_size: None,
+        }
+    }
+
+    /// Lists up to `max` candidates for each ambiguous input.
+    pub fn with_max_ambiguous(self, max: usize) -> Self {
+        Self {
+            max_ambiguous: max,
+            ..self
+        }
+    }
+
+    /// Shows each file's permission bits in the summary (`--show-permissions`).
+    pub fn with_permissions(self, show_permissions: bool) -> Self {
+        Self {
+            show_permissions,
+            ..self
+        }
+    }
+
+    /// Shows each file's detected language in the summary, and whether it
+    /// can be skeletonized (`--annotate-language`).
+    pub fn with_languages(self, show_languages: bool) -> Self {
+        Self {
+            show_languages,
+            ..self
+        }
+    }
+
+    /// Counts the rendered `--footer`, if any, in the summary's totals.
+    pub fn with_footer(self, footer: Option<&str>) -> Self {
+        Self {
+            footer_size: footer.map(|footer| (footer.lines().count(), footer.chars().count())),
+            ..self
+        }
+    }
+
+    /// Notes in the summary which headers were lengthened because different
+    /// files shared them.
+    pub fn with_header_collisions(self, header_collisions: Vec<(String, Vec<String>)>) -> Self {
+        Self {
+            header_collisions,
+            ..self
+        }
+    }
+
+    /// Lists in the summary the files that were removed during the run.
+    pub fn with_removed_files(self, removed_files: Vec<String>) -> Self {
+        Self {
+            removed_files,
+            ..self
+        }
+    }
+
+    /// Lists in the summary the zero-length files that were left out.
+    pub fn with_empty_files(self, empty_files: Vec<String>) -> Self {
+        Self {
+            empty_files,
+            ..self
+        }
+    }
+
+    /// Warns in the summary about files that may have unsaved edits.
+    pub fn with_unsaved_edits(self, unsaved_edits: Vec<(String, Vec<String>)>) -> Self {
+        Self {
+            unsaved_edits,
+            ..self
+        }
+    }
+
+    /// Lists in the summary the files left out to keep within `limit`
+    /// (`--max-total-lines`), if any.
+    pub fn with_line_budget(self, limit: Option<usize>, left_out: Vec<(String, usize)>) -> Self {
+        Self {
+            over_line_budget: limit
+                .filter(|_| !left_out.is_empty())
+                .map(|limit| (limit, left_out)),
+            ..self
+        }
+    }
+
+    /// Prints the files left out by `--max-total-lines`, if any.
+    pub fn print_line_budget_omissions(&self) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        self.write_line_budget_omissions(&mut stderr)
+    }
+
+    /// Writes the files left out by `--max-total-lines` to any writer.
+    pub fn write_line_budget_omissions(&self, stderr: &mut impl Write) -> io::Result<()> {
+        let Some((limit, left_out)) = &self.over_line_budget else {
+            return Ok(());
+        };
+        writeln!(
+            stderr,
+            "{} {}",
+            self.warning_style.apply_to("✂️"),
+            self.warning_style.apply_to(format!(
+                "Left out {} to stay within --max-total-lines {}:",
+                match left_out.len() {
+                    1 => "1 file".to_string(),
+                    n => format!("{} files", n),
+                },
+                group_digits(*limit)
+            ))
+        )?;
+        for (path, lines) in left_out {
+            writeln!(
+                stderr,
+                "  • {} {}",
+                self.filename_style.apply_to(path),
+                self.metadata_style
+                    .apply_to(format!("({} lines)", group_digits(*lines)))
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Prints the files that may have unsaved edits in an editor, if any.
+    pub fn print_unsaved_edits(&self) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        self.write_unsaved_edits(&mut stderr)
+    }
+
+    /// Writes the unsaved-edits warning to any writer, if there's anything
+    /// to warn about.
+    pub fn write_unsaved_edits(&self, stderr: &mut impl Write) -> io::Result<()> {
+        if self.unsaved_edits.is_empty() {
+            return Ok(());
+        }
+        writeln!(
+            stderr,
+            "{} {}",
+            self.warning_style.apply_to("⚠️"),
+            self.warning_style.apply_to(format!(
+                "{} may have unsaved edits in an editor, so what was included may be out of date:",
+                match self.unsaved_edits.len() {
+                    1 => "1 file".to_string(),
+                    n => format!("{} files", n),
+                }
+            ))
+        )?;
+        for (path, artifacts) in &self.unsaved_edits {
+            writeln!(
+                stderr,
+                "  • {} {}",
+                self.filename_style.apply_to(path),
+                self.metadata_style
+                    .apply_to(format!("({})", artifacts.join(", ")))
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Prints the warnings about walks that skipped unreadable directories.
+    pub fn print_skipped_walks(&self, warnings: &[String]) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        self.write_skipped_walks(&mut stderr, warnings)
+    }
+
+    /// Writes the warnings about walks that skipped unreadable directories
+    /// to any writer.
+    pub fn write_skipped_walks(
+        &self,
+        stderr: &mut impl Write,
+        warnings: &[String],
+    ) -> io::Result<()> {
+        for warning in warnings {
+            writeln!(
+                stderr,
+                "{} {}",
+                self.warning_style.apply_to("⚠️"),
+                self.warning_style.apply_to(warning)
+            )?;
         }
+        Ok(())
     }
 
-    /// Prints a detailed report of all file resolution errors.
+    /// Writes the resolution error report to any writer.
     /// This function orchestrates the printing of different error sections.
-    pub fn print_resolution_errors(
+    pub fn write_resolution_errors(
         &self,
-        path_errors: &[&InputResolution],
-        not_founds: &[&InputResolution],
-        ambiguities: &[&InputResolution],
-        invalid_globs: &[&InputResolution],
-        successful_files: &[ResolvedFile],
+        stderr: &mut impl Write,
+        report: &ResolutionReport,
     ) -> io::Result<()> {
-        let mut stderr = self.term.clone();
+        let successful_files = &report.files;
 
         writeln!(
             stderr,
@@ -51,6 +293,58 @@ impl DisplayManager {
         )?;
         writeln!(stderr, "{}", self.metadata_style.apply_to("-".repeat(50)))?;
 
+        self.write_unresolved_sections(stderr, report)?;
+
+        if !successful_files.is_empty() {
+            writeln!(
+                stderr,
+                "\n{}",
+                self.success_style
+                    .apply_to("However, these files were successfully resolved:")
+            )?;
+            for resolved_file in successful_files {
+                self.report_successful_file_case(stderr, resolved_file)?;
+            }
+        }
+
+        writeln!(
+            stderr,
+            "\n{}",
+            self.metadata_style
+                .apply_to("Please resolve the issues above and try again.")
+        )?;
+        Ok(())
+    }
+
+    /// Writes the unresolved-input warnings to any writer.
+    pub fn write_resolution_warnings(
+        &self,
+        stderr: &mut impl Write,
+        report: &ResolutionReport,
+    ) -> io::Result<()> {
+        writeln!(
+            stderr,
+            "{} {}",
+            self.warning_style.apply_to("⚠️"),
+            self.warning_style
+                .apply_to("Some inputs could not be resolved and were skipped:")
+        )?;
+        self.write_unresolved_sections(stderr, report)?;
+        writeln!(stderr)
+    }
+
+    /// Writes one section per kind of resolution failure, skipping empty ones.
+    fn write_unresolved_sections(
+        &self,
+        stderr: &mut impl Write,
+        report: &ResolutionReport,
+    ) -> io::Result<()> {
+        let path_errors = &report.path_does_not_exist_errors;
+        let not_founds = &report.not_founds;
+        let ambiguities = &report.ambiguities;
+        let invalid_globs = &report.invalid_globs;
+        let too_many_files = &report.too_many_files;
+
         if !path_errors.is_empty() {
             writeln!(
                 stderr,
@@ -59,7 +353,7 @@ impl DisplayManager {
                     .apply_to("The following specified paths do not exist:")
             )?;
             for case in path_errors {
-                self.report_path_does_not_exist_case(&mut stderr, case)?;
+                self.report_path_does_not_exist_case(stderr, case)?;
             }
         }
 
@@ -71,84 +365,172 @@ impl DisplayManager {
                     .apply_to("The following glob patterns are invalid:")
             )?;
             for case in invalid_globs {
-                self.report_invalid_glob_case(&mut stderr, case)?;
+                self.report_invalid_glob_case(stderr, case)?;
             }
         }
 
-        if !not_founds.is_empty() {
+        if !report.invalid_regexes.is_empty() {
             writeln!(
                 stderr,
                 "\n{}",
-                self.warning_style
-                    .apply_to("The following inputs could not be found:")
+                self.error_style
+                    .apply_to("The following regular expressions are invalid:")
             )?;
-            for case in not_founds {
-                self.report_not_found_case(&mut stderr, case)?;
+            for case in &report.invalid_regexes {
+                self.report_invalid_glob_case(stderr, case)?;
             }
         }
 
-        if !ambiguities.is_empty() {
+        if !too_many_files.is_empty() {
             writeln!(
                 stderr,
                 "\n{}",
-                self.ambiguous_style
-                    .apply_to("The following inputs are ambiguous:")
+                self.error_style
+                    .apply_to("The following inputs expanded to too many files:")
             )?;
-            for case in ambiguities {
-                self.report_ambiguous_case(&mut stderr, case)?;
+            for case in too_many_files {
+                self.report_too_many_files_case(stderr, case)?;
             }
+            writeln!(
+                stderr,
+                "  {}",
+                self.metadata_style.apply_to(
+                    "Narrow the pattern, or pass --max-files-per-input 0 to disable the limit."
+                )
+            )?;
         }
 
-        if !successful_files.is_empty() {
+        if !report.outside_working_dir.is_empty() {
             writeln!(
                 stderr,
                 "\n{}",
-                self.success_style
-                    .apply_to("However, these files were successfully resolved:")
+                self.error_style
+                    .apply_to("The following inputs reach outside the working directory:")
             )?;
-            for resolved_file in successful_files {
-                self.report_successful_file_case(&mut stderr, resolved_file)?;
+            for case in &report.outside_working_dir {
+                self.report_outside_working_dir_case(stderr, case)?;
             }
+            writeln!(
+                stderr,
+                "  {}",
+                self.metadata_style.apply_to(
+                    "Name such files explicitly, or pass --allow-outside to include them."
+                )
+            )?;
         }
 
-        writeln!(
-            stderr,
-            "\n{}",
-            self.metadata_style
-                .apply_to("Please resolve the issues above and try again.")
-        )?;
+        if !report.stopped_searches.is_empty() {
+            writeln!(
+                stderr,
+                "\n{}",
+                self.error_style
+                    .apply_to("The following searches were stopped before they finished:")
+            )?;
+            for case in &report.stopped_searches {
+                self.report_search_stopped_case(stderr, case)?;
+            }
+            if report.stopped_searches.iter().any(|case| {
+                !matches!(
+                    case,
+                    InputResolution::SearchStopped {
+                        reason: SearchStop::Interrupted,
+                        ..
+                    }
+                )
+            }) {
+                writeln!(
+                    stderr,
+                    "  {}",
+                    self.metadata_style.apply_to(
+                        "Narrow the pattern, e.g. start it with a directory ('src/**/*.rs' rather than '**/*.rs'), or raise --search-entry-limit or --search-time-limit (0 disables them)."
+                    )
+                )?;
+            }
+        }
+
+        if !report.excluded.is_empty() {
+            writeln!(
+                stderr,
+                "\n{}",
+                self.warning_style
+                    .apply_to("The following inputs matched only excluded files:")
+            )?;
+            for case in &report.excluded {
+                self.report_excluded_case(stderr, case)?;
+            }
+        }
+
+        if !not_founds.is_empty() {
+            writeln!(
+                stderr,
+                "\n{}",
+                self.warning_style
+                    .apply_to("The following inputs could not be found:")
+            )?;
+            for case in not_founds {
+                self.report_not_found_case(stderr, case)?;
+            }
+        }
+
+        if !ambiguities.is_empty() {
+            writeln!(
+                stderr,
+                "\n{}",
+                self.ambiguous_style
+                    .apply_to("The following inputs are ambiguous:")
+            )?;
+            for case in ambiguities {
+                self.report_ambiguous_case(stderr, case)?;
+            }
+        }
         Ok(())
     }
 
     /// Prints the final summary report after a successful operation.
     pub fn print_operation_summary_and_preview(
         &self,
-        contexts: &[FileContext], // <-- Receives the new struct
+        contexts: &[FileContext],
+        omitted: &[(String, String)],
         clipboard_result: &Result<(), arboard::Error>,
-        output_count: usize,
-        unit_str: &str,
-        depth: Option<usize>,
     ) -> io::Result<()> {
         let mut stderr = self.term.clone();
-        let summary_verb = if depth.is_some() {
+        self.write_operation_summary_and_preview(&mut stderr, contexts, omitted, clipboard_result)
+    }
+
+    /// Writes the final summary report to any writer: totals for what was
+    /// produced, broken down by mode, then each included file.
+    pub fn write_operation_summary_and_preview(
+        &self,
+        stderr: &mut impl Write,
+        contexts: &[FileContext],
+        omitted: &[(String, String)],
+        clipboard_result: &Result<(), arboard::Error>,
+    ) -> io::Result<()> {
+        let totals = ContextTotals::of(contexts);
+        let (footer_lines, footer_chars) = self.footer_size.unwrap_or_default();
+        let summary_verb = if totals.full.files == 0 && totals.skeleton.files > 0 {
             "Context skeleton copied"
         } else {
             "Context copied"
         };
-        let file_count = contexts.len();
 
         match clipboard_result {
             Ok(_) => {
                 writeln!(
                     stderr,
-                    "\n{} {} to clipboard ({} {}, {} {})",
+                    "\n{} {} to clipboard: {}; {} lines, {} characters in total{}",
                     self.success_style.apply_to("✅"),
                     summary_verb,
-                    self.metadata_style.apply_to(file_count.to_string()),
+                    self.metadata_style.apply_to(mode_breakdown(&totals)),
+                    self.metadata_style
+                        .apply_to(group_digits(totals.total.lines + footer_lines)),
                     self.metadata_style
-                        .apply_to(if file_count == 1 { "file" } else { "files" }),
-                    self.metadata_style.apply_to(output_count.to_string()),
-                    self.metadata_style.apply_to(unit_str)
+                        .apply_to(group_digits(totals.total.chars + footer_chars)),
+                    if self.footer_size.is_some() {
+                        " with the footer"
+                    } else {
+                        ""
+                    }
                 )?;
             }
             Err(err) => {
@@ -172,6 +554,50 @@ impl DisplayManager {
             }
         }
 
+        self.write_unsaved_edits(stderr)?;
+
+        if let Some(depths) = depth_breakdown(contexts) {
+            writeln!(
+                stderr,
+                "{} {}",
+                self.metadata_style.apply_to("Skeleton depth:"),
+                self.metadata_style.apply_to(depths)
+            )?;
+        }
+
+        let collapsed: Vec<usize> = contexts
+            .iter()
+            .map(|context| context.file_count)
+            .filter(|&count| count > 1)
+            .collect();
+        if !collapsed.is_empty() {
+            writeln!(
+                stderr,
+                "{} {}",
+                self.metadata_style.apply_to("Identical skeletons:"),
+                self.metadata_style.apply_to(format!(
+                    "{} files collapsed into {} block{}",
+                    collapsed.iter().sum::<usize>(),
+                    collapsed.len(),
+                    if collapsed.len() == 1 { "" } else { "s" }
+                ))
+            )?;
+        }
+
+        for (header, headers) in &self.header_collisions {
+            writeln!(
+                stderr,
+                "{} {}",
+                self.warning_style
+                    .apply_to("Same header for different files:"),
+                self.metadata_style.apply_to(format!(
+                    "{} (shown as {})",
+                    header,
+                    headers.join(", ")
+                ))
+            )?;
+        }
+
         writeln!(stderr, "{}", self.metadata_style.apply_to("=".repeat(40)))?;
         writeln!(
             stderr,
@@ -187,21 +613,17 @@ impl DisplayManager {
             )?;
         } else {
             for (i, context) in contexts.iter().enumerate() {
-                let (icon, label) = if let Some(d) = depth {
-                    (
+                let (icon, label, metric) = match (context.mode(), context.depth) {
+                    (ContextMode::Skeleton, Some(d)) => (
                         "🧬",
                         format!("{} (skeleton only; depth={})", context.display_path, d),
-                    )
-                } else {
-                    ("📄", context.display_path.clone())
-                };
-
-                let (metric_value, metric_unit) = if depth.is_some() {
-                    // Skeleton mode: count characters from the context's content.
-                    (context.content.chars().count(), "characters")
-                } else {
-                    // Full file mode: count lines from the context's content.
-                    (context.content.lines().count(), "lines")
+                        format!("{} characters", group_digits(context.char_count)),
+                    ),
+                    _ => (
+                        "📄",
+                        context.display_path.clone(),
+                        format!("{} lines", group_digits(context.line_count)),
+                    ),
                 };
 
                 writeln!(
@@ -213,26 +635,800 @@ impl DisplayManager {
 
                 writeln!(
                     stderr,
-                    "    {} {} {}", // e.g., "📄 125 lines" or "🧬 850 characters"
+                    "    {} {}", // e.g., "📄 125 lines" or "🧬 850 characters"
                     self.metadata_style.apply_to(icon),
-                    self.metadata_style.apply_to(metric_value.to_string()),
-                    self.metadata_style.apply_to(metric_unit)
+                    self.metadata_style.apply_to(metric)
                 )?;
+                if self.show_permissions
+                    && let Some(permissions) = context.permissions
+                {
+                    writeln!(
+                        stderr,
+                        "    {} {}{}",
+                        self.metadata_style.apply_to("🔐"),
+                        self.metadata_style.apply_to(permissions.symbolic()),
+                        self.metadata_style.apply_to(if permissions.executable {
+                            " (executable)"
+                        } else {
+                            ""
+                        })
+                    )?;
+                }
+                if self.show_languages {
+                    writeln!(
+                        stderr,
+                        "    {} {}",
+                        self.metadata_style.apply_to("🔤"),
+                        self.metadata_style
+                            .apply_to(language_annotation(context.language.as_deref()))
+                    )?;
+                }
+                if context.truncated_lines > 0 {
+                    writeln!(
+                        stderr,
+                        "    {} {}",
+                        self.warning_style.apply_to("⏎"),
+                        self.warning_style.apply_to(format!(
+                            "{} long line{} truncated (--max-line-length)",
+                            group_digits(context.truncated_lines),
+                            if context.truncated_lines == 1 {
+                                ""
+                            } else {
+                                "s"
+                            }
+                        ))
+                    )?;
+                }
+                if let Some(line) = context.syntax_error_line {
+                    writeln!(
+                        stderr,
+                        "    {} {}",
+                        self.warning_style.apply_to("⚠️"),
+                        self.warning_style.apply_to(format!(
+                            "File has syntax errors (first at line {}); skeleton may be incomplete",
+                            line
+                        ))
+                    )?;
+                }
+            }
+        }
+        if !omitted.is_empty() {
+            writeln!(stderr)?;
+            self.write_omitted_skeletons(stderr, omitted)?;
+        }
+        if !self.removed_files.is_empty() {
+            writeln!(stderr)?;
+            writeln!(
+                stderr,
+                "{} {}",
+                self.warning_style.apply_to("⚠️"),
+                self.warning_style.apply_to(format!(
+                    "Skipped {} removed during run:",
+                    match self.removed_files.len() {
+                        1 => "1 file".to_string(),
+                        n => format!("{} files", n),
+                    }
+                ))
+            )?;
+            for path in &self.removed_files {
+                writeln!(stderr, "  • {}", self.filename_style.apply_to(path))?;
+            }
+        }
+        if !self.empty_files.is_empty() {
+            writeln!(stderr)?;
+            writeln!(
+                stderr,
+                "{} {}",
+                self.metadata_style.apply_to("∅"),
+                self.metadata_style.apply_to(format!(
+                    "Skipped {} (pass --include-empty-files to keep them):",
+                    match self.empty_files.len() {
+                        1 => "1 empty file".to_string(),
+                        n => format!("{} empty files", n),
+                    }
+                ))
+            )?;
+            for path in &self.empty_files {
+                writeln!(stderr, "  • {}", self.filename_style.apply_to(path))?;
             }
         }
+        if self.over_line_budget.is_some() {
+            writeln!(stderr)?;
+            self.write_line_budget_omissions(stderr)?;
+        }
         writeln!(stderr, "\n{}", self.metadata_style.apply_to("=".repeat(40)))?;
         Ok(())
     }
 
-    // --- Private Error Reporters ---
+    /// Prints the files left out because no skeleton could be extracted
+    /// (`--no-skeleton-fallback`).
+    pub fn print_omitted_skeletons(&self, omitted: &[(String, String)]) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        self.write_omitted_skeletons(&mut stderr, omitted)
+    }
 
-    fn report_path_does_not_exist_case(
+    /// Writes the files left out for lack of a skeleton to any writer.
+    pub fn write_omitted_skeletons(
         &self,
-        stderr: &mut Term,
-        case: &InputResolution,
+        stderr: &mut impl Write,
+        omitted: &[(String, String)],
     ) -> io::Result<()> {
-        if let InputResolution::PathDoesNotExist {
-            input_string,
+        writeln!(
+            stderr,
+            "{} {}",
+            self.warning_style.apply_to("⚠️"),
+            self.warning_style.apply_to(format!(
+                "Omitted {} without a skeleton (--no-skeleton-fallback):",
+                match omitted.len() {
+                    1 => "1 file".to_string(),
+                    n => format!("{} files", n),
+                }
+            ))
+        )?;
+        for (path, error) in omitted {
+            writeln!(
+                stderr,
+                "  • {} {}",
+                self.filename_style.apply_to(path),
+                self.metadata_style.apply_to(format!("({})", error))
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Prints how this run's files differ from those in the manifest at
+    /// `since` (`--diff-last`).
+    pub fn print_file_set_diff(&self, since: &Path, diff: &FileSetDiff) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        self.write_file_set_diff(&mut stderr, since, diff)
+    }
+
+    /// Writes the `--diff-last` report to any writer.
+    pub fn write_file_set_diff(
+        &self,
+        stderr: &mut impl Write,
+        since: &Path,
+        diff: &FileSetDiff,
+    ) -> io::Result<()> {
+        if diff.is_empty() {
+            return writeln!(
+                stderr,
+                "{} {}",
+                self.metadata_style.apply_to("="),
+                self.metadata_style.apply_to(format!(
+                    "No changes since {} ({} file{} unchanged).",
+                    since.display(),
+                    diff.unchanged,
+                    if diff.unchanged == 1 { "" } else { "s" }
+                ))
+            );
+        }
+        writeln!(
+            stderr,
+            "{} {} added, {} removed, {} modified, {} unchanged",
+            self.warning_style
+                .apply_to(format!("Changes since {}:", since.display())),
+            diff.added.len(),
+            diff.removed.len(),
+            diff.modified.len(),
+            diff.unchanged
+        )?;
+        for (marker, style, paths) in [
+            ("+", &self.success_style, &diff.added),
+            ("-", &self.error_style, &diff.removed),
+            ("~", &self.warning_style, &diff.modified),
+        ] {
+            for path in paths {
+                writeln!(
+                    stderr,
+                    "  {} {}",
+                    style.apply_to(marker),
+                    self.filename_style.apply_to(path)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows what's about to be copied and what it would replace, and asks
+    /// whether to go ahead (`--confirm-clipboard`). Only "y" or "yes" confirm.
+    pub fn confirm_clipboard_overwrite(
+        &self,
+        file_count: usize,
+        output: &str,
+        current: &ClipboardContents,
+        editable: bool,
+    ) -> io::Result<Confirmation> {
+        let mut stderr = self.term.clone();
+        self.write_clipboard_confirmation_prompt(
+            &mut stderr,
+            file_count,
+            output,
+            current,
+            editable,
+        )?;
+        let answer = self.term.read_line()?;
+        Ok(match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => Confirmation::Overwrite,
+            "e" | "edit" if editable => Confirmation::EditSelection,
+            _ => Confirmation::Cancel,
+        })
+    }
+
+    /// Writes the `--confirm-clipboard` question to any writer. With
+    /// `editable`, it also offers to leave some of the files out.
+    pub fn write_clipboard_confirmation_prompt(
+        &self,
+        stderr: &mut impl Write,
+        file_count: usize,
+        output: &str,
+        current: &ClipboardContents,
+        editable: bool,
+    ) -> io::Result<()> {
+        let current = match current {
+            ClipboardContents::Empty => "an empty clipboard".to_string(),
+            ClipboardContents::Text { chars, lines } => format!(
+                "text on the clipboard ({} lines, {} characters)",
+                group_digits(*lines),
+                group_digits(*chars)
+            ),
+            ClipboardContents::Image { width, height } => {
+                format!("an image on the clipboard ({}×{})", width, height)
+            }
+            ClipboardContents::Unreadable => {
+                "clipboard contents that aren't plain text (e.g. rich text or files)".to_string()
+            }
+        };
+        writeln!(
+            stderr,
+            "About to copy {} ({} lines, {} characters), replacing {}.",
+            match file_count {
+                1 => "1 file".to_string(),
+                n => format!("{} files", n),
+            },
+            group_digits(output.lines().count()),
+            group_digits(output.chars().count()),
+            current
+        )?;
+        write!(
+            stderr,
+            "{} ",
+            self.warning_style.apply_to(if editable {
+                "Overwrite the clipboard? [y/N, or e to edit the selection]"
+            } else {
+                "Overwrite the clipboard? [y/N]"
+            })
+        )?;
+        stderr.flush()
+    }
+
+    /// Lists `contexts` by number and reads which of them to leave out.
+    pub fn ask_files_to_exclude(&self, contexts: &[FileContext]) -> io::Result<String> {
+        let mut stderr = self.term.clone();
+        self.write_exclusion_menu(&mut stderr, contexts)?;
+        self.term.read_line()
+    }
+
+    /// Writes the numbered file list of the selection editor, numbered as in
+    /// the summary preview, and its question.
+    pub fn write_exclusion_menu(
+        &self,
+        stderr: &mut impl Write,
+        contexts: &[FileContext],
+    ) -> io::Result<()> {
+        writeln!(stderr)?;
+        for (i, context) in contexts.iter().enumerate() {
+            writeln!(
+                stderr,
+                "{:>4}. {} {}",
+                self.metadata_style.apply_to(i + 1),
+                self.filename_style.apply_to(&context.display_path),
+                self.metadata_style.apply_to(format!(
+                    "({} line{})",
+                    group_digits(context.line_count),
+                    if context.line_count == 1 { "" } else { "s" }
+                ))
+            )?;
+        }
+        write!(
+            stderr,
+            "{} ",
+            self.warning_style
+                .apply_to("Numbers of the files to leave out (e.g. 2 5-7):")
+        )?;
+        stderr.flush()
+    }
+
+    /// Prints the `--exclude` options that leave the same files out next time.
+    pub fn print_exclude_hint(&self, paths: &[String]) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        self.write_exclude_hint(&mut stderr, paths)
+    }
+
+    /// Writes the `--exclude` options for `paths`, quoted for the shell.
+    pub fn write_exclude_hint(&self, stderr: &mut impl Write, paths: &[String]) -> io::Result<()> {
+        let options: Vec<String> = paths
+            .iter()
+            .map(|path| format!("--exclude {}", shell_quote(path)))
+            .collect();
+        writeln!(
+            stderr,
+            "{} {}",
+            self.metadata_style
+                .apply_to("To leave the same files out next time, add:"),
+            options.join(" ")
+        )
+    }
+
+    /// Warns that one file makes up most of the output, which tends to drown out
+    /// the other files in a prompt. Its size is given in characters when the
+    /// shares were measured in characters, and in lines otherwise.
+    pub fn print_balance_warning(
+        &self,
+        context: &FileContext,
+        share: f64,
+        in_characters: bool,
+    ) -> io::Result<()> {
+        self.write_balance_warning(&mut self.term.clone(), context, share, in_characters)
+    }
+
+    /// Writes the balance warning to any writer.
+    pub fn write_balance_warning(
+        &self,
+        stderr: &mut impl Write,
+        context: &FileContext,
+        share: f64,
+        in_characters: bool,
+    ) -> io::Result<()> {
+        let (size, unit) = if in_characters {
+            (context.char_count, "characters")
+        } else {
+            (context.line_count, "lines")
+        };
+        writeln!(
+            stderr,
+            "{} {} {}",
+            self.warning_style.apply_to("⚠️"),
+            self.filename_style.apply_to(&context.display_path),
+            self.warning_style.apply_to(format!(
+                "makes up {:.0}% of the context ({} {}).",
+                share * 100.0,
+                size,
+                unit
+            ))
+        )?;
+        let suggestion = if context.depth.is_some() {
+            "Consider leaving it out or narrowing your inputs."
+        } else {
+            "Consider leaving it out, or using --depth to include only its skeleton."
+        };
+        writeln!(
+            stderr,
+            "    {}",
+            self.metadata_style.apply_to(format!(
+                "{} (Silence with --no-balance-warning.)",
+                suggestion
+            ))
+        )
+    }
+
+    /// Confirms that `--output` wrote the context to `path`.
+    pub fn print_output_written(&self, path: &Path, output: &str, bytes: u64) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        self.write_output_written(&mut stderr, path, output, bytes)
+    }
+
+    /// Writes the `--output` confirmation: the output's size, and `bytes`
+    /// (its size on disk) when it was compressed.
+    pub fn write_output_written(
+        &self,
+        stderr: &mut impl Write,
+        path: &Path,
+        output: &str,
+        bytes: u64,
+    ) -> io::Result<()> {
+        let mut details = format!(
+            "{} lines, {} characters",
+            group_digits(output.lines().count()),
+            group_digits(output.chars().count())
+        );
+        if crate::output::is_gzip_path(path) {
+            details.push_str(&format!(
+                "; gzip-compressed to {} bytes",
+                group_digits(bytes as usize)
+            ));
+        }
+        writeln!(
+            stderr,
+            "{} Context written to {}: {}",
+            self.success_style.apply_to("✅"),
+            self.filename_style.apply_to(path.display()),
+            self.metadata_style.apply_to(details)
+        )
+    }
+
+    /// Reports the outcome of copying `count` paths for `--copy-paths-only`.
+    pub fn print_paths_copied(
+        &self,
+        count: usize,
+        clipboard_result: &Result<(), arboard::Error>,
+    ) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        self.write_paths_copied(&mut stderr, count, clipboard_result)
+    }
+
+    /// Writes the `--copy-paths-only` confirmation, or the clipboard error
+    /// and a note that the paths go to stdout instead.
+    pub fn write_paths_copied(
+        &self,
+        stderr: &mut impl Write,
+        count: usize,
+        clipboard_result: &Result<(), arboard::Error>,
+    ) -> io::Result<()> {
+        match clipboard_result {
+            Ok(()) => writeln!(
+                stderr,
+                "{} Copied {} to clipboard.",
+                self.success_style.apply_to("✅"),
+                self.metadata_style.apply_to(format!(
+                    "{} file path{}",
+                    group_digits(count),
+                    if count == 1 { "" } else { "s" }
+                ))
+            ),
+            Err(err) => {
+                writeln!(
+                    stderr,
+                    "{} Failed to copy to clipboard.",
+                    self.warning_style.apply_to("⚠️")
+                )?;
+                writeln!(
+                    stderr,
+                    "    {}: {}",
+                    self.warning_style.apply_to("Error"),
+                    self.warning_style.apply_to(err.to_string())
+                )?;
+                writeln!(
+                    stderr,
+                    "    {}",
+                    self.metadata_style
+                        .apply_to("The paths will be printed to stdout as a fallback.")
+                )
+            }
+        }
+    }
+
+    /// Confirms that a delayed clipboard clear has been scheduled.
+    pub fn print_clear_scheduled(&self, delay: Duration) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        writeln!(
+            stderr,
+            "{} {}",
+            self.metadata_style.apply_to("🧹"),
+            self.metadata_style.apply_to(format!(
+                "Clipboard will be cleared in {:?} if unchanged.",
+                delay
+            ))
+        )
+    }
+
+    /// Warns that the background clear process could not be started.
+    pub fn print_clear_schedule_failed(&self, err: &io::Error) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        writeln!(
+            stderr,
+            "{} Could not schedule clipboard clear: {}",
+            self.warning_style.apply_to("⚠️"),
+            self.warning_style.apply_to(err.to_string())
+        )
+    }
+
+    /// Confirms that the clipboard was cleared by `--clear`.
+    pub fn print_clipboard_cleared(&self) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        writeln!(
+            stderr,
+            "{} Clipboard cleared.",
+            self.success_style.apply_to("✅")
+        )
+    }
+
+    /// Reports the outcome of `--clipboard-check`.
+    pub fn print_clipboard_check(
+        &self,
+        backend: &str,
+        result: &Result<(), String>,
+    ) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        writeln!(
+            stderr,
+            "{} {}",
+            self.metadata_style.apply_to("Clipboard backend:"),
+            self.filename_style.apply_to(backend)
+        )?;
+        match result {
+            Ok(()) => writeln!(
+                stderr,
+                "{} Clipboard is working (wrote and read back a test string).",
+                self.success_style.apply_to("✅")
+            ),
+            Err(err) => {
+                writeln!(
+                    stderr,
+                    "{} Clipboard check failed.",
+                    self.warning_style.apply_to("⚠️")
+                )?;
+                writeln!(
+                    stderr,
+                    "    {}: {}",
+                    self.warning_style.apply_to("Error"),
+                    self.warning_style.apply_to(err)
+                )?;
+                writeln!(
+                    stderr,
+                    "    {}",
+                    self.metadata_style.apply_to(
+                        "ctx-pick will print context to stdout instead; use --to-stdout to pipe it."
+                    )
+                )
+            }
+        }
+    }
+
+    /// Prints a phase-by-phase explanation of how `input` was resolved (`--why`).
+    pub fn print_resolution_trace(
+        &self,
+        input: &str,
+        trace: &ResolutionTrace,
+        resolution: &InputResolution,
+    ) -> io::Result<()> {
+        self.write_resolution_trace(&mut self.term.clone(), input, trace, resolution)
+    }
+
+    /// Writes the `--why` trace to any writer.
+    pub fn write_resolution_trace(
+        &self,
+        stderr: &mut impl Write,
+        input: &str,
+        trace: &ResolutionTrace,
+        resolution: &InputResolution,
+    ) -> io::Result<()> {
+        const MAX_TO_SHOW: usize = 10;
+
+        writeln!(
+            stderr,
+            "{} {}",
+            self.filename_style.apply_to("Resolution trace for"),
+            self.warning_style.apply_to(format!("'{}'", input))
+        )?;
+        writeln!(stderr, "{}", self.metadata_style.apply_to("-".repeat(50)))?;
+
+        if let Some(count) = trace.symbol_definition_count {
+            writeln!(
+                stderr,
+                "{} {} definition{} found",
+                self.metadata_style.apply_to("Symbol search:"),
+                count,
+                if count == 1 { "" } else { "s" }
+            )?;
+        } else {
+            // Phase 1: direct path.
+            let direct_summary = match &trace.direct_match {
+                Some(DirectMatch::File) => "matched an existing file".to_string(),
+                Some(DirectMatch::Directory { file_count }) => format!(
+                    "matched a directory containing {} file{}",
+                    file_count,
+                    if *file_count == 1 { "" } else { "s" }
+                ),
+                None => "no such file or directory".to_string(),
+            };
+            writeln!(
+                stderr,
+                "{} {} {}",
+                self.metadata_style.apply_to("1. Direct path:"),
+                direct_summary,
+                self.metadata_style
+                    .apply_to(format!("(checked: {:?})", trace.direct_path_checked))
+            )?;
+
+            // Phase 2: glob.
+            let glob_summary = if trace.direct_match.is_some() {
+                "skipped (direct path matched)".to_string()
+            } else if !trace.treated_as_glob {
+                "not a glob pattern".to_string()
+            } else {
+                match trace.glob_match_count {
+                    Some(count) => format!(
+                        "treated as a glob; matched {} file{}",
+                        count,
+                        if count == 1 { "" } else { "s" }
+                    ),
+                    None => "treated as a glob, but the pattern is invalid".to_string(),
+                }
+            };
+            writeln!(
+                stderr,
+                "{} {}",
+                self.metadata_style.apply_to("2. Glob:"),
+                glob_summary
+            )?;
+
+            // Phase 3: fuzzy search.
+            if trace.fuzzy_searched {
+                let count = trace.fuzzy_candidates.len();
+                writeln!(
+                    stderr,
+                    "{} {} {} the input",
+                    self.metadata_style.apply_to("3. Fuzzy search:"),
+                    count,
+                    if count == 1 {
+                        "file contains"
+                    } else {
+                        "files contain"
+                    }
+                )?;
+                for candidate in trace.fuzzy_candidates.iter().take(MAX_TO_SHOW) {
+                    writeln!(
+                        stderr,
+                        "    {} {}",
+                        self.metadata_style.apply_to("→"),
+                        self.highlight_fuzzy_match(candidate)
+                    )?;
+                }
+                if count > MAX_TO_SHOW {
+                    writeln!(
+                        stderr,
+                        "    {} ... and {} more",
+                        self.metadata_style.apply_to("→"),
+                        self.metadata_style
+                            .apply_to((count - MAX_TO_SHOW).to_string())
+                    )?;
+                }
+            } else {
+                let reason = if trace.direct_match.is_some() {
+                    "skipped (direct path matched)"
+                } else {
+                    "skipped (handled as a glob)"
+                };
+                writeln!(
+                    stderr,
+                    "{} {}",
+                    self.metadata_style.apply_to("3. Fuzzy search:"),
+                    reason
+                )?;
+            }
+        }
+
+        // Outcome.
+        writeln!(stderr)?;
+        match resolution {
+            InputResolution::Success(files) => {
+                writeln!(
+                    stderr,
+                    "{} would include {} file{}:",
+                    self.success_style.apply_to("Result:"),
+                    files.len(),
+                    if files.len() == 1 { "" } else { "s" }
+                )?;
+                for file in files.iter().take(MAX_TO_SHOW) {
+                    self.report_successful_file_case(stderr, file)?;
+                }
+                if files.len() > MAX_TO_SHOW {
+                    writeln!(
+                        stderr,
+                        "  {} ... and {} more",
+                        self.metadata_style.apply_to("✓"),
+                        files.len() - MAX_TO_SHOW
+                    )?;
+                }
+            }
+            InputResolution::Ambiguous { candidates, .. } => writeln!(
+                stderr,
+                "{} ambiguous; {} files match, so none is selected. Use a longer path or a glob.",
+                self.ambiguous_style.apply_to("Result:"),
+                candidates.len()
+            )?,
+            InputResolution::InvalidGlobPattern { error, .. } => writeln!(
+                stderr,
+                "{} invalid glob pattern ({})",
+                self.error_style.apply_to("Result:"),
+                error
+            )?,
+            InputResolution::InvalidRegex { error, .. } => writeln!(
+                stderr,
+                "{} invalid regular expression ({})",
+                self.error_style.apply_to("Result:"),
+                error
+            )?,
+            InputResolution::TooManyFiles {
+                file_count, limit, ..
+            } => writeln!(
+                stderr,
+                "{} too many files; matched {} but the per-input limit is {}",
+                self.error_style.apply_to("Result:"),
+                file_count,
+                limit
+            )?,
+            InputResolution::Excluded {
+                file_count, rule, ..
+            } => writeln!(
+                stderr,
+                "{} excluded; all {} matched file{} are excluded by {}",
+                self.warning_style.apply_to("Result:"),
+                file_count,
+                if *file_count == 1 { "" } else { "s" },
+                rule
+            )?,
+            InputResolution::OutsideWorkingDir { file_count, .. } => writeln!(
+                stderr,
+                "{} refused; {} matched file{} outside the working directory (pass --allow-outside)",
+                self.error_style.apply_to("Result:"),
+                file_count,
+                if *file_count == 1 { " is" } else { "s are" }
+            )?,
+            InputResolution::SearchStopped {
+                entries, reason, ..
+            } => writeln!(
+                stderr,
+                "{} search stopped after {} entries; {}",
+                self.error_style.apply_to("Result:"),
+                group_digits(*entries),
+                describe_search_stop(*reason)
+            )?,
+            InputResolution::NotFound { .. } => writeln!(
+                stderr,
+                "{} not found",
+                self.warning_style.apply_to("Result:")
+            )?,
+            InputResolution::PathDoesNotExist { path_tried, .. } => writeln!(
+                stderr,
+                "{} path does not exist {}",
+                self.error_style.apply_to("Result:"),
+                self.metadata_style
+                    .apply_to(format!("(checked: {:?})", path_tried))
+            )?,
+        }
+        Ok(())
+    }
+
+    /// Renders a fuzzy candidate's path with the matched substring highlighted.
+    fn highlight_fuzzy_match(&self, candidate: &FuzzyCandidate) -> String {
+        self.highlight_match(
+            &candidate.display_path,
+            Some(candidate.match_start..candidate.match_start + candidate.match_len),
+        )
+    }
+
+    /// Renders `path` with the byte range `matched` (if any) highlighted.
+    fn highlight_match(&self, path: &Path, matched: Option<Range<usize>>) -> String {
+        let path = path.to_string_lossy();
+        let Some(matched) = matched else {
+            return self.filename_style.apply_to(path).to_string();
+        };
+        match (
+            path.get(..matched.start),
+            path.get(matched.clone()),
+            path.get(matched.end..),
+        ) {
+            (Some(before), Some(matched), Some(after)) => format!(
+                "{}{}{}",
+                self.filename_style.apply_to(before),
+                self.ambiguous_style.apply_to(matched).underlined(),
+                self.filename_style.apply_to(after)
+            ),
+            _ => self.filename_style.apply_to(path).to_string(),
+        }
+    }
+
+    // --- Private Error Reporters ---
+
+    fn report_path_does_not_exist_case(
+        &self,
+        stderr: &mut impl Write,
+        case: &InputResolution,
+    ) -> io::Result<()> {
+        if let InputResolution::PathDoesNotExist {
+            input_string,
             path_tried,
         } = case
         {
@@ -251,12 +1447,16 @@ impl DisplayManager {
 
     fn report_invalid_glob_case(
         &self,
-        stderr: &mut Term,
+        stderr: &mut impl Write,
         case: &InputResolution,
     ) -> io::Result<()> {
         if let InputResolution::InvalidGlobPattern {
             input_string,
             error,
+        }
+        | InputResolution::InvalidRegex {
+            input_string,
+            error,
         } = case
         {
             writeln!(
@@ -271,7 +1471,445 @@ impl DisplayManager {
         Ok(())
     }
 
-    fn report_not_found_case(&self, stderr: &mut Term, case: &InputResolution) -> io::Result<()> {
+    fn report_excluded_case(
+        &self,
+        stderr: &mut impl Write,
+        case: &InputResolution,
+    ) -> io::Result<()> {
+        if let InputResolution::Excluded {
+            input_string,
+            file_count,
+            rule,
+        } = case
+        {
+            writeln!(
+                stderr,
+                "  {} {} {}",
+                self.metadata_style.apply_to("•"),
+                self.warning_style
+                    .apply_to(format!("Input: '{}'", input_string)),
+                self.metadata_style.apply_to(if *file_count == 1 {
+                    format!("matched 1 file, excluded by {}", rule)
+                } else {
+                    format!("matched {} files, all excluded by {}", file_count, rule)
+                })
+            )?;
+        }
+        Ok(())
+    }
+
+    fn report_too_many_files_case(
+        &self,
+        stderr: &mut impl Write,
+        case: &InputResolution,
+    ) -> io::Result<()> {
+        if let InputResolution::TooManyFiles {
+            input_string,
+            file_count,
+            limit,
+            sample_paths,
+        } = case
+        {
+            writeln!(
+                stderr,
+                "  {} {} {}",
+                self.metadata_style.apply_to("•"),
+                self.error_style
+                    .apply_to(format!("Input: '{}'", input_string)),
+                self.metadata_style
+                    .apply_to(format!("({} files; limit is {})", file_count, limit))
+            )?;
+            for path in sample_paths {
+                writeln!(
+                    stderr,
+                    "    {} {}",
+                    self.metadata_style.apply_to("→"),
+                    self.filename_style.apply_to(format!("{:?}", path))
+                )?;
+            }
+            if *file_count > sample_paths.len() {
+                writeln!(
+                    stderr,
+                    "    {} ... and {} more",
+                    self.metadata_style.apply_to("→"),
+                    self.metadata_style
+                        .apply_to((file_count - sample_paths.len()).to_string())
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn report_search_stopped_case(
+        &self,
+        stderr: &mut impl Write,
+        case: &InputResolution,
+    ) -> io::Result<()> {
+        if let InputResolution::SearchStopped {
+            input_string,
+            entries,
+            reason,
+        } = case
+        {
+            writeln!(
+                stderr,
+                "  {} {} {}",
+                self.metadata_style.apply_to("•"),
+                self.error_style
+                    .apply_to(format!("Input: '{}'", input_string)),
+                self.metadata_style.apply_to(format!(
+                    "(stopped after {} entries; {})",
+                    group_digits(*entries),
+                    describe_search_stop(*reason)
+                ))
+            )?;
+        }
+        Ok(())
+    }
+
+    fn report_outside_working_dir_case(
+        &self,
+        stderr: &mut impl Write,
+        case: &InputResolution,
+    ) -> io::Result<()> {
+        if let InputResolution::OutsideWorkingDir {
+            input_string,
+            file_count,
+            sample_paths,
+        } = case
+        {
+            writeln!(
+                stderr,
+                "  {} {} {}",
+                self.metadata_style.apply_to("•"),
+                self.error_style
+                    .apply_to(format!("Input: '{}'", input_string)),
+                self.metadata_style.apply_to(format!(
+                    "({} file{} outside)",
+                    file_count,
+                    if *file_count == 1 { "" } else { "s" }
+                ))
+            )?;
+            for path in sample_paths {
+                writeln!(
+                    stderr,
+                    "    {} {}",
+                    self.metadata_style.apply_to("→"),
+                    self.filename_style.apply_to(format!("{:?}", path))
+                )?;
+            }
+            if *file_count > sample_paths.len() {
+                writeln!(
+                    stderr,
+                    "    {} ... and {} more",
+                    self.metadata_style.apply_to("→"),
+                    self.metadata_style
+                        .apply_to((file_count - sample_paths.len()).to_string())
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Notes that the default excludes were turned off because the working
+    /// directory itself lies inside a directory they would exclude.
+    pub fn print_default_excludes_disabled(
+        &self,
+        directory: &str,
+        pattern: &str,
+    ) -> io::Result<()> {
+        let mut stderr = self.term.clone();
+        writeln!(
+            stderr,
+            "{} {}",
+            self.warning_style.apply_to("⚠️"),
+            self.warning_style.apply_to(format!(
+                "The working directory is inside '{}', which matches the default exclude '{}'; default excludes are off for this run.",
+                directory, pattern
+            ))
+        )
+    }
+
+    /// Reports that the deduplicated file set exceeds `--max-files`.
+    pub fn write_too_many_files_total(
+        &self,
+        stderr: &mut impl Write,
+        file_count: usize,
+        limit: usize,
+    ) -> io::Result<()> {
+        writeln!(
+            stderr,
+            "{} {}",
+            self.error_style.apply_to("Too many files:"),
+            format_args!(
+                "inputs resolved to {} files, but --max-files is {}.",
+                file_count, limit
+            )
+        )?;
+        writeln!(
+            stderr,
+            "{}",
+            self.metadata_style
+                .apply_to("Narrow your inputs or raise --max-files.")
+        )
+    }
+
+    /// Writes the unreadable-file report to any writer.
+    pub fn write_read_errors(
+        &self,
+        stderr: &mut impl Write,
+        failures: &[(String, String)],
+        skipped: bool,
+    ) -> io::Result<()> {
+        let count = match failures.len() {
+            1 => "1 file".to_string(),
+            n => format!("{} files", n),
+        };
+        if skipped {
+            writeln!(
+                stderr,
+                "{} {}",
+                self.warning_style.apply_to("⚠️"),
+                self.warning_style.apply_to(format!(
+                    "Skipped {} that could not be read or processed:",
+                    count
+                ))
+            )?;
+        } else {
+            writeln!(
+                stderr,
+                "{}",
+                self.error_style
+                    .apply_to(format!("Could not read or process {}:", count))
+            )?;
+        }
+        for (path, error) in failures {
+            writeln!(
+                stderr,
+                "  • {} {}",
+                self.filename_style.apply_to(path),
+                self.metadata_style.apply_to(format!("({})", error))
+            )?;
+        }
+        writeln!(stderr)
+    }
+
+    /// Prints the `--doctor` report to stdout, since it is the run's output.
+    pub fn print_doctor_report(&self, checks: &[Check]) -> io::Result<()> {
+        self.write_doctor_report(&mut Term::stdout(), checks)
+    }
+
+    /// Writes the `--doctor` report to any writer: one line per check (details
+    /// spanning several lines are indented under the first), then a tally.
+    pub fn write_doctor_report(&self, out: &mut impl Write, checks: &[Check]) -> io::Result<()> {
+        let name_width = checks
+            .iter()
+            .map(|check| check.name.len())
+            .max()
+            .unwrap_or(0);
+        for check in checks {
+            let marker = match check.status {
+                CheckStatus::Pass => self.success_style.apply_to("PASS"),
+                CheckStatus::Warn => self.warning_style.apply_to("WARN"),
+                CheckStatus::Fail => self.error_style.apply_to("FAIL"),
+            };
+            let indent = " ".repeat("[PASS] ".len() + name_width + 2);
+            let detail = check.detail.replace('\n', &format!("\n{}", indent));
+            writeln!(
+                out,
+                "[{}] {:<width$}  {}",
+                marker,
+                check.name,
+                detail,
+                width = name_width
+            )?;
+        }
+        let count = |status| checks.iter().filter(|check| check.status == status).count();
+        writeln!(
+            out,
+            "\n{} passed, {} warning{}, {} failed",
+            count(CheckStatus::Pass),
+            count(CheckStatus::Warn),
+            if count(CheckStatus::Warn) == 1 {
+                ""
+            } else {
+                "s"
+            },
+            count(CheckStatus::Fail)
+        )
+    }
+
+    /// Prints the `--stats` report to stdout, since it is the run's output.
+    pub fn print_stats(&self, stats: &Stats) -> io::Result<()> {
+        self.write_stats(&mut Term::stdout(), stats)
+    }
+
+    /// Writes the `--stats` report: totals per language, the largest files,
+    /// and, with a depth, how much skeletons would shrink each language.
+    pub fn write_stats(&self, out: &mut impl Write, stats: &Stats) -> io::Result<()> {
+        writeln!(
+            out,
+            "{} {} files ({} lines, ~{} tokens)",
+            self.filename_style.apply_to("Stats for"),
+            group_digits(stats.total.files),
+            group_digits(stats.total.lines),
+            group_digits(stats.total.estimated_tokens)
+        )?;
+
+        writeln!(out, "\n{}", self.filename_style.apply_to("By language:"))?;
+        let width = stats
+            .by_language
+            .iter()
+            .map(|language| language.language.len())
+            .max()
+            .unwrap_or(0);
+        for language in &stats.by_language {
+            writeln!(
+                out,
+                "  {:<width$}  {:>6} file{:<1}  {:>9} lines  {:>10} tokens",
+                language.language,
+                group_digits(language.files),
+                if language.files == 1 { "" } else { "s" },
+                group_digits(language.lines),
+                format!("~{}", group_digits(language.estimated_tokens)),
+                width = width
+            )?;
+        }
+
+        writeln!(out, "\n{}", self.filename_style.apply_to("Largest files:"))?;
+        for (i, file) in stats.largest_files.iter().enumerate() {
+            writeln!(
+                out,
+                "  {:>2}. {} {}",
+                i + 1,
+                file.path,
+                self.metadata_style.apply_to(format!(
+                    "({} lines, ~{} tokens)",
+                    group_digits(file.lines),
+                    group_digits(file.estimated_tokens)
+                ))
+            )?;
+        }
+
+        match &stats.depth_estimates {
+            None => writeln!(
+                out,
+                "\n{}",
+                self.metadata_style
+                    .apply_to("Pass --depth N to estimate how much skeletons would save.")
+            )?,
+            Some((depth, estimates)) => {
+                writeln!(
+                    out,
+                    "\n{}",
+                    self.filename_style
+                        .apply_to(format!("At --depth {} (estimated from samples):", depth))
+                )?;
+                for estimate in estimates {
+                    writeln!(
+                        out,
+                        "  {:<width$}  skeletons are {:>3.0}% of full size  {:>10} tokens {}",
+                        estimate.language,
+                        estimate.ratio * 100.0,
+                        format!("~{}", group_digits(estimate.estimated_tokens)),
+                        self.metadata_style.apply_to(format!(
+                            "({} file{} sampled)",
+                            estimate.sampled_files,
+                            if estimate.sampled_files == 1 { "" } else { "s" }
+                        )),
+                        width = width
+                    )?;
+                }
+                // Languages without skeleton support stay at full size.
+                let estimated_total: usize = stats
+                    .by_language
+                    .iter()
+                    .map(|language| {
+                        estimates
+                            .iter()
+                            .find(|estimate| estimate.language == language.language)
+                            .map_or(language.estimated_tokens, |estimate| {
+                                estimate.estimated_tokens
+                            })
+                    })
+                    .sum();
+                writeln!(
+                    out,
+                    "  Estimated total: ~{} tokens (files without skeleton support count in full)",
+                    group_digits(estimated_total)
+                )?;
+            }
+        }
+
+        for (path, error) in &stats.unreadable {
+            writeln!(
+                out,
+                "{} Could not read {}: {}",
+                self.warning_style.apply_to("⚠️"),
+                path,
+                error
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Lists each resolved file with its effective depth and where that came
+    /// from, or the `.ctx-pick.toml` that excluded it (for `--verbose`).
+    pub fn write_file_settings(
+        &self,
+        stderr: &mut impl Write,
+        files: &[ResolvedFile],
+        settings: &[FileSettings],
+        working_dir: &Path,
+    ) -> io::Result<()> {
+        let relative = |path: &Path| {
+            pathdiff::diff_paths(path, working_dir)
+                .unwrap_or_else(|| path.to_path_buf())
+                .display()
+                .to_string()
+        };
+
+        writeln!(
+            stderr,
+            "{}",
+            self.filename_style.apply_to("Effective settings:")
+        )?;
+        for (file, file_settings) in files.iter().zip(settings) {
+            let summary = if let Some(config_path) = &file_settings.excluded_by {
+                self.warning_style
+                    .apply_to(format!("excluded (by {})", relative(config_path)))
+                    .to_string()
+            } else {
+                match (&file_settings.depth, &file_settings.depth_source) {
+                    (Some(depth), Some(DepthSource::Cli)) => format!("depth {} (--depth)", depth),
+                    (Some(depth), Some(DepthSource::DirConfig(config_path))) => {
+                        format!("depth {} ({})", depth, relative(config_path))
+                    }
+                    (Some(depth), Some(DepthSource::InputFile(location))) => {
+                        format!("depth {} ({})", depth, location)
+                    }
+                    (None, Some(DepthSource::InputFile(location))) => {
+                        format!("full content ({})", location)
+                    }
+                    _ => "full content".to_string(),
+                }
+            };
+            writeln!(
+                stderr,
+                "  {} {}: {}",
+                self.metadata_style.apply_to("•"),
+                self.filename_style.apply_to(file.display_path().display()),
+                summary
+            )?;
+        }
+        Ok(())
+    }
+
+    fn report_not_found_case(
+        &self,
+        stderr: &mut impl Write,
+        case: &InputResolution,
+    ) -> io::Result<()> {
         if let InputResolution::NotFound { input_string } = case {
             writeln!(
                 stderr,
@@ -284,10 +1922,14 @@ impl DisplayManager {
         Ok(())
     }
 
-    fn report_ambiguous_case(&self, stderr: &mut Term, case: &InputResolution) -> io::Result<()> {
+    fn report_ambiguous_case(
+        &self,
+        stderr: &mut impl Write,
+        case: &InputResolution,
+    ) -> io::Result<()> {
         if let InputResolution::Ambiguous {
             input_string,
-            conflicting_paths,
+            candidates,
         } = case
         {
             write!(
@@ -303,17 +1945,46 @@ impl DisplayManager {
             )?;
             writeln!(stderr, "{}", self.ambiguous_style.apply_to("matched:"))?;
 
-            const MAX_TO_SHOW: usize = 8;
-            for (i, path) in conflicting_paths.iter().enumerate() {
-                if i < MAX_TO_SHOW {
+            let now = SystemTime::now();
+            let mut scope = None;
+            for (i, candidate) in candidates.iter().enumerate() {
+                if i < self.max_ambiguous {
+                    // With `--scope-to-inputs`, candidates are grouped by directory.
+                    if let Some(candidate_scope) = &candidate.scope
+                        && scope != Some(candidate_scope)
+                    {
+                        scope = Some(candidate_scope);
+                        writeln!(
+                            stderr,
+                            "    {}",
+                            self.metadata_style
+                                .apply_to(format!("in {}:", candidate_scope.display()))
+                        )?;
+                    }
+                    let mut details = Vec::new();
+                    if let Some(size) = candidate.size {
+                        details.push(format_size(size));
+                    }
+                    if let Some(modified) = candidate.modified {
+                        details.push(format!("modified {}", format_age(modified, now)));
+                    }
                     writeln!(
                         stderr,
-                        "    {} {}",
+                        "    {} {}{}",
                         self.metadata_style.apply_to("→"),
-                        self.filename_style.apply_to(format!("{:?}", path))
+                        self.highlight_match(&candidate.display_path, candidate.matched.clone()),
+                        if details.is_empty() {
+                            String::new()
+                        } else {
+                            format!(
+                                "  {}",
+                                self.metadata_style
+                                    .apply_to(format!("({})", details.join(", ")))
+                            )
+                        }
                     )?;
                 } else {
-                    let remaining = conflicting_paths.len() - MAX_TO_SHOW;
+                    let remaining = candidates.len() - self.max_ambiguous;
                     writeln!(
                         stderr,
                         "    {} ... and {} more match{}.",
@@ -330,7 +2001,7 @@ impl DisplayManager {
 
     fn report_successful_file_case(
         &self,
-        stderr: &mut Term,
+        stderr: &mut impl Write,
         resolved_file: &ResolvedFile,
     ) -> io::Result<()> {
         writeln!(
@@ -338,7 +2009,126 @@ impl DisplayManager {
             "  {} {}",
             self.metadata_style.apply_to("✓"),
             self.filename_style
-                .apply_to(format!("{:?}", resolved_file.display_path()))
+                .apply_to(format!("{:?}", resolved_file.display_label()))
         )
     }
 }
+
+/// Describes how many files were included in each mode, with the size unit
+/// that suits it, e.g. `9 files full (1,204 lines), 4 skeletons (6,318 characters)`.
+fn mode_breakdown(totals: &ContextTotals) -> String {
+    let mut parts = Vec::new();
+    if totals.full.files > 0 {
+        parts.push(format!(
+            "{} file{} full ({} lines)",
+            totals.full.files,
+            if totals.full.files == 1 { "" } else { "s" },
+            group_digits(totals.full.lines)
+        ));
+    }
+    if totals.skeleton.files > 0 {
+        parts.push(format!(
+            "{} skeleton{} ({} characters)",
+            totals.skeleton.files,
+            if totals.skeleton.files == 1 { "" } else { "s" },
+            group_digits(totals.skeleton.chars)
+        ));
+    }
+    if parts.is_empty() {
+        return "0 files".to_string();
+    }
+    parts.join(", ")
+}
+
+/// Describes the depth the skeletons were built at, so the run can be
+/// reproduced: just the depth if every skeleton used the same one, otherwise
+/// the depths used for each language, e.g. `ex=3, rs=2/4`. `None` if there
+/// are no skeletons.
+fn depth_breakdown(contexts: &[FileContext]) -> Option<String> {
+    let mut by_language: BTreeMap<&str, BTreeSet<usize>> = BTreeMap::new();
+    for context in contexts {
+        if let Some(depth) = context.depth {
+            by_language
+                .entry(context.language.as_deref().unwrap_or("other"))
+                .or_default()
+                .insert(depth);
+        }
+    }
+    let all_depths: BTreeSet<usize> = by_language.values().flatten().copied().collect();
+    match all_depths.len() {
+        0 => None,
+        1 => all_depths.first().map(|depth| depth.to_string()),
+        _ => Some(
+            by_language
+                .iter()
+                .map(|(language, depths)| {
+                    let depths: Vec<String> = depths.iter().map(usize::to_string).collect();
+                    format!("{}={}", language, depths.join("/"))
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    }
+}
+
+/// A file size for display, e.g. `812 B`, `4.2 KB` or `1.3 MB`.
+fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let bytes_f = bytes as f64;
+    if bytes_f < KB {
+        format!("{} B", bytes)
+    } else if bytes_f < KB * KB {
+        format!("{:.1} KB", bytes_f / KB)
+    } else {
+        format!("{:.1} MB", bytes_f / (KB * KB))
+    }
+}
+
+/// How long before `now` a time was, coarsely, e.g. `just now`, `5m ago`, `2d ago`.
+fn format_age(time: SystemTime, now: SystemTime) -> String {
+    let seconds = now.duration_since(time).map_or(0, |age| age.as_secs());
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3_600 => format!("{}m ago", seconds / 60),
+        3_600..86_400 => format!("{}h ago", seconds / 3_600),
+        _ => format!("{}d ago", seconds / 86_400),
+    }
+}
+
+/// Why a search was stopped, e.g. `over the --search-time-limit of 30s`.
+fn describe_search_stop(reason: SearchStop) -> String {
+    match reason {
+        SearchStop::EntryLimit(limit) => {
+            format!("over the --search-entry-limit of {}", group_digits(limit))
+        }
+        SearchStop::TimeLimit(limit) => format!("over the --search-time-limit of {:?}", limit),
+        SearchStop::Interrupted => "interrupted by Ctrl-C".to_string(),
+    }
+}
+
+/// Quotes `text` for a POSIX shell if it contains anything but characters
+/// that are safe bare in a path.
+pub fn shell_quote(text: &str) -> String {
+    if !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./+@%:,=".contains(c))
+    {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
+
+/// Formats `n` with comma thousands separators, e.g. `12,345`.
+fn group_digits(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
@@ -0,0 +1,261 @@
+// src/doctor.rs
+
+use crate::clipboard;
+use crate::dir_config;
+use crate::git;
+use crate::grammars;
+use crate::symbol_extractor;
+use crate::user_config;
+use console::Term;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// The outcome of one `--doctor` check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Works, but something may surprise the user.
+    Warn,
+    /// Broken; `--doctor` exits non-zero.
+    Fail,
+}
+
+/// One line of the `--doctor` report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Runs every check against the environment and `working_dir`.
+pub fn run_checks(working_dir: &Path) -> Vec<Check> {
+    vec![
+        check_clipboard(),
+        check_session(),
+        check_colors(),
+        check_git(working_dir),
+        check_grammars(),
+        check_user_grammars(),
+        check_config_files(working_dir),
+        check_working_dir(working_dir),
+    ]
+}
+
+/// Round-trips a test string through the clipboard, restoring what was there.
+fn check_clipboard() -> Check {
+    let backend = clipboard::backend_name();
+    match clipboard::self_test() {
+        Ok(()) => Check::new(
+            "Clipboard",
+            CheckStatus::Pass,
+            format!("{}: wrote and read back a test string", backend),
+        ),
+        Err(e) => Check::new(
+            "Clipboard",
+            CheckStatus::Fail,
+            format!(
+                "{}: {}. Use --to-stdout to pipe output instead.",
+                backend, e
+            ),
+        ),
+    }
+}
+
+/// Over SSH or inside tmux the system clipboard may not be the one the user
+/// is looking at; there, an OSC 52 terminal escape is the usual way through.
+fn check_session() -> Check {
+    let over_ssh = env::var_os("SSH_CONNECTION").is_some() || env::var_os("SSH_TTY").is_some();
+    let in_tmux = env::var_os("TMUX").is_some();
+    match (over_ssh, in_tmux) {
+        (true, _) => Check::new(
+            "Session",
+            CheckStatus::Warn,
+            "SSH session: the clipboard is the remote machine's. Copying to your local \
+             clipboard needs OSC 52 support in your terminal; until then, use --to-stdout.",
+        ),
+        (false, true) => Check::new(
+            "Session",
+            CheckStatus::Warn,
+            "inside tmux: if copies don't arrive, OSC 52 via `set -g set-clipboard on` is advisable.",
+        ),
+        (false, false) => Check::new("Session", CheckStatus::Pass, "local terminal session"),
+    }
+}
+
+/// Reports whether styled output will show up on stderr.
+fn check_colors() -> Check {
+    let term = Term::stderr();
+    if env::var_os("NO_COLOR").is_some() {
+        Check::new("Colors", CheckStatus::Pass, "disabled by NO_COLOR")
+    } else if !term.is_term() {
+        Check::new(
+            "Colors",
+            CheckStatus::Warn,
+            "stderr is not a terminal; output is plain",
+        )
+    } else if console::colors_enabled_stderr() {
+        Check::new("Colors", CheckStatus::Pass, "supported")
+    } else {
+        Check::new(
+            "Colors",
+            CheckStatus::Warn,
+            "the terminal doesn't advertise color support; output is plain",
+        )
+    }
+}
+
+/// Git is only needed by the git-backed flags, so its absence is a warning.
+fn check_git(working_dir: &Path) -> Check {
+    match git::version(working_dir) {
+        Ok(version) => Check::new("Git", CheckStatus::Pass, version),
+        Err(e) => Check::new(
+            "Git",
+            CheckStatus::Warn,
+            format!(
+                "{}; --at, --compare, --changed-only and --blame-hints won't work",
+                e
+            ),
+        ),
+    }
+}
+
+/// Lists the compiled-in grammars, failing if any won't load.
+fn check_grammars() -> Check {
+    let supported = symbol_extractor::supported_languages();
+    let broken: Vec<String> = supported
+        .iter()
+        .filter_map(|key| {
+            symbol_extractor::check_grammar(key)
+                .err()
+                .map(|e| format!("{} ({})", key, e))
+        })
+        .collect();
+    if !broken.is_empty() {
+        return Check::new(
+            "Grammars",
+            CheckStatus::Fail,
+            format!("failed to load: {}", broken.join(", ")),
+        );
+    }
+    let missing = symbol_extractor::missing_language_features();
+    if missing.is_empty() {
+        Check::new("Grammars", CheckStatus::Pass, supported.join(", "))
+    } else {
+        Check::new(
+            "Grammars",
+            CheckStatus::Pass,
+            format!(
+                "{} (built without {})",
+                supported.join(", "),
+                missing.join(", ")
+            ),
+        )
+    }
+}
+
+/// Loads each grammar listed in the user config. One that fails to load only
+/// warns: its files fall back to full content.
+fn check_user_grammars() -> Check {
+    let config = user_config::config_path().map_or("no config path".to_string(), |path| {
+        path.display().to_string()
+    });
+    if grammars::configured().is_empty() {
+        return Check::new(
+            "User grammars",
+            CheckStatus::Pass,
+            format!("none configured ({})", config),
+        );
+    }
+    let mut loaded = Vec::new();
+    let mut failed = Vec::new();
+    for key in grammars::configured().keys() {
+        match symbol_extractor::check_grammar(key) {
+            Ok(()) => loaded.push(key.as_str()),
+            Err(e) => failed.push(format!("{}: {}", key, e)),
+        }
+    }
+    if failed.is_empty() {
+        return Check::new(
+            "User grammars",
+            CheckStatus::Pass,
+            format!("{} ({})", loaded.join(", "), config),
+        );
+    }
+    let mut detail = format!(
+        "failed to load (their files fall back to full content):\n{}",
+        failed.join("\n")
+    );
+    if !loaded.is_empty() {
+        detail.push_str(&format!("\nloaded: {}", loaded.join(", ")));
+    }
+    Check::new("User grammars", CheckStatus::Warn, detail)
+}
+
+/// Finds the `.ctx-pick.toml` files under `working_dir` and parses each.
+/// An invalid one is ignored (with a warning) at run time, so it only warns.
+pub fn check_config_files(working_dir: &Path) -> Check {
+    let paths = dir_config::discover(working_dir);
+    if paths.is_empty() {
+        return Check::new(
+            "Config files",
+            CheckStatus::Pass,
+            format!("no {} files found", dir_config::DIR_CONFIG_FILE_NAME),
+        );
+    }
+    let relative = |path: &Path| {
+        pathdiff::diff_paths(path, working_dir)
+            .unwrap_or_else(|| path.to_path_buf())
+            .display()
+            .to_string()
+    };
+    let problems: Vec<String> = paths
+        .iter()
+        .filter_map(|path| {
+            dir_config::check_file(path)
+                .err()
+                .map(|e| format!("{}: {}", relative(path), e))
+        })
+        .collect();
+    let found = paths.iter().map(|path| relative(path)).collect::<Vec<_>>();
+    if problems.is_empty() {
+        Check::new(
+            "Config files",
+            CheckStatus::Pass,
+            format!("{} parsed: {}", found.len(), found.join(", ")),
+        )
+    } else {
+        Check::new(
+            "Config files",
+            CheckStatus::Warn,
+            format!("invalid and ignored:\n{}", problems.join("\n")),
+        )
+    }
+}
+
+/// ctx-pick only reads from the working directory, so it must be listable.
+fn check_working_dir(working_dir: &Path) -> Check {
+    match fs::read_dir(working_dir) {
+        Ok(_) => Check::new(
+            "Working directory",
+            CheckStatus::Pass,
+            format!("{} is readable", working_dir.display()),
+        ),
+        Err(e) => Check::new(
+            "Working directory",
+            CheckStatus::Fail,
+            format!("cannot list {}: {}", working_dir.display(), e),
+        ),
+    }
+}
@@ -0,0 +1,285 @@
+// src/documents.rs
+
+//! Outlines of prose documents, LaTeX and reStructuredText: their section
+//! structure with the first sentence of each section. `--depth` sets how many
+//! levels of sections are shown, counted from the outermost one the document
+//! uses.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Returns true if a language key names a document format outlined here.
+pub fn is_document_language(file_extension: &str) -> bool {
+    matches!(file_extension, "tex" | "ltx" | "rst")
+}
+
+/// Builds the outline of a document, or returns `None` if the language key
+/// isn't one handled here.
+pub fn skeleton(
+    source_code: &str,
+    file_extension: &str,
+    max_depth: usize,
+) -> Option<Result<String, String>> {
+    let entries = match file_extension {
+        "tex" | "ltx" => latex_entries(source_code),
+        "rst" => rst_entries(source_code),
+        _ => return None,
+    };
+    let mut lines = Vec::new();
+    for entry in entries.iter().filter(|entry| entry.level < max_depth) {
+        let indent = "  ".repeat(entry.level);
+        lines.push(format!("{}{}", indent, entry.heading));
+        if let Some(summary) = &entry.summary {
+            lines.push(format!("{}  {}", indent, summary));
+        }
+    }
+    Some(Ok(if lines.is_empty() {
+        "(No structure found)".to_string()
+    } else {
+        lines.join("\n")
+    }))
+}
+
+/// A section heading (or RST directive), `level` sections deep.
+struct Entry {
+    level: usize,
+    heading: String,
+    /// The first sentence of the section's text.
+    summary: Option<String>,
+}
+
+/// The longest summary kept; longer first sentences are cut with `...`.
+const MAX_SUMMARY_CHARS: usize = 160;
+
+/// The first sentence of `text`, with whitespace collapsed: up to the first
+/// `.`, `?` or `!` followed by the end of the text or a word that isn't
+/// lowercase.
+fn first_sentence(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    let end = text
+        .match_indices(['.', '?', '!'])
+        .map(|(index, _)| index + 1)
+        // A lowercase word after the stop means an abbreviation ("e.g. this").
+        .find(|&end| match text[end..].strip_prefix(' ') {
+            Some(next) => !next.starts_with(char::is_lowercase),
+            None => text[end..].is_empty(),
+        })
+        .unwrap_or(text.len());
+    let sentence = &text[..end];
+    Some(match sentence.char_indices().nth(MAX_SUMMARY_CHARS) {
+        Some((cut, _)) => format!("{}...", &sentence[..cut]),
+        None => sentence.to_string(),
+    })
+}
+
+// --- LaTeX ---
+
+/// The sectioning commands, outermost first.
+const LATEX_SECTIONS: &[&str] = &[
+    "part",
+    "chapter",
+    "section",
+    "subsection",
+    "subsubsection",
+    "paragraph",
+    "subparagraph",
+];
+
+static LATEX_SECTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\(part|chapter|section|subsection|subsubsection|paragraph|subparagraph)\*?\s*(?:\[[^\]]*\])?\s*\{").unwrap()
+});
+
+/// A line of nothing but commands and their arguments, e.g. `\label{x}` or
+/// `\begin{figure}[t]`, which has no prose to summarize.
+static LATEX_COMMAND_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\s*\\[a-zA-Z@]+\*?(\[[^\]]*\]|\{[^{}]*\})*)*\s*$").unwrap());
+
+/// Cuts a `%` comment off a line (`\%` is a literal percent sign).
+fn strip_latex_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    for (index, &byte) in bytes.iter().enumerate() {
+        if byte == b'%' && (index == 0 || bytes[index - 1] != b'\\') {
+            return &line[..index];
+        }
+    }
+    line
+}
+
+/// The text of a `{...}` group whose opening brace is just before `start`,
+/// and the index just past its closing brace.
+fn braced_group(text: &str, start: usize) -> (&str, usize) {
+    let mut depth = 1;
+    for (index, c) in text[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (&text[start..start + index], start + index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    (&text[start..], text.len())
+}
+
+/// The sections of a LaTeX document (and its `\title`), each with the first
+/// sentence of its first paragraph.
+fn latex_entries(source: &str) -> Vec<Entry> {
+    let text: String = source
+        .lines()
+        .map(strip_latex_comment)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text
+        .find("\\begin{document}")
+        .map_or(text.as_str(), |start| &text[start..]);
+
+    // Each heading with its sectioning level and where its text starts.
+    let mut headings: Vec<(usize, String, usize, usize)> = Vec::new();
+    for captures in LATEX_SECTION.captures_iter(text) {
+        let whole = captures.get(0).unwrap();
+        let command = captures.get(1).unwrap().as_str();
+        let level = LATEX_SECTIONS
+            .iter()
+            .position(|name| *name == command)
+            .unwrap_or(0);
+        let (title, end) = braced_group(text, whole.end());
+        let heading = format!(
+            "{}{{{}}}",
+            &text[whole.start()..whole.end() - 1].trim_end(),
+            title.split_whitespace().collect::<Vec<_>>().join(" ")
+        );
+        headings.push((level, heading, whole.start(), end));
+    }
+    let outermost = headings.iter().map(|(level, ..)| *level).min().unwrap_or(0);
+
+    let mut entries = Vec::new();
+    if let Some(start) = source.find("\\title{") {
+        let (title, _) = braced_group(source, start + "\\title{".len());
+        entries.push(Entry {
+            level: 0,
+            heading: format!(
+                "\\title{{{}}}",
+                title.split_whitespace().collect::<Vec<_>>().join(" ")
+            ),
+            summary: None,
+        });
+    }
+    for (index, (level, heading, _, body_start)) in headings.iter().enumerate() {
+        let body_end = headings
+            .get(index + 1)
+            .map_or(text.len(), |(_, _, next_start, _)| *next_start);
+        entries.push(Entry {
+            level: level - outermost,
+            heading: heading.clone(),
+            summary: first_sentence(&latex_first_paragraph(&text[*body_start..body_end])),
+        });
+    }
+    entries
+}
+
+/// The first paragraph of prose in a section's text, skipping lines that
+/// are only commands (labels, environments, figures).
+fn latex_first_paragraph(body: &str) -> String {
+    let mut paragraph = Vec::new();
+    for line in body.lines() {
+        if LATEX_COMMAND_LINE.is_match(line) {
+            if !paragraph.is_empty() && line.trim().is_empty() {
+                break;
+            }
+            continue;
+        }
+        paragraph.push(line.trim());
+    }
+    paragraph.join(" ")
+}
+
+// --- reStructuredText ---
+
+/// Returns true if `line` is an RST section adornment: one punctuation
+/// character repeated.
+fn is_adornment(line: &str) -> bool {
+    let line = line.trim_end();
+    let mut chars = line.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    line.len() >= 2 && first.is_ascii_punctuation() && chars.all(|c| c == first)
+}
+
+/// The sections of an RST document, each with the first sentence of its
+/// first paragraph, and the directives (`.. toctree::`, `.. autoclass::
+/// Name`) at the start of a line, one level inside the section they're in.
+fn rst_entries(source: &str) -> Vec<Entry> {
+    let lines: Vec<&str> = source.lines().collect();
+    // Adornment styles (character, overlined) in order of first use; a
+    // style's position is its level.
+    let mut styles: Vec<(char, bool)> = Vec::new();
+    let mut entries: Vec<Entry> = Vec::new();
+    // The section the following paragraph would summarize, if it has none yet.
+    let mut awaiting_summary: Option<usize> = None;
+    let mut level = 0;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        let next = lines.get(index + 1).copied().unwrap_or("");
+        let after = lines.get(index + 2).copied().unwrap_or("");
+
+        // `====\nTitle\n====` or `Title\n====`.
+        let overlined = is_adornment(line)
+            && !next.trim().is_empty()
+            && is_adornment(after)
+            && after.trim_end() == line.trim_end();
+        let underlined = !overlined
+            && !line.trim().is_empty()
+            && !line.starts_with(' ')
+            && is_adornment(next)
+            && next.trim_end().len() >= line.trim_end().chars().count();
+        if overlined || underlined {
+            let (title, adornment) = if overlined {
+                (next, after)
+            } else {
+                (line, next)
+            };
+            let style = (adornment.chars().next().unwrap_or('='), overlined);
+            level = styles.iter().position(|s| *s == style).unwrap_or_else(|| {
+                styles.push(style);
+                styles.len() - 1
+            });
+            entries.push(Entry {
+                level,
+                heading: title.trim().to_string(),
+                summary: None,
+            });
+            awaiting_summary = Some(entries.len() - 1);
+            index += if overlined { 3 } else { 2 };
+            continue;
+        }
+
+        if let Some(directive) = line.strip_prefix(".. ").filter(|rest| rest.contains("::")) {
+            entries.push(Entry {
+                level: if entries.is_empty() { 0 } else { level + 1 },
+                heading: format!(".. {}", directive.trim()),
+                summary: None,
+            });
+        } else if !line.trim().is_empty()
+            && !line.starts_with([' ', '\t'])
+            && !line.starts_with("..")
+            && let Some(section) = awaiting_summary.take()
+        {
+            let mut paragraph = vec![line];
+            while let Some(more) = lines.get(index + 1).filter(|l| !l.trim().is_empty()) {
+                paragraph.push(more);
+                index += 1;
+            }
+            entries[section].summary = first_sentence(&paragraph.join(" "));
+        }
+        index += 1;
+    }
+    entries
+}
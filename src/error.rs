@@ -1,7 +1,32 @@
+use std::fmt;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Error)]
 pub enum AppError {
     #[error("I/O error: {0}")]
     IoError(String),
+    #[error("Clipboard error: {0}")]
+    ClipboardError(String),
+    #[error("Could not extract skeleton: {0}")]
+    SkeletonError(String),
+    #[error("Git error: {0}")]
+    GitError(String),
+    #[error("File picker error: {0}")]
+    PickerError(String),
+    #[error("Invalid --input-file line {0}")]
+    InputFileError(String),
+    #[error(
+        "The working directory{} no longer exists; it may have been deleted. Change to an existing directory, or pass --cwd with an absolute path.",
+        .0.as_ref().map(|dir| format!(" {:?}", dir)).unwrap_or_default()
+    )]
+    WorkingDirectoryMissing(Option<std::path::PathBuf>),
+}
+
+// `main` returns `AppError`, and Rust reports that with `Debug`; show the
+// message (and any advice in it) rather than the variant.
+impl fmt::Debug for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
 }
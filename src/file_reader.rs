@@ -0,0 +1,185 @@
+// src/file_reader.rs
+
+use crate::types::LineRange;
+use memmap2::Mmap;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Files at least this large are memory-mapped when only some of their lines
+/// are wanted, so the rest of the file is never read from disk.
+const MMAP_THRESHOLD_BYTES: u64 = 1024 * 1024;
+
+/// How much of a file `is_binary` looks at, as git does.
+pub const BINARY_SNIFF_BYTES: u64 = 8000;
+
+/// Returns true if a file looks binary: it has a NUL byte near its start.
+pub fn is_binary(path: &Path) -> io::Result<bool> {
+    let mut start = Vec::new();
+    File::open(path)?
+        .take(BINARY_SNIFF_BYTES)
+        .read_to_end(&mut start)?;
+    Ok(start.contains(&0))
+}
+
+/// Reads a file as UTF-8 text, optionally keeping only the lines in `range`.
+///
+/// Large files are memory-mapped when a range is given, so only the pages up
+/// to the last selected line are touched. Smaller files (and whole-file reads)
+/// use an ordinary read.
+pub fn read_lines(path: &Path, range: Option<LineRange>) -> io::Result<String> {
+    let Some(range) = range else {
+        return fs::read_to_string(path);
+    };
+
+    let file = File::open(path)?;
+    let selected = if file.metadata()?.len() >= MMAP_THRESHOLD_BYTES {
+        // SAFETY: the map is only read, and dropped before returning. If another
+        // process truncates the file meanwhile we may read stale or zeroed bytes,
+        // which at worst yields odd text or a UTF-8 error, not unsoundness here.
+        let map = unsafe { Mmap::map(&file)? };
+        select_lines(&map, range).to_vec()
+    } else {
+        let bytes = fs::read(path)?;
+        select_lines(&bytes, range).to_vec()
+    };
+    String::from_utf8(selected).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Reads a list of paths, one per line, as canonical paths. Relative paths
+/// are taken from `base`; blank lines and paths that don't exist are skipped.
+pub fn read_path_list(list: &Path, base: &Path) -> io::Result<HashSet<PathBuf>> {
+    Ok(fs::read_to_string(list)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| base.join(line).canonicalize().ok())
+        .collect())
+}
+
+/// Keeps only the lines in `range` of text that is already in memory.
+pub fn slice_lines(text: &str, range: LineRange) -> &str {
+    // Line boundaries are ASCII newlines, so the slice is valid UTF-8.
+    std::str::from_utf8(select_lines(text.as_bytes(), range)).unwrap_or("")
+}
+
+/// Keeps only the lines within `context_lines` of a `changed` range, in order,
+/// replacing each run of omitted lines with a `[... lines A–B unchanged ...]`
+/// marker. Overlapping or adjacent windows are merged.
+pub fn excerpt_lines(text: &str, changed: &[LineRange], context_lines: usize) -> String {
+    let total_lines = text.lines().count();
+    let mut excerpt = String::new();
+    let mut next_line = 1;
+    for window in context_windows(changed, context_lines, total_lines)
+        .into_iter()
+        .chain(std::iter::once(LineRange {
+            start: total_lines + 1,
+            end: total_lines,
+        }))
+    {
+        if window.start > next_line {
+            excerpt.push_str(&unchanged_marker(next_line, window.start - 1));
+        }
+        if window.start <= window.end {
+            excerpt.push_str(slice_lines(text, window));
+            if !excerpt.ends_with('\n') {
+                excerpt.push('\n');
+            }
+        }
+        next_line = window.end + 1;
+    }
+    excerpt
+}
+
+/// Keeps only the lines matching `pattern` and `context_lines` around each,
+/// in the style of `grep -n -C`: each line is prefixed with its number, then
+/// `:` for a match or `-` for context, and `--` separates groups that aren't
+/// contiguous. Line numbers count from `first_line`.
+pub fn grep_lines(text: &str, pattern: &Regex, context_lines: usize, first_line: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let matches: Vec<LineRange> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| pattern.is_match(line))
+        .map(|(index, _)| LineRange {
+            start: index + 1,
+            end: index + 1,
+        })
+        .collect();
+
+    let mut excerpt = String::new();
+    for (i, window) in context_windows(&matches, context_lines, lines.len())
+        .into_iter()
+        .enumerate()
+    {
+        if i > 0 {
+            excerpt.push_str("--\n");
+        }
+        for number in window.start..=window.end {
+            let line = lines[number - 1];
+            let separator = if pattern.is_match(line) { ':' } else { '-' };
+            excerpt.push_str(&format!(
+                "{}{}{}\n",
+                number + first_line - 1,
+                separator,
+                line
+            ));
+        }
+    }
+    excerpt
+}
+
+/// Widens each range by `context_lines` on both sides (within `1..=total_lines`),
+/// merging windows that overlap or touch, in order.
+fn context_windows(
+    ranges: &[LineRange],
+    context_lines: usize,
+    total_lines: usize,
+) -> Vec<LineRange> {
+    let mut windows: Vec<LineRange> = ranges
+        .iter()
+        .filter(|range| range.start <= total_lines)
+        .map(|range| LineRange {
+            start: range.start.saturating_sub(context_lines).max(1),
+            end: (range.end + context_lines).min(total_lines),
+        })
+        .collect();
+    windows.sort();
+    let mut merged: Vec<LineRange> = Vec::new();
+    for window in windows {
+        match merged.last_mut() {
+            Some(last) if window.start <= last.end + 1 => last.end = last.end.max(window.end),
+            _ => merged.push(window),
+        }
+    }
+    merged
+}
+
+/// The line standing in for lines `start..=end` left out of an excerpt.
+fn unchanged_marker(start: usize, end: usize) -> String {
+    if start == end {
+        format!("[... line {} unchanged ...]\n", start)
+    } else {
+        format!("[... lines {}–{} unchanged ...]\n", start, end)
+    }
+}
+
+/// Returns the bytes of lines `range.start..=range.end` (1-based), including
+/// the newline ending the last one. Scanning stops at the end of the range.
+fn select_lines(bytes: &[u8], range: LineRange) -> &[u8] {
+    let mut line = 1;
+    let mut start = if range.start == 1 { Some(0) } else { None };
+    for (offset, _) in bytes.iter().enumerate().filter(|(_, b)| **b == b'\n') {
+        if line == range.end {
+            return &bytes[start.unwrap_or(offset + 1)..offset + 1];
+        }
+        line += 1;
+        if line == range.start {
+            start = Some(offset + 1);
+        }
+    }
+    // The range runs past the last newline.
+    &bytes[start.unwrap_or(bytes.len())..]
+}
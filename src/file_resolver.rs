@@ -1,21 +1,180 @@
 // src/file_resolver.rs
 
-use crate::config::Config;
-use crate::types::{InputResolution, ResolvedFile};
-use glob::glob; // Import the glob function
+use crate::config::{Config, DEFAULT_EXCLUDES};
+use crate::language;
+use crate::symbol_extractor;
+use crate::types::{
+    AmbiguousMatch, DirectMatch, FuzzyCandidate, InputResolution, LineRange, ResolutionTrace,
+    ResolvedFile, SearchStop, Tag,
+};
+use ignore::WalkBuilder;
+use regex::Regex;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::env;
+use std::ffi::OsStr;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::Instant;
 use walkdir::{DirEntry, WalkDir};
 
+/// Returns true if a file or directory name is hidden (starts with a dot).
+fn is_hidden_name(name: &OsStr) -> bool {
+    name.to_str().is_some_and(|name| name.starts_with('.'))
+}
+
+/// Which names fuzzy search and directory expansion skip for `query`: hidden
+/// ones by default, just `.git` with `--hidden`, and none when the query
+/// itself starts with a hidden component (`.github/workflows`, `.env`).
+fn hidden_filter(query: &str, config: &Config) -> impl Fn(&OsStr) -> bool + Copy + use<> {
+    let explicit = Path::new(query)
+        .components()
+        .find(|component| !matches!(component, Component::CurDir | Component::ParentDir))
+        .is_some_and(|component| match component {
+            Component::Normal(name) => is_hidden_name(name),
+            _ => false,
+        });
+    let hidden = config.hidden;
+    move |name: &OsStr| {
+        !explicit
+            && if hidden {
+                name == ".git"
+            } else {
+                is_hidden_name(name)
+            }
+    }
+}
+
+/// Returns true if `skip` applies to any component of `relative`.
+fn has_skipped_component(relative: &Path, skip: &impl Fn(&OsStr) -> bool) -> bool {
+    relative.components().any(|component| match component {
+        Component::Normal(name) => skip(name),
+        _ => false,
+    })
+}
+
 // Helper to check if a WalkDir entry is a file.
 fn is_walkdir_file_entry(entry: &DirEntry) -> bool {
     entry.file_type().is_file()
 }
 
+/// Counts the entries a glob or fuzzy search visits, so that one that would
+/// take too long (e.g. `**/**/**/*` over a huge tree) is stopped with an
+/// error instead of hanging, as is one interrupted with Ctrl-C.
+struct SearchBudget<'c> {
+    config: &'c Config,
+    started: Instant,
+    entries: usize,
+}
+
+impl<'c> SearchBudget<'c> {
+    fn new(config: &'c Config) -> Self {
+        Self {
+            config,
+            started: Instant::now(),
+            entries: 0,
+        }
+    }
+
+    /// Records one more visited entry, failing once the search should stop.
+    fn charge(&mut self) -> Result<(), SearchStop> {
+        self.entries += 1;
+        if crate::interrupt::requested() {
+            return Err(SearchStop::Interrupted);
+        }
+        if let Some(limit) = self.config.search_entry_limit
+            && self.entries > limit
+        {
+            return Err(SearchStop::EntryLimit(limit));
+        }
+        match self.config.search_time_limit {
+            Some(limit) if self.started.elapsed() > limit => Err(SearchStop::TimeLimit(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    fn stopped<'a>(&self, input_string: &'a str, reason: SearchStop) -> InputResolution<'a> {
+        InputResolution::SearchStopped {
+            input_string,
+            entries: self.entries,
+            reason,
+        }
+    }
+}
+
+thread_local! {
+    /// Warnings about entries skipped by the walks on this thread, until
+    /// `take_skipped_walks` collects them.
+    static SKIPPED_WALKS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Takes the warnings about walks that skipped unreadable entries since the
+/// last call, one per input and cause, for the caller to show.
+pub fn take_skipped_walks() -> Vec<String> {
+    SKIPPED_WALKS.take()
+}
+
+/// Tallies entries a walk had to skip, so that an incomplete result is
+/// reported once at the end instead of being silently swallowed.
+#[derive(Default)]
+struct SkippedEntries {
+    permission_denied: usize,
+    other_errors: usize,
+}
+
+impl SkippedEntries {
+    fn record(&mut self, error: Option<&io::Error>) {
+        match error.map(io::Error::kind) {
+            Some(io::ErrorKind::PermissionDenied) => self.permission_denied += 1,
+            _ => self.other_errors += 1,
+        }
+    }
+
+    /// Records a warning for `input` if anything was skipped, for
+    /// `take_skipped_walks`.
+    fn warn(&self, input: &str) {
+        let mut warnings = Vec::new();
+        if self.permission_denied > 0 {
+            warnings.push(format!(
+                "{} {} skipped due to permissions while resolving '{}'; results may be incomplete.",
+                self.permission_denied,
+                if self.permission_denied == 1 {
+                    "directory"
+                } else {
+                    "directories"
+                },
+                input
+            ));
+        }
+        if self.other_errors > 0 {
+            warnings.push(format!(
+                "{} {} skipped due to I/O errors while resolving '{}'; results may be incomplete.",
+                self.other_errors,
+                if self.other_errors == 1 {
+                    "entry"
+                } else {
+                    "entries"
+                },
+                input
+            ));
+        }
+        SKIPPED_WALKS.with_borrow_mut(|skipped| skipped.extend(warnings));
+    }
+}
+
 /// Attempts to create a ResolvedFile instance from a given path.
 fn create_resolved_file(path_to_resolve: &Path, config: &Config) -> Result<ResolvedFile, String> {
-    let canonical_path = fs::canonicalize(path_to_resolve)
-        .map_err(|e| format!("Failed to canonicalize path {:?}: {}", path_to_resolve, e))?;
+    let canonical_path = fs::canonicalize(path_to_resolve).map_err(|e| {
+        if config.working_dir.is_dir() {
+            format!("Failed to canonicalize path {:?}: {}", path_to_resolve, e)
+        } else {
+            format!(
+                "the working directory {:?} no longer exists",
+                config.working_dir
+            )
+        }
+    })?;
 
     let display_path = pathdiff::diff_paths(&canonical_path, &config.working_dir)
         .unwrap_or_else(|| canonical_path.clone());
@@ -23,22 +182,639 @@ fn create_resolved_file(path_to_resolve: &Path, config: &Config) -> Result<Resol
     Ok(ResolvedFile::new(display_path, canonical_path))
 }
 
+/// Expands a leading `~` and any `$VAR` / `${VAR}` references in an input.
+///
+/// The shell normally does this, but inputs don't always pass through one.
+/// Unknown variables (and `~` when no home directory is known) are left
+/// untouched, so literal names still resolve if they exist.
+pub fn expand_input(input: &str) -> Cow<'_, str> {
+    if !input.starts_with('~') && !input.contains('$') {
+        return Cow::Borrowed(input);
+    }
+
+    let mut expanded = String::with_capacity(input.len());
+    let mut rest = input;
+
+    // Only `~` on its own or followed by a separator means the home directory;
+    // `~user` forms are left alone.
+    if let Some(after_tilde) = input.strip_prefix('~')
+        && (after_tilde.is_empty() || after_tilde.starts_with(['/', std::path::MAIN_SEPARATOR]))
+        && let Some(home) = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))
+    {
+        expanded.push_str(&home.to_string_lossy());
+        rest = after_tilde;
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after_dollar = &rest[dollar + 1..];
+
+        let (name, consumed) = if let Some(braced) = after_dollar.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after_dollar
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after_dollar.len());
+            (&after_dollar[..end], end)
+        };
+
+        match env::var(name) {
+            Ok(value) if !name.is_empty() => {
+                expanded.push_str(&value);
+                rest = &after_dollar[consumed..];
+            }
+            _ => {
+                // Unknown or malformed: keep the `$` literally and move on.
+                expanded.push('$');
+                rest = after_dollar;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Cow::Owned(expanded)
+}
+
+/// Returns the form of an input used for filesystem lookups.
+fn lookup_query<'s>(input_str: &'s str, config: &Config) -> Cow<'s, str> {
+    if config.expand_inputs {
+        expand_input(input_str)
+    } else {
+        Cow::Borrowed(input_str)
+    }
+}
+
+/// The number of sample paths carried by a `TooManyFiles` resolution.
+const TOO_MANY_FILES_SAMPLE_SIZE: usize = 5;
+
+/// How `Excluded` resolutions describe the built-in excludes.
+const DEFAULT_EXCLUDES_RULE: &str = "the default excludes for lockfiles and generated files (pass --no-default-excludes, or name the files directly)";
+
+/// Returns true if `file` matches one of the built-in `DEFAULT_EXCLUDES`.
+fn is_default_excluded(file: &ResolvedFile) -> bool {
+    let Some(file_name) = file.display_path().file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    DEFAULT_EXCLUDES
+        .iter()
+        .any(|pattern| glob::Pattern::new(pattern).is_ok_and(|p| p.matches(file_name)))
+}
+
+/// Returns true if `file` (after following symlinks) lies under the working directory.
+fn is_inside_working_dir(file: &ResolvedFile, config: &Config) -> bool {
+    let working_dir =
+        fs::canonicalize(&config.working_dir).unwrap_or_else(|_| config.working_dir.clone());
+    file.canonical_path().starts_with(working_dir)
+}
+
+/// Refuses `files` with an `OutsideWorkingDir` error if any of them lies
+/// outside the working directory, unless `--allow-outside` was given.
+fn check_containment<'a>(
+    input_str: &'a str,
+    files: Vec<ResolvedFile>,
+    config: &Config,
+) -> Result<Vec<ResolvedFile>, InputResolution<'a>> {
+    if config.allow_outside || config.git_tree.is_some() || config.archive.is_some() {
+        return Ok(files);
+    }
+    let outside: Vec<PathBuf> = files
+        .iter()
+        .filter(|file| !is_inside_working_dir(file, config))
+        .map(|file| file.canonical_path().to_path_buf())
+        .collect();
+    if outside.is_empty() {
+        return Ok(files);
+    }
+    Err(InputResolution::OutsideWorkingDir {
+        input_string: input_str,
+        file_count: outside.len(),
+        sample_paths: outside
+            .into_iter()
+            .take(TOO_MANY_FILES_SAMPLE_SIZE)
+            .collect(),
+    })
+}
+
+/// Wraps the files an expanding input (directory or glob) matched in a
+/// `Success`, or a `TooManyFiles` error if they exceed the configured limit.
+/// Files outside the working directory are refused, and files matching
+/// `DEFAULT_EXCLUDES` are dropped first, unless disabled.
+fn check_expansion_limit<'a>(
+    input_str: &'a str,
+    files: Vec<ResolvedFile>,
+    config: &Config,
+) -> InputResolution<'a> {
+    let mut files = match check_containment(input_str, files, config) {
+        Ok(files) => files,
+        Err(refused) => return refused,
+    };
+    if config.default_excludes && !files.is_empty() {
+        let matched_count = files.len();
+        files.retain(|file| !is_default_excluded(file));
+        if files.is_empty() {
+            return InputResolution::Excluded {
+                input_string: input_str,
+                file_count: matched_count,
+                rule: DEFAULT_EXCLUDES_RULE.to_string(),
+            };
+        }
+    }
+    match config.max_files_per_input {
+        Some(limit) if files.len() > limit => InputResolution::TooManyFiles {
+            input_string: input_str,
+            file_count: files.len(),
+            limit,
+            sample_paths: files
+                .iter()
+                .take(TOO_MANY_FILES_SAMPLE_SIZE)
+                .map(|f| f.display_path().to_path_buf())
+                .collect(),
+        },
+        _ => InputResolution::Success(files),
+    }
+}
+
+/// Prefixes that turn an input into a symbol search, e.g. `fn:create_resolved_file`.
+const SYMBOL_PREFIXES: &[&str] = &["fn:", "sym:"];
+
+/// Returns the symbol name if `input` is a symbol search.
+fn symbol_query(input: &str) -> Option<&str> {
+    SYMBOL_PREFIXES
+        .iter()
+        .find_map(|prefix| input.strip_prefix(prefix))
+        .filter(|symbol| !symbol.is_empty())
+}
+
+/// Returns true if a definition named `tag_name` answers a search for `symbol`.
+/// Qualified names match on their last segment, so `setup` finds Lua's `M.setup`.
+fn tag_matches(tag_name: &str, symbol: &str) -> bool {
+    tag_name == symbol || tag_name.rsplit(['.', ':']).next() == Some(symbol)
+}
+
+/// Resolves a symbol search to the file containing the symbol's definition.
+///
+/// Every supported source file under the working directory is parsed (with the
+/// same ignore rules as `--overview`). More than one definition is ambiguous,
+/// and the candidates are reported as `path:line`.
+fn resolve_symbol<'a>(
+    input_str: &'a str,
+    symbol: &str,
+    config: &Config,
+    trace: Option<&mut ResolutionTrace>,
+) -> InputResolution<'a> {
+    let mut definitions = find_definitions(&symbol_search_paths(input_str, config), symbol, config);
+    if let Some(trace) = trace {
+        trace.symbol_definition_count = Some(definitions.len());
+    }
+    match definitions.len() {
+        0 => InputResolution::NotFound {
+            input_string: input_str,
+        },
+        1 => match check_containment(input_str, vec![definitions.remove(0).0], config) {
+            Ok(files) => InputResolution::Success(files),
+            Err(refused) => refused,
+        },
+        _ => ambiguous_definitions(input_str, &definitions),
+    }
+}
+
+/// Resolves `symbol` to the lines of its definition plus `context_lines`
+/// on either side (`--context-around-symbol`), searching `scope` if given
+/// and otherwise the whole working directory like a `sym:` input. More than
+/// one definition is ambiguous, even within one file.
+pub fn resolve_symbol_context<'a>(
+    symbol: &'a str,
+    context_lines: usize,
+    scope: Option<&[ResolvedFile]>,
+    config: &Config,
+) -> InputResolution<'a> {
+    let paths = match scope {
+        Some(files) => files
+            .iter()
+            .map(|file| file.canonical_path().to_path_buf())
+            .collect(),
+        None => symbol_search_paths(symbol, config),
+    };
+    let definitions = find_definitions(&paths, symbol, config);
+    match definitions.as_slice() {
+        [] => InputResolution::NotFound {
+            input_string: symbol,
+        },
+        [(file, tag)] => {
+            if let Err(refused) = check_containment(symbol, vec![file.clone()], config) {
+                return refused;
+            }
+            let total_lines = fs::read_to_string(file.canonical_path())
+                .map_or(tag.end_line, |content| content.lines().count());
+            InputResolution::Success(vec![file.clone().with_line_range(LineRange {
+                start: tag.line.saturating_sub(context_lines).max(1),
+                end: (tag.end_line + context_lines).min(total_lines.max(tag.end_line)),
+            })])
+        }
+        _ => ambiguous_definitions(symbol, &definitions),
+    }
+}
+
+/// The files a symbol search looks through: everything under the working
+/// directory, with the same ignore rules as `--overview`.
+fn symbol_search_paths(input_str: &str, config: &Config) -> Vec<PathBuf> {
+    let mut skipped = SkippedEntries::default();
+    let paths = ignore_aware_walker(&config.working_dir)
+        .follow_links(config.follow_symlinks)
+        .build()
+        .filter_map(|e| e.map_err(|err| skipped.record(err.io_error())).ok())
+        .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+        .map(ignore::DirEntry::into_path)
+        .collect();
+    skipped.warn(input_str);
+    paths
+}
+
+/// Finds the definitions named `symbol` in the supported source files among
+/// `paths`, sorted by path and line.
+fn find_definitions(paths: &[PathBuf], symbol: &str, config: &Config) -> Vec<(ResolvedFile, Tag)> {
+    let mut definitions: Vec<(ResolvedFile, Tag)> = Vec::new();
+    for path in paths {
+        let Some(language) = language::detect_language(path, "")
+            .filter(|key| symbol_extractor::is_supported_extension(key))
+        else {
+            continue;
+        };
+        // Unreadable or non-UTF-8 files simply can't contain a match.
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        let Ok((tags, searched)) = symbol_extractor::search_tags(&content, &language) else {
+            continue;
+        };
+        let matching: Vec<Tag> = tags
+            .into_iter()
+            .filter(|tag| tag_matches(&tag.name, symbol))
+            .collect();
+        if matching.is_empty() {
+            continue;
+        }
+        if let Ok(resolved) = create_resolved_file(path, config) {
+            // Likely included, so its skeleton can reuse the tree.
+            searched.keep();
+            definitions.extend(matching.into_iter().map(|tag| (resolved.clone(), tag)));
+        }
+    }
+    definitions.sort_by(|(a, a_tag), (b, b_tag)| {
+        a.display_path()
+            .cmp(b.display_path())
+            .then(a_tag.line.cmp(&b_tag.line))
+    });
+    definitions
+}
+
+/// Reports several definitions of a symbol as an ambiguity, each candidate
+/// shown as `path:line`.
+fn ambiguous_definitions<'a>(
+    input_str: &'a str,
+    definitions: &[(ResolvedFile, Tag)],
+) -> InputResolution<'a> {
+    InputResolution::Ambiguous {
+        input_string: input_str,
+        candidates: definitions
+            .iter()
+            .map(|(file, tag)| {
+                ambiguous_match(
+                    PathBuf::from(format!("{}:{}", file.display_path().display(), tag.line)),
+                    Some(file.canonical_path()),
+                    "",
+                )
+            })
+            .collect(),
+    }
+}
+
+/// Resolves an input against a fixed list of relative `paths`, such as the
+/// files of a git revision (`--at`) or an archive (`--archive`), using the
+/// same three phases as the working directory: an exact file or directory
+/// path, then a glob, then a substring search over the paths. `resolve`
+/// turns a matched path into the file to read.
+fn resolve_in_tree<'a>(
+    input_str: &'a str,
+    query: &str,
+    paths: &[PathBuf],
+    resolve: impl Fn(&PathBuf) -> ResolvedFile,
+    config: &Config,
+    mut trace: Option<&mut ResolutionTrace>,
+) -> InputResolution<'a> {
+    // --- Phase 1: Direct Match ---
+    let query_path: PathBuf = Path::new(query)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect();
+    if let Some(path) = paths.iter().find(|path| **path == query_path) {
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.direct_match = Some(DirectMatch::File);
+        }
+        return InputResolution::Success(vec![resolve(path)]);
+    }
+    let under_dir: Vec<&PathBuf> = paths
+        .iter()
+        .filter(|path| path.starts_with(&query_path))
+        .collect();
+    if !under_dir.is_empty() {
+        let dir_levels = query_path.components().count();
+        let skip = hidden_filter(query, config);
+        let files_in_dir: Vec<ResolvedFile> = under_dir
+            .into_iter()
+            .filter(|path| {
+                config
+                    .dir_depth
+                    .is_none_or(|depth| path.components().count() - dir_levels <= depth)
+            })
+            .filter(|path| {
+                !path
+                    .strip_prefix(&query_path)
+                    .is_ok_and(|relative| has_skipped_component(relative, &skip))
+            })
+            .map(&resolve)
+            .collect();
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.direct_match = Some(DirectMatch::Directory {
+                file_count: files_in_dir.len(),
+            });
+        }
+        return check_expansion_limit(input_str, files_in_dir, config);
+    }
+
+    // --- Phase 2: Glob Pattern Match ---
+    let is_glob_pattern = query.contains(&['*', '?', '[', '{'][..]);
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.treated_as_glob = is_glob_pattern;
+    }
+    if is_glob_pattern {
+        let pattern = match glob::Pattern::new(query) {
+            Ok(pattern) => pattern,
+            Err(pattern_error) => {
+                return InputResolution::InvalidGlobPattern {
+                    input_string: input_str,
+                    error: pattern_error.to_string(),
+                };
+            }
+        };
+        // Match `*` within a single path component, as filesystem globs do.
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        let matched: Vec<ResolvedFile> = paths
+            .iter()
+            .filter(|path| pattern.matches_path_with(path, options))
+            .map(&resolve)
+            .collect();
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.glob_match_count = Some(matched.len());
+        }
+        return if matched.is_empty() {
+            InputResolution::NotFound {
+                input_string: input_str,
+            }
+        } else {
+            check_expansion_limit(input_str, matched, config)
+        };
+    }
+
+    // --- Phase 3: Fuzzy Search (Fallback) ---
+    let mut candidates: Vec<&PathBuf> = paths
+        .iter()
+        .filter(|path| path.to_string_lossy().contains(query))
+        .filter(|path| !has_skipped_component(path, &hidden_filter(query, config)))
+        .filter(|path| {
+            config.search_roots.is_empty()
+                || search_root_of(&config.working_dir.join(path), config).is_some()
+        })
+        .collect();
+    candidates.sort_by_cached_key(|path| search_root_of(&config.working_dir.join(path), config));
+    if let Some(trace) = trace {
+        trace.fuzzy_searched = true;
+        trace.fuzzy_candidates = candidates
+            .iter()
+            .filter_map(|path| {
+                let match_start = path.to_string_lossy().find(query)?;
+                Some(FuzzyCandidate {
+                    display_path: path.to_path_buf(),
+                    match_start,
+                    match_len: query.len(),
+                })
+            })
+            .collect();
+    }
+    match candidates.as_slice() {
+        [] => InputResolution::NotFound {
+            input_string: input_str,
+        },
+        [path] => InputResolution::Success(vec![resolve(path)]),
+        _ => InputResolution::Ambiguous {
+            input_string: input_str,
+            // The files aren't on disk, so the disk says nothing about them.
+            candidates: candidates
+                .into_iter()
+                .map(|path| AmbiguousMatch {
+                    scope: scope_of(&config.working_dir.join(path), config),
+                    ..ambiguous_match(path.clone(), None, query)
+                })
+                .collect(),
+        },
+    }
+}
+
+/// Resolves a `--regex` pattern to every file whose path relative to the
+/// working directory (with `/` separators) matches it, in path order. With
+/// `--at`, the revision's files are matched instead.
+pub fn resolve_regex<'a>(pattern: &'a str, config: &Config) -> InputResolution<'a> {
+    let regex = match Regex::new(pattern) {
+        Ok(regex) => regex,
+        Err(regex_error) => {
+            // Syntax errors draw the pattern over several lines; the last one
+            // says what is wrong.
+            let message = regex_error.to_string();
+            let error = message
+                .lines()
+                .last()
+                .unwrap_or(&message)
+                .trim_start_matches("error: ")
+                .to_string();
+            return InputResolution::InvalidRegex {
+                input_string: pattern,
+                error,
+            };
+        }
+    };
+    let matches = |relative_path: &Path| {
+        regex.is_match(
+            &relative_path
+                .to_string_lossy()
+                .replace(std::path::MAIN_SEPARATOR, "/"),
+        )
+    };
+
+    let matched: Vec<ResolvedFile> = if let Some(tree) = &config.git_tree {
+        tree.paths
+            .iter()
+            .filter(|path| matches(path))
+            .map(|path| {
+                ResolvedFile::new(path.clone(), config.working_dir.join(path))
+                    .at_revision(&tree.revision)
+            })
+            .collect()
+    } else if let Some(archive) = &config.archive {
+        archive
+            .paths
+            .iter()
+            .filter(|path| matches(path))
+            .map(|path| archive.resolve(path))
+            .collect()
+    } else {
+        let mut skipped = SkippedEntries::default();
+        let files = WalkDir::new(&config.working_dir)
+            .follow_links(config.follow_symlinks)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.map_err(|err| skipped.record(err.io_error())).ok())
+            .filter(is_walkdir_file_entry)
+            .filter(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(&config.working_dir)
+                    .is_ok_and(&matches)
+            })
+            .filter_map(|entry| match create_resolved_file(entry.path(), config) {
+                Ok(resolved) => Some(resolved),
+                Err(err_msg) => {
+                    eprintln!(
+                        "Warning: Regex matched file {:?} but could not process it: {}",
+                        entry.path(),
+                        err_msg
+                    );
+                    None
+                }
+            })
+            .collect();
+        skipped.warn(pattern);
+        files
+    };
+
+    if matched.is_empty() {
+        InputResolution::NotFound {
+            input_string: pattern,
+        }
+    } else {
+        check_expansion_limit(pattern, matched, config)
+    }
+}
+
+/// Splits a `path:START-END` input into its path and (1-based, inclusive) line range.
+fn split_line_range(input: &str) -> Option<(&str, LineRange)> {
+    let (path, range) = input.rsplit_once(':')?;
+    let (start, end) = range.split_once('-')?;
+    let (start, end): (usize, usize) = (start.parse().ok()?, end.parse().ok()?);
+    (!path.is_empty() && start >= 1 && start <= end).then_some((path, LineRange { start, end }))
+}
+
 /// Resolves a single input string into an `InputResolution` outcome.
 ///
-/// This function now uses a three-phase resolution strategy:
+/// Inputs prefixed with `fn:` or `sym:` are symbol searches and resolve to the
+/// file defining that symbol. A `:START-END` suffix selects a line range from
+/// whatever the rest of the input resolves to. Anything else uses a three-phase
+/// resolution strategy:
 /// 1. Direct Match: Checks if the input is a literal, existing file or directory.
 /// 2. Glob Match: If not a direct match, checks if the input is a valid glob pattern.
 /// 3. Fuzzy Search: If neither of the above, falls back to a recursive fuzzy search.
 pub fn resolve_input_string<'a>(input_str: &'a str, config: &Config) -> InputResolution<'a> {
+    resolve_input_string_traced(input_str, config, None)
+}
+
+/// Resolves a single input string like `resolve_input_string`, also returning
+/// a trace of how each phase behaved (used by `--why`).
+pub fn trace_input_string<'a>(
+    input_str: &'a str,
+    config: &Config,
+) -> (InputResolution<'a>, ResolutionTrace) {
+    let mut trace = ResolutionTrace::new(
+        config.working_dir.join(
+            if config.expand_inputs {
+                expand_input(input_str)
+            } else {
+                Cow::Borrowed(input_str)
+            }
+            .as_ref(),
+        ),
+    );
+    let resolution = resolve_input_string_traced(input_str, config, Some(&mut trace));
+    (resolution, trace)
+}
+
+fn resolve_input_string_traced<'a>(
+    input_str: &'a str,
+    config: &Config,
+    mut trace: Option<&mut ResolutionTrace>,
+) -> InputResolution<'a> {
+    if let Some(symbol) = symbol_query(input_str) {
+        return resolve_symbol(input_str, symbol, config, trace);
+    }
+
+    // A `path:START-END` suffix selects lines, unless a file by that literal name exists.
+    if let Some((path_part, line_range)) = split_line_range(input_str)
+        && !config
+            .working_dir
+            .join(lookup_query(input_str, config).as_ref())
+            .exists()
+    {
+        return match resolve_input_string_traced(path_part, config, trace) {
+            InputResolution::Success(files) => InputResolution::Success(
+                files
+                    .into_iter()
+                    .map(|file| file.with_line_range(line_range))
+                    .collect(),
+            ),
+            other => other.with_input_string(input_str),
+        };
+    }
+
+    // Filesystem lookups use the expanded form; reports keep the input as typed.
+    let expanded = lookup_query(input_str, config);
+    let query: &str = &expanded;
+
+    if let Some(tree) = &config.git_tree {
+        let resolve = |path: &PathBuf| {
+            ResolvedFile::new(path.clone(), config.working_dir.join(path))
+                .at_revision(&tree.revision)
+        };
+        return resolve_in_tree(input_str, query, &tree.paths, resolve, config, trace);
+    }
+    if let Some(archive) = &config.archive {
+        let resolve = |path: &PathBuf| archive.resolve(path);
+        return resolve_in_tree(input_str, query, &archive.paths, resolve, config, trace);
+    }
+
     // --- Phase 1: Direct Match ---
     // First, check if the input string is a literal path to an existing file or directory.
     // This ensures that filenames containing glob characters (e.g., "file[1].txt") are
     // found correctly if they exist.
-    let path_to_check = config.working_dir.join(input_str);
+    let path_to_check = config.working_dir.join(query);
     if path_to_check.exists() {
         if path_to_check.is_file() {
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.direct_match = Some(DirectMatch::File);
+            }
             return match create_resolved_file(&path_to_check, config) {
-                Ok(resolved) => InputResolution::Success(vec![resolved]),
+                Ok(resolved) => {
+                    // Named explicitly, so it's allowed, but worth pointing out.
+                    if !config.allow_outside && !is_inside_working_dir(&resolved, config) {
+                        eprintln!(
+                            "Note: '{}' is outside the working directory; including it because it was named explicitly.",
+                            input_str
+                        );
+                    }
+                    InputResolution::Success(vec![resolved])
+                }
                 Err(err_msg) => {
                     eprintln!(
                         "Warning: Found explicit file '{}' but could not process it: {}",
@@ -52,89 +828,192 @@ pub fn resolve_input_string<'a>(input_str: &'a str, config: &Config) -> InputRes
             };
         } else if path_to_check.is_dir() {
             // Expand the directory and collect all files within it.
-            let files_in_dir: Vec<ResolvedFile> = WalkDir::new(&path_to_check)
-                .min_depth(1)
-                .follow_links(true)
-                .into_iter()
-                .filter_map(|e| e.ok()) // Ignore walk errors (e.g., permissions)
-                .filter(|e| e.file_type().is_file())
-                .filter_map(|entry| match create_resolved_file(entry.path(), config) {
+            // Sorted so the expansion order doesn't depend on the filesystem.
+            // The working directory itself (e.g. `.`) is walked like
+            // `--overview`, honouring ignore files and skipping hidden
+            // entries: all of `target/` and `.git/` is rarely what was meant.
+            let mut skipped = SkippedEntries::default();
+            let is_working_dir =
+                fs::canonicalize(&path_to_check).ok() == fs::canonicalize(&config.working_dir).ok();
+            let skip = hidden_filter(query, config);
+            let paths: Vec<PathBuf> = if is_working_dir {
+                ignore_aware_walker(&path_to_check)
+                    .follow_links(config.follow_symlinks)
+                    .hidden(false)
+                    .filter_entry(move |e| e.depth() == 0 || !skip(e.file_name()))
+                    .max_depth(config.dir_depth)
+                    .sort_by_file_name(|a, b| a.cmp(b))
+                    .build()
+                    .filter_map(|e| e.map_err(|err| skipped.record(err.io_error())).ok())
+                    .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+                    .map(ignore::DirEntry::into_path)
+                    .collect()
+            } else {
+                WalkDir::new(&path_to_check)
+                    .min_depth(1)
+                    .max_depth(config.dir_depth.unwrap_or(usize::MAX))
+                    .follow_links(config.follow_symlinks)
+                    .sort_by_file_name()
+                    .into_iter()
+                    .filter_entry(|e| e.depth() == 0 || !skip(e.file_name()))
+                    .filter_map(|e| e.map_err(|err| skipped.record(err.io_error())).ok())
+                    .filter(|e| e.file_type().is_file())
+                    .map(walkdir::DirEntry::into_path)
+                    .collect()
+            };
+            let files_in_dir: Vec<ResolvedFile> = paths
+                .iter()
+                .filter_map(|path| match create_resolved_file(path, config) {
                     Ok(resolved) => Some(resolved),
                     Err(err_msg) => {
                         eprintln!(
                             "Warning: Could not process file {:?} in directory '{}': {}",
-                            entry.path(),
-                            input_str,
-                            err_msg
+                            path, input_str, err_msg
                         );
                         None
                     }
                 })
                 .collect();
-            return InputResolution::Success(files_in_dir);
+            skipped.warn(input_str);
+            if is_working_dir {
+                eprintln!(
+                    "Note: '{}' is the whole working directory; expanding it to {} file{}, skipping hidden files and those your ignore files exclude.",
+                    input_str,
+                    files_in_dir.len(),
+                    if files_in_dir.len() == 1 { "" } else { "s" }
+                );
+            }
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.direct_match = Some(DirectMatch::Directory {
+                    file_count: files_in_dir.len(),
+                });
+            }
+            return check_expansion_limit(input_str, files_in_dir, config);
         }
     }
 
     // --- Phase 2: Glob Pattern Match ---
     // If it's not a direct path, check if it looks like a glob pattern.
-    let is_glob_pattern = input_str.contains(&['*', '?', '[', '{'][..]);
+    let is_glob_pattern = query.contains(&['*', '?', '[', '{'][..]);
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.treated_as_glob = is_glob_pattern;
+    }
     if is_glob_pattern {
-        return match glob(input_str) {
+        // Validate the pattern as the user wrote it, so error positions refer to their input.
+        if let Err(pattern_error) = glob::Pattern::new(query) {
+            return InputResolution::InvalidGlobPattern {
+                input_string: input_str,
+                error: pattern_error.to_string(),
+            };
+        }
+
+        // Anchor relative patterns at the working directory (escaping it, in case
+        // the directory name itself contains glob metacharacters).
+        let pattern = if Path::new(query).is_absolute() {
+            query.to_string()
+        } else {
+            let escaped_dir = glob::Pattern::escape(&config.working_dir.to_string_lossy());
+            format!("{}{}{}", escaped_dir, std::path::MAIN_SEPARATOR, query)
+        };
+        let pattern = match glob::Pattern::new(&pattern) {
+            Ok(pattern) => pattern,
             Err(pattern_error) => {
-                // The glob pattern itself is invalid.
-                InputResolution::InvalidGlobPattern {
+                return InputResolution::InvalidGlobPattern {
                     input_string: input_str,
                     error: pattern_error.to_string(),
+                };
+            }
+        };
+
+        // Walk from the pattern's literal prefix with the same walker as a
+        // directory input, so symlinked directories are followed (or not)
+        // alike and a symlink loop is skipped instead of walked until the
+        // search budget runs out.
+        let (root, depth) = glob_walk_root(query, config);
+        if !root.exists() {
+            // The pattern's literal prefix names nothing on disk (or it was
+            // removed since the run started), so nothing can match. Walking
+            // it would report the missing root as an unreadable directory.
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.glob_match_count = Some(0);
+            }
+            return InputResolution::NotFound {
+                input_string: input_str,
+            };
+        }
+        let mut resolved_files: Vec<ResolvedFile> = Vec::new();
+        let mut skipped = SkippedEntries::default();
+        let mut budget = SearchBudget::new(config);
+        let walker = WalkDir::new(&root)
+            .max_depth(depth.unwrap_or(usize::MAX))
+            .follow_links(config.follow_symlinks)
+            .sort_by_file_name();
+        for entry in walker {
+            if let Err(reason) = budget.charge() {
+                return budget.stopped(input_str, reason);
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    skipped.record(err.io_error());
+                    continue;
                 }
+            };
+            if !entry.file_type().is_file()
+                || !pattern.matches_path_with(entry.path(), GLOB_MATCH_OPTIONS)
+            {
+                continue;
             }
-            Ok(paths) => {
-                // The glob pattern is valid; now resolve the matched paths.
-                let mut resolved_files: Vec<ResolvedFile> = Vec::new();
-                for entry in paths {
-                    match entry {
-                        Ok(path) => {
-                            if path.is_file() {
-                                match create_resolved_file(&path, config) {
-                                    Ok(resolved) => resolved_files.push(resolved),
-                                    Err(err_msg) => {
-                                        eprintln!(
-                                            "Warning: Glob matched file {:?} but could not process it: {}",
-                                            path, err_msg
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        Err(glob_error) => {
-                            eprintln!(
-                                "Warning: Error while processing glob match for '{}': {}",
-                                input_str, glob_error
-                            );
-                        }
-                    }
+            match create_resolved_file(entry.path(), config) {
+                Ok(resolved) => resolved_files.push(resolved),
+                Err(err_msg) => {
+                    eprintln!(
+                        "Warning: Glob matched file {:?} but could not process it: {}",
+                        entry.path(),
+                        err_msg
+                    );
                 }
+            }
+        }
+        skipped.warn(input_str);
 
-                if resolved_files.is_empty() {
-                    // Valid glob, but it matched no files.
-                    InputResolution::NotFound {
-                        input_string: input_str,
-                    }
-                } else {
-                    // Glob successfully matched one or more files. This is not an ambiguity.
-                    InputResolution::Success(resolved_files)
-                }
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.glob_match_count = Some(resolved_files.len());
+        }
+        return if resolved_files.is_empty() {
+            // Valid glob, but it matched no files.
+            InputResolution::NotFound {
+                input_string: input_str,
             }
+        } else {
+            // Glob successfully matched one or more files. This is not an ambiguity.
+            check_expansion_limit(input_str, resolved_files, config)
         };
     }
 
     // --- Phase 3: Fuzzy Search (Fallback) ---
     // If it's not a direct path or a glob, perform a recursive search for a partial match.
     let mut candidate_paths: Vec<PathBuf> = Vec::new();
-    let walker = WalkDir::new(&config.working_dir)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| is_walkdir_file_entry(e));
+    let mut skipped = SkippedEntries::default();
+    let skip = hidden_filter(query, config);
+    let mut budget = SearchBudget::new(config);
+    let mut stop = None;
+    let walker = search_roots(config).iter().flat_map(|root| {
+        WalkDir::new(root)
+            .follow_links(config.follow_symlinks)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !skip(e.file_name()))
+    });
+    let walker = walker
+        .take_while(|_| match budget.charge() {
+            Ok(()) => true,
+            Err(reason) => {
+                stop = Some(reason);
+                false
+            }
+        })
+        .filter_map(|e| e.map_err(|err| skipped.record(err.io_error())).ok())
+        .filter(is_walkdir_file_entry);
 
     for entry in walker {
         let entry_path = entry.path();
@@ -142,21 +1021,44 @@ pub fn resolve_input_string<'a>(input_str: &'a str, config: &Config) -> InputRes
             .unwrap_or_else(|| entry_path.to_path_buf());
 
         // Match if the relative path contains the input string.
-        if relative_path.to_string_lossy().contains(input_str) {
+        let relative_str = relative_path.to_string_lossy();
+        if let Some(match_start) = relative_str.find(query) {
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.fuzzy_candidates.push(FuzzyCandidate {
+                    display_path: relative_path.clone(),
+                    match_start,
+                    match_len: query.len(),
+                });
+            }
             candidate_paths.push(entry.into_path());
         }
     }
 
+    if let Some(reason) = stop {
+        return budget.stopped(input_str, reason);
+    }
+    skipped.warn(input_str);
     candidate_paths.sort();
     candidate_paths.dedup();
+    candidate_paths.sort_by_cached_key(|path| proximity(path, &config.invocation_dir));
+    candidate_paths.sort_by_cached_key(|path| search_root_of(path, config));
+    if let Some(trace) = trace {
+        trace.fuzzy_searched = true;
+        trace
+            .fuzzy_candidates
+            .sort_by(|a, b| a.display_path.cmp(&b.display_path));
+        trace
+            .fuzzy_candidates
+            .dedup_by(|a, b| a.display_path == b.display_path);
+    }
 
     match candidate_paths.len() {
         0 => {
             // No fuzzy matches found. Distinguish between a bad path and a simple not-found.
-            if input_str.contains(std::path::MAIN_SEPARATOR) {
+            if query.contains(std::path::MAIN_SEPARATOR) {
                 InputResolution::PathDoesNotExist {
                     input_string: input_str,
-                    path_tried: config.working_dir.join(input_str),
+                    path_tried: config.working_dir.join(query),
                 }
             } else {
                 InputResolution::NotFound {
@@ -167,7 +1069,10 @@ pub fn resolve_input_string<'a>(input_str: &'a str, config: &Config) -> InputRes
         1 => {
             // Exactly one fuzzy match found.
             match create_resolved_file(&candidate_paths[0], config) {
-                Ok(resolved) => InputResolution::Success(vec![resolved]),
+                Ok(resolved) => match check_containment(input_str, vec![resolved], config) {
+                    Ok(files) => InputResolution::Success(files),
+                    Err(refused) => refused,
+                },
                 Err(err_msg) => {
                     eprintln!(
                         "Warning: Found unique match for '{}' but failed to process it: {}",
@@ -181,15 +1086,263 @@ pub fn resolve_input_string<'a>(input_str: &'a str, config: &Config) -> InputRes
         }
         _ => {
             // Multiple fuzzy matches found, which is an ambiguity.
-            let conflicting_display_paths: Vec<PathBuf> = candidate_paths
+            let candidates: Vec<AmbiguousMatch> = candidate_paths
                 .iter()
-                .map(|p| pathdiff::diff_paths(p, &config.working_dir).unwrap_or_else(|| p.clone()))
+                .map(|p| {
+                    let display_path =
+                        pathdiff::diff_paths(p, &config.working_dir).unwrap_or_else(|| p.clone());
+                    AmbiguousMatch {
+                        scope: scope_of(p, config),
+                        ..ambiguous_match(display_path, Some(p), query)
+                    }
+                })
                 .collect();
 
             InputResolution::Ambiguous {
                 input_string: input_str,
-                conflicting_paths: conflicting_display_paths,
+                candidates,
             }
         }
     }
 }
+
+/// The directories fuzzy search walks: the `--scope-to-inputs` roots if
+/// there are any, otherwise the whole working directory.
+fn search_roots(config: &Config) -> &[PathBuf] {
+    if config.search_roots.is_empty() {
+        std::slice::from_ref(&config.working_dir)
+    } else {
+        &config.search_roots
+    }
+}
+
+/// The index of the first of `config.search_roots` that contains `path`, so
+/// candidates from the same directory input are listed together.
+fn search_root_of(path: &Path, config: &Config) -> Option<usize> {
+    config
+        .search_roots
+        .iter()
+        .position(|root| path.starts_with(root))
+}
+
+/// The directory input an ambiguous candidate was found under, relative to
+/// the working directory, when fuzzy search is scoped to inputs.
+fn scope_of(path: &Path, config: &Config) -> Option<PathBuf> {
+    let root = &config.search_roots[search_root_of(path, config)?];
+    let relative = pathdiff::diff_paths(root, &config.working_dir).unwrap_or_else(|| root.clone());
+    Some(if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative
+    })
+}
+
+/// Returns the inputs that name directories, as paths under the working
+/// directory, in the order given. These are the roots `--scope-to-inputs`
+/// confines fuzzy search to. With `--at`, a directory is one the revision
+/// has files under.
+pub fn directory_inputs(inputs: &[impl AsRef<str>], config: &Config) -> Vec<PathBuf> {
+    inputs
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|input| symbol_query(input).is_none())
+        .filter_map(|input| {
+            let path: PathBuf = config
+                .working_dir
+                .join(lookup_query(input, config).as_ref())
+                .components()
+                .filter(|component| !matches!(component, Component::CurDir))
+                .collect();
+            let tree_paths = match (&config.git_tree, &config.archive) {
+                (Some(tree), _) => Some(&tree.paths),
+                (None, Some(archive)) => Some(&archive.paths),
+                (None, None) => None,
+            };
+            let is_dir = match tree_paths {
+                Some(paths) => path
+                    .strip_prefix(&config.working_dir)
+                    .is_ok_and(|relative| {
+                        paths
+                            .iter()
+                            .any(|file| file.starts_with(relative) && file != relative)
+                    }),
+                None => path.is_dir(),
+            };
+            is_dir.then_some(path)
+        })
+        .collect()
+}
+
+/// How far a fuzzy candidate is from `invocation_dir`, for ordering ambiguous
+/// matches: the levels up from `invocation_dir` to the nearest directory the
+/// two share, then the levels down from there to the candidate. Files under
+/// `invocation_dir` come first, then those in sibling directories, then the
+/// rest; within each, shallower files come first.
+fn proximity(path: &Path, invocation_dir: &Path) -> (usize, usize) {
+    let parent = path.parent().unwrap_or(path);
+    let shared = parent
+        .components()
+        .zip(invocation_dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (
+        invocation_dir.components().count() - shared,
+        parent.components().count() - shared,
+    )
+}
+
+/// Describes one candidate of an ambiguous input: where `query` first appears
+/// in its display path, and the size and modification time of the file at
+/// `path`, if there is one on disk.
+fn ambiguous_match(display_path: PathBuf, path: Option<&Path>, query: &str) -> AmbiguousMatch {
+    let matched = Some(query)
+        .filter(|query| !query.is_empty())
+        .and_then(|query| display_path.to_string_lossy().find(query))
+        .map(|start| start..start + query.len());
+    let metadata = path.and_then(|path| fs::metadata(path).ok());
+    AmbiguousMatch {
+        display_path,
+        matched,
+        scope: None,
+        size: metadata.as_ref().map(fs::Metadata::len),
+        modified: metadata.and_then(|metadata| metadata.modified().ok()),
+    }
+}
+
+/// How glob inputs match: `*` and `?` stay within one path component, as
+/// in a shell, while `**` spans any number of them. Hidden files match.
+const GLOB_MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// The directory a glob input's walk starts from (its leading components
+/// without metacharacters, anchored at the working directory) and how deep
+/// below it a match can lie; `None` if a `**` lets it lie at any depth.
+fn glob_walk_root(query: &str, config: &Config) -> (PathBuf, Option<usize>) {
+    let mut root = if Path::new(query).is_absolute() {
+        PathBuf::from(std::path::MAIN_SEPARATOR_STR)
+    } else {
+        config.working_dir.clone()
+    };
+    let mut parts = query
+        .split(['/', std::path::MAIN_SEPARATOR])
+        .filter(|part| !part.is_empty())
+        .peekable();
+    while let Some(part) = parts.next_if(|part| !part.contains(['*', '?', '['])) {
+        root.push(part);
+    }
+    let rest: Vec<&str> = parts.collect();
+    let depth = (!rest.contains(&"**")).then_some(rest.len());
+    (root, depth)
+}
+
+/// Builds a directory walker that skips hidden files and everything git would ignore.
+///
+/// Besides the repo-local `.gitignore`, `.ignore` and `.git/info/exclude`, this
+/// honors the user's global gitignore (`core.excludesFile`, or the default
+/// `$XDG_CONFIG_HOME/git/ignore`), which typically lists editor swap files and
+/// OS cruft. Ignore files are honored even outside a git repository.
+fn ignore_aware_walker(root: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .follow_links(true)
+        .hidden(true)
+        .ignore(true)
+        .git_ignore(true)
+        .git_exclude(true)
+        .git_global(true)
+        .require_git(false);
+    builder
+}
+
+/// The pseudo-input reported when `--overview` finds nothing to include.
+pub const OVERVIEW_INPUT: &str = "--overview";
+
+/// Lists every file under the working directory that git wouldn't ignore, as
+/// paths relative to it, sorted. Used to populate the interactive picker.
+/// Files reached through a symlink that leads outside the working directory
+/// are left out, unless `--allow-outside` was given.
+pub fn project_files(config: &Config) -> Vec<PathBuf> {
+    let root = &config.working_dir;
+    let mut skipped = SkippedEntries::default();
+    let mut paths: Vec<PathBuf> = ignore_aware_walker(root)
+        .follow_links(config.follow_symlinks)
+        .build()
+        .filter_map(|e| e.map_err(|err| skipped.record(err.io_error())).ok())
+        .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+        .filter(|e| {
+            config.allow_outside
+                || create_resolved_file(e.path(), config)
+                    .is_ok_and(|file| is_inside_working_dir(&file, config))
+        })
+        .map(|entry| pathdiff::diff_paths(entry.path(), root).unwrap_or_else(|| entry.into_path()))
+        .collect();
+    skipped.warn(".");
+    paths.sort();
+    paths
+}
+
+/// Resolves every source file under the working directory that skeleton mode
+/// can handle, for use by `--overview`.
+///
+/// Unlike directory expansion, this walk respects `.gitignore` (including the
+/// global gitignore), `.ignore` and hidden-file conventions, since the goal is
+/// a map of the project's own code rather than everything on disk. Otherwise
+/// it's limited like one: by `--dir-depth`, the default excludes, the working
+/// directory and `--max-files-per-input`.
+pub fn resolve_overview(config: &Config) -> InputResolution<'static> {
+    if let Some(archive) = &config.archive {
+        let files: Vec<ResolvedFile> = archive
+            .paths
+            .iter()
+            .filter(|path| {
+                language::detect_language(path, "")
+                    .is_some_and(|key| symbol_extractor::is_supported_extension(&key))
+            })
+            .map(|path| archive.resolve(path))
+            .collect();
+        return if files.is_empty() {
+            InputResolution::NotFound {
+                input_string: OVERVIEW_INPUT,
+            }
+        } else {
+            check_expansion_limit(OVERVIEW_INPUT, files, config)
+        };
+    }
+    let mut skipped = SkippedEntries::default();
+    let mut resolved_files: Vec<ResolvedFile> = ignore_aware_walker(&config.working_dir)
+        .follow_links(config.follow_symlinks)
+        .max_depth(config.dir_depth)
+        .build()
+        .filter_map(|e| e.map_err(|err| skipped.record(err.io_error())).ok())
+        .filter(|e| e.file_type().is_some_and(|ft| ft.is_file()))
+        .filter(|e| {
+            language::detect_language(e.path(), "")
+                .is_some_and(|key| symbol_extractor::is_supported_extension(&key))
+        })
+        .filter_map(|entry| match create_resolved_file(entry.path(), config) {
+            Ok(resolved) => Some(resolved),
+            Err(err_msg) => {
+                eprintln!(
+                    "Warning: Could not process file {:?} for overview: {}",
+                    entry.path(),
+                    err_msg
+                );
+                None
+            }
+        })
+        .collect();
+    skipped.warn(OVERVIEW_INPUT);
+
+    if resolved_files.is_empty() {
+        return InputResolution::NotFound {
+            input_string: OVERVIEW_INPUT,
+        };
+    }
+
+    // Sort so the overview reads in a stable, directory-grouped order.
+    resolved_files.sort_by(|a, b| a.display_path().cmp(b.display_path()));
+    check_expansion_limit(OVERVIEW_INPUT, resolved_files, config)
+}
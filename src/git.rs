@@ -0,0 +1,317 @@
+// src/git.rs
+
+use crate::types::LineRange;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The files in a git revision, as seen from the working directory.
+#[derive(Debug, Clone)]
+pub struct GitTree {
+    /// The revision as the user wrote it (e.g. `v1.4.0`, `HEAD~3`).
+    pub revision: String,
+    /// Every file in the revision under the working directory, relative to it, sorted.
+    pub paths: Vec<PathBuf>,
+}
+
+/// Runs `git` in `working_dir` and returns its stdout, or its stderr as the error.
+fn run_git(working_dir: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(working_dir)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Fails with a message naming `flag` unless `working_dir` is inside a git repository.
+pub fn require_repository(working_dir: &Path, flag: &str) -> Result<(), String> {
+    run_git(working_dir, &["rev-parse", "--show-toplevel"])
+        .map(|_| ())
+        .map_err(|_| {
+            format!(
+                "{} requires the working directory to be inside a git repository",
+                flag
+            )
+        })
+}
+
+/// Returns the output of `git --version`, or why git couldn't be run.
+pub fn version(working_dir: &Path) -> Result<String, String> {
+    let output = run_git(working_dir, &["--version"])?;
+    Ok(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+impl GitTree {
+    /// Lists the files of `revision` under `working_dir`.
+    ///
+    /// Fails if `working_dir` isn't inside a git repository or the revision
+    /// doesn't name a commit or tree.
+    pub fn load(working_dir: &Path, revision: &str) -> Result<Self, String> {
+        require_repository(working_dir, "--at")?;
+        run_git(
+            working_dir,
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{}^{{tree}}", revision),
+            ],
+        )
+        .map_err(|_| format!("Unknown git revision '{}'", revision))?;
+
+        // Run from the working directory, `ls-tree` lists only the files beneath
+        // it, with paths relative to it.
+        let listing = run_git(
+            working_dir,
+            &["ls-tree", "-r", "-z", "--name-only", revision],
+        )?;
+        let mut paths: Vec<PathBuf> = listing
+            .split(|byte| *byte == 0)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| PathBuf::from(String::from_utf8_lossy(entry).into_owned()))
+            .collect();
+        paths.sort();
+
+        Ok(Self {
+            revision: revision.to_string(),
+            paths,
+        })
+    }
+}
+
+impl GitTree {
+    /// Returns true if the revision has a file at `path` (relative to the working directory).
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths
+            .binary_search_by(|p| p.as_path().cmp(path))
+            .is_ok()
+    }
+}
+
+/// The lines of each file that changed between a revision and the working tree.
+#[derive(Debug, Clone)]
+pub struct ChangedLines {
+    /// The revision as the user wrote it.
+    pub revision: String,
+    /// Changed line ranges in the current version of each file, keyed by path
+    /// relative to the working directory. Deleted files are left out.
+    pub files: BTreeMap<PathBuf, Vec<LineRange>>,
+}
+
+impl ChangedLines {
+    /// Collects the changes under `working_dir` since `revision`, from
+    /// `git diff`. Untracked files aren't part of the diff, so they're absent.
+    pub fn load(working_dir: &Path, revision: &str) -> Result<Self, String> {
+        require_repository(working_dir, "--changed-only")?;
+        run_git(
+            working_dir,
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{}^{{commit}}", revision),
+            ],
+        )
+        .map_err(|_| format!("Unknown git revision '{}'", revision))?;
+
+        // Zero context lines make every hunk exactly the changed lines;
+        // `--relative` limits the diff to the working directory and makes its
+        // paths relative to it.
+        let diff = run_git(
+            working_dir,
+            &[
+                "-c",
+                "core.quotepath=off",
+                "diff",
+                "--no-color",
+                "--no-ext-diff",
+                "--no-prefix",
+                "--unified=0",
+                "--relative",
+                revision,
+                "--",
+            ],
+        )?;
+        Ok(Self {
+            revision: revision.to_string(),
+            files: parse_changed_lines(&String::from_utf8_lossy(&diff)),
+        })
+    }
+
+    /// Returns the changed ranges of `path` (relative to the working
+    /// directory), or `None` if it didn't change.
+    pub fn ranges(&self, path: &Path) -> Option<&[LineRange]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+}
+
+/// Reads the new-side line ranges of each hunk in a `--unified=0` diff.
+fn parse_changed_lines(diff: &str) -> BTreeMap<PathBuf, Vec<LineRange>> {
+    let mut files: BTreeMap<PathBuf, Vec<LineRange>> = BTreeMap::new();
+    let mut current: Option<PathBuf> = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            current = (path != "/dev/null").then(|| PathBuf::from(path));
+        } else if let Some(hunk) = line.strip_prefix("@@ ")
+            && let Some(path) = &current
+            && let Some(range) = hunk_new_range(hunk)
+        {
+            files.entry(path.clone()).or_default().push(range);
+        }
+    }
+    files
+}
+
+/// Parses the `+START[,COUNT]` part of a hunk header. A pure deletion
+/// (`COUNT` 0) becomes the line it follows, so its surroundings are shown.
+fn hunk_new_range(hunk: &str) -> Option<LineRange> {
+    let new_side = hunk
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match new_side.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (new_side.parse::<usize>().ok()?, 1),
+    };
+    Some(match count {
+        0 => LineRange {
+            start: start.max(1),
+            end: start.max(1),
+        },
+        _ => LineRange {
+            start,
+            end: start + count - 1,
+        },
+    })
+}
+
+/// The commit that last touched some lines of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameCommit {
+    pub author: String,
+    /// The author time, in seconds since the Unix epoch.
+    pub time: i64,
+    /// False for lines changed in the working tree but not yet committed.
+    pub committed: bool,
+}
+
+/// Which commit last touched each line of a file in the working tree.
+#[derive(Debug, Clone)]
+pub struct Blame {
+    commits: Vec<BlameCommit>,
+    /// For each line (0-based), an index into `commits`.
+    lines: Vec<usize>,
+}
+
+impl BlameCommit {
+    /// A short note on the change, e.g. `changed 3 days ago by Ada`, with its
+    /// age measured back from `now` (Unix seconds).
+    pub fn summary(&self, now: i64) -> String {
+        if !self.committed {
+            return "uncommitted changes".to_string();
+        }
+        let days = (now - self.time).max(0) / (24 * 60 * 60);
+        let plural = |count: i64, unit: &str| {
+            format!(
+                "{} {}{} ago",
+                count,
+                unit,
+                if count == 1 { "" } else { "s" }
+            )
+        };
+        let age = match days {
+            0 => "today".to_string(),
+            1..60 => plural(days, "day"),
+            60..730 => plural(days / 30, "month"),
+            _ => plural(days / 365, "year"),
+        };
+        format!("changed {} by {}", age, self.author)
+    }
+}
+
+impl Blame {
+    /// Runs `git blame` on the working-tree file at absolute `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let run_dir = path
+            .parent()
+            .ok_or_else(|| format!("No directory above {:?}", path))?;
+        let file_name = path.file_name().unwrap_or(path.as_os_str());
+        let output = run_git(
+            run_dir,
+            &["blame", "--porcelain", "--", &file_name.to_string_lossy()],
+        )?;
+        Ok(Self::parse(&String::from_utf8_lossy(&output)))
+    }
+
+    /// Reads `git blame --porcelain` output. Each line's entry starts with a
+    /// `SHA ORIG FINAL [COUNT]` header; the first entry for a commit is
+    /// followed by its details, and every entry ends with the tab-prefixed line.
+    fn parse(porcelain: &str) -> Self {
+        let mut commits: Vec<BlameCommit> = Vec::new();
+        let mut by_sha: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut lines = Vec::new();
+        let mut current: Option<usize> = None;
+        for line in porcelain.lines() {
+            if line.starts_with('\t') {
+                if let Some(index) = current.take() {
+                    lines.push(index);
+                }
+            } else if let Some(index) = current {
+                if let Some(author) = line.strip_prefix("author ") {
+                    commits[index].author = author.to_string();
+                } else if let Some(time) = line.strip_prefix("author-time ") {
+                    commits[index].time = time.parse().unwrap_or(0);
+                }
+            } else if let Some(sha) = line.split(' ').next() {
+                let index = *by_sha.entry(sha).or_insert_with(|| {
+                    commits.push(BlameCommit {
+                        author: String::new(),
+                        time: 0,
+                        committed: sha.bytes().any(|byte| byte != b'0'),
+                    });
+                    commits.len() - 1
+                });
+                current = Some(index);
+            }
+        }
+        Self { commits, lines }
+    }
+
+    /// Returns the most recent commit to touch any line in `range`.
+    /// Uncommitted changes count as the most recent of all.
+    pub fn latest_in(&self, range: LineRange) -> Option<&BlameCommit> {
+        self.lines
+            .get(range.start.saturating_sub(1)..range.end.min(self.lines.len()))?
+            .iter()
+            .map(|&index| &self.commits[index])
+            .max_by_key(|commit| (!commit.committed, commit.time))
+    }
+}
+
+/// Reads the file at absolute `path` as of `revision`.
+///
+/// The file (or even its directory) may no longer exist on disk, so git runs
+/// from the nearest directory above it that does.
+pub fn read_file_at(path: &Path, revision: &str) -> Result<String, String> {
+    let run_dir = path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.is_dir())
+        .ok_or_else(|| format!("No existing directory above {:?}", path))?;
+    let relative_path = path.strip_prefix(run_dir).unwrap_or(path);
+    // `./` makes git resolve the path relative to `run_dir` rather than the
+    // repository root. Git object paths always use forward slashes.
+    let object = format!(
+        "{}:./{}",
+        revision,
+        relative_path
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/")
+    );
+    let bytes = run_git(run_dir, &["show", &object])?;
+    String::from_utf8(bytes).map_err(|_| format!("{} is not valid UTF-8", object))
+}
@@ -0,0 +1,101 @@
+// src/grammars.rs
+
+//! Tree-sitter grammars loaded at runtime from shared libraries, for languages
+//! without a built-in grammar. Only libraries listed in the user's own config
+//! file are ever loaded; `.ctx-pick.toml` files in a project can't add any.
+
+use crate::user_config;
+use libloading::Library;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use tree_sitter::{Language, Parser};
+use tree_sitter_language::LanguageFn;
+
+/// A grammar listed in the user config as `[grammars.<language key>]`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserGrammar {
+    /// The shared library (`.so`, `.dylib` or `.dll`) the grammar is built into.
+    pub library: PathBuf,
+    /// The function returning the language; `tree_sitter_<key>` by default.
+    pub symbol: Option<String>,
+}
+
+/// Each user grammar loaded so far, or why it couldn't be, so a library is
+/// loaded (and a failure reported) only once.
+static LOADED: LazyLock<Mutex<HashMap<String, Result<Language, String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The grammars listed in the user config, by language key.
+pub fn configured() -> &'static BTreeMap<String, UserGrammar> {
+    &user_config::get().grammars
+}
+
+/// Returns the user's grammar for a language key: `None` if the config lists
+/// none, otherwise the language or why its library couldn't be loaded. The
+/// first failure for each key is also printed as a warning.
+pub fn language(key: &str) -> Option<Result<Language, String>> {
+    let grammar = configured().get(key)?;
+    let mut loaded = LOADED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let result = loaded.entry(key.to_string()).or_insert_with(|| {
+        let result = load(key, grammar);
+        if let Err(e) = &result {
+            eprintln!("Warning: {}", e);
+        }
+        result
+    });
+    Some(result.clone())
+}
+
+/// Loads the grammar for `key` from its library and checks that a parser
+/// accepts it (its ABI version must be one this tree-sitter supports).
+pub fn load(key: &str, grammar: &UserGrammar) -> Result<Language, String> {
+    let symbol = grammar
+        .symbol
+        .clone()
+        .unwrap_or_else(|| format!("tree_sitter_{}", key));
+    // SAFETY: loading a library runs its initializers, and the symbol is
+    // trusted to be a tree-sitter language function, just as a grammar's
+    // generated bindings trust it. Only libraries the user listed in their
+    // own config get here.
+    let language = unsafe {
+        let library = Library::new(&grammar.library).map_err(|e| {
+            format!(
+                "Could not load grammar library {:?}: {}",
+                grammar.library,
+                describe(&e)
+            )
+        })?;
+        let function = *library
+            .get::<unsafe extern "C" fn() -> *const ()>(symbol.as_bytes())
+            .map_err(|e| {
+                format!(
+                    "Grammar library {:?} has no '{}': {}",
+                    grammar.library,
+                    symbol,
+                    describe(&e)
+                )
+            })?;
+        // The language's tables live in the library, so it stays loaded for
+        // the rest of the process.
+        std::mem::forget(library);
+        Language::new(LanguageFn::from_raw(function))
+    };
+    Parser::new()
+        .set_language(&language)
+        .map_err(|e| format!("Grammar library {:?} can't be used: {}", grammar.library, e))?;
+    Ok(language)
+}
+
+/// A libloading error with the system's reason (e.g. from `dlerror`), which
+/// its own message leaves out.
+fn describe(e: &libloading::Error) -> String {
+    match std::error::Error::source(e) {
+        Some(source) => format!("{}: {}", e, source),
+        None => e.to_string(),
+    }
+}
@@ -0,0 +1,151 @@
+// src/imports.rs
+
+use crate::symbol_extractor;
+use crate::types::{FileContext, ImportStatement};
+use std::collections::HashMap;
+
+/// The comment marker for each language `extract_imports` supports.
+fn comment_prefix(language: &str) -> &'static str {
+    match language {
+        "py" => "#",
+        _ => "//",
+    }
+}
+
+/// Collapses whitespace so formatting differences don't keep two copies of
+/// the same import apart.
+fn normalize(import: &str) -> String {
+    import.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Moves import statements found in more than one full-content file into a
+/// "Shared imports" block per language, placed before the files (for
+/// `--dedupe-imports`). Each block groups the imports by the files that use
+/// them, and each file gets a comment where its shared imports were.
+///
+/// Skeletons and languages without import support are left alone.
+pub fn dedupe(contexts: Vec<FileContext>) -> Vec<FileContext> {
+    let imports: Vec<Vec<ImportStatement>> = contexts
+        .iter()
+        .map(|context| match (&context.language, context.depth) {
+            (Some(language), None) if symbol_extractor::supports_imports(language) => {
+                symbol_extractor::extract_imports(&context.content, language).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        })
+        .collect();
+
+    // For each language and import, in order of first appearance: the import's
+    // first spelling and the contexts that contain it.
+    let mut languages: Vec<&str> = Vec::new();
+    let mut by_import: HashMap<(&str, String), (&str, Vec<usize>)> = HashMap::new();
+    let mut order: Vec<(&str, String)> = Vec::new();
+    for (index, (context, file_imports)) in contexts.iter().zip(&imports).enumerate() {
+        let Some(language) = context.language.as_deref() else {
+            continue;
+        };
+        for import in file_imports {
+            let key = (language, normalize(&import.text));
+            let (_, users) = by_import.entry(key.clone()).or_insert_with(|| {
+                order.push(key);
+                (import.text.as_str(), Vec::new())
+            });
+            if !users.contains(&index) {
+                users.push(index);
+            }
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+    }
+    let is_shared = |language: &str, import: &ImportStatement| {
+        by_import
+            .get(&(language, normalize(&import.text)))
+            .is_some_and(|(_, users)| users.len() > 1)
+    };
+
+    let mut headers = Vec::new();
+    for language in languages {
+        // Imports used by the same set of files are listed together.
+        let mut groups: Vec<(&Vec<usize>, Vec<&str>)> = Vec::new();
+        for key in order
+            .iter()
+            .filter(|(key_language, _)| *key_language == language)
+        {
+            let (text, users) = &by_import[key];
+            if users.len() < 2 {
+                continue;
+            }
+            match groups
+                .iter_mut()
+                .find(|(group_users, _)| *group_users == users)
+            {
+                Some((_, texts)) => texts.push(text),
+                None => groups.push((users, vec![text])),
+            }
+        }
+        if groups.is_empty() {
+            continue;
+        }
+        let content = groups
+            .iter()
+            .map(|(users, texts)| {
+                let files: Vec<&str> = users
+                    .iter()
+                    .map(|&index| contexts[index].display_path.as_str())
+                    .collect();
+                format!(
+                    "{} Used by {}\n{}\n",
+                    comment_prefix(language),
+                    files.join(", "),
+                    texts.join("\n")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        headers.push(FileContext::new(
+            format!("Shared imports ({})", language),
+            content,
+            Some(language.to_string()),
+            None,
+        ));
+    }
+
+    // Which of each file's imports are shared, before the contexts are consumed.
+    let shared: Vec<Vec<&ImportStatement>> = contexts
+        .iter()
+        .zip(&imports)
+        .map(|(context, file_imports)| {
+            let language = context.language.as_deref().unwrap_or("");
+            file_imports
+                .iter()
+                .filter(|import| is_shared(language, import))
+                .collect()
+        })
+        .collect();
+
+    let files = contexts.into_iter().zip(shared).map(|(context, shared)| {
+        let Some(language) = context.language.as_deref().filter(|_| !shared.is_empty()) else {
+            return context;
+        };
+        let mut content = String::new();
+        let mut copied_up_to = 0;
+        for (position, import) in shared.iter().enumerate() {
+            content.push_str(&context.content[copied_up_to..import.lines.start]);
+            if position == 0 {
+                content.push_str(&format!(
+                    "{} ({} shared import{} listed under \"Shared imports ({})\")\n",
+                    comment_prefix(language),
+                    shared.len(),
+                    if shared.len() == 1 { "" } else { "s" },
+                    language
+                ));
+            }
+            copied_up_to = import.lines.end;
+        }
+        content.push_str(&context.content[copied_up_to..]);
+        context.with_content(content)
+    });
+
+    headers.into_iter().chain(files).collect()
+}
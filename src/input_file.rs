@@ -0,0 +1,114 @@
+// src/input_file.rs
+
+//! Reads `--input-file` lists: one selection per line, written like a command
+//! line (`src/**/*.ts --exclude '*.test.ts'`), with options that apply to the
+//! files that line's inputs resolve to. Each line goes through clap, so the
+//! syntax and error messages match the real command line.
+
+use crate::error::AppError;
+use crate::types::ResolvedFile;
+use clap::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The options a line of an input file may carry.
+#[derive(Parser, Debug)]
+#[command(
+    name = "input-file line",
+    no_binary_name = true,
+    disable_help_flag = true
+)]
+struct LineArgs {
+    /// Inputs, in any form the command line accepts.
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Skeleton depth for this line's files, over --depth and .ctx-pick.toml.
+    #[arg(long, value_name = "LEVEL", conflicts_with = "full")]
+    depth: Option<usize>,
+
+    /// Full content for this line's files, over --depth and .ctx-pick.toml.
+    #[arg(long)]
+    full: bool,
+
+    /// Leave out this line's files matching a glob. A pattern without a `/`
+    /// is matched against file names, otherwise against the path from the
+    /// working directory.
+    #[arg(long, value_name = "GLOB", value_parser = glob::Pattern::new)]
+    exclude: Vec<glob::Pattern>,
+}
+
+/// One line of an input file.
+#[derive(Debug)]
+pub struct InputEntry {
+    /// The input file, for messages.
+    pub source: PathBuf,
+    /// The 1-based line number, for messages.
+    pub line: usize,
+    pub inputs: Vec<String>,
+    /// `Some(None)` for `--full`, `Some(Some(depth))` for `--depth`, and
+    /// `None` when the line leaves the depth to the rest of the run.
+    pub depth: Option<Option<usize>>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl InputEntry {
+    /// Whether one of the line's `--exclude` patterns matches `file`.
+    pub fn excludes(&self, file: &ResolvedFile) -> bool {
+        let path = file.display_path();
+        let name = Path::new(path.file_name().unwrap_or_default());
+        self.exclude.iter().any(|pattern| {
+            if pattern.as_str().contains('/') {
+                pattern.matches_path(path)
+            } else {
+                pattern.matches_path(name)
+            }
+        })
+    }
+
+    /// Where the entry came from, as `FILE:LINE`.
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.source.display(), self.line)
+    }
+}
+
+/// Reads the entries of the input file at `path`.
+pub fn read(path: &Path) -> Result<Vec<InputEntry>, AppError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| AppError::IoError(format!("Could not read --input-file {:?}: {}", path, e)))?;
+    parse(&text, path).map_err(AppError::InputFileError)
+}
+
+/// Parses the text of an input file. Blank lines and lines starting with `#`
+/// are skipped; words are split and quoted as in a POSIX shell.
+pub fn parse(text: &str, source: &Path) -> Result<Vec<InputEntry>, String> {
+    let mut entries = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let location = format!("{}:{}", source.display(), index + 1);
+        let words = shlex::split(trimmed)
+            .ok_or_else(|| format!("{}: unbalanced quotes or trailing backslash", location))?;
+        let args = LineArgs::try_parse_from(words).map_err(|e| {
+            // clap's rendering ends with a usage section that names the
+            // internal parser; the first line says what's wrong.
+            let rendered = e.to_string();
+            let message = rendered.lines().next().unwrap_or_default();
+            format!("{}: {}", location, message.trim_start_matches("error: "))
+        })?;
+        entries.push(InputEntry {
+            source: source.to_path_buf(),
+            line: index + 1,
+            inputs: args.inputs,
+            depth: if args.full {
+                Some(None)
+            } else {
+                args.depth.map(Some)
+            },
+            exclude: args.exclude,
+        });
+    }
+    Ok(entries)
+}
@@ -0,0 +1,35 @@
+//! Ctrl-C handling. The first Ctrl-C asks the run to stop: a glob or fuzzy
+//! search in progress ends early, and nothing is copied or written. A second
+//! Ctrl-C exits at once, for when the run is stuck somewhere that doesn't
+//! check for the first.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use signal_hook::consts::SIGINT;
+
+/// The exit code after an interrupt, as shells report for SIGINT.
+pub const EXIT_CODE: i32 = 130;
+
+static REQUESTED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Replaces the default Ctrl-C behavior (killing the process) with a request
+/// to stop that `requested` reports. Does nothing if it can't be installed.
+pub fn install() {
+    let flag = Arc::new(AtomicBool::new(false));
+    // Registered first, so it sees the flag before this Ctrl-C sets it, and
+    // only exits on a second one.
+    if signal_hook::flag::register_conditional_shutdown(SIGINT, EXIT_CODE, Arc::clone(&flag))
+        .is_ok()
+        && signal_hook::flag::register(SIGINT, Arc::clone(&flag)).is_ok()
+    {
+        let _ = REQUESTED.set(flag);
+    }
+}
+
+/// Whether Ctrl-C has been pressed since `install`.
+pub fn requested() -> bool {
+    REQUESTED
+        .get()
+        .is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
@@ -0,0 +1,369 @@
+// src/language.rs
+
+use regex::Regex;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Well-known filenames and the language key they map to. These take
+/// precedence over the extension (e.g. `CMakeLists.txt` is CMake, not text).
+const KNOWN_FILENAMES: &[(&str, &str)] = &[
+    ("Makefile", "makefile"),
+    ("makefile", "makefile"),
+    ("GNUmakefile", "makefile"),
+    ("Dockerfile", "dockerfile"),
+    ("Containerfile", "dockerfile"),
+    ("Jenkinsfile", "groovy"),
+    ("Rakefile", "rb"),
+    ("Gemfile", "rb"),
+    ("Vagrantfile", "rb"),
+    ("Justfile", "just"),
+    ("justfile", "just"),
+    ("CMakeLists.txt", "cmake"),
+    ("LICENSE", "text"),
+    ("LICENCE", "text"),
+    ("COPYING", "text"),
+    ("NOTICE", "text"),
+    ("AUTHORS", "text"),
+    ("CODEOWNERS", "text"),
+    (".gitignore", "gitignore"),
+    (".dockerignore", "gitignore"),
+    (".gitattributes", "gitattributes"),
+    (".editorconfig", "ini"),
+    (".env", "sh"),
+];
+
+/// Filename prefixes for families of related files, e.g. `Dockerfile.dev`.
+const KNOWN_FILENAME_PREFIXES: &[(&str, &str)] = &[
+    ("Dockerfile.", "dockerfile"),
+    ("Containerfile.", "dockerfile"),
+    ("Makefile.", "makefile"),
+    (".env.", "sh"),
+];
+
+/// Shebang interpreters (with version suffixes stripped) and their language key.
+const KNOWN_INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "py"),
+    ("sh", "sh"),
+    ("bash", "sh"),
+    ("dash", "sh"),
+    ("zsh", "sh"),
+    ("ksh", "sh"),
+    ("node", "js"),
+    ("deno", "ts"),
+    ("bun", "ts"),
+    ("ts-node", "ts"),
+    ("ruby", "rb"),
+    ("perl", "pl"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("elixir", "exs"),
+    ("escript", "erl"),
+];
+
+/// Emacs's `-*- mode: python -*-` (or `-*- python -*-`) on one of the first
+/// two lines.
+static EMACS_MODELINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"-\*-\s*(?:.*?\bmode:\s*)?([\w+-]+)\s*(?:;.*?)?-\*-").unwrap());
+
+/// Vim's `vim: set ft=python:` (or `filetype=`) on one of the first or last
+/// five lines.
+static VIM_MODELINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|\s)(?:vim?|ex):.*?\b(?:ft|filetype)=([\w+-]+)").unwrap());
+
+/// Openings that identify a file from its first non-blank line.
+const LEADING_MARKERS: &[(&str, &str)] = &[
+    ("<?php", "php"),
+    ("<?xml", "xml"),
+    ("<!doctype html", "html"),
+    ("<html", "html"),
+];
+
+/// Lines characteristic of a language, for guessing the language of a file
+/// with no extension, known name, shebang or modeline.
+static KEYWORD_MARKERS: LazyLock<Vec<(&'static str, Vec<Regex>)>> = LazyLock::new(|| {
+    let markers: &[(&str, &[&str])] = &[
+        (
+            "py",
+            &[
+                r"^(?:async\s+)?def\s+\w+\s*\(.*\)\s*(?:->.*)?:$",
+                r"^from\s+[\w.]+\s+import\s",
+                r"^class\s+\w+\s*(?:\(.*\))?\s*:$",
+                r"^if\s+__name__\s*==",
+            ],
+        ),
+        (
+            "sh",
+            &[
+                r"^\s*(?:fi|done|esac)$",
+                r"^\s*(?:if|while)\s+\[\[?\s",
+                r"^set\s+-[euxo]",
+                r"^\s*export\s+[A-Z_][A-Z0-9_]*=",
+                r"^[\w-]+\s*\(\)\s*\{$",
+            ],
+        ),
+        (
+            "rs",
+            &[
+                r"^\s*(?:pub(?:\([\w:]+\))?\s+)?fn\s+\w+\s*[<(]",
+                r"^use\s+(?:crate|std|super|self)::",
+                r"^\s*impl\b.*\{$",
+                r"^\s*let\s+mut\s",
+            ],
+        ),
+        (
+            "js",
+            &[
+                r#"\brequire\(['"][^'"]+['"]\)"#,
+                r"^module\.exports\b",
+                r"^\s*(?:export\s+)?(?:const|let)\s+\w+\s*=\s*(?:async\s*)?\(.*\)\s*=>",
+                r"^\s*console\.log\(",
+            ],
+        ),
+        (
+            "rb",
+            &[
+                r#"^\s*require(?:_relative)?\s+['"]"#,
+                r"^\s*def\s+[\w?!.]+(?:\(.*\))?$",
+                r"^\s*(?:module|class)\s+[A-Z]\w*(?:\s*<\s*[A-Z][\w:]*)?$",
+            ],
+        ),
+        (
+            "lua",
+            &[
+                r"^\s*local\s+function\s",
+                r"^\s*local\s+\w+\s*=",
+                r"^\s*function\s+[\w.:]+\s*\(.*\)$",
+            ],
+        ),
+    ];
+    markers
+        .iter()
+        .map(|(key, patterns)| {
+            let patterns = patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern).unwrap())
+                .collect();
+            (*key, patterns)
+        })
+        .collect()
+});
+
+/// How many lines from the top of a file the keyword heuristic looks at.
+const KEYWORD_SCAN_LINES: usize = 100;
+
+/// Common language names and the language key (extension) they correspond to.
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("rust", "rs"),
+    ("python", "py"),
+    ("typescript", "ts"),
+    ("javascript", "js"),
+    ("shell", "sh"),
+    ("bash", "sh"),
+    ("ruby", "rb"),
+    ("perl", "pl"),
+    ("lua", "lua"),
+    ("vimscript", "vim"),
+    ("viml", "vim"),
+    ("elixir", "ex"),
+    ("erlang", "erl"),
+    ("terraform", "tf"),
+    ("hcl", "hcl"),
+    ("make", "makefile"),
+    ("docker", "dockerfile"),
+];
+
+/// Language keys that are common fence hints without appearing in the
+/// tables above, for validating `--lang-override`.
+const COMMON_LANGUAGE_KEYS: &[&str] = &[
+    "c", "cc", "cpp", "cs", "css", "csv", "dart", "diff", "go", "graphql", "h", "hpp", "hs",
+    "html", "java", "jl", "json", "jsonc", "jsx", "kt", "less", "md", "ml", "php", "proto", "r",
+    "scala", "scss", "sql", "swift", "tex", "toml", "tsx", "txt", "xml", "yaml", "yml",
+];
+
+/// Whether `key` is a language key ctx-pick knows: one it detects, one
+/// named in its tables, a common fence hint, or one it can skeletonize.
+pub fn is_known_language(key: &str) -> bool {
+    COMMON_LANGUAGE_KEYS.contains(&key)
+        || [
+            KNOWN_FILENAMES,
+            KNOWN_FILENAME_PREFIXES,
+            KNOWN_INTERPRETERS,
+            LEADING_MARKERS,
+            LANGUAGE_NAMES,
+        ]
+        .iter()
+        .any(|table| table.iter().any(|(_, known)| *known == key))
+        || KEYWORD_MARKERS.iter().any(|(known, _)| *known == key)
+        || crate::symbol_extractor::is_supported_extension(key)
+        || crate::symbol_extractor::is_always_full(key)
+}
+
+/// Normalizes a user-supplied language (a name like `rust` or an extension
+/// like `rs`) into a language key. Unknown values are passed through lowercased.
+pub fn language_key_from_name(name: &str) -> String {
+    let lowered = name.trim().trim_start_matches('.').to_ascii_lowercase();
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(full_name, _)| *full_name == lowered)
+        .map(|(_, key)| key.to_string())
+        .unwrap_or(lowered)
+}
+
+/// Looks up a language key by filename alone: an exact match against the
+/// well-known names table, or a well-known prefix such as `Dockerfile.`.
+pub fn language_for_filename(file_name: &str) -> Option<&'static str> {
+    KNOWN_FILENAMES
+        .iter()
+        .find(|(name, _)| *name == file_name)
+        .or_else(|| {
+            KNOWN_FILENAME_PREFIXES
+                .iter()
+                .find(|(prefix, _)| file_name.starts_with(prefix))
+        })
+        .map(|(_, key)| *key)
+}
+
+/// Determines the language key for a file, used both as the Markdown fence
+/// hint and to select a skeleton grammar.
+///
+/// Well-known filenames (e.g. `Makefile`, `Dockerfile.dev`, `.gitignore`) are
+/// checked first, then the file extension, then the shebang line of `content`
+/// (e.g. `#!/usr/bin/env python3`), and finally, for files such as
+/// `bin/deploy`, whatever `language_from_content` can tell.
+pub fn detect_language(path: &Path, content: &str) -> Option<String> {
+    if let Some(key) = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .and_then(language_for_filename)
+    {
+        return Some(key.to_string());
+    }
+
+    if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
+        return Some(ext.to_string());
+    }
+
+    language_from_shebang(content)
+        .map(str::to_string)
+        .or_else(|| language_from_content(content))
+}
+
+/// Guesses a language from content alone: an Emacs or Vim modeline, a
+/// telling first line such as `<?php`, or else lines characteristic of one
+/// language. Deliberately conservative: a guess needs at least two such lines
+/// and none pointing at another language, and anything less gets no answer
+/// rather than a wrong one.
+fn language_from_content(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let modeline = lines
+        .iter()
+        .take(2)
+        .find_map(|line| EMACS_MODELINE.captures(line))
+        .or_else(|| {
+            lines
+                .iter()
+                .take(5)
+                .chain(lines.iter().skip(5).rev().take(5))
+                .find_map(|line| VIM_MODELINE.captures(line))
+        });
+    if let Some(captures) = modeline {
+        return Some(language_key_from_name(&captures[1]));
+    }
+
+    let first_line = lines.iter().find(|line| !line.trim().is_empty())?;
+    let first_line = first_line.trim_start().to_ascii_lowercase();
+    if let Some((_, key)) = LEADING_MARKERS
+        .iter()
+        .find(|(marker, _)| first_line.starts_with(marker))
+    {
+        return Some(key.to_string());
+    }
+
+    let scanned = &lines[..lines.len().min(KEYWORD_SCAN_LINES)];
+    let mut matching = KEYWORD_MARKERS
+        .iter()
+        .map(|(key, patterns)| {
+            let count = scanned
+                .iter()
+                .filter(|line| {
+                    let line = line.trim_end();
+                    patterns.iter().any(|pattern| pattern.is_match(line))
+                })
+                .count();
+            (*key, count)
+        })
+        .filter(|(_, count)| *count > 0);
+    match (matching.next(), matching.next()) {
+        (Some((key, count)), None) if count >= 2 => Some(key.to_string()),
+        _ => None,
+    }
+}
+
+/// A language forced on the files matching a glob (`--lang-override`).
+#[derive(Debug, Clone)]
+pub struct LanguageOverride {
+    pattern: glob::Pattern,
+    language: String,
+}
+
+impl LanguageOverride {
+    /// Returns true if the override applies to `path`. Patterns without a `/`
+    /// match the file name, like `.gitignore` patterns; others match the path.
+    fn matches(&self, path: &Path) -> bool {
+        if self.pattern.as_str().contains('/') {
+            self.pattern.matches_path(path)
+        } else {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| self.pattern.matches(name))
+        }
+    }
+}
+
+/// Parses a `GLOB=LANG` override, for clap. `LANG` is a name or extension as
+/// `--force-language` takes, and must be a language ctx-pick knows (see
+/// `is_known_language`). It needn't have a skeleton grammar; skeleton mode
+/// reports that for the files concerned, as for any other language.
+pub fn parse_override(value: &str) -> Result<LanguageOverride, String> {
+    let (pattern, name) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected GLOB=LANG, got '{}'", value))?;
+    let pattern = glob::Pattern::new(pattern)
+        .map_err(|e| format!("invalid glob pattern '{}': {}", pattern, e))?;
+    let language = language_key_from_name(name);
+    if !is_known_language(&language) {
+        return Err(format!("'{}' is not a known language", name));
+    }
+    Ok(LanguageOverride { pattern, language })
+}
+
+/// Determines the language key for a file: the last of `overrides` matching
+/// its path if there is one, and otherwise whatever `detect_language` finds.
+pub fn language_for(path: &Path, content: &str, overrides: &[LanguageOverride]) -> Option<String> {
+    overrides
+        .iter()
+        .rev()
+        .find(|language_override| language_override.matches(path))
+        .map(|language_override| language_override.language.clone())
+        .or_else(|| detect_language(path, content))
+}
+
+/// Parses a `#!` line and maps its interpreter to a language key.
+fn language_from_shebang(content: &str) -> Option<&'static str> {
+    let first_line = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = first_line.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+
+    // `#!/usr/bin/env [-S] python3` names the real interpreter after `env`.
+    if interpreter == "env" {
+        interpreter = words.find(|w| !w.starts_with('-'))?;
+    }
+
+    // Strip version suffixes such as `python3` or `python3.12`.
+    let base = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    KNOWN_INTERPRETERS
+        .iter()
+        .find(|(name, _)| *name == base)
+        .map(|(_, key)| *key)
+}
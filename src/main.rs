@@ -1,19 +1,145 @@
+mod archive;
+mod clipboard;
+mod compare;
 mod config;
+mod dir_config;
 mod display;
+mod doctor;
+mod documents;
 mod error;
+mod file_reader;
 mod file_resolver;
+mod git;
+mod grammars;
+mod imports;
+mod input_file;
+mod interrupt;
+mod language;
+mod manifest;
+mod output;
+mod picker;
+mod pipeline;
+mod prompts;
+mod schemas;
+mod sfc;
+mod stats;
 mod symbol_extractor;
+#[cfg(test)]
+mod test_harness;
 mod types;
+mod unsaved;
+mod user_config;
 
 use crate::{
+    clipboard::ClipboardTarget,
+    compare::CompareFormat,
     config::Config,
-    display::DisplayManager,
+    dir_config::DirConfigs,
+    display::{Confirmation, DisplayManager},
     error::AppError,
-    types::{FileContext, InputResolution, ResolvedFile},
+    git::{ChangedLines, GitTree},
+    manifest::{Manifest, ManifestOptions, RecordedManifest, RunSummary},
+    output::{OutputFormat, RenderOptions},
+    symbol_extractor::{SkeletonOptions, SymbolOrder},
+    types::{FileContext, FilePermissions, LineRange, ResolvedFile, Tag},
 };
 use arboard::Clipboard;
-use clap::Parser;
-use std::{collections::BTreeSet, path::Path, path::PathBuf};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use regex::Regex;
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{IsTerminal, Read, Write};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+use std::time::Duration;
+
+/// What to do when some inputs can't be resolved.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum MissingPolicy {
+    /// Carry on with whatever resolved, report the misses as warnings, and exit 0.
+    Skip,
+    /// Report the misses and stop without producing output (exit 1).
+    Error,
+    /// Carry on like `skip`, but exit 1 afterwards.
+    Warn,
+}
+
+/// Which path `--resolve-only` prints for each file.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum PathStyle {
+    /// The path as the output headers show it, relative to the working directory.
+    Display,
+    /// The absolute path with symlinks resolved.
+    Canonical,
+}
+
+/// What to do when a resolved file can't be read, e.g. because it was
+/// replaced in the moment between finding it and reading it. Files removed
+/// in that moment are always skipped.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ReadErrorPolicy {
+    /// Leave the file out of the output and the counts, with a warning.
+    Skip,
+    /// Put the error message in the output in place of the file's content.
+    Embed,
+    /// Report the files and stop without producing output (exit 1).
+    Fail,
+}
+
+/// A named bundle of options for a common way of using the tool. Each piece
+/// applies only where the option (or one it conflicts with) isn't given
+/// explicitly, so `--mode skim --depth 5` keeps the other pieces.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    /// Whole files, as without --mode.
+    Full,
+    /// = --depth 3 --compact. Leaves out test stripping and manifests,
+    /// which ctx-pick doesn't have.
+    Skim,
+    /// = --changed-only, which takes every changed file when no inputs are
+    /// given. Leaves out adding the changed files' tests, which ctx-pick
+    /// doesn't do.
+    Review,
+}
+
+impl Mode {
+    /// Fills in the options this mode stands for that the command line left
+    /// unset, per `matches`.
+    fn apply(self, cli: &mut Cli, matches: &clap::ArgMatches) {
+        let given = |ids: &[&str]| {
+            ids.iter()
+                .any(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+        };
+        match self {
+            Mode::Full => {}
+            Mode::Skim => {
+                if !given(&["depth", "changed_only", "only_matching_lines"]) {
+                    cli.depth = Some(DEFAULT_OVERVIEW_DEPTH);
+                }
+                if !given(&["compact", "no_compact"]) {
+                    cli.compact = true;
+                }
+            }
+            Mode::Review => {
+                if !given(&[
+                    "changed_only",
+                    "at",
+                    "compare",
+                    "depth",
+                    "overview",
+                    "head",
+                    "archive",
+                    "context_around_symbol",
+                    "only_matching_lines",
+                ]) {
+                    cli.changed_only = Some("HEAD".to_string());
+                }
+            }
+        }
+    }
+}
 
 /// A versatile CLI tool that finds files by name, path, or glob pattern,
 /// extracts their content or a structural 'skeleton', formats it as
@@ -29,202 +155,2287 @@ use std::{collections::BTreeSet, path::Path, path::PathBuf};
 struct Cli {
     /// A space-separated list of files, partial names, folders, or glob patterns.
     /// e.g., 'main.rs', 'src/utils', 'src/**/*.ts'
-    #[arg(required = true, num_args = 1..)]
+    /// If omitted in a terminal, an interactive file picker opens instead.
+    #[arg(num_args = 1..)]
     inputs: Vec<String>,
 
+    /// Also include every file whose path (relative to the working directory,
+    /// with '/' separators) matches this regular expression, e.g.
+    /// 'src/.*_handler\.rs$'. May be given more than once.
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Include files whose relative path matches a regex."
+    )]
+    regex: Vec<String>,
+
+    /// Run as if started in DIR: inputs resolve against it and fuzzy search
+    /// walks it. An absolute DIR works even if the current directory has been
+    /// deleted, e.g. a temporary build directory that was cleaned up.
+    #[arg(
+        long,
+        visible_alias = "root",
+        value_name = "DIR",
+        help = "Resolve inputs against DIR instead of the current directory."
+    )]
+    cwd: Option<PathBuf>,
+
+    /// Let directory, glob, regex and fuzzy inputs include files outside the
+    /// working directory (e.g. '../**/*.pem', or through a symlink). Files
+    /// named explicitly are always allowed.
+    #[arg(
+        long,
+        help = "Allow expansions to include files outside the working directory."
+    )]
+    allow_outside: bool,
+
+    /// Skip symlinks, to files or directories, when expanding directory and
+    /// glob inputs or searching for fuzzy ones. A symlink named in the input
+    /// itself (e.g. 'vendor/lib' or 'vendor/lib/*.rs') is still followed.
+    #[arg(long, help = "Skip symlinks while expanding and searching.")]
+    no_follow_symlinks: bool,
+
+    /// Include hidden files and directories (names starting with '.') in
+    /// fuzzy search and directory expansion. Inputs that start with a hidden
+    /// component, like '.github/workflows', include them regardless.
+    #[arg(
+        long,
+        help = "Include hidden files in fuzzy search and directory expansion."
+    )]
+    hidden: bool,
+
+    /// Resolve fuzzy inputs only within the directories named among the
+    /// other inputs, e.g. 'ctx-pick services/auth services/billing
+    /// payments.rs'. Ambiguous matches are listed per directory.
+    #[arg(
+        long,
+        help = "Fuzzy-search only within the directories given as inputs."
+    )]
+    scope_to_inputs: bool,
+
+    /// The most files a single directory or glob input may expand to before it is
+    /// reported as an error. Explicit single-file inputs are unaffected.
+    /// Pass 0 to disable the limit.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = config::DEFAULT_MAX_FILES_PER_INPUT,
+        help = "Max files one directory or glob input may expand to (0 = unlimited)."
+    )]
+    max_files_per_input: usize,
+
+    /// Stop a glob or fuzzy search once it has visited N files and
+    /// directories, and report it as an error, rather than appear to hang on
+    /// a pattern like '**/**/**/*' or one rooted at '/'. Pass 0 to disable
+    /// the limit.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = config::DEFAULT_SEARCH_ENTRY_LIMIT,
+        help = "Max entries one glob or fuzzy search may visit (0 = unlimited)."
+    )]
+    search_entry_limit: usize,
+
+    /// Stop a glob or fuzzy search that runs longer than DURATION (e.g.
+    /// '10s', '2m'; default 30s), and report it as an error. Pass 0 to
+    /// disable the limit.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = clipboard::parse_duration,
+        help = "Max time one glob or fuzzy search may run (default 30s; 0 = unlimited)."
+    )]
+    search_time_limit: Option<Duration>,
+
+    /// How many levels below a directory input to expand into: 1 takes only
+    /// the files directly inside it, 2 also those in its subdirectories, and
+    /// so on. Unlimited by default. Globs and fuzzy matches are unaffected.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Only expand directory inputs N levels deep."
+    )]
+    dir_depth: Option<u64>,
+
+    /// The most files to include overall, counted after duplicates are removed.
+    #[arg(long, value_name = "N", help = "Max files to include in total.")]
+    max_files: Option<usize>,
+
+    /// Stop adding files once their lines would add up to more than N: the
+    /// file that would cross it and every file after it are left out and
+    /// listed in the summary. Files are taken in output order, and counted
+    /// as read (skeletons by their skeleton's lines).
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Stop adding files past N lines in total."
+    )]
+    max_total_lines: Option<usize>,
+
+    /// Don't expand a leading '~' or '$VAR' references in inputs. Use this for
+    /// filenames that genuinely contain '$'.
+    #[arg(long, help = "Treat inputs literally; don't expand ~ or $VAR.")]
+    no_expand: bool,
+
+    /// Apply a named bundle of options; see the values below for what each
+    /// stands for. Options given explicitly take precedence over the bundle.
+    #[arg(long, value_enum, help = "Use a named bundle of options.")]
+    mode: Option<Mode>,
+
     /// Instead of full file content, extract a structural 'skeleton' of the code
     /// (e.g., function signatures, struct definitions) up to a certain depth.
     /// A depth of 3-5 is usually effective.
     #[arg(
         long,
         value_name = "LEVEL",
+        required_if_eq("format", "symbols-json"),
         help = "Extract a code skeleton at a specific depth."
     )]
     depth: Option<usize>,
 
-    /// Print the final context to stdout instead of copying to the clipboard.
-    /// This is useful for piping the output to other commands.
-    #[arg(long, help = "Print to stdout instead of the clipboard")]
-    to_stdout: bool,
-}
+    /// The separator placed between top-level symbols in skeleton mode. Defaults
+    /// to a newline (one symbol per line); '\n' and '\t' escapes are understood,
+    /// so ' ' restores a single flat line.
+    #[arg(
+        long,
+        value_name = "SEP",
+        default_value = "\\n",
+        help = "Separator between top-level symbols in skeletons."
+    )]
+    symbol_separator: String,
 
-fn main() -> Result<(), AppError> {
-    let cli = Cli::parse();
-    let config = Config::new()?;
-    let display = DisplayManager::new();
-
-    // Resolve all user inputs into a list of `InputResolution` enums.
-    let mut all_resolutions: Vec<InputResolution<'_>> = Vec::new();
-    for input_str in &cli.inputs {
-        let resolution = file_resolver::resolve_input_string(input_str, &config);
-        all_resolutions.push(resolution);
-    }
-
-    // Process all resolutions, bucketing them into successes and various error types.
-    let mut final_ordered_files: Vec<ResolvedFile> = Vec::new();
-    let mut seen_canonical_paths: BTreeSet<PathBuf> = BTreeSet::new();
-
-    let mut path_does_not_exist_errors: Vec<&InputResolution<'_>> = Vec::new();
-    let mut not_founds: Vec<&InputResolution<'_>> = Vec::new();
-    let mut ambiguities_found: Vec<&InputResolution<'_>> = Vec::new();
-    let mut invalid_glob_patterns: Vec<&InputResolution<'_>> = Vec::new(); // New error bucket
-
-    for resolution in &all_resolutions {
-        match resolution {
-            InputResolution::Success(resolved_files_for_input) => {
-                for resolved_file in resolved_files_for_input {
-                    if seen_canonical_paths.insert(resolved_file.canonical_path().to_path_buf()) {
-                        final_ordered_files.push(resolved_file.clone());
-                    }
-                }
-            }
-            InputResolution::Ambiguous { .. } => {
-                ambiguities_found.push(resolution);
-            }
-            InputResolution::NotFound { .. } => {
-                not_founds.push(resolution);
-            }
-            InputResolution::PathDoesNotExist { .. } => {
-                path_does_not_exist_errors.push(resolution);
-            }
-            // Add the new case for our glob pattern errors
-            InputResolution::InvalidGlobPattern { .. } => {
-                invalid_glob_patterns.push(resolution);
-            }
-        }
-    }
+    /// In Rust skeletons, keep `macro_rules!` bodies instead of collapsing
+    /// them to `{ ... }`.
+    #[arg(long, help = "Keep macro_rules! bodies in Rust skeletons.")]
+    keep_macro_bodies: bool,
 
-    // If any unrecoverable errors occurred, print a detailed report and exit.
-    let has_errors = !path_does_not_exist_errors.is_empty()
-        || !not_founds.is_empty()
-        || !ambiguities_found.is_empty()
-        || !invalid_glob_patterns.is_empty();
+    /// In Rust skeletons, doc comments longer than N lines are cut to their
+    /// first paragraph, marked with `(...)`. 0 keeps doc comments whole.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = symbol_extractor::DEFAULT_DOC_COMMENT_MAX_LINES,
+        help = "Cut longer Rust doc comments to their first paragraph (0 keeps them)."
+    )]
+    doc_comment_lines: usize,
 
-    if has_errors {
-        display
-            .print_resolution_errors(
-                &path_does_not_exist_errors,
-                &not_founds,
-                &ambiguities_found,
-                &invalid_glob_patterns, // Pass the new bucket to the display manager
-                &final_ordered_files,
-            )
-            .unwrap_or_else(|e| eprintln!("Critical display error: {}", e));
+    /// In skeleton mode, keep only the first N top-level symbols of each file
+    /// (in --symbol-order), followed by a "... and X more symbols" marker.
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Keep only the first N symbols of each skeleton."
+    )]
+    max_symbols: Option<usize>,
 
-        std::process::exit(1);
-    }
+    /// The order of top-level symbols in skeletons and `--format
+    /// symbols-json`: as they appear in the file, alphabetically by name, or
+    /// grouped by kind. Comments stay with the symbol that follows them.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = SymbolOrder::Source,
+        help = "Order skeleton symbols by source position, name or kind."
+    )]
+    symbol_order: SymbolOrder,
 
-    // If no files were successfully resolved from the inputs, inform the user and exit.
-    if final_ordered_files.is_empty() {
-        eprintln!(
-            "{}",
-            display
-                .warning_style
-                .apply_to("No files were found or resolved based on your input.")
-        );
-        std::process::exit(1);
-    }
+    /// Append each file's size to its Markdown header, e.g. 'src/main.rs (214 lines)'.
+    /// Skeletons report characters instead of lines.
+    #[arg(long, help = "Show each file's line count in its header.")]
+    include_file_size_in_header: bool,
 
-    // 1. Process all resolved files into our FileContext struct.
-    let file_contexts = generate_file_contexts(&final_ordered_files, cli.depth);
+    /// Write a JSON manifest of what went into the output: each file's path,
+    /// content hash and size, the rendering options, and a hash of the full
+    /// output. The same tree and options always produce the same manifest.
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write a JSON manifest of the output to PATH."
+    )]
+    manifest_out: Option<PathBuf>,
 
-    // 2. Build the final Markdown string for the output.
-    let mut markdown_output = String::new();
-    for context in &file_contexts {
-        let lang_hint = if cli.depth.is_some() {
-            ""
-        } else {
-            Path::new(&context.display_path)
-                .extension()
-                .and_then(|s| s.to_str())
-                .unwrap_or("")
-        };
-        markdown_output.push_str(&format!(
-            "{}\n```{}\n{}\n```\n\n",
-            context.display_path,
-            lang_hint,
-            context.content.trim_end()
-        ));
-    }
+    /// Report on stderr which files were added, removed or modified since
+    /// the run that wrote MANIFEST (a --manifest-out file). Without a value,
+    /// compares with the previous manifest at the --manifest-out path before
+    /// it's overwritten, so re-running the same command shows what changed.
+    #[arg(
+        long,
+        value_name = "MANIFEST",
+        num_args = 0..=1,
+        require_equals = true,
+        help = "Report which files changed since an earlier --manifest-out."
+    )]
+    diff_last: Option<Option<PathBuf>>,
 
-    if cli.to_stdout {
-        // --- Script-Friendly Path ---
-        // Just print the final Markdown to standard output and exit.
-        print!("{}", markdown_output);
-    } else {
-        // --- Interactive/Clipboard Path ---
-        let (total_metric, unit_str) = if cli.depth.is_some() {
-            (markdown_output.len(), "characters")
-        } else {
-            let total_lines = file_contexts
-                .iter()
-                .map(|ctx| ctx.content.lines().count())
-                .sum();
-            (total_lines, "lines")
-        };
+    /// Include only the first LINES lines of each file. Files given with an
+    /// explicit `path:START-END` range keep their range. Large files are
+    /// memory-mapped so the rest of the file is never read.
+    #[arg(
+        long,
+        value_name = "LINES",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Include only the first LINES lines of each file."
+    )]
+    head: Option<u64>,
 
-        let clipboard_result = match Clipboard::new() {
-            Ok(mut clipboard) => clipboard.set_text(markdown_output.clone()),
-            Err(err) => Err(err),
-        };
+    /// Include lockfiles, minified bundles and generated files when expanding
+    /// directories and globs. See the README for the default exclude list.
+    #[arg(long, help = "Don't skip lockfiles and generated files in expansions.")]
+    no_default_excludes: bool,
 
-        display
-            .print_operation_summary_and_preview(
-                &file_contexts,
-                &clipboard_result,
-                total_metric,
-                unit_str,
-                cli.depth,
-            )
-            .unwrap_or_else(|e| eprintln!("Display error during summary: {}", e));
+    /// Drop the files listed in FILE, one path per line, from whatever the
+    /// inputs resolve to, e.g. the buffers already open in an editor. Paths
+    /// may be absolute or relative to the working directory, and are compared
+    /// by canonical path; blank lines and paths that don't exist are ignored.
+    /// May be given more than once.
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Leave out the files listed (one per line) in FILE."
+    )]
+    exclude_from: Vec<PathBuf>,
 
-        if clipboard_result.is_err() {
-            println!("{}", markdown_output);
-        }
-    }
+    /// Leave out the file at PATH from whatever the inputs resolve to,
+    /// compared by canonical path as with --exclude-from. May be given more
+    /// than once; editing the selection at the --confirm-clipboard prompt
+    /// prints the --exclude options that reproduce the edit.
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Leave out the file at PATH (repeatable)."
+    )]
+    exclude: Vec<PathBuf>,
 
-    Ok(())
-}
+    /// Read further inputs from FILE, one selection per line, each written
+    /// like a command line and optionally followed by options for just the
+    /// files it resolves to: `--depth LEVEL`, `--full`, and `--exclude GLOB`
+    /// (repeatable). Blank lines and lines starting with `#` are skipped.
+    /// May be given more than once.
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read inputs, with per-line options, from FILE."
+    )]
+    input_file: Vec<PathBuf>,
 
-/// Processes a list of resolved files, returning a vector containing the
-/// context (full or skeleton) for each.
-fn generate_file_contexts(files: &[ResolvedFile], depth: Option<usize>) -> Vec<FileContext> {
-    let mut contexts = Vec::new();
+    /// Resolve inputs against the files of a git revision (a tag, branch, or
+    /// commit) instead of the working directory, reading content from git.
+    #[arg(
+        long,
+        value_name = "REF",
+        help = "Pick files as of a git revision instead of the working tree."
+    )]
+    at: Option<String>,
 
-    for resolved_file in files {
-        let display_path = resolved_file.display_path().to_string_lossy().to_string();
-        let file_content_result = std::fs::read_to_string(resolved_file.canonical_path());
+    /// Resolve inputs against the files of a `.zip`, `.tar`, `.tar.gz` or
+    /// `.tgz` source archive instead of the working directory, reading content
+    /// from its entries without unpacking it. Paths, globs and fuzzy search
+    /// match paths inside the archive; headers show them prefixed with the
+    /// archive's file name.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["at", "compare", "changed_only"],
+        help = "Pick files from a zip or tar archive instead of the working tree."
+    )]
+    archive: Option<PathBuf>,
 
-        let final_content = match file_content_result {
-            Err(e) => format!(
-                "Error: Could not read file content for {:?}.\nDetails: {}",
-                display_path, e
-            ),
-            Ok(content) => {
-                if let Some(max_depth) = depth {
-                    let extension = resolved_file
-                        .display_path()
-                        .extension()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("");
-                    match symbol_extractor::create_skeleton_by_depth(&content, extension, max_depth)
-                    {
-                        Ok(symbols) => symbols,
-                        Err(e) => format!(
-                            "---\n-- ERROR: Could not extract symbols from {:?}: {}\n-- Falling back to full file content.\n---\n\n{}",
-                            display_path, e, content
-                        ),
-                    }
-                } else {
-                    content
-                }
-            }
-        };
+    /// For each file, also include its version at a git revision: as a second
+    /// block, or a diff with `--compare-format diff`. Files unchanged since the
+    /// revision are included once.
+    #[arg(
+        long,
+        value_name = "REF",
+        conflicts_with = "at",
+        help = "Include each file's version at a git revision too."
+    )]
+    compare: Option<String>,
 
-        contexts.push(FileContext {
-            display_path,
-            content: final_content,
-        });
-    }
-    contexts
+    /// How `--compare` presents the two versions of a changed file.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = CompareFormat::Blocks,
+        requires = "compare",
+        help = "Show compared versions as two blocks or a diff."
+    )]
+    compare_format: CompareFormat,
+
+    /// Include only the lines changed since a git revision (`HEAD`, i.e.
+    /// uncommitted changes, unless given as `--changed-only=REF`), plus
+    /// `--context-lines` around them, as ordinary code with markers for the
+    /// lines left out. Files without changes are dropped; with no inputs,
+    /// every changed file is included.
+    #[arg(
+        long,
+        value_name = "REF",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "HEAD",
+        conflicts_with_all = ["at", "compare", "depth", "overview", "head"],
+        help = "Include only the lines changed since a git revision (default HEAD)."
+    )]
+    changed_only: Option<String>,
+
+    /// How many lines to keep around each change with `--changed-only`,
+    /// around each match with `--only-matching-lines`, or around the
+    /// definition with `--context-around-symbol`.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = DEFAULT_CONTEXT_LINES,
+        help = "Lines of context for --changed-only, --only-matching-lines and --context-around-symbol."
+    )]
+    context_lines: usize,
+
+    /// Include only the definition of the symbol NAME, with --context-lines
+    /// lines on either side, instead of whole files. Inputs, if given, limit
+    /// the files searched; otherwise the whole working directory is, as for a
+    /// `sym:` input. A name defined more than once is reported as ambiguous,
+    /// listing every definition.
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with = "changed_only",
+        help = "Include a symbol's definition and the lines around it."
+    )]
+    context_around_symbol: Option<String>,
+
+    /// Keep only the selected files whose content matches this regular
+    /// expression, e.g. 'TODO|FIXME'. Without inputs, every file in the
+    /// working directory is searched.
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_parser = Regex::new,
+        help = "Keep only files whose content matches a regex."
+    )]
+    grep: Option<Regex>,
+
+    /// Instead of each whole file, include only the lines matching `--grep`,
+    /// numbered and with `--context-lines` around them, like `grep -n -C`.
+    #[arg(
+        long,
+        requires = "grep",
+        conflicts_with_all = ["depth", "overview", "changed_only", "compare"],
+        help = "Include only the lines matching --grep, with context."
+    )]
+    only_matching_lines: bool,
+
+    /// Move import statements that several full-content files share into a
+    /// "Shared imports" block ahead of the files, noting which files use them.
+    /// Rust, Python and TypeScript imports are recognized.
+    #[arg(
+        long,
+        help = "Collapse imports repeated across files into a shared block."
+    )]
+    dedupe_imports: bool,
+
+    /// Collapse files whose skeletons came out identical (common in generated
+    /// code) into one block, headed by every path that shares it.
+    #[arg(
+        long,
+        help = "Collapse identical skeletons into one block listing their files."
+    )]
+    dedup_skeletons: bool,
+
+    /// With --dedup-skeletons, also collapse skeletons that differ only in
+    /// trailing whitespace, line endings or trailing blank lines, as vendored
+    /// copies of a file often do. The first file's skeleton is the one shown.
+    #[arg(
+        long,
+        requires = "dedup_skeletons",
+        help = "Ignore trailing whitespace and line endings when deduplicating."
+    )]
+    dedup_ignore_whitespace: bool,
+
+    /// When a file's skeleton can't be extracted (e.g. its language isn't
+    /// supported), leave the file out and list it in the summary, instead of
+    /// including its full content with an error banner.
+    #[arg(
+        long,
+        help = "Omit files whose skeleton fails instead of including them in full."
+    )]
+    no_skeleton_fallback: bool,
+
+    /// Annotate each top-level symbol in a skeleton with its most recent git
+    /// change, e.g. '[changed 3 days ago by Ada]', from `git blame`.
+    #[arg(
+        long,
+        help = "Annotate skeleton symbols with when and by whom they last changed."
+    )]
+    blame_hints: bool,
+
+    /// What to do when some inputs can't be resolved: stop (`error`), or carry
+    /// on with the rest and exit 0 (`skip`) or non-zero (`warn`).
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = MissingPolicy::Error,
+        help = "How to handle unresolved inputs."
+    )]
+    on_missing: MissingPolicy,
+
+    /// What to do when a file that was found can't be read, or ctx-pick
+    /// crashes processing it: leave it out with a warning (`skip`), put the
+    /// error in the output (`embed`), or stop (`fail`). Files deleted since
+    /// they were found are always left out.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ReadErrorPolicy::Skip,
+        help = "How to handle files that can't be read after resolution."
+    )]
+    on_read_error: ReadErrorPolicy,
+
+    /// Stop with an error naming any binary file among those resolved,
+    /// instead of leaving it out with a warning. Catches globs that reach
+    /// further than intended.
+    #[arg(long, help = "Fail instead of skipping binary files.")]
+    fail_on_binary: bool,
+
+    /// How many candidates to list when an input matches several files.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = display::DEFAULT_MAX_AMBIGUOUS,
+        help = "List up to N candidates for an ambiguous input."
+    )]
+    max_ambiguous: usize,
+
+    /// Report the settings chosen for each file, such as its skeleton depth and
+    /// which `.ctx-pick.toml` (if any) supplied it.
+    #[arg(short, long, help = "Show the effective settings for each file.")]
+    verbose: bool,
+
+    /// Hard-wrap lines longer than N characters in full-content output, marking
+    /// each continuation with '↪'. Skeletons are left alone.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Hard-wrap lines longer than N characters."
+    )]
+    wrap: Option<u64>,
+
+    /// Cut lines longer than N characters in full-content output, ending each
+    /// with '⏎…', for files with a few enormous lines (string tables, data
+    /// literals). The summary says how many lines of each file were cut.
+    /// Defaults to `max_line_length` in the user config; 0 turns it off.
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Truncate lines longer than N characters (0 = off)."
+    )]
+    max_line_length: Option<usize>,
+
+    /// The shape of the output: Markdown, Markdown laid out for pasting into
+    /// chat UIs (`chat`: a bold filename above each fence), the `<documents>`
+    /// XML that files-to-prompt writes with `--cxml` (byte for byte), or, with
+    /// --depth, the symbols extracted from each file as JSON (`symbols-json`).
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Markdown,
+        help = "Output format."
+    )]
+    format: OutputFormat,
+
+    /// Print a census of the resolved files instead of building context: files,
+    /// lines and estimated tokens per language, the largest files, and, with
+    /// --depth, how much skeletons would shrink each language. Nothing is copied.
+    #[arg(long, help = "Print statistics about the resolved files and exit.")]
+    stats: bool,
+
+    /// Print the paths of the resolved files to stdout, one per line, instead
+    /// of building context, so other commands can use ctx-pick's resolution.
+    /// e.g., `ctx-pick --resolve-only --print0 'src/**/*.rs' | xargs -0 wc -l`
+    #[arg(
+        long,
+        conflicts_with = "stats",
+        help = "Print the resolved file paths and exit."
+    )]
+    resolve_only: bool,
+
+    /// End each path printed by --resolve-only with a NUL byte instead of a
+    /// newline, for `xargs -0`.
+    #[arg(
+        long,
+        requires = "resolve_only",
+        help = "NUL-terminate --resolve-only paths."
+    )]
+    print0: bool,
+
+    /// End each path printed by --resolve-only with a newline (the default).
+    #[arg(
+        long,
+        requires = "resolve_only",
+        conflicts_with = "print0",
+        help = "Newline-terminate --resolve-only paths."
+    )]
+    print_lines: bool,
+
+    /// Which path --resolve-only prints: as the headers show it, relative to
+    /// the working directory, or canonical (absolute, symlinks resolved).
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = PathStyle::Display,
+        requires = "resolve_only",
+        help = "Path style for --resolve-only."
+    )]
+    path_style: PathStyle,
+
+    /// Copy the resolved file paths to the clipboard, one per line as the
+    /// headers show them, instead of their content. Handy for pasting the
+    /// selection into another tool.
+    #[arg(
+        long,
+        conflicts_with_all = ["stats", "resolve_only", "output", "to_stdout"],
+        help = "Copy the resolved file paths instead of their content."
+    )]
+    copy_paths_only: bool,
+
+    /// Remove blank lines and each file's common indentation from full content,
+    /// to save tokens. Lossy for whitespace-sensitive languages such as Python
+    /// and YAML, so a warning names any such files.
+    #[arg(
+        long,
+        overrides_with = "no_compact",
+        help = "Strip blank lines and common indentation."
+    )]
+    compact: bool,
+
+    /// Keep blank lines and indentation, e.g. to override the `--compact`
+    /// of `--mode skim` or a shell alias.
+    #[arg(long, overrides_with = "compact", help = "Don't compact content.")]
+    no_compact: bool,
+
+    /// Keep CRLF line endings as they are in the files. By default they're
+    /// converted to LF, so the output is consistent across platforms.
+    #[arg(long, help = "Don't convert CRLF line endings to LF.")]
+    keep_crlf: bool,
+
+    /// Keep the indentation of line ranges and `::symbol` extractions. By
+    /// default, the indentation all their lines share is removed and the
+    /// header notes how much, e.g. "(indentation reduced by 12 spaces)".
+    /// Only uniform indentation (all spaces or all tabs) is removed, and full
+    /// files are never changed.
+    #[arg(long, help = "Don't remove the shared indentation of line ranges.")]
+    keep_indent: bool,
+
+    /// Include zero-length files as empty blocks. By default they're left out
+    /// and listed in the summary, which keeps directories with placeholder
+    /// files (e.g. `__init__.py`) from adding empty fences.
+    #[arg(long, help = "Include zero-length files instead of skipping them.")]
+    include_empty_files: bool,
+
+    /// Prepend a numbered table of contents listing each included file and its
+    /// line count. The table isn't counted in the copied-size summary.
+    #[arg(long, help = "Prepend a numbered list of the included files.")]
+    toc: bool,
+
+    /// Number each file block, e.g. '### [7] src/display.rs', and prepend an
+    /// index of the numbers, paths and a one-line description of each file
+    /// (its first doc comment or first line), so a model can be pointed at
+    /// "[7] and [12]". The numbers match the summary preview. With
+    /// `--format cxml`, they match each document's index. The index isn't
+    /// counted in the copied-size summary.
+    #[arg(
+        long,
+        conflicts_with = "toc",
+        help = "Number each file block and prepend an index of them."
+    )]
+    anchors: bool,
+
+    /// Append a block recording how the output was made, so it can be
+    /// reproduced: the ctx-pick version, the command line as typed, the
+    /// working directory, the `.ctx-pick.toml` files that applied and the
+    /// time. Not added to `--format symbols-json`, which must stay JSON.
+    #[arg(
+        long,
+        overrides_with = "no_footer",
+        help = "Append the command line and version to the output."
+    )]
+    footer: bool,
+
+    /// Leave out the footer, e.g. to override a `--footer` in a shell alias.
+    #[arg(long, overrides_with = "footer", help = "Don't append a footer.")]
+    no_footer: bool,
+
+    /// Warn when a single file makes up more than PERCENT of the output
+    /// (default 60, or the user config's `balance_threshold`).
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        value_parser = clap::value_parser!(u8).range(1..=100),
+        help = "Share of the output one file may take before a warning."
+    )]
+    balance_threshold: Option<u8>,
+
+    /// Don't warn when one file dominates the output.
+    #[arg(long, help = "Suppress the oversized-file warning.")]
+    no_balance_warning: bool,
+
+    /// Don't warn about included files that an editor may hold unsaved edits
+    /// to, going by the swap, lock and auto-save files next to them. Set
+    /// `warn_unsaved_edits = false` in the user config to turn it off for good.
+    #[arg(long, help = "Suppress the unsaved-edits warning.")]
+    no_unsaved_warning: bool,
+
+    /// After a successful run, print a one-line JSON record of what was output:
+    /// file count, destination, characters, bytes, lines and estimated tokens.
+    /// It goes to stdout, or to stderr when stdout carries the context itself.
+    #[arg(long, help = "Print a machine-readable JSON summary line.")]
+    porcelain: bool,
+
+    /// Show each file's permissions: its Unix permission bits and whether it's
+    /// executable, in the summary and as a `permissions` list in --porcelain.
+    /// Where there are no permission bits (Windows), read-only and executable
+    /// extensions are shown instead.
+    #[arg(long, help = "Show each file's permissions and executable bit.")]
+    show_permissions: bool,
+
+    /// Show each file's detected language in the summary, and whether skeleton
+    /// mode can extract a skeleton for it, e.g. to check that '.tsx' files are
+    /// recognized before asking for --depth. Honors --lang-override.
+    #[arg(long, help = "Show each file's detected language in the summary.")]
+    annotate_language: bool,
+
+    /// After a successful run, print the SHA-256 of the output on a line of
+    /// its own (the `sha256` of `--porcelain`), for caching on the exact
+    /// context. The same inputs over unchanged files give the same hash.
+    /// Like `--porcelain`, it goes to stderr when stdout carries the context.
+    #[arg(
+        long,
+        conflicts_with = "porcelain",
+        help = "Print a hash of the output, for caching."
+    )]
+    stable_hash: bool,
+
+    /// Wrap the context in a built-in instruction block for a common task:
+    /// 'review', 'explain' or 'test-gen' (see --list-templates).
+    #[arg(
+        long,
+        value_name = "NAME",
+        value_parser = prompts::parse_template,
+        help = "Wrap the context in a built-in prompt (see --list-templates)."
+    )]
+    prompt_template: Option<&'static prompts::PromptTemplate>,
+
+    /// List the built-in prompt templates and exit.
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs", "overview", "prompt_template"],
+        help = "List the built-in prompt templates and exit."
+    )]
+    list_templates: bool,
+
+    /// Write the final context to FILE instead of copying it to the clipboard.
+    /// A path ending in `.gz` is gzip-compressed, e.g. for an archive of
+    /// prompts.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "to_stdout",
+        help = "Write to FILE instead of the clipboard (.gz compresses)."
+    )]
+    output: Option<PathBuf>,
+
+    /// Print the final context to stdout instead of copying to the clipboard.
+    /// This is useful for piping the output to other commands.
+    #[arg(long, help = "Print to stdout instead of the clipboard")]
+    to_stdout: bool,
+
+    /// Include a skeleton of every supported source file in the working directory,
+    /// respecting .gitignore. Uses a depth of 3 unless --depth is also given.
+    /// Limited like a directory input by --dir-depth, --max-files-per-input
+    /// and the default excludes. Any explicit inputs are included alongside
+    /// the overview.
+    #[arg(long, help = "Skeletonize every source file in the project.")]
+    overview: bool,
+
+    /// After a successful copy, clear the clipboard once DURATION has elapsed
+    /// (e.g. '30s', '5m', '1h'). The clipboard is only cleared if it still holds
+    /// the context ctx-pick wrote, so anything copied since is left alone.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = clipboard::parse_duration,
+        help = "Clear the clipboard after a delay (e.g. 30s, 5m)."
+    )]
+    clear_after: Option<Duration>,
+
+    /// Clear the clipboard immediately and exit.
+    #[arg(long, conflicts_with_all = ["inputs", "overview"], help = "Clear the clipboard and exit.")]
+    clear: bool,
+
+    /// Write a test string to the clipboard, read it back, and report whether
+    /// the clipboard works on this system. Useful when output unexpectedly
+    /// falls back to stdout.
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs", "overview", "clear"],
+        help = "Check that the clipboard works on this system and exit."
+    )]
+    clipboard_check: bool,
+
+    /// Before copying, show what's about to be copied and what's on the
+    /// clipboard now, and ask for confirmation. Skipped when the clipboard is
+    /// empty or stdin/stderr isn't a terminal.
+    #[arg(long, help = "Ask before overwriting the clipboard.")]
+    confirm_clipboard: bool,
+
+    /// Which clipboard to copy to. `primary` is the X11 PRIMARY selection
+    /// (pasted with a middle click); writing it needs `xclip` or `xsel`, and
+    /// it isn't available on macOS or Windows. Also applies to --clear and
+    /// --clear-after.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "TARGET",
+        default_value_t = ClipboardTarget::Clipboard,
+        help = "Copy to the clipboard or the X11 PRIMARY selection."
+    )]
+    clipboard_target: ClipboardTarget,
+
+    /// Run a series of environment checks (clipboard round-trip, SSH/tmux
+    /// session, colors, git, compiled-in grammars, `.ctx-pick.toml` files, and
+    /// working-directory access) and report each as pass, warn or fail. Exits
+    /// non-zero if any check fails.
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs", "overview", "clear", "clipboard_check", "why"],
+        help = "Diagnose the environment and exit."
+    )]
+    doctor: bool,
+
+    /// Explain how a single input would be resolved: whether it matched a literal
+    /// path, was treated as a glob, or fell back to fuzzy search, and which file
+    /// (if any) would be selected. Nothing is read or copied.
+    #[arg(
+        long,
+        value_name = "INPUT",
+        conflicts_with_all = ["inputs", "overview", "clear", "clipboard_check"],
+        help = "Explain how an input resolves, without copying anything."
+    )]
+    why: Option<String>,
+
+    /// Read source code from stdin and print its skeleton to stdout, bypassing
+    /// file resolution entirely. Requires --depth and --force-language.
+    /// e.g., `pbpaste | ctx-pick --stdin-content --force-language rust --depth 4`
+    #[arg(
+        long,
+        requires_all = ["depth", "force_language"],
+        conflicts_with_all = ["inputs", "overview", "clear", "clipboard_check", "why"],
+        help = "Skeletonize source code read from stdin."
+    )]
+    stdin_content: bool,
+
+    /// The language of the code read by --stdin-content, as a name ('rust',
+    /// 'python', 'typescript') or an extension ('rs', 'py', 'ts').
+    #[arg(
+        long,
+        value_name = "LANG",
+        requires = "stdin_content",
+        help = "Language of the code given to --stdin-content."
+    )]
+    force_language: Option<String>,
+
+    /// Force the language of files matching a glob, overriding detection for
+    /// both the fence hint and the skeleton grammar. Globs without a `/` match
+    /// file names. Repeatable; the last matching override wins.
+    /// e.g., `--lang-override '*.inc=php' --lang-override 'bin/*=python'`
+    #[arg(
+        long,
+        value_name = "GLOB=LANG",
+        value_parser = language::parse_override,
+        help = "Force the language of files matching GLOB. Repeatable."
+    )]
+    lang_override: Vec<language::LanguageOverride>,
+
+    /// Internal: run as the detached helper spawned by --clear-after.
+    #[arg(long, hide = true, num_args = 2, value_names = ["SECS", "HASH"])]
+    clear_watch: Option<Vec<u64>>,
+}
+
+/// The skeleton depth used by `--overview` when `--depth` is not given.
+const DEFAULT_OVERVIEW_DEPTH: usize = 3;
+
+/// The default for `--context-lines`, matching `git diff`.
+const DEFAULT_CONTEXT_LINES: usize = 3;
+
+fn main() -> Result<(), AppError> {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Some(mode) = cli.mode {
+        mode.apply(&mut cli, &matches);
+    }
+    let mut input_entries = Vec::new();
+    for path in &cli.input_file {
+        input_entries.extend(input_file::read(path)?);
+    }
+
+    // Inputs are required unless another mode needs none, or there's a
+    // terminal to run the interactive picker in.
+    let needs_inputs = cli.inputs.is_empty()
+        && cli.regex.is_empty()
+        && input_entries.is_empty()
+        && !(cli.overview
+            || cli.clear
+            || cli.list_templates
+            || cli.clear_watch.is_some()
+            || cli.clipboard_check
+            || cli.doctor
+            || cli.why.is_some()
+            || cli.context_around_symbol.is_some()
+            || cli.stdin_content
+            || cli.changed_only.is_some()
+            || cli.grep.is_some());
+    let pick_interactively =
+        needs_inputs && std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    if needs_inputs && !pick_interactively {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::MissingRequiredArgument,
+                "the following required arguments were not provided:\n  <INPUTS>...\n\n(Run in a terminal without inputs to pick files interactively.)",
+            )
+            .exit();
+    }
+    if cli.diff_last == Some(None) && cli.manifest_out.is_none() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::MissingRequiredArgument,
+                "--diff-last needs a manifest to compare with: pass --diff-last=MANIFEST, or --manifest-out PATH to compare with the manifest already at PATH",
+            )
+            .exit();
+    }
+
+    if cli.list_templates {
+        print!("{}", prompts::list());
+        return Ok(());
+    }
+
+    if let Some(watch) = &cli.clear_watch {
+        // Background helper: nobody is watching its output, so just exit.
+        let _ = clipboard::run_clear_watch(watch[0], watch[1], cli.clipboard_target);
+        return Ok(());
+    }
+
+    if cli.stdin_content {
+        return skeletonize_stdin(&cli);
+    }
+
+    // From here on, Ctrl-C stops the run before anything is copied.
+    interrupt::install();
+
+    let mut config = Config::new(cli.cwd.as_deref())?;
+    // A census is meant for large trees and copies nothing, so it isn't limited.
+    config.max_files_per_input = Some(cli.max_files_per_input).filter(|&n| n > 0 && !cli.stats);
+    config.search_entry_limit = Some(cli.search_entry_limit).filter(|&n| n > 0);
+    if let Some(limit) = cli.search_time_limit {
+        config.search_time_limit = Some(limit).filter(|limit| !limit.is_zero());
+    }
+    config.dir_depth = cli.dir_depth.map(|depth| depth as usize);
+    config.expand_inputs = !cli.no_expand;
+    config.default_excludes = !cli.no_default_excludes;
+    config.allow_outside = cli.allow_outside;
+    config.follow_symlinks = !cli.no_follow_symlinks;
+    config.hidden = cli.hidden;
+    config.balance_warning_threshold = balance_warning_threshold(&cli);
+    if let Some(revision) = &cli.at {
+        config.git_tree =
+            Some(GitTree::load(&config.working_dir, revision).map_err(AppError::GitError)?);
+    }
+    if let Some(path) = &cli.archive {
+        config.archive = Some(
+            archive::Archive::load(&config.working_dir.join(path)).map_err(AppError::IoError)?,
+        );
+    }
+    let display = DisplayManager::new()
+        .with_max_ambiguous(cli.max_ambiguous)
+        .with_permissions(cli.show_permissions)
+        .with_languages(cli.annotate_language);
+
+    if config.default_excludes
+        && let Some((directory, pattern)) = config::default_exclude_covering(&config.working_dir)
+    {
+        config.default_excludes = false;
+        display
+            .print_default_excludes_disabled(&directory, pattern)
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+    }
+
+    if cli.doctor {
+        let checks = doctor::run_checks(&config.working_dir);
+        display
+            .print_doctor_report(&checks)
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        if checks
+            .iter()
+            .any(|check| check.status == doctor::CheckStatus::Fail)
+        {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if cli.clipboard_check {
+        let result = clipboard::self_test();
+        display
+            .print_clipboard_check(&clipboard::backend_name(), &result)
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        if result.is_err() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(input) = &cli.why {
+        let (resolution, trace) = file_resolver::trace_input_string(input, &config);
+        let resolution = DirConfigs::new(&config.working_dir).check_input(input, resolution);
+        display
+            .print_skipped_walks(&file_resolver::take_skipped_walks())
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        display
+            .print_resolution_trace(input, &trace, &resolution)
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        return Ok(());
+    }
+
+    if cli.clear {
+        clipboard::clear_now(cli.clipboard_target)
+            .map_err(|e| AppError::ClipboardError(e.to_string()))?;
+        display
+            .print_clipboard_cleared()
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        return Ok(());
+    }
+
+    // Loaded only now, so that the diagnostics above run outside a git
+    // repository even with `--mode review`.
+    let compare_tree = cli
+        .compare
+        .as_deref()
+        .map(|revision| GitTree::load(&config.working_dir, revision))
+        .transpose()
+        .map_err(AppError::GitError)?;
+    let changed_lines = cli
+        .changed_only
+        .as_deref()
+        .map(|revision| ChangedLines::load(&config.working_dir, revision))
+        .transpose()
+        .map_err(AppError::GitError)?;
+    if cli.blame_hints {
+        git::require_repository(&config.working_dir, "--blame-hints")
+            .map_err(AppError::GitError)?;
+    }
+
+    // An overview is always a skeleton; fall back to a sensible default depth.
+    if cli.overview && cli.depth.is_none() {
+        cli.depth = Some(DEFAULT_OVERVIEW_DEPTH);
+    }
+
+    if pick_interactively {
+        match picker::pick_files(&config).map_err(AppError::PickerError)? {
+            Some(selected) if !selected.is_empty() => cli.inputs = selected,
+            _ => {
+                eprintln!("{}", display.warning_style.apply_to("No files selected."));
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Without inputs, `--changed-only` takes every changed file.
+    if let Some(changes) = &changed_lines
+        && cli.inputs.is_empty()
+        && cli.regex.is_empty()
+        && input_entries.is_empty()
+    {
+        if changes.files.is_empty() {
+            eprintln!(
+                "{}",
+                display
+                    .warning_style
+                    .apply_to(format!("No changes since {}.", changes.revision))
+            );
+            std::process::exit(1);
+        }
+        cli.inputs = changes
+            .files
+            .keys()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+    }
+
+    // Without inputs, `--grep` searches the whole working directory.
+    if cli.grep.is_some()
+        && cli.inputs.is_empty()
+        && cli.regex.is_empty()
+        && input_entries.is_empty()
+        && !cli.overview
+    {
+        cli.inputs = vec![".".to_string()];
+    }
+
+    if cli.scope_to_inputs {
+        let inputs: Vec<String> = cli
+            .inputs
+            .iter()
+            .chain(input_entries.iter().flat_map(|entry| &entry.inputs))
+            .cloned()
+            .collect();
+        config.search_roots = file_resolver::directory_inputs(&inputs, &config);
+        if config.search_roots.is_empty() {
+            eprintln!(
+                "Warning: --scope-to-inputs was given, but no input is a directory; fuzzy inputs search the whole working directory."
+            );
+        }
+    }
+
+    let revisions = pipeline::Revisions {
+        compare_tree: compare_tree.as_ref(),
+        changed_lines: changed_lines.as_ref(),
+    };
+    let run = pipeline::run(
+        &cli,
+        &input_entries,
+        &config,
+        &revisions,
+        skeleton_options(&cli),
+        &display,
+        &mut console::Term::stderr(),
+    );
+    let resolution_errors = run.has_resolution_errors();
+    let skeleton_options = run.skeleton_options;
+    let rendered = match run.outcome {
+        Ok(pipeline::Outcome::Rendered(rendered)) => rendered,
+        Ok(pipeline::Outcome::Selected(files)) if cli.stats => {
+            let stats = stats::Stats::collect(&files, cli.depth, &skeleton_options);
+            display
+                .print_stats(&stats)
+                .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+            return Ok(());
+        }
+        Ok(pipeline::Outcome::Selected(files)) => {
+            if cli.resolve_only {
+                let terminator = if cli.print0 { b'\0' } else { b'\n' };
+                write_paths(
+                    &mut std::io::stdout().lock(),
+                    &files,
+                    cli.path_style,
+                    terminator,
+                )
+                .map_err(|e| AppError::IoError(format!("Failed to write paths: {}", e)))?;
+            } else {
+                let mut paths = Vec::new();
+                write_paths(&mut paths, &files, PathStyle::Display, b'\n')
+                    .map_err(|e| AppError::IoError(format!("Failed to write paths: {}", e)))?;
+                let paths = String::from_utf8_lossy(&paths).into_owned();
+                let clipboard_result = Clipboard::new().and_then(|mut clipboard| {
+                    clipboard::set_text(&mut clipboard, cli.clipboard_target, paths.clone())
+                });
+                display
+                    .print_paths_copied(files.len(), &clipboard_result)
+                    .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+                if clipboard_result.is_err() {
+                    print!("{}", paths);
+                }
+            }
+            if resolution_errors && cli.on_missing == MissingPolicy::Warn {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Err(pipeline::Halt::Failed) => std::process::exit(1),
+        Err(pipeline::Halt::Interrupted(stage)) => exit_interrupted(&display, stage),
+        Err(pipeline::Halt::Error(e)) => return Err(e),
+    };
+    let pipeline::Rendered {
+        files: mut included_files,
+        contexts: mut file_contexts,
+        output: mut full_output,
+        footer,
+        omitted,
+        removed,
+        empty,
+        over_line_budget,
+        header_collisions,
+        unsaved_edits,
+    } = *rendered;
+    let display = display
+        .with_header_collisions(header_collisions)
+        .with_removed_files(removed)
+        .with_empty_files(empty)
+        .with_footer(footer.as_deref())
+        .with_unsaved_edits(unsaved_edits)
+        .with_line_budget(cli.max_total_lines, over_line_budget);
+
+    // Checked again before anything is written, so an interrupted run never
+    // leaves a partial result on the clipboard or in a file.
+    if interrupt::requested() {
+        exit_interrupted(
+            &display,
+            &format!(
+                "after reading {} file{}",
+                file_contexts.len(),
+                if file_contexts.len() == 1 { "" } else { "s" }
+            ),
+        );
+    }
+
+    // The clipboard is opened before the manifest is written, so that
+    // `--confirm-clipboard` asks first and the manifest reflects any files
+    // left out in answer.
+    let mut clipboard = (cli.output.is_none() && !cli.to_stdout).then(Clipboard::new);
+    if cli.confirm_clipboard
+        && std::io::stdin().is_terminal()
+        && std::io::stderr().is_terminal()
+        && let Some(Ok(clipboard)) = &mut clipboard
+    {
+        // Ask before replacing anything, if there's someone to ask.
+        let current = clipboard::current_contents(clipboard, cli.clipboard_target);
+        // Files can only be left out one by one while each has its own block.
+        let editable =
+            cli.format != OutputFormat::SymbolsJson && file_contexts.len() == included_files.len();
+        let mut excluded = Vec::new();
+        if current != clipboard::ClipboardContents::Empty {
+            loop {
+                let answer = display
+                    .confirm_clipboard_overwrite(
+                        included_files.len(),
+                        &full_output,
+                        &current,
+                        editable,
+                    )
+                    .unwrap_or(Confirmation::Cancel);
+                match answer {
+                    Confirmation::Overwrite => break,
+                    Confirmation::Cancel => {
+                        eprintln!(
+                            "{}",
+                            display
+                                .warning_style
+                                .apply_to("Nothing copied; the clipboard is unchanged.")
+                        );
+                        return Ok(());
+                    }
+                    Confirmation::EditSelection => {
+                        let answer = display
+                            .ask_files_to_exclude(&file_contexts)
+                            .unwrap_or_default();
+                        let numbers = match parse_selection(&answer, file_contexts.len()) {
+                            Ok(numbers) if numbers.len() == file_contexts.len() => {
+                                eprintln!("Leaving out every file would leave nothing to copy.");
+                                continue;
+                            }
+                            Ok(numbers) => numbers,
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                continue;
+                            }
+                        };
+                        excluded.extend(numbers.iter().map(|&number| {
+                            included_files[number - 1]
+                                .0
+                                .display_path()
+                                .to_string_lossy()
+                                .into_owned()
+                        }));
+                        for &number in numbers.iter().rev() {
+                            file_contexts.remove(number - 1);
+                            included_files.remove(number - 1);
+                        }
+                        full_output = render_output(
+                            &cli,
+                            &file_contexts,
+                            &included_files,
+                            footer.as_deref(),
+                            &skeleton_options,
+                        );
+                    }
+                }
+            }
+        }
+        if !excluded.is_empty() {
+            display
+                .print_exclude_hint(&excluded)
+                .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        }
+    }
+
+    let diff_last = match &cli.diff_last {
+        Some(Some(path)) => Some(path),
+        Some(None) => cli.manifest_out.as_ref(),
+        None => None,
+    };
+    if cli.manifest_out.is_some() || diff_last.is_some() {
+        let manifest = Manifest::new(manifest_options(&cli), &file_contexts, &full_output);
+        // A missing manifest is the first run of a command, not an error.
+        if let Some(previous) = diff_last
+            && previous.exists()
+        {
+            match RecordedManifest::read(previous) {
+                Ok(recorded) => display
+                    .print_file_set_diff(previous, &manifest.diff_against(&recorded))
+                    .unwrap_or_else(|e| eprintln!("Display error: {}", e)),
+                Err(e) => eprintln!("{}", display.warning_style.apply_to(e)),
+            }
+        }
+        if let Some(manifest_path) = &cli.manifest_out {
+            manifest
+                .write_to(manifest_path)
+                .map_err(AppError::IoError)?;
+        }
+    }
+
+    let print_porcelain = |destination: &'static str| {
+        let line = if cli.porcelain {
+            let summary = RunSummary::new(included_files.len(), destination, &full_output);
+            if cli.show_permissions {
+                summary.with_permissions(&file_contexts).to_json_line()
+            } else {
+                summary.to_json_line()
+            }
+        } else if cli.stable_hash {
+            manifest::sha256_hex(&full_output)
+        } else {
+            return;
+        };
+        if destination == "stdout" {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    };
+
+    if let Some(path) = &cli.output {
+        // --- File Path ---
+        let bytes = output::write_to_file(path, &full_output).map_err(|e| {
+            AppError::IoError(format!("Failed to write output to {:?}: {}", path, e))
+        })?;
+        display
+            .print_output_written(path, &full_output, bytes)
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        print_porcelain("file");
+        display
+            .print_unsaved_edits()
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        display
+            .print_line_budget_omissions()
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        if !omitted.is_empty() {
+            display
+                .print_omitted_skeletons(&omitted)
+                .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        }
+    } else if cli.to_stdout {
+        // --- Script-Friendly Path ---
+        // Just print the final Markdown to standard output and exit.
+        print!("{}", full_output);
+        print_porcelain("stdout");
+        display
+            .print_unsaved_edits()
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        display
+            .print_line_budget_omissions()
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        if !omitted.is_empty() {
+            display
+                .print_omitted_skeletons(&omitted)
+                .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        }
+    } else {
+        // --- Interactive/Clipboard Path ---
+        let clipboard_result =
+            match clipboard.expect("opened when there's no --output or --to-stdout") {
+                Ok(mut clipboard) => {
+                    clipboard::set_text(&mut clipboard, cli.clipboard_target, full_output.clone())
+                }
+                Err(err) => Err(err),
+            };
+
+        display
+            .print_operation_summary_and_preview(&file_contexts, &omitted, &clipboard_result)
+            .unwrap_or_else(|e| eprintln!("Display error during summary: {}", e));
+
+        if let Some((dominant, share)) =
+            find_dominant_file(&file_contexts, config.balance_warning_threshold)
+        {
+            display
+                .print_balance_warning(dominant, share, measured_in_characters(&file_contexts))
+                .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        }
+
+        if clipboard_result.is_err() {
+            println!("{}", full_output);
+            print_porcelain("stdout");
+        } else {
+            print_porcelain("clipboard");
+        }
+        if clipboard_result.is_ok()
+            && let Some(delay) = cli.clear_after
+        {
+            match clipboard::spawn_delayed_clear(delay, &full_output, cli.clipboard_target) {
+                Ok(()) => display.print_clear_scheduled(delay),
+                Err(e) => display.print_clear_schedule_failed(&e),
+            }
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        }
+    }
+
+    if resolution_errors && cli.on_missing == MissingPolicy::Warn {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// The rendering options recorded in the `--manifest-out` manifest.
+fn manifest_options(cli: &Cli) -> ManifestOptions {
+    ManifestOptions {
+        inputs: cli.inputs.clone(),
+        regex: cli.regex.clone(),
+        exclude_from: cli
+            .exclude_from
+            .iter()
+            .map(|list| list.display().to_string())
+            .collect(),
+        exclude: cli
+            .exclude
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+        input_file: cli
+            .input_file
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+        max_total_lines: cli.max_total_lines,
+        depth: cli.depth,
+        overview: cli.overview,
+        symbol_separator: skeleton_options(cli).symbol_separator,
+        elide_macro_bodies: skeleton_options(cli).elide_macro_bodies,
+        doc_comment_max_lines: skeleton_options(cli).doc_comment_max_lines,
+        max_symbols: cli.max_symbols,
+        symbol_order: cli.symbol_order,
+        changed_only: cli.changed_only.clone(),
+        context_lines: (cli.changed_only.is_some() || cli.only_matching_lines)
+            .then_some(cli.context_lines),
+        grep: cli.grep.as_ref().map(Regex::to_string),
+        only_matching_lines: cli.only_matching_lines,
+        blame_hints: cli.blame_hints,
+        dedupe_imports: cli.dedupe_imports,
+        dedup_skeletons: cli.dedup_skeletons,
+        dedup_ignore_whitespace: cli.dedup_ignore_whitespace,
+        format: cli.format,
+        include_file_size_in_header: cli.include_file_size_in_header,
+        toc: cli.toc,
+        anchors: cli.anchors,
+        prompt_template: cli.prompt_template.map(|template| template.name),
+    }
+}
+
+/// Renders `contexts` (generated for `files`) in the `--format`, with any
+/// table of contents or anchor index, wraps it in the `--prompt-template`
+/// and appends the rendered `footer`.
+fn render_output(
+    cli: &Cli,
+    contexts: &[FileContext],
+    files: &[(ResolvedFile, Option<usize>)],
+    footer: Option<&str>,
+    skeleton_options: &SkeletonOptions,
+) -> String {
+    let render_options = RenderOptions {
+        include_file_size_in_header: cli.include_file_size_in_header,
+        anchors: cli.anchors,
+    };
+    let markdown_output = match cli.format {
+        OutputFormat::Markdown => output::render_markdown(contexts, &render_options),
+        OutputFormat::Chat => output::render_chat(contexts, &render_options),
+        OutputFormat::Cxml => output::render_cxml(contexts),
+        OutputFormat::SymbolsJson => {
+            output::render_symbols_json(&file_symbols(files, skeleton_options))
+        }
+    };
+    // The table of contents and anchor index are navigation, not context,
+    // so they're kept out of the size metric reported below. JSON output has
+    // array indices instead.
+    let full_output = if cli.toc && cli.format == OutputFormat::Markdown {
+        output::render_toc(contexts) + &markdown_output
+    } else if cli.anchors && cli.format != OutputFormat::SymbolsJson {
+        output::render_anchor_index(contexts) + &markdown_output
+    } else {
+        markdown_output
+    };
+    let output = match cli.prompt_template {
+        Some(template) => template.wrap(&full_output),
+        None => full_output,
+    };
+    match footer {
+        // A Markdown rule needs a blank line above it, or it underlines the
+        // line before as a heading.
+        Some(footer) if cli.format != OutputFormat::Cxml && !output.ends_with("\n\n") => {
+            let separator = if output.ends_with('\n') { "\n" } else { "\n\n" };
+            output + separator + footer
+        }
+        Some(footer) => output + footer,
+        None => output,
+    }
+}
+
+/// How many of `contexts`, taken in order, fit within `limit` lines in
+/// total (`--max-total-lines`).
+fn within_line_budget(contexts: &[FileContext], limit: usize) -> usize {
+    let mut total = 0;
+    contexts
+        .iter()
+        .take_while(|context| {
+            total += context.line_count;
+            total <= limit
+        })
+        .count()
+}
+
+/// The on-disk `files` with editor artifacts next to them, by display path,
+/// each with the artifacts' descriptions.
+fn unsaved_edits(files: &[(ResolvedFile, Option<usize>)]) -> Vec<(String, Vec<String>)> {
+    files
+        .iter()
+        .filter(|(file, _)| file.is_on_disk())
+        .filter_map(|(file, _)| {
+            let artifacts = unsaved::artifacts_for(file.canonical_path());
+            (!artifacts.is_empty()).then(|| {
+                (
+                    file.display_path().to_string_lossy().into_owned(),
+                    artifacts
+                        .iter()
+                        .map(unsaved::EditorArtifact::describe)
+                        .collect(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// This run's command line as `--footer` records it: each argument as
+/// typed, quoted for a shell.
+fn command_line() -> String {
+    std::iter::once("ctx-pick".to_string())
+        .chain(
+            std::env::args_os()
+                .skip(1)
+                .map(|arg| display::shell_quote(&arg.to_string_lossy())),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses the answer to the selection editor: 1-based file numbers and
+/// ranges such as `2 5-7`, separated by spaces or commas, out of `count`.
+fn parse_selection(answer: &str, count: usize) -> Result<BTreeSet<usize>, String> {
+    let mut numbers = BTreeSet::new();
+    for part in answer.split([' ', ',']).filter(|part| !part.is_empty()) {
+        let (start, end) = part.split_once('-').unwrap_or((part, part));
+        let (Ok(start), Ok(end)) = (start.trim().parse::<usize>(), end.trim().parse::<usize>())
+        else {
+            return Err(format!("'{}' isn't a file number or range.", part));
+        };
+        if start == 0 || end > count || start > end {
+            return Err(format!(
+                "'{}' is outside the files listed (1-{}).",
+                part, count
+            ));
+        }
+        numbers.extend(start..=end);
+    }
+    Ok(numbers)
+}
+
+/// Reports that Ctrl-C stopped the run at `stage` and exits with
+/// `interrupt::EXIT_CODE`.
+fn exit_interrupted(display: &DisplayManager, stage: &str) -> ! {
+    eprintln!(
+        "{}",
+        display.warning_style.apply_to(format!(
+            "Interrupted {}; nothing was copied or written.",
+            stage
+        ))
+    );
+    std::process::exit(interrupt::EXIT_CODE);
+}
+
+/// Builds the skeleton rendering options from the command line.
+fn skeleton_options(cli: &Cli) -> SkeletonOptions {
+    SkeletonOptions {
+        symbol_separator: cli
+            .symbol_separator
+            .replace("\\n", "\n")
+            .replace("\\t", "\t"),
+        elide_macro_bodies: !cli.keep_macro_bodies,
+        doc_comment_max_lines: (cli.doc_comment_lines > 0).then_some(cli.doc_comment_lines),
+        max_symbols: cli.max_symbols,
+        full_content_fallback: !cli.no_skeleton_fallback,
+        blame_hints: cli.blame_hints.then(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() as i64)
+        }),
+        language_overrides: cli.lang_override.clone(),
+        symbol_order: cli.symbol_order,
+        dedent_slices: !cli.keep_indent,
+        include_empty_files: cli.include_empty_files,
+    }
+}
+
+/// Writes the path of each of `files` in `style`, each followed by
+/// `terminator` (for `--resolve-only`). Paths are written as raw bytes, so
+/// names that aren't UTF-8 survive.
+fn write_paths(
+    out: &mut impl Write,
+    files: &[ResolvedFile],
+    style: PathStyle,
+    terminator: u8,
+) -> std::io::Result<()> {
+    for file in files {
+        let path = match style {
+            PathStyle::Display => file.display_path(),
+            PathStyle::Canonical => file.canonical_path(),
+        };
+        out.write_all(path.as_os_str().as_encoded_bytes())?;
+        out.write_all(&[terminator])?;
+    }
+    out.flush()
+}
+
+/// Reads source code from stdin and prints its skeleton to stdout (`--stdin-content`).
+fn skeletonize_stdin(cli: &Cli) -> Result<(), AppError> {
+    let mut source = String::new();
+    std::io::stdin()
+        .read_to_string(&mut source)
+        .map_err(|e| AppError::IoError(format!("Failed to read stdin: {}", e)))?;
+    println!("{}", stdin_skeleton(&source, cli)?);
+    Ok(())
+}
+
+/// The skeleton `--stdin-content` prints for `source`.
+fn stdin_skeleton(source: &str, cli: &Cli) -> Result<String, AppError> {
+    // Both are guaranteed by clap's `requires_all`.
+    let language = language::language_key_from_name(
+        cli.force_language
+            .as_deref()
+            .expect("--force-language is required"),
+    );
+    let depth = cli.depth.expect("--depth is required");
+
+    symbol_extractor::create_skeleton_by_depth(source, &language, depth, &skeleton_options(cli))
+        .map_err(AppError::SkeletonError)
+}
+
+/// The share of the output one file may take before the balance warning:
+/// `--balance-threshold`, else the user config's `balance_threshold`, else
+/// the default. `None` under `--no-balance-warning`.
+fn balance_warning_threshold(cli: &Cli) -> Option<f64> {
+    if cli.no_balance_warning {
+        return None;
+    }
+    Some(
+        cli.balance_threshold
+            .or(user_config::get().balance_threshold)
+            .map_or(config::DEFAULT_BALANCE_WARNING_THRESHOLD, |percent| {
+                f64::from(percent) / 100.0
+            }),
+    )
+}
+
+/// Whether the balance warning measures files in characters: when any of
+/// them was rendered as a skeleton, whose line count says little about its
+/// size. Otherwise it counts lines.
+fn measured_in_characters(contexts: &[FileContext]) -> bool {
+    contexts.iter().any(|ctx| ctx.depth.is_some())
+}
+
+/// Finds a file that makes up more than `threshold` of the total output, measured
+/// as `measured_in_characters` says. Returns it with its share. There is none
+/// without a threshold (the warning is off).
+fn find_dominant_file(
+    contexts: &[FileContext],
+    threshold: Option<f64>,
+) -> Option<(&FileContext, f64)> {
+    let threshold = threshold?;
+    // With a single file there is nothing for it to crowd out.
+    if contexts.len() < 2 {
+        return None;
+    }
+    let in_characters = measured_in_characters(contexts);
+    let size = |ctx: &FileContext| {
+        if in_characters {
+            ctx.char_count
+        } else {
+            ctx.line_count
+        }
+    };
+    let total: usize = contexts.iter().map(size).sum();
+    if total == 0 {
+        return None;
+    }
+    let largest = contexts.iter().max_by_key(|ctx| size(ctx))?;
+    let share = size(largest) as f64 / total as f64;
+    (share > threshold).then_some((largest, share))
+}
+
+/// The path shown for a file, with a `:START-END` suffix if only some of its
+/// lines were included and an `@ REV` suffix if it was read from git. The end
+/// reflects the lines actually read, so a range past the end isn't overstated.
+fn display_path_for(file: &ResolvedFile, content: Option<&str>) -> String {
+    let mut label = file.display_path().to_string_lossy().to_string();
+    if let Some(range) = file.line_range()
+        && is_partial(file, content)
+    {
+        let lines_read = content.map_or(0, |c| c.lines().count());
+        let end = if lines_read == 0 {
+            range.end
+        } else {
+            range.start + lines_read - 1
+        };
+        label.push_str(&format!(":{}-{}", range.start, end));
+    }
+    if let Some(revision) = file.revision() {
+        label.push_str(&format!(" @ {}", revision));
+    }
+    label
+}
+
+/// Whether only some of the file's lines were read into `content`. A head
+/// longer than the file is just the whole file.
+fn is_partial(file: &ResolvedFile, content: Option<&str>) -> bool {
+    file.line_range().is_some_and(|range| {
+        let whole_file = content.is_some_and(|c| range.start == 1 && c.lines().count() < range.end);
+        !whole_file
+    })
+}
+
+/// Reads the (selected lines of the) file, from git if it's pinned to a
+/// revision, or from its archive.
+fn read_file_content(file: &ResolvedFile) -> Result<String, String> {
+    let content = match (file.revision(), file.archive()) {
+        (Some(revision), _) => git::read_file_at(file.canonical_path(), revision)?,
+        (None, Some(archive)) => archive::read_entry(archive, file.canonical_path())?,
+        (None, None) => {
+            return file_reader::read_lines(file.canonical_path(), file.line_range())
+                .map_err(|e| e.to_string());
+        }
+    };
+    Ok(match file.line_range() {
+        Some(range) => file_reader::slice_lines(&content, range).to_string(),
+        None => content,
+    })
+}
+
+/// Extracts the symbols of each skeletonized file in `files` (for `--format
+/// symbols-json`). Files that can't be read or parsed have none.
+fn file_symbols(
+    files: &[(ResolvedFile, Option<usize>)],
+    skeleton_options: &SkeletonOptions,
+) -> Vec<output::FileSymbols> {
+    files
+        .iter()
+        .filter(|(_, depth)| depth.is_some())
+        .map(|(file, _)| {
+            let content = read_file_content(file).unwrap_or_default();
+            let language = language::language_for(
+                file.display_path(),
+                &content,
+                &skeleton_options.language_overrides,
+            );
+            let first_line = file.line_range().map_or(1, |range| range.start);
+            let mut tags = language
+                .as_deref()
+                .and_then(|language| symbol_extractor::extract_tags(&content, language).ok())
+                .unwrap_or_default();
+            skeleton_options.symbol_order.sort_tags(&mut tags);
+            let symbols = tags
+                .into_iter()
+                .map(|tag| Tag {
+                    line: tag.line + first_line - 1,
+                    end_line: tag.end_line + first_line - 1,
+                    ..tag
+                })
+                .collect();
+            output::FileSymbols {
+                path: display_path_for(file, Some(&content)),
+                language,
+                symbols,
+            }
+        })
+        .collect()
+}
+
+/// Converts CRLF line endings to LF in every context (unless `--keep-crlf`).
+fn normalize_newlines(contexts: Vec<FileContext>) -> Vec<FileContext> {
+    contexts
+        .into_iter()
+        .map(|context| {
+            if !context.content.contains("\r\n") {
+                return context;
+            }
+            let content = context.content.replace("\r\n", "\n");
+            context.with_content(content)
+        })
+        .collect()
+}
+
+/// Collapses skeletons with identical content into the first of them, whose
+/// header then lists every file sharing it (for `--dedup-skeletons`). With
+/// `ignore_whitespace`, content is compared as `whitespace_normalized`.
+fn dedup_skeletons(contexts: Vec<FileContext>, ignore_whitespace: bool) -> Vec<FileContext> {
+    let mut collapsed: Vec<FileContext> = Vec::with_capacity(contexts.len());
+    // Where each distinct skeleton's block is, and the other paths sharing it.
+    let mut blocks: HashMap<String, (usize, Vec<String>)> = HashMap::new();
+    for context in contexts {
+        if context.depth.is_some() {
+            let key = if ignore_whitespace {
+                whitespace_normalized(&context.content)
+            } else {
+                context.content.clone()
+            };
+            if let Some((_, sharing)) = blocks.get_mut(&key) {
+                sharing.push(context.display_path);
+                continue;
+            }
+            blocks.insert(key, (collapsed.len(), Vec::new()));
+        }
+        collapsed.push(context);
+    }
+    for (index, sharing) in blocks.into_values() {
+        if sharing.is_empty() {
+            continue;
+        }
+        let context = &mut collapsed[index];
+        context.file_count += sharing.len();
+        context.display_path = std::iter::once(context.display_path.as_str())
+            .chain(sharing.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(", ");
+    }
+    collapsed
+}
+
+/// `content` with line endings made `\n`, trailing whitespace dropped from
+/// each line and trailing blank lines dropped, for
+/// `--dedup-ignore-whitespace`.
+fn whitespace_normalized(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().map(str::trim_end).collect();
+    lines.join("\n").trim_end().to_string()
+}
+
+/// Lengthens the headers of different files that would otherwise share one
+/// (e.g. the same relative path under two symlinked roots) with as many of
+/// their parent directories as it takes to tell them apart. `contexts` are
+/// the ones generated for `files`, in order. Returns each shared header with
+/// the headers shown in its place, for the summary.
+fn disambiguate_headers(
+    files: &[(ResolvedFile, Option<usize>)],
+    contexts: &mut [FileContext],
+) -> Vec<(String, Vec<String>)> {
+    let mut by_header: Vec<(String, Vec<usize>)> = Vec::new();
+    for (index, context) in contexts.iter().enumerate() {
+        match by_header
+            .iter_mut()
+            .find(|(header, _)| *header == context.display_path)
+        {
+            Some((_, indices)) => indices.push(index),
+            None => by_header.push((context.display_path.clone(), vec![index])),
+        }
+    }
+
+    // The last `count` components of `path`.
+    let tail = |path: &Path, count: usize| -> PathBuf {
+        let components: Vec<_> = path.components().collect();
+        components[components.len().saturating_sub(count)..]
+            .iter()
+            .collect()
+    };
+    let mut collisions = Vec::new();
+    for (header, indices) in by_header {
+        if indices.len() < 2 {
+            continue;
+        }
+        let paths: Vec<&Path> = indices
+            .iter()
+            .map(|&index| files[index].0.canonical_path())
+            .collect();
+        let shown = files[indices[0]].0.display_path().components().count();
+        let longest = paths.iter().map(|path| path.components().count()).max();
+        let mut lengthened: Vec<PathBuf> = paths.iter().map(|path| path.to_path_buf()).collect();
+        for count in shown + 1..longest.unwrap_or(0) {
+            let candidates: Vec<PathBuf> = paths.iter().map(|path| tail(path, count)).collect();
+            if candidates.iter().collect::<HashSet<_>>().len() == candidates.len() {
+                lengthened = candidates;
+                break;
+            }
+        }
+        let mut headers = Vec::new();
+        for (&index, path) in indices.iter().zip(lengthened) {
+            // Keep any `:START-END` or `@ REV` suffix after the path.
+            let suffix = &header[files[index].0.display_path().to_string_lossy().len()..];
+            contexts[index].display_path = format!("{}{}", path.display(), suffix);
+            headers.push(contexts[index].display_path.clone());
+        }
+        collisions.push((header, headers));
+    }
+    collisions
+}
+
+/// Compacts the full-content contexts (for `--compact`), warning once about
+/// any whose language depends on whitespace.
+fn compact_contexts(contexts: Vec<FileContext>) -> Vec<FileContext> {
+    let sensitive: Vec<&str> = contexts
+        .iter()
+        .filter(|context| {
+            context.depth.is_none()
+                && context
+                    .language
+                    .as_deref()
+                    .is_some_and(|key| output::WHITESPACE_SENSITIVE_LANGUAGES.contains(&key))
+        })
+        .map(|context| context.display_path.as_str())
+        .collect();
+    if !sensitive.is_empty() {
+        eprintln!(
+            "Warning: --compact may change the meaning of whitespace-sensitive files: {}",
+            sensitive.join(", ")
+        );
+    }
+    contexts
+        .into_iter()
+        .map(|context| {
+            if context.depth.is_some() {
+                return context;
+            }
+            let content = output::compact(&context.content);
+            context.with_content(content)
+        })
+        .collect()
+}
+
+/// Hard-wraps the full-content contexts at `width` characters (for `--wrap`).
+fn wrap_contexts(contexts: Vec<FileContext>, width: usize) -> Vec<FileContext> {
+    contexts
+        .into_iter()
+        .map(|context| {
+            if context.depth.is_some() {
+                return context;
+            }
+            let content = output::hard_wrap(&context.content, width);
+            context.with_content(content)
+        })
+        .collect()
+}
+
+/// Cuts lines longer than `max` characters in full-content contexts (for
+/// `--max-line-length`), counting them in each context's `truncated_lines`.
+fn truncate_contexts(contexts: Vec<FileContext>, max: usize) -> Vec<FileContext> {
+    contexts
+        .into_iter()
+        .map(|context| {
+            if context.depth.is_some() {
+                return context;
+            }
+            let (content, truncated_lines) = output::truncate_long_lines(&context.content, max);
+            FileContext {
+                truncated_lines,
+                ..context.with_content(content)
+            }
+        })
+        .collect()
+}
+
+/// Adds each file's version at `tree`'s revision to `current` (the contexts
+/// generated for `files`), formatted for `--compare`.
+fn compare_contexts(
+    files: &[(ResolvedFile, Option<usize>)],
+    current: Vec<FileContext>,
+    tree: &GitTree,
+    format: CompareFormat,
+    skeleton_options: &SkeletonOptions,
+) -> Vec<FileContext> {
+    let previous_files: Vec<(ResolvedFile, Option<usize>)> = files
+        .iter()
+        .filter(|(file, _)| tree.contains(file.display_path()))
+        .map(|(file, depth)| (file.clone().at_revision(&tree.revision), *depth))
+        .collect();
+    // An empty old version is still a version to compare against.
+    let skeleton_options = SkeletonOptions {
+        include_empty_files: true,
+        ..skeleton_options.clone()
+    };
+    let mut previous_contexts =
+        generate_file_contexts(&previous_files, &skeleton_options).into_iter();
+    let previous: Vec<Option<FileContext>> = files
+        .iter()
+        .map(|(file, _)| {
+            tree.contains(file.display_path())
+                .then(|| previous_contexts.next())
+                .flatten()
+        })
+        .collect();
+    compare::combine(current, previous, &tree.revision, format)
+}
+
+/// Cuts each of `contexts` (generated in full for `files`) down to the lines
+/// changed since `changes`'s revision, plus `context_lines` around them.
+fn changed_contexts(
+    files: &[(ResolvedFile, Option<usize>)],
+    contexts: Vec<FileContext>,
+    changes: &ChangedLines,
+    context_lines: usize,
+) -> Vec<FileContext> {
+    contexts
+        .into_iter()
+        .zip(files)
+        .map(|(context, (file, _))| {
+            let ranges = changes.ranges(file.display_path()).unwrap_or_default();
+            FileContext {
+                permissions: context.permissions,
+                ..FileContext::new(
+                    format!(
+                        "{} (changed since {})",
+                        context.display_path, changes.revision
+                    ),
+                    file_reader::excerpt_lines(&context.content, ranges, context_lines),
+                    context.language,
+                    context.depth,
+                )
+            }
+        })
+        .collect()
+}
+
+/// Returns true if `file` is binary. Files that can't be read, or are read
+/// at a git revision, aren't checked.
+fn is_binary_file(file: &ResolvedFile) -> bool {
+    match file.archive() {
+        Some(archive) => archive::is_binary_entry(archive, file.canonical_path()),
+        None => {
+            file.revision().is_none()
+                && file_reader::is_binary(file.canonical_path()).unwrap_or(false)
+        }
+    }
+}
+
+/// Returns true if `file`'s content matches `pattern`, or if it can't be read.
+fn content_matches(file: &ResolvedFile, pattern: &Regex) -> bool {
+    read_file_content(file)
+        .ok()
+        .is_none_or(|content| pattern.is_match(&content))
+}
+
+/// Replaces each full-content context with just its lines matching `pattern`
+/// and `context_lines` around them, numbered like `grep -n -C` output.
+fn matching_line_contexts(
+    files: &[(ResolvedFile, Option<usize>)],
+    contexts: Vec<FileContext>,
+    pattern: &Regex,
+    context_lines: usize,
+) -> Vec<FileContext> {
+    contexts
+        .into_iter()
+        .zip(files)
+        .map(|(context, (file, _))| {
+            let first_line = file.line_range().map_or(1, |range| range.start);
+            FileContext {
+                permissions: context.permissions,
+                ..FileContext::new(
+                    format!("{} (lines matching /{}/)", context.display_path, pattern),
+                    file_reader::grep_lines(&context.content, pattern, context_lines, first_line),
+                    context.language,
+                    context.depth,
+                )
+            }
+        })
+        .collect()
+}
+
+/// Why a resolved file has no context. Each carries its display path and the error.
+#[derive(Debug, PartialEq, Eq)]
+enum ContextFailure {
+    /// The file couldn't be read; it may have changed since it was resolved.
+    Unreadable(String, String),
+    /// ctx-pick panicked while processing the file.
+    Crashed(String, String),
+    /// The file was deleted after it was resolved, e.g. by a rebuild.
+    Removed(String),
+    /// The file is zero-length, and `--include-empty-files` wasn't given.
+    Empty(String),
+    /// No skeleton could be extracted, and `--no-skeleton-fallback` rules out
+    /// using the full content instead.
+    NoSkeleton(String, String),
+}
+
+/// Processes a list of resolved files, each paired with its skeleton depth,
+/// returning the context (full or skeleton) for each, or why there is none.
+///
+/// Parse trees kept by symbol search are dropped afterwards, whether or not
+/// a skeleton used them.
+///
+/// A panic while processing one file, such as a grammar choking on input it
+/// wasn't written for, costs that file alone: it's reported as `Crashed`
+/// instead of with the default panic message, and the other files carry on.
+fn read_file_contexts(
+    files: &[(ResolvedFile, Option<usize>)],
+    skeleton_options: &SkeletonOptions,
+) -> Vec<Result<FileContext, ContextFailure>> {
+    let contexts = read_file_contexts_with(files, |resolved_file, depth| {
+        read_file_context(resolved_file, depth, skeleton_options)
+    });
+    symbol_extractor::clear_parse_cache();
+    contexts
+}
+
+/// `read_file_contexts` with the per-file work done by `read`, each call
+/// isolated from the others' panics.
+fn read_file_contexts_with(
+    files: &[(ResolvedFile, Option<usize>)],
+    read: impl Fn(&ResolvedFile, Option<usize>) -> Result<FileContext, ContextFailure>,
+) -> Vec<Result<FileContext, ContextFailure>> {
+    quiet_panics_while_reading();
+    files
+        .iter()
+        .map(|(resolved_file, depth)| {
+            READING_FILE.set(true);
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| read(resolved_file, *depth)));
+            READING_FILE.set(false);
+            result.unwrap_or_else(|payload| {
+                Err(ContextFailure::Crashed(
+                    display_path_for(resolved_file, None),
+                    panic_message(payload.as_ref()),
+                ))
+            })
+        })
+        .collect()
+}
+
+thread_local! {
+    /// Whether this thread is inside `read_file_context`, whose panics are
+    /// caught and reported per file.
+    static READING_FILE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Installs, once, a panic hook that stays quiet for panics caught by
+/// `read_file_contexts` and defers to the previous hook for all others.
+fn quiet_panics_while_reading() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !READING_FILE.get() {
+                default_hook(info);
+            }
+        }));
+    });
+}
+
+/// Describes a caught panic by the message it was raised with, if any.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str));
+    match message {
+        Some(message) => format!("ctx-pick crashed while processing it: {}", message),
+        None => "ctx-pick crashed while processing it".to_string(),
+    }
+}
+
+/// The context (full or skeleton) for one file at `depth`, or why there is none.
+fn read_file_context(
+    resolved_file: &ResolvedFile,
+    depth: Option<usize>,
+    skeleton_options: &SkeletonOptions,
+) -> Result<FileContext, ContextFailure> {
+    let content = match read_file_content(resolved_file) {
+        Ok(content) => content,
+        Err(e) => {
+            let display_path = display_path_for(resolved_file, None);
+            return Err(
+                if resolved_file.is_on_disk() && !resolved_file.canonical_path().exists() {
+                    ContextFailure::Removed(display_path)
+                } else {
+                    ContextFailure::Unreadable(display_path, e)
+                },
+            );
+        }
+    };
+    let mut display_path = display_path_for(resolved_file, Some(&content));
+    if content.is_empty()
+        && resolved_file.line_range().is_none()
+        && !skeleton_options.include_empty_files
+    {
+        return Err(ContextFailure::Empty(display_path));
+    }
+    let language = language::language_for(
+        resolved_file.display_path(),
+        &content,
+        &skeleton_options.language_overrides,
+    );
+    let depth = depth.filter(|_| {
+        !language
+            .as_deref()
+            .is_some_and(symbol_extractor::is_always_full)
+    });
+    if language.as_deref() == Some("tfvars") {
+        eprintln!(
+            "Warning: {:?} is included in full; .tfvars files often hold credentials, so check it before sharing.",
+            display_path
+        );
+    }
+    if resolved_file
+        .display_path()
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name == ".env" || name.starts_with(".env."))
+    {
+        eprintln!(
+            "Warning: {:?} is an environment file; these often hold credentials, so check it before sharing.",
+            display_path
+        );
+    }
+
+    let mut syntax_error_line = None;
+    let final_content = if let Some(max_depth) = depth {
+        let extension = language.as_deref().unwrap_or("");
+        // Blame covers the working tree only; files git doesn't
+        // track simply get no hints.
+        let blame = skeleton_options
+            .blame_hints
+            .filter(|_| resolved_file.is_on_disk())
+            .and_then(|now| {
+                git::Blame::load(resolved_file.canonical_path())
+                    .ok()
+                    .map(|blame| (blame, now))
+            });
+        let first_line = resolved_file.line_range().map_or(1, |range| range.start);
+        match symbol_extractor::create_skeleton_with_hints(
+            &content,
+            extension,
+            max_depth,
+            skeleton_options,
+            |lines| {
+                let (blame, now) = blame.as_ref()?;
+                let lines = LineRange {
+                    start: lines.start + first_line - 1,
+                    end: lines.end + first_line - 1,
+                };
+                blame.latest_in(lines).map(|commit| commit.summary(*now))
+            },
+        ) {
+            Ok(symbols) => {
+                syntax_error_line = symbol_extractor::first_syntax_error(&content, extension)
+                    .map(|line| line + first_line - 1);
+                symbols
+            }
+            Err(e) if !skeleton_options.full_content_fallback => {
+                return Err(ContextFailure::NoSkeleton(display_path, e));
+            }
+            Err(e) => format!(
+                "---\n-- ERROR: Could not extract symbols from {:?}: {}\n-- Falling back to full file content.\n---\n\n{}",
+                display_path, e, content
+            ),
+        }
+    } else if skeleton_options.dedent_slices
+        && is_partial(resolved_file, Some(&content))
+        && let Some((dedented, removed)) = output::dedent(&content)
+    {
+        display_path.push_str(&format!(" (indentation reduced by {})", removed));
+        dedented
+    } else {
+        content
+    };
+
+    // A file read at a git revision or from an archive has no
+    // permissions of its own on disk.
+    let permissions = resolved_file
+        .is_on_disk()
+        .then(|| std::fs::metadata(resolved_file.canonical_path()).ok())
+        .flatten()
+        .map(|metadata| FilePermissions::of(resolved_file.canonical_path(), &metadata));
+
+    Ok(FileContext {
+        syntax_error_line,
+        permissions,
+        ..FileContext::new(display_path, final_content, language, depth)
+    })
+}
+
+/// Like `read_file_contexts`, but a file without a context gets one
+/// describing the error in place of its content. Files removed since they
+/// were resolved are left out, with a warning, and so are empty files unless
+/// `skeleton_options` includes them.
+fn generate_file_contexts(
+    files: &[(ResolvedFile, Option<usize>)],
+    skeleton_options: &SkeletonOptions,
+) -> Vec<FileContext> {
+    read_file_contexts(files, skeleton_options)
+        .into_iter()
+        .zip(files)
+        .filter_map(|(result, (file, depth))| match result {
+            Ok(context) => Some(context),
+            Err(
+                ContextFailure::Unreadable(display_path, e)
+                | ContextFailure::Crashed(display_path, e),
+            ) => Some(read_error_context(
+                file,
+                *depth,
+                display_path,
+                e,
+                skeleton_options,
+            )),
+            Err(ContextFailure::Removed(display_path)) => {
+                eprintln!(
+                    "Warning: Skipping {:?}; it was removed during the run.",
+                    display_path
+                );
+                None
+            }
+            Err(ContextFailure::Empty(_)) => None,
+            Err(ContextFailure::NoSkeleton(display_path, e)) => Some(FileContext::new(
+                display_path.clone(),
+                format!(
+                    "Skeleton omitted: could not extract symbols from {:?}: {}",
+                    display_path, e
+                ),
+                language::language_for(
+                    file.display_path(),
+                    "",
+                    &skeleton_options.language_overrides,
+                ),
+                *depth,
+            )),
+        })
+        .collect()
+}
+
+/// A context standing in for a file that couldn't be read (`--on-read-error embed`).
+fn read_error_context(
+    file: &ResolvedFile,
+    depth: Option<usize>,
+    display_path: String,
+    e: String,
+    skeleton_options: &SkeletonOptions,
+) -> FileContext {
+    FileContext::new(
+        display_path.clone(),
+        format!(
+            "Error: Could not read file content for {:?}.\nDetails: {}",
+            display_path, e
+        ),
+        language::language_for(
+            file.display_path(),
+            "",
+            &skeleton_options.language_overrides,
+        ),
+        depth,
+    )
 }
@@ -0,0 +1,278 @@
+// src/manifest.rs
+
+use crate::output::OutputFormat;
+use crate::symbol_extractor::SymbolOrder;
+use crate::types::FileContext;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Bumped whenever the manifest's shape changes incompatibly.
+const MANIFEST_VERSION: u32 = 1;
+
+/// A rough characters-per-token ratio for English text and code. The manifest
+/// labels the resulting counts as estimates; no tokenizer is involved.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimates the number of tokens in `text` from its length.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// A record of exactly what went into one run's output, for `--manifest-out`.
+///
+/// Everything in it is derived from the inputs and the rendered output, never
+/// from timestamps or absolute paths, so re-running on the same tree with the
+/// same options produces a byte-identical manifest.
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub options: ManifestOptions,
+    pub files: Vec<ManifestFile>,
+    pub output: ContentStats,
+}
+
+/// The options that affect what is rendered.
+#[derive(Debug, Serialize)]
+pub struct ManifestOptions {
+    pub inputs: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub regex: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude_from: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub input_file: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_total_lines: Option<usize>,
+    pub depth: Option<usize>,
+    pub overview: bool,
+    pub symbol_separator: String,
+    pub elide_macro_bodies: bool,
+    pub doc_comment_max_lines: Option<usize>,
+    pub max_symbols: Option<usize>,
+    #[serde(skip_serializing_if = "SymbolOrder::is_source")]
+    pub symbol_order: SymbolOrder,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_only: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grep: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub only_matching_lines: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub blame_hints: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dedupe_imports: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dedup_skeletons: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dedup_ignore_whitespace: bool,
+    pub format: OutputFormat,
+    pub include_file_size_in_header: bool,
+    pub toc: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub anchors: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_template: Option<&'static str>,
+}
+
+/// One included file, in output order.
+#[derive(Debug, Serialize)]
+pub struct ManifestFile {
+    pub path: String,
+    /// The skeleton depth the content was extracted at, if any.
+    pub depth: Option<usize>,
+    #[serde(flatten)]
+    pub content: ContentStats,
+}
+
+/// A hash and size metrics for a piece of text.
+#[derive(Debug, Serialize)]
+pub struct ContentStats {
+    pub sha256: String,
+    pub bytes: usize,
+    pub lines: usize,
+    pub estimated_tokens: usize,
+}
+
+impl ContentStats {
+    fn of(text: &str) -> Self {
+        Self {
+            sha256: sha256_hex(text),
+            bytes: text.len(),
+            lines: text.lines().count(),
+            estimated_tokens: estimate_tokens(text),
+        }
+    }
+}
+
+/// The SHA-256 of `text`, as lowercase hex.
+pub fn sha256_hex(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// How the files of this run differ from those recorded in an earlier
+/// manifest (`--diff-last`). Paths are display paths, so a file counts as
+/// modified when its included content changed, including its skeleton depth.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FileSetDiff {
+    /// Files not in the earlier manifest, in output order.
+    pub added: Vec<String>,
+    /// Files only in the earlier manifest, in its order.
+    pub removed: Vec<String>,
+    /// Files in both whose content hash differs, in output order.
+    pub modified: Vec<String>,
+    pub unchanged: usize,
+}
+
+impl FileSetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// The parts of a manifest written by an earlier run that `--diff-last`
+/// compares. Other fields, including from newer manifest versions, are ignored.
+#[derive(Debug, Deserialize)]
+pub struct RecordedManifest {
+    files: Vec<RecordedFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedFile {
+    path: String,
+    sha256: String,
+}
+
+impl RecordedManifest {
+    /// Reads the manifest at `path`.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read manifest {:?}: {}", path, e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| format!("{:?} is not a ctx-pick manifest: {}", path, e))
+    }
+}
+
+/// The one-line JSON record printed by `--porcelain` after a successful run.
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    /// The number of files included.
+    pub files: usize,
+    /// Where the output went: `clipboard`, `stdout` or `file`.
+    pub destination: &'static str,
+    pub characters: usize,
+    #[serde(flatten)]
+    pub output: ContentStats,
+    /// Each file's permissions, with `--show-permissions`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Vec<PermissionsEntry>>,
+}
+
+/// One file's permissions in the `--porcelain` record.
+#[derive(Debug, Serialize)]
+pub struct PermissionsEntry {
+    pub path: String,
+    /// The Unix permission bits in octal (e.g. `"0755"`); `null` elsewhere.
+    pub mode: Option<String>,
+    pub executable: bool,
+    pub readonly: bool,
+}
+
+impl RunSummary {
+    pub fn new(files: usize, destination: &'static str, output: &str) -> Self {
+        Self {
+            files,
+            destination,
+            characters: output.chars().count(),
+            output: ContentStats::of(output),
+            permissions: None,
+        }
+    }
+
+    /// Adds the permissions of each of `contexts` read from disk.
+    pub fn with_permissions(self, contexts: &[FileContext]) -> Self {
+        let permissions = contexts
+            .iter()
+            .filter_map(|context| {
+                let permissions = context.permissions?;
+                Some(PermissionsEntry {
+                    path: context.display_path.clone(),
+                    mode: permissions.mode.map(|mode| format!("{:04o}", mode)),
+                    executable: permissions.executable,
+                    readonly: permissions.readonly,
+                })
+            })
+            .collect();
+        Self {
+            permissions: Some(permissions),
+            ..self
+        }
+    }
+
+    /// Serializes the summary as a single line of JSON.
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("run summary serializes")
+    }
+}
+
+impl Manifest {
+    /// Builds a manifest for `contexts` (as included, i.e. skeletons where a
+    /// depth applied) and the full rendered `output`.
+    pub fn new(options: ManifestOptions, contexts: &[FileContext], output: &str) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            options,
+            files: contexts
+                .iter()
+                .map(|context| ManifestFile {
+                    path: context.display_path.clone(),
+                    depth: context.depth,
+                    content: ContentStats::of(&context.content),
+                })
+                .collect(),
+            output: ContentStats::of(output),
+        }
+    }
+
+    /// Compares this run's files with those in `previous`.
+    pub fn diff_against(&self, previous: &RecordedManifest) -> FileSetDiff {
+        let previous_hashes: HashMap<&str, &str> = previous
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), file.sha256.as_str()))
+            .collect();
+        let mut diff = FileSetDiff::default();
+        for file in &self.files {
+            match previous_hashes.get(file.path.as_str()) {
+                None => diff.added.push(file.path.clone()),
+                Some(&hash) if hash != file.content.sha256 => diff.modified.push(file.path.clone()),
+                Some(_) => diff.unchanged += 1,
+            }
+        }
+        let current: HashSet<&str> = self.files.iter().map(|file| file.path.as_str()).collect();
+        diff.removed = previous
+            .files
+            .iter()
+            .filter(|file| !current.contains(file.path.as_str()))
+            .map(|file| file.path.clone())
+            .collect();
+        diff
+    }
+
+    /// Writes the manifest to `path` as pretty-printed JSON.
+    pub fn write_to(&self, path: &Path) -> Result<(), String> {
+        let mut json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        json.push('\n');
+        fs::write(path, json).map_err(|e| format!("Failed to write manifest to {:?}: {}", path, e))
+    }
+}
@@ -0,0 +1,433 @@
+// src/output.rs
+
+use crate::types::{FileContext, Tag};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The overall shape of the output.
+#[derive(clap::ValueEnum, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Each file's header followed by a fenced code block.
+    #[default]
+    Markdown,
+    /// Markdown laid out for chat UIs: each file's name in bold on its own
+    /// line, then its fenced code block.
+    Chat,
+    /// The `<documents>` XML of files-to-prompt's `--cxml` option.
+    Cxml,
+    /// The symbols extracted from each skeletonized file, as JSON.
+    #[serde(rename = "symbols-json")]
+    SymbolsJson,
+}
+
+/// Options controlling how file contexts are rendered into the final output.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Append each file's size to its header, e.g. `src/main.rs (214 lines)`.
+    pub include_file_size_in_header: bool,
+    /// Number each header as a Markdown heading, e.g. `### [7] src/display.rs`,
+    /// to match the entries of `render_anchor_index` (`--anchors`).
+    pub anchors: bool,
+}
+
+/// Language keys whose usual Markdown fence name differs from the key.
+const FENCE_HINTS: &[(&str, &str)] = &[("tex", "latex"), ("ltx", "latex")];
+
+/// The fence hint for a language key.
+fn fence_hint(language: &str) -> &str {
+    FENCE_HINTS
+        .iter()
+        .find(|(key, _)| *key == language)
+        .map_or(language, |(_, hint)| hint)
+}
+
+/// Renders file contexts as Markdown: each file's display path followed by
+/// its content in a fenced code block.
+pub fn render_markdown(contexts: &[FileContext], options: &RenderOptions) -> String {
+    let mut markdown_output = String::new();
+    for (index, context) in contexts.iter().enumerate() {
+        // Skeletons aren't valid source, so they get no fence hint.
+        let lang_hint = if context.depth.is_some() {
+            ""
+        } else {
+            context.language.as_deref().map_or("", fence_hint)
+        };
+        let header = header_for(context, options);
+        let header = if options.anchors {
+            format!("### [{}] {}", index + 1, header)
+        } else {
+            header
+        };
+        markdown_output.push_str(&format!(
+            "{}\n```{}\n{}\n```\n\n",
+            header,
+            lang_hint,
+            context.content.trim_end()
+        ));
+    }
+    markdown_output
+}
+
+/// Renders file contexts for pasting into a chat UI (`--format chat`). Some
+/// chat renderers mangle a bare path directly above a fence, so each header
+/// is set apart as a bold code span (which also keeps names like
+/// `__init__.py` from turning into emphasis), followed by a blank line. Each
+/// fence is longer than any run of backticks in its content, so an embedded
+/// fence can't end the block early.
+pub fn render_chat(contexts: &[FileContext], options: &RenderOptions) -> String {
+    let mut output = String::new();
+    for (index, context) in contexts.iter().enumerate() {
+        let lang_hint = if context.depth.is_some() {
+            ""
+        } else {
+            context.language.as_deref().map_or("", fence_hint)
+        };
+        let anchor = if options.anchors {
+            format!("[{}] ", index + 1)
+        } else {
+            String::new()
+        };
+        let content = context.content.trim_end();
+        let fence = "`".repeat(longest_backtick_run(content).max(2) + 1);
+        output.push_str(&format!(
+            "**{}`{}`**\n\n{}{}\n{}\n{}\n\n",
+            anchor,
+            header_for(context, options),
+            fence,
+            lang_hint,
+            content,
+            fence
+        ));
+    }
+    output
+}
+
+/// The length of the longest run of consecutive backticks in `text`.
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Renders file contexts exactly as files-to-prompt's `--cxml` does: one
+/// `<document>` per file, indexed from 1 in output order, with the content
+/// copied raw (no entity escaping) and followed by a newline.
+pub fn render_cxml(contexts: &[FileContext]) -> String {
+    let mut output = String::from("<documents>\n");
+    for (index, context) in contexts.iter().enumerate() {
+        output.push_str(&format!(
+            "<document index=\"{}\">\n<source>{}</source>\n<document_content>\n{}\n</document_content>\n</document>\n",
+            index + 1,
+            context.display_path,
+            context.content
+        ));
+    }
+    output.push_str("</documents>\n");
+    output
+}
+
+/// The symbols extracted from one file, for `--format symbols-json`.
+#[derive(Debug, serde::Serialize)]
+pub struct FileSymbols {
+    pub path: String,
+    pub language: Option<String>,
+    /// Sorted by position. When only a line range of the file was selected,
+    /// `line` still counts from the start of the file, while `start_byte` is
+    /// an offset into the selected lines.
+    pub symbols: Vec<Tag>,
+}
+
+/// Renders the symbols of each file as a pretty-printed JSON array.
+pub fn render_symbols_json(files: &[FileSymbols]) -> String {
+    // Plain strings, numbers and vectors always serialize.
+    serde_json::to_string_pretty(files).expect("symbols should serialize") + "\n"
+}
+
+/// Renders a numbered table of contents: one entry per file, in output order,
+/// naming it exactly as its header does and giving its line count.
+pub fn render_toc(contexts: &[FileContext]) -> String {
+    let mut toc = String::from("Contents:\n");
+    for (index, context) in contexts.iter().enumerate() {
+        toc.push_str(&format!(
+            "{}. {} ({} line{})\n",
+            index + 1,
+            context.display_path,
+            context.line_count,
+            if context.line_count == 1 { "" } else { "s" }
+        ));
+    }
+    toc.push('\n');
+    toc
+}
+
+/// Renders the index that `--anchors` puts ahead of the output: each file's
+/// number, which matches its header, its `<document>` index and its place in
+/// the summary preview, then its path and a one-line description.
+pub fn render_anchor_index(contexts: &[FileContext]) -> String {
+    let mut index = String::from("Index:\n");
+    for (i, context) in contexts.iter().enumerate() {
+        index.push_str(&format!("[{}] {}", i + 1, context.display_path));
+        if let Some(description) = describe(&context.content) {
+            index.push_str(&format!(": {}", description));
+        }
+        index.push('\n');
+    }
+    index.push('\n');
+    index
+}
+
+/// Doc comment openers recognized when describing a file for the index.
+const DOC_COMMENT_MARKERS: &[&str] = &["//!", "///", "/**", "\"\"\"", "'''"];
+
+/// The longest description the anchor index gives a file, in characters.
+const MAX_DESCRIPTION_CHARS: usize = 80;
+
+/// Describes a file in one line: the text of its first doc comment, or else
+/// its first non-empty line, shortened to `MAX_DESCRIPTION_CHARS`.
+fn describe(content: &str) -> Option<String> {
+    let doc_comment = content.lines().map(str::trim).find_map(|line| {
+        let marker = DOC_COMMENT_MARKERS
+            .iter()
+            .find(|marker| line.starts_with(**marker))?;
+        let text = line[marker.len()..]
+            .trim_end_matches("*/")
+            .trim_end_matches(marker)
+            .trim();
+        (!text.is_empty()).then_some(text)
+    });
+    let line =
+        doc_comment.or_else(|| content.lines().map(str::trim).find(|line| !line.is_empty()))?;
+    Some(if line.chars().count() > MAX_DESCRIPTION_CHARS {
+        let shortened: String = line.chars().take(MAX_DESCRIPTION_CHARS - 1).collect();
+        format!("{}…", shortened.trim_end())
+    } else {
+        line.to_string()
+    })
+}
+
+/// What `--footer` records at the end of the output, so that whoever the
+/// output is shared with can run the same command over the same tree.
+#[derive(Debug, Clone)]
+pub struct Footer {
+    /// The command line, quoted for a shell. Inputs appear as typed, so
+    /// `$VAR` references are never expanded into it.
+    pub command: String,
+    pub working_dir: PathBuf,
+    /// The `.ctx-pick.toml` files that applied, relative to `working_dir`.
+    pub dir_configs: Vec<PathBuf>,
+    pub timestamp: SystemTime,
+}
+
+impl Footer {
+    /// Renders the footer to follow output in `format`: a block set off by a
+    /// rule for Markdown and chat, and a comment after `</documents>` for
+    /// cxml. It starts on a fresh paragraph given output ending in one.
+    pub fn render(&self, format: OutputFormat) -> String {
+        let mut lines = vec![
+            format!(
+                "Generated by ctx-pick {} at {}",
+                env!("CARGO_PKG_VERSION"),
+                utc_timestamp(self.timestamp)
+            ),
+            format!("Command: {}", self.command),
+            format!("Working directory: {}", self.working_dir.display()),
+        ];
+        if !self.dir_configs.is_empty() {
+            let paths: Vec<String> = self
+                .dir_configs
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            lines.push(format!("Directory configs: {}", paths.join(", ")));
+        }
+        match format {
+            OutputFormat::Cxml => format!("<!--\n{}\n-->\n", lines.join("\n")),
+            _ => format!("---\n{}\n", lines.join("\n")),
+        }
+    }
+}
+
+/// Formats `time` as an RFC 3339 UTC timestamp to the second, e.g.
+/// `2024-05-01T09:30:00Z`.
+fn utc_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, second_of_day) = (seconds / 86_400, seconds % 86_400);
+    // Converts days since 1970-01-01 to a civil date; see Howard Hinnant's
+    // `civil_from_days`.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        second_of_day / 3_600,
+        second_of_day / 60 % 60,
+        second_of_day % 60
+    )
+}
+
+/// Language keys where indentation carries meaning, so `--compact` warns.
+pub const WHITESPACE_SENSITIVE_LANGUAGES: &[&str] = &["py", "yaml", "yml"];
+
+/// Drops blank lines and removes the indentation every remaining line shares.
+pub fn compact(content: &str) -> String {
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let common_indent = common_indent(lines.iter().copied()).len();
+    let mut compacted = String::with_capacity(content.len());
+    for line in lines {
+        compacted.push_str(&line[common_indent..]);
+        compacted.push('\n');
+    }
+    compacted
+}
+
+/// Removes the indentation every non-blank line of `content` shares, for a
+/// slice of a file read in full. Returns the dedented content and how much
+/// was removed (e.g. "12 spaces"), or `None` if there's nothing to remove or
+/// the shared indentation mixes tabs and spaces, which no count describes.
+pub fn dedent(content: &str) -> Option<(String, String)> {
+    let indent = common_indent(content.lines().filter(|line| !line.trim().is_empty()));
+    let unit = match indent.bytes().next()? {
+        b' ' => "space",
+        b'\t' => "tab",
+        _ => return None,
+    };
+    if indent.bytes().any(|byte| byte != indent.as_bytes()[0]) {
+        return None;
+    }
+    let dedented = content
+        .split_inclusive('\n')
+        .map(|line| {
+            line.strip_prefix(indent)
+                .unwrap_or_else(|| line.trim_start_matches([' ', '\t']))
+        })
+        .collect();
+    let plural = if indent.len() == 1 { "" } else { "s" };
+    Some((dedented, format!("{} {}{}", indent.len(), unit, plural)))
+}
+
+/// The leading whitespace that all of `lines` begin with.
+fn common_indent<'a>(lines: impl Iterator<Item = &'a str>) -> &'a str {
+    lines
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .reduce(|common, indent| {
+            let shared = common
+                .bytes()
+                .zip(indent.bytes())
+                .take_while(|(a, b)| a == b)
+                .count();
+            &common[..shared]
+        })
+        .unwrap_or("")
+}
+
+/// Prefixes each continuation line produced by `hard_wrap`.
+pub const WRAP_CONTINUATION_MARKER: &str = "↪ ";
+
+/// Splits every line longer than `width` characters into `width`-character
+/// pieces, starting each continuation with `WRAP_CONTINUATION_MARKER`.
+pub fn hard_wrap(content: &str, width: usize) -> String {
+    let mut wrapped = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let (text, ending) = match line.strip_suffix('\n') {
+            Some(text) => (text, "\n"),
+            None => (line, ""),
+        };
+        let chars: Vec<char> = text.chars().collect();
+        for (i, piece) in chars.chunks(width.max(1)).enumerate() {
+            if i > 0 {
+                wrapped.push('\n');
+                wrapped.push_str(WRAP_CONTINUATION_MARKER);
+            }
+            wrapped.extend(piece);
+        }
+        wrapped.push_str(ending);
+    }
+    wrapped
+}
+
+/// Ends each line cut short by `truncate_long_lines`.
+pub const TRUNCATION_MARKER: &str = "⏎…";
+
+/// Cuts every line longer than `max` characters down to its first `max`,
+/// followed by `TRUNCATION_MARKER`. Returns the new content and how many
+/// lines were cut. No line breaks are added, so unlike wrapping this can't
+/// start a line with a fence that wasn't there before.
+pub fn truncate_long_lines(content: &str, max: usize) -> (String, usize) {
+    let mut truncated = String::with_capacity(content.len());
+    let mut cut = 0;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        match text.char_indices().nth(max) {
+            Some((end, _)) => {
+                truncated.push_str(&text[..end]);
+                truncated.push_str(TRUNCATION_MARKER);
+                truncated.push_str(&line[text.len()..]);
+                cut += 1;
+            }
+            None => truncated.push_str(line),
+        }
+    }
+    (truncated, cut)
+}
+
+/// Builds the header line shown above a file's code block.
+fn header_for(context: &FileContext, options: &RenderOptions) -> String {
+    if !options.include_file_size_in_header {
+        return context.display_path.clone();
+    }
+    // Mirror the summary's units: characters for skeletons, lines otherwise.
+    let (count, unit) = if context.depth.is_some() {
+        (context.content.chars().count(), "characters")
+    } else {
+        (context.content.lines().count(), "lines")
+    };
+    let unit = if count == 1 {
+        unit.trim_end_matches('s')
+    } else {
+        unit
+    };
+    format!("{} ({} {})", context.display_path, count, unit)
+}
+
+/// Writes the rendered output to `path` (`--output`), gzip-compressed when
+/// the path ends in `.gz`. Returns the number of bytes written.
+pub fn write_to_file(path: &Path, output: &str) -> io::Result<u64> {
+    let file = BufWriter::new(File::create(path)?);
+    let file = if is_gzip_path(path) {
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        encoder.write_all(output.as_bytes())?;
+        encoder.finish()?
+    } else {
+        let mut file = file;
+        file.write_all(output.as_bytes())?;
+        file
+    };
+    let file = file.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+    file.metadata().map(|metadata| metadata.len())
+}
+
+/// Returns true if `--output` compresses what it writes to `path`.
+pub fn is_gzip_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
@@ -0,0 +1,43 @@
+// src/picker.rs
+
+use crate::config::Config;
+use crate::file_resolver;
+use skim::prelude::*;
+
+/// Opens a fuzzy finder over the files under the working directory
+/// (respecting `.gitignore` and friends) and returns the ones the user
+/// selected, as paths relative to it.
+///
+/// Tab toggles a file, Enter accepts. Returns `Ok(None)` if the user aborted.
+pub fn pick_files(config: &Config) -> Result<Option<Vec<String>>, String> {
+    let candidates: Vec<String> = file_resolver::project_files(config)
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    if candidates.is_empty() {
+        return Ok(Some(Vec::new()));
+    }
+
+    let options = SkimOptionsBuilder::default()
+        .multi(true)
+        .prompt("ctx-pick> ")
+        .build()
+        .map_err(|e| e.to_string())?;
+    let output = Skim::run_items(options, candidates).map_err(|e| e.to_string())?;
+    if output.is_abort {
+        return Ok(None);
+    }
+
+    // With nothing marked, Enter picks the highlighted file, as in fzf.
+    let selected = if output.selected_items.is_empty() {
+        output.current.into_iter().collect()
+    } else {
+        output.selected_items
+    };
+    Ok(Some(
+        selected
+            .iter()
+            .map(|matched| matched.item.output().into_owned())
+            .collect(),
+    ))
+}
@@ -0,0 +1,602 @@
+// src/pipeline.rs
+
+//! One run from inputs to rendered context: resolving the inputs, choosing
+//! and filtering the files, reading them and rendering the output. `main`
+//! loads what the run needs and delivers its result; the test harness runs
+//! the very same steps.
+
+use crate::config::Config;
+use crate::dir_config::{self, DepthSource, DirConfigs, FileSettings};
+use crate::display::DisplayManager;
+use crate::error::AppError;
+use crate::file_reader;
+use crate::file_resolver;
+use crate::git::{ChangedLines, GitTree};
+use crate::input_file::InputEntry;
+use crate::interrupt;
+use crate::output::{Footer, OutputFormat};
+use crate::symbol_extractor::SkeletonOptions;
+use crate::types::{FileContext, InputResolution, LineRange, ResolutionReport, ResolvedFile};
+use crate::user_config;
+use crate::{Cli, ContextFailure, MissingPolicy, ReadErrorPolicy};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// The git revisions a run compares against, loaded by the caller.
+#[derive(Default)]
+pub struct Revisions<'r> {
+    /// The tree `--compare` diffs against.
+    pub compare_tree: Option<&'r GitTree>,
+    /// The changes `--changed-only` keeps.
+    pub changed_lines: Option<&'r ChangedLines>,
+}
+
+/// Why a run stopped short of a context. The reason has already been
+/// written, except for an `Error`.
+#[derive(Debug)]
+pub enum Halt {
+    /// Nothing is left to include, or an input needs fixing first.
+    Failed,
+    /// Ctrl-C was pressed; says when, e.g. "after resolving inputs".
+    Interrupted(&'static str),
+    Error(AppError),
+}
+
+impl From<AppError> for Halt {
+    fn from(error: AppError) -> Self {
+        Halt::Error(error)
+    }
+}
+
+/// What a run produced.
+pub enum Outcome {
+    /// The chosen files, for `--resolve-only`, `--copy-paths-only` and
+    /// `--stats`, which don't read them.
+    Selected(Vec<ResolvedFile>),
+    Rendered(Box<Rendered>),
+}
+
+/// The context built by a run, with what its summary reports.
+pub struct Rendered {
+    /// The files in the output, in order, each with its skeleton depth.
+    pub files: Vec<(ResolvedFile, Option<usize>)>,
+    /// One per file, except where `--dedup-skeletons` merged some.
+    pub contexts: Vec<FileContext>,
+    pub output: String,
+    /// The rendered `--footer`, kept for rendering the output again.
+    pub footer: Option<String>,
+    /// Files left out for lack of a skeleton, with why.
+    pub omitted: Vec<(String, String)>,
+    /// Files removed after they were resolved.
+    pub removed: Vec<String>,
+    /// Empty files, left out.
+    pub empty: Vec<String>,
+    /// Files over `--max-total-lines`, with their line counts.
+    pub over_line_budget: Vec<(String, usize)>,
+    /// Headers shared by several files, with the paths now shown for them.
+    pub header_collisions: Vec<(String, Vec<String>)>,
+    /// Files an editor may hold unsaved edits to, with the evidence.
+    pub unsaved_edits: Vec<(String, Vec<String>)>,
+}
+
+/// A run: the inputs' resolutions, and what became of them.
+pub struct Run<'a> {
+    /// Each input's resolution, in the order the inputs were given.
+    pub resolutions: Vec<InputResolution<'a>>,
+    /// The skeleton options the files were read with, including language
+    /// overrides from `.ctx-pick.toml` files.
+    pub skeleton_options: SkeletonOptions,
+    pub outcome: Result<Outcome, Halt>,
+}
+
+/// The depth an `--input-file` line gave each of its files, by canonical
+/// path, with the line.
+type EntryDepths = HashMap<PathBuf, (Option<usize>, String)>;
+
+/// Builds the context `cli` asks for. Warnings, notes and the reasons for
+/// a `Halt` go to `stderr`, through `display`.
+pub fn run<'a>(
+    cli: &'a Cli,
+    input_entries: &'a [InputEntry],
+    config: &Config,
+    revisions: &Revisions<'_>,
+    mut skeleton_options: SkeletonOptions,
+    display: &DisplayManager,
+    stderr: &mut impl Write,
+) -> Run<'a> {
+    let mut dir_configs = DirConfigs::new(&config.working_dir);
+    let (mut resolutions, entry_depths) = resolve(cli, input_entries, config, &mut dir_configs);
+    // `--context-around-symbol` looks for the symbol in whatever the inputs
+    // resolved to, and takes just its definition from there.
+    if let Some(symbol) = &cli.context_around_symbol {
+        let report = ResolutionReport::from_resolutions(&resolutions);
+        if report.has_errors() {
+            display
+                .write_resolution_errors(stderr, &report)
+                .unwrap_or_else(|e| eprintln!("Critical display error: {}", e));
+            return Run {
+                resolutions,
+                skeleton_options,
+                outcome: Err(Halt::Failed),
+            };
+        }
+        let scope = (!resolutions.is_empty()).then_some(report.files);
+        resolutions = vec![file_resolver::resolve_symbol_context(
+            symbol,
+            cli.context_lines,
+            scope.as_deref(),
+            config,
+        )];
+    }
+    let report = ResolutionReport::from_resolutions(&resolutions);
+    let outcome = build(
+        cli,
+        config,
+        revisions,
+        &report,
+        &entry_depths,
+        &mut dir_configs,
+        &mut skeleton_options,
+        display,
+        stderr,
+    );
+    Run {
+        resolutions,
+        skeleton_options,
+        outcome,
+    }
+}
+
+impl Run<'_> {
+    /// Whether any input failed to resolve (and `--on-missing` let the run
+    /// carry on without it).
+    pub fn has_resolution_errors(&self) -> bool {
+        ResolutionReport::from_resolutions(&self.resolutions).has_errors()
+    }
+}
+
+/// Resolves every input: the command line's, each `--input-file` line's,
+/// `--regex` and `--overview`. Also returns the depth each `--input-file`
+/// line gave its files, with the line.
+fn resolve<'a>(
+    cli: &'a Cli,
+    input_entries: &'a [InputEntry],
+    config: &Config,
+    dir_configs: &mut DirConfigs,
+) -> (Vec<InputResolution<'a>>, EntryDepths) {
+    // An input whose files are all excluded by `.ctx-pick.toml` files is
+    // reported as such.
+    let mut all_resolutions: Vec<InputResolution<'a>> = Vec::new();
+    for input_str in &cli.inputs {
+        let resolution = file_resolver::resolve_input_string(input_str, config);
+        all_resolutions.push(dir_configs.check_input(input_str, resolution));
+    }
+    // Each `--input-file` line's options apply to the files its inputs
+    // resolve to; a file picked by several lines takes the first one's.
+    let mut entry_depths = EntryDepths::new();
+    for entry in input_entries {
+        for input_str in &entry.inputs {
+            let resolution = file_resolver::resolve_input_string(input_str, config);
+            let mut resolution = dir_configs.check_input(input_str, resolution);
+            if let InputResolution::Success(files) = &mut resolution {
+                let file_count = files.len();
+                files.retain(|file| !entry.excludes(file));
+                if files.is_empty() && file_count > 0 {
+                    resolution = InputResolution::Excluded {
+                        input_string: input_str,
+                        file_count,
+                        rule: format!("--exclude on {}", entry.location()),
+                    };
+                } else if let Some(depth) = entry.depth {
+                    for file in files.iter() {
+                        entry_depths
+                            .entry(file.canonical_path().to_path_buf())
+                            .or_insert_with(|| (depth, entry.location()));
+                    }
+                }
+            }
+            all_resolutions.push(resolution);
+        }
+    }
+    for pattern in &cli.regex {
+        let resolution = file_resolver::resolve_regex(pattern, config);
+        all_resolutions.push(dir_configs.check_input(pattern, resolution));
+    }
+    if cli.overview {
+        all_resolutions.push(file_resolver::resolve_overview(config));
+    }
+    (all_resolutions, entry_depths)
+}
+
+/// Everything after resolution: checks the resolutions, chooses and filters
+/// the files, then reads, transforms and renders them.
+#[allow(clippy::too_many_arguments)]
+fn build(
+    cli: &Cli,
+    config: &Config,
+    revisions: &Revisions<'_>,
+    report: &ResolutionReport<'_, '_>,
+    entry_depths: &EntryDepths,
+    dir_configs: &mut DirConfigs,
+    skeleton_options: &mut SkeletonOptions,
+    display: &DisplayManager,
+    stderr: &mut impl Write,
+) -> Result<Outcome, Halt> {
+    display
+        .write_skipped_walks(stderr, &file_resolver::take_skipped_walks())
+        .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+
+    // Ctrl-C stops a search in progress, which is reported with any other
+    // unresolved inputs; nothing further is done.
+    if interrupt::requested() {
+        if report.has_errors() {
+            display
+                .write_resolution_warnings(stderr, report)
+                .unwrap_or_else(|e| eprintln!("Critical display error: {}", e));
+        }
+        return Err(Halt::Interrupted("after resolving inputs"));
+    }
+
+    // If any unrecoverable errors occurred, report them and stop. A working
+    // directory that vanished mid-run explains them all at once.
+    if report.has_errors() {
+        config.ensure_working_dir_exists()?;
+        if cli.on_missing == MissingPolicy::Error {
+            display
+                .write_resolution_errors(stderr, report)
+                .unwrap_or_else(|e| eprintln!("Critical display error: {}", e));
+            return Err(Halt::Failed);
+        }
+        display
+            .write_resolution_warnings(stderr, report)
+            .unwrap_or_else(|e| eprintln!("Critical display error: {}", e));
+    }
+
+    // If no files were successfully resolved from the inputs, say so, and
+    // whether exclude rules (rather than a lack of matches) are the reason.
+    if report.files.is_empty() {
+        let message = if report.excluded.is_empty() {
+            "No files were found or resolved based on your input."
+        } else {
+            "No files left to include: exclude rules filtered out everything that matched (see above)."
+        };
+        return fail(display, stderr, message);
+    }
+
+    if let Some(limit) = cli.max_files
+        && report.files.len() > limit
+    {
+        display
+            .write_too_many_files_total(stderr, report.files.len(), limit)
+            .unwrap_or_else(|e| eprintln!("Critical display error: {}", e));
+        return Err(Halt::Failed);
+    }
+
+    // 1. Work out each file's depth and exclusion from `--depth` and any
+    //    `.ctx-pick.toml` files between it and the working directory.
+    let file_settings: Vec<FileSettings> = report
+        .files
+        .iter()
+        .map(|file| {
+            let mut settings = dir_configs.settings_for(file, cli.depth);
+            if let Some((depth, location)) = entry_depths.get(file.canonical_path()) {
+                settings.depth = *depth;
+                settings.depth_source = Some(DepthSource::InputFile(location.clone()));
+            }
+            settings
+        })
+        .collect();
+    if cli.verbose {
+        display
+            .write_file_settings(stderr, &report.files, &file_settings, &config.working_dir)
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+    }
+    let mut included_files: Vec<(ResolvedFile, Option<usize>)> = report
+        .files
+        .iter()
+        .zip(&file_settings)
+        .filter(|(_, settings)| settings.excluded_by.is_none())
+        .map(|(file, settings)| match (file.line_range(), cli.head) {
+            (None, Some(lines)) => (
+                file.clone()
+                    .with_line_range(LineRange::head(lines as usize)),
+                settings.depth,
+            ),
+            _ => (file.clone(), settings.depth),
+        })
+        .collect();
+    if included_files.is_empty() {
+        return fail(
+            display,
+            stderr,
+            &format!(
+                "Every resolved file is excluded by a {} file.",
+                dir_config::DIR_CONFIG_FILE_NAME
+            ),
+        );
+    }
+    // Overrides from `.ctx-pick.toml` files go first, so `--lang-override` wins.
+    let dir_overrides = dir_configs.language_overrides(included_files.iter().map(|(file, _)| file));
+    skeleton_options
+        .language_overrides
+        .splice(0..0, dir_overrides);
+
+    if !cli.exclude_from.is_empty() || !cli.exclude.is_empty() {
+        let mut listed: HashSet<PathBuf> = cli
+            .exclude
+            .iter()
+            .filter_map(|path| config.working_dir.join(path).canonicalize().ok())
+            .collect();
+        for list in &cli.exclude_from {
+            listed.extend(
+                file_reader::read_path_list(list, &config.working_dir).map_err(|e| {
+                    AppError::IoError(format!("Could not read --exclude-from {:?}: {}", list, e))
+                })?,
+            );
+        }
+        included_files.retain(|(file, _)| !listed.contains(file.canonical_path()));
+        if included_files.is_empty() {
+            return fail(
+                display,
+                stderr,
+                "Every resolved file is excluded by --exclude or --exclude-from.",
+            );
+        }
+    }
+
+    // Binary files have no text worth including. Files at a revision are
+    // read from git, and unreadable ones are reported below.
+    let binary: Vec<(ResolvedFile, Option<usize>)>;
+    (binary, included_files) = included_files
+        .into_iter()
+        .partition(|(file, _)| crate::is_binary_file(file));
+    if !binary.is_empty() {
+        if cli.fail_on_binary {
+            for (file, _) in &binary {
+                writeln!(
+                    stderr,
+                    "{}",
+                    display.error_style.apply_to(format!(
+                        "Error: {:?} is a binary file (--fail-on-binary).",
+                        file.display_label()
+                    ))
+                )
+                .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+            }
+            return Err(Halt::Failed);
+        }
+        for (file, _) in &binary {
+            writeln!(
+                stderr,
+                "Warning: Skipping binary file {:?}.",
+                file.display_label()
+            )
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        }
+        if included_files.is_empty() {
+            return fail(display, stderr, "Every resolved file is binary.");
+        }
+    }
+
+    // `--changed-only` works on whole files in full, and drops unchanged ones.
+    if let Some(changes) = revisions.changed_lines {
+        included_files = included_files
+            .into_iter()
+            .filter(|(file, _)| changes.ranges(file.display_path()).is_some())
+            .map(|(file, _)| (file.without_line_range(), None))
+            .collect();
+        if included_files.is_empty() {
+            return fail(
+                display,
+                stderr,
+                &format!(
+                    "None of the selected files changed since {}.",
+                    changes.revision
+                ),
+            );
+        }
+    }
+
+    // `--grep` drops files without a match. Unreadable ones are kept, so
+    // they're reported like any other read error below.
+    if let Some(pattern) = &cli.grep {
+        included_files.retain(|(file, _)| crate::content_matches(file, pattern));
+        if included_files.is_empty() {
+            return fail(
+                display,
+                stderr,
+                &format!(
+                    "None of the selected files have content matching /{}/.",
+                    pattern
+                ),
+            );
+        }
+    }
+
+    if cli.resolve_only || cli.copy_paths_only || cli.stats {
+        return Ok(Outcome::Selected(
+            included_files.into_iter().map(|(file, _)| file).collect(),
+        ));
+    }
+
+    // 2. Process the included files into our FileContext struct. Files that
+    //    can't be read are handled per `--on-read-error`; files without a
+    //    skeleton are left out under `--no-skeleton-fallback`.
+    let results = crate::read_file_contexts(&included_files, skeleton_options);
+    let mut file_contexts = Vec::new();
+    let mut unreadable = Vec::new();
+    let mut omitted = Vec::new();
+    let mut removed = Vec::new();
+    let mut empty = Vec::new();
+    let mut kept_files = Vec::new();
+    for ((file, depth), result) in included_files.into_iter().zip(results) {
+        match result {
+            Ok(context) => file_contexts.push(context),
+            Err(
+                ContextFailure::Unreadable(display_path, e)
+                | ContextFailure::Crashed(display_path, e),
+            ) if cli.on_read_error == ReadErrorPolicy::Embed => {
+                file_contexts.push(crate::read_error_context(
+                    &file,
+                    depth,
+                    display_path,
+                    e,
+                    skeleton_options,
+                ));
+            }
+            Err(
+                ContextFailure::Unreadable(display_path, e)
+                | ContextFailure::Crashed(display_path, e),
+            ) => {
+                unreadable.push((display_path, e));
+                continue;
+            }
+            Err(ContextFailure::NoSkeleton(display_path, e)) => {
+                omitted.push((display_path, e));
+                continue;
+            }
+            // Whatever `--on-read-error` says, a file that's gone has nothing to show.
+            Err(ContextFailure::Removed(display_path)) => {
+                removed.push(display_path);
+                continue;
+            }
+            Err(ContextFailure::Empty(display_path)) => {
+                empty.push(display_path);
+                continue;
+            }
+        }
+        kept_files.push((file, depth));
+    }
+    if !removed.is_empty() {
+        config.ensure_working_dir_exists()?;
+    }
+    let mut included_files = kept_files;
+    let mut over_line_budget = Vec::new();
+    if let Some(limit) = cli.max_total_lines {
+        let fit = crate::within_line_budget(&file_contexts, limit);
+        over_line_budget = file_contexts
+            .split_off(fit)
+            .into_iter()
+            .map(|context| (context.display_path, context.line_count))
+            .collect();
+        included_files.truncate(fit);
+    }
+    let header_collisions = crate::disambiguate_headers(&included_files, &mut file_contexts);
+    let unsaved_edits = if cli.no_unsaved_warning || !user_config::get().warn_unsaved_edits {
+        Vec::new()
+    } else {
+        crate::unsaved_edits(&included_files)
+    };
+    if !unreadable.is_empty() {
+        config.ensure_working_dir_exists()?;
+        let skipped = cli.on_read_error == ReadErrorPolicy::Skip;
+        display
+            .write_read_errors(stderr, &unreadable, skipped)
+            .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        if !skipped {
+            return Err(Halt::Failed);
+        }
+    }
+    if file_contexts.is_empty() {
+        if !omitted.is_empty() {
+            display
+                .write_omitted_skeletons(stderr, &omitted)
+                .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+        }
+        let message = if !over_line_budget.is_empty() {
+            "No files left to include: the first file alone is over --max-total-lines."
+        } else if empty.is_empty() {
+            "No files left to include: none of them could be read or skeletonized."
+        } else {
+            "No files left to include: none of them could be read or skeletonized, or they're empty (see --include-empty-files)."
+        };
+        return fail(display, stderr, message);
+    }
+
+    // 3. Rework the contexts as asked, and render them.
+    if let Some(tree) = revisions.compare_tree {
+        file_contexts = crate::compare_contexts(
+            &included_files,
+            file_contexts,
+            tree,
+            cli.compare_format,
+            skeleton_options,
+        );
+    }
+    if let Some(changes) = revisions.changed_lines {
+        file_contexts =
+            crate::changed_contexts(&included_files, file_contexts, changes, cli.context_lines);
+    }
+    if let Some(pattern) = &cli.grep
+        && cli.only_matching_lines
+    {
+        file_contexts = crate::matching_line_contexts(
+            &included_files,
+            file_contexts,
+            pattern,
+            cli.context_lines,
+        );
+    }
+    if cli.dedupe_imports {
+        file_contexts = crate::imports::dedupe(file_contexts);
+    }
+    if cli.dedup_skeletons {
+        file_contexts = crate::dedup_skeletons(file_contexts, cli.dedup_ignore_whitespace);
+    }
+    if !cli.keep_crlf {
+        file_contexts = crate::normalize_newlines(file_contexts);
+    }
+    if cli.compact {
+        file_contexts = crate::compact_contexts(file_contexts);
+    }
+    if let Some(max) = cli
+        .max_line_length
+        .or(user_config::get().max_line_length)
+        .filter(|&max| max > 0)
+    {
+        file_contexts = crate::truncate_contexts(file_contexts, max);
+    }
+    if let Some(width) = cli.wrap {
+        file_contexts = crate::wrap_contexts(file_contexts, width as usize);
+    }
+
+    // Built once, so the output re-rendered after editing the selection
+    // keeps the same footer.
+    let footer = (cli.footer && cli.format != OutputFormat::SymbolsJson).then(|| {
+        Footer {
+            command: crate::command_line(),
+            working_dir: config.working_dir.clone(),
+            dir_configs: dir_configs.applied(),
+            timestamp: SystemTime::now(),
+        }
+        .render(cli.format)
+    });
+    let output = crate::render_output(
+        cli,
+        &file_contexts,
+        &included_files,
+        footer.as_deref(),
+        skeleton_options,
+    );
+    Ok(Outcome::Rendered(Box::new(Rendered {
+        files: included_files,
+        contexts: file_contexts,
+        output,
+        footer,
+        omitted,
+        removed,
+        empty,
+        over_line_budget,
+        header_collisions,
+        unsaved_edits,
+    })))
+}
+
+/// Writes `message` as a warning and halts the run.
+fn fail<T>(display: &DisplayManager, stderr: &mut impl Write, message: &str) -> Result<T, Halt> {
+    writeln!(stderr, "{}", display.warning_style.apply_to(message))
+        .unwrap_or_else(|e| eprintln!("Display error: {}", e));
+    Err(Halt::Failed)
+}
@@ -0,0 +1,87 @@
+// src/prompts.rs
+
+//! Built-in prompt templates (`--prompt-template`): instruction blocks for
+//! common LLM tasks that wrap the file context.
+
+/// A named instruction block placed ahead of the context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptTemplate {
+    pub name: &'static str,
+    /// A one-line summary for `--list-templates`.
+    pub description: &'static str,
+    pub instructions: &'static str,
+}
+
+/// The built-in templates, in the order `--list-templates` shows them.
+pub const TEMPLATES: &[PromptTemplate] = &[
+    PromptTemplate {
+        name: "review",
+        description: "Ask for a code review: bugs, edge cases, security, maintainability.",
+        instructions: "Review the code below as an experienced engineer on this project would. \
+Look for bugs, edge cases that aren't handled, security problems, and anything that will be \
+hard to maintain. For each issue, name the file and the code involved, explain why it's a \
+problem, and suggest a fix. Skip style nitpicks unless they hide a real problem, and say so if \
+you find nothing significant.",
+    },
+    PromptTemplate {
+        name: "explain",
+        description: "Ask for an explanation of how the code works.",
+        instructions: "Explain the code below to a developer who is new to this project. Start \
+with what it does as a whole and how the files fit together, then walk through the important \
+parts: the main data structures, the flow of control, and anything surprising or non-obvious. \
+Refer to code by file path and name.",
+    },
+    PromptTemplate {
+        name: "test-gen",
+        description: "Ask for tests covering the code.",
+        instructions: "Write tests for the code below. Cover its main behavior, edge cases and \
+error paths, using the test framework and conventions the project already follows. Put each \
+test where this project would keep it, and note any behavior that can't be tested from the \
+code shown.",
+    },
+];
+
+/// Looks up a template by name, for clap; the error lists the names.
+pub fn parse_template(name: &str) -> Result<&'static PromptTemplate, String> {
+    TEMPLATES
+        .iter()
+        .find(|template| template.name == name)
+        .ok_or_else(|| {
+            format!(
+                "no built-in template named '{}' (available: {})",
+                name,
+                TEMPLATES
+                    .iter()
+                    .map(|template| template.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+impl PromptTemplate {
+    /// Wraps the rendered context in the template's instructions.
+    pub fn wrap(&self, context: &str) -> String {
+        format!("{}\n\n{}", self.instructions, context)
+    }
+}
+
+/// The `--list-templates` listing: each name with its description.
+pub fn list() -> String {
+    let width = TEMPLATES
+        .iter()
+        .map(|template| template.name.len())
+        .max()
+        .unwrap_or(0);
+    TEMPLATES
+        .iter()
+        .map(|template| {
+            format!(
+                "{:width$}  {}\n",
+                template.name,
+                template.description,
+                width = width
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,849 @@
+// src/schemas.rs
+
+//! Hand-rolled skeletons for interface definitions: Protocol Buffers,
+//! GraphQL, and OpenAPI specs in YAML or JSON. Like the tree-sitter skeletons,
+//! each level of `--depth` shows one more level of nesting.
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use std::fmt;
+
+/// Returns true if a language key names an interface definition language
+/// (every file of which has a skeleton; YAML and JSON only sometimes do).
+pub fn is_schema_language(file_extension: &str) -> bool {
+    matches!(file_extension, "proto" | "graphql" | "gql")
+}
+
+/// Builds the skeleton of an interface definition, or returns `None` if the
+/// language key isn't one handled here.
+pub fn skeleton(
+    source_code: &str,
+    file_extension: &str,
+    max_depth: usize,
+) -> Option<Result<String, String>> {
+    let lines = match file_extension {
+        "proto" => proto_outline(&tokenize(source_code, "//"), max_depth),
+        "graphql" | "gql" => graphql_outline(&tokenize(source_code, "#"), max_depth),
+        "yaml" | "yml" | "json" => {
+            let document = if file_extension == "json" {
+                match serde_json::from_str(source_code) {
+                    Ok(document) => document,
+                    Err(e) => return Some(Err(format!("Invalid JSON: {}", e))),
+                }
+            } else {
+                parse_yaml(source_code)
+            };
+            match openapi_outline(&document, max_depth) {
+                Some(lines) => lines,
+                None => {
+                    return Some(Err(
+                        "Not an OpenAPI document; YAML and JSON files only have a skeleton when they're OpenAPI specs"
+                            .to_string(),
+                    ));
+                }
+            }
+        }
+        _ => return None,
+    };
+    Some(Ok(if lines.is_empty() {
+        "(No structure found)".to_string()
+    } else {
+        lines.join("\n")
+    }))
+}
+
+// --- Tokens shared by Protocol Buffers and GraphQL ---
+
+/// Splits source into words (identifiers, numbers, dotted names, `$vars`),
+/// string literals (quotes kept) and single punctuation characters, dropping
+/// comments. `...` is one token.
+fn tokenize(source: &str, line_comment: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() || c == ',' && line_comment == "#" {
+            // Commas are insignificant in GraphQL.
+            rest = &rest[c.len_utf8()..];
+        } else if rest.starts_with(line_comment) {
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+        } else if line_comment == "//" && rest.starts_with("/*") {
+            rest = rest.find("*/").map_or("", |end| &rest[end + 2..]);
+        } else if let Some(block) = rest.strip_prefix("\"\"\"") {
+            let end = block.find("\"\"\"").map_or(block.len(), |end| end + 3);
+            tokens.push(format!("\"\"\"{}", &block[..end.min(block.len())]));
+            rest = &block[end.min(block.len())..];
+        } else if c == '"' || c == '\'' {
+            let mut end = rest.len();
+            let mut escaped = false;
+            for (index, next) in rest.char_indices().skip(1) {
+                match next {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    _ if next == c => {
+                        end = index + 1;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            tokens.push(rest[..end].to_string());
+            rest = &rest[end..];
+        } else if rest.starts_with("...") {
+            tokens.push("...".to_string());
+            rest = &rest[3..];
+        } else if is_word_char(c) {
+            let end = rest
+                .find(|next: char| !is_word_char(next))
+                .unwrap_or(rest.len());
+            tokens.push(rest[..end].to_string());
+            rest = &rest[end..];
+        } else {
+            tokens.push(c.to_string());
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    tokens
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '$')
+}
+
+fn is_word(token: &str) -> bool {
+    token.starts_with(is_word_char)
+}
+
+fn is_string(token: &str) -> bool {
+    token.starts_with(['"', '\''])
+}
+
+/// Joins tokens back into source-like text: `name(arg: Type): [Item!]!`,
+/// `map<string, Item>`, `@deprecated(reason: "old")`.
+fn join_tokens(tokens: &[String]) -> String {
+    let mut text = String::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let glued = match (
+            index.checked_sub(1).map(|i| tokens[i].as_str()),
+            token.as_str(),
+        ) {
+            (None, _) | (Some("..."), "on") => index == 0,
+            (Some("(" | "[" | "<" | "@" | "..."), _) => true,
+            (_, ")" | "]" | ">" | ";" | ":" | "!") => true,
+            (Some(","), _) => false,
+            (_, ",") => true,
+            (Some(previous), "(" | "<") => is_word(previous) && previous != "returns",
+            _ => false,
+        };
+        if !glued {
+            text.push(' ');
+        }
+        text.push_str(token);
+    }
+    text
+}
+
+/// Returns the index just past the `}` closing the block whose contents start
+/// at `start`.
+fn skip_block(tokens: &[String], start: usize) -> usize {
+    let mut depth = 1;
+    let mut index = start;
+    while index < tokens.len() && depth > 0 {
+        match tokens[index].as_str() {
+            "{" => depth += 1,
+            "}" => depth -= 1,
+            _ => {}
+        }
+        index += 1;
+    }
+    index
+}
+
+/// Pushes `head { ... }` for a block at `level`, opening it with `contents`
+/// (which writes the inner lines) if it's shallower than `max_depth`.
+fn push_block(
+    lines: &mut Vec<String>,
+    head: String,
+    level: usize,
+    max_depth: usize,
+    contents: impl FnOnce(&mut Vec<String>),
+) {
+    let indent = "  ".repeat(level - 1);
+    if level >= max_depth {
+        lines.push(format!("{}{} {{ ... }}", indent, head));
+        return;
+    }
+    let opening = lines.len();
+    lines.push(format!("{}{} {{", indent, head));
+    contents(lines);
+    if lines.len() == opening + 1 {
+        lines[opening].push('}');
+    } else {
+        lines.push(format!("{}}}", indent));
+    }
+}
+
+// --- Protocol Buffers ---
+
+/// Outlines a `.proto` file: top-level statements (`syntax`, `package`,
+/// `import`) and definitions, with each depth level opening one more level of
+/// `message`/`service`/`enum`/`oneof` blocks. `option`s and `[...]` field
+/// options are left out, and `rpc`s are shown as one-line signatures.
+fn proto_outline(tokens: &[String], max_depth: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut position = 0;
+    proto_block(tokens, &mut position, 1, max_depth, &mut lines);
+    lines
+}
+
+fn proto_block(
+    tokens: &[String],
+    position: &mut usize,
+    level: usize,
+    max_depth: usize,
+    lines: &mut Vec<String>,
+) {
+    let indent = "  ".repeat(level - 1);
+    while *position < tokens.len() {
+        if tokens[*position] == "}" {
+            *position += 1;
+            return;
+        }
+        // Read one statement, up to its `;` or the `{` opening its block.
+        let start = *position;
+        let mut nesting = 0usize;
+        while let Some(token) = tokens.get(*position) {
+            match token.as_str() {
+                "[" | "(" | "<" => nesting += 1,
+                "]" | ")" | ">" => nesting = nesting.saturating_sub(1),
+                ";" | "{" | "}" if nesting == 0 => break,
+                _ => {}
+            }
+            *position += 1;
+        }
+        let statement = without_field_options(&tokens[start..*position]);
+        let is_option = statement.first().is_some_and(|token| token == "option");
+        match tokens.get(*position).map(String::as_str) {
+            Some("{") => {
+                *position += 1;
+                let is_rpc = statement.first().is_some_and(|token| token == "rpc");
+                if is_option || is_rpc || statement.iter().any(|token| token == "=") {
+                    // An option block, an aggregate option value, or an rpc's options.
+                    *position = skip_block(tokens, *position);
+                    if tokens.get(*position).is_some_and(|token| token == ";") {
+                        *position += 1;
+                    }
+                    if is_rpc {
+                        lines.push(format!("{}{};", indent, join_tokens(&statement)));
+                    }
+                } else {
+                    push_block(lines, join_tokens(&statement), level, max_depth, |lines| {
+                        proto_block(tokens, position, level + 1, max_depth, lines)
+                    });
+                    if level >= max_depth {
+                        *position = skip_block(tokens, *position);
+                    }
+                }
+            }
+            Some(";") => {
+                *position += 1;
+                if !is_option && !statement.is_empty() {
+                    lines.push(format!("{}{};", indent, join_tokens(&statement)));
+                }
+            }
+            _ if statement.is_empty() => {}
+            _ => lines.push(format!("{}{}", indent, join_tokens(&statement))),
+        }
+    }
+}
+
+/// Drops `[deprecated = true]`-style option lists from a field.
+fn without_field_options(statement: &[String]) -> Vec<String> {
+    let mut kept = Vec::new();
+    let mut in_options = 0usize;
+    for token in statement {
+        match token.as_str() {
+            "[" => in_options += 1,
+            "]" => in_options = in_options.saturating_sub(1),
+            _ if in_options == 0 => kept.push(token.clone()),
+            _ => {}
+        }
+    }
+    kept
+}
+
+// --- GraphQL ---
+
+/// The keywords that start a top-level GraphQL definition.
+const GRAPHQL_DEFINITIONS: &[&str] = &[
+    "type",
+    "interface",
+    "input",
+    "enum",
+    "union",
+    "scalar",
+    "schema",
+    "directive",
+    "extend",
+    "query",
+    "mutation",
+    "subscription",
+    "fragment",
+];
+
+/// What the items of a GraphQL `{ ... }` block are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GraphqlBody {
+    /// Fields of a type, interface or input: `name(args): Type`.
+    Fields,
+    /// Values of an enum.
+    EnumValues,
+    /// A selection set of an operation or fragment.
+    Selections,
+}
+
+/// A GraphQL definition, field, enum value or selection.
+struct GraphqlItem {
+    description: Option<String>,
+    head: Vec<String>,
+    body: Option<Vec<GraphqlItem>>,
+}
+
+/// Outlines a GraphQL document: definitions at depth 1, their fields, enum
+/// values and selections a level deeper each. Descriptions are trimmed to
+/// their first line, as a `#` comment above what they describe.
+fn graphql_outline(tokens: &[String], max_depth: usize) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut description = None;
+    let mut head: Vec<String> = Vec::new();
+    let mut nesting = 0usize;
+    let mut index = 0;
+    let finish = |head: &mut Vec<String>, description: &mut Option<String>, items: &mut Vec<_>| {
+        if !head.is_empty() {
+            items.push(GraphqlItem {
+                description: description.take(),
+                head: std::mem::take(head),
+                body: None,
+            });
+        }
+    };
+    while index < tokens.len() {
+        let token = tokens[index].as_str();
+        index += 1;
+        match token {
+            "(" => nesting += 1,
+            ")" => nesting = nesting.saturating_sub(1),
+            _ => {}
+        }
+        if nesting > 0 {
+            head.push(token.to_string());
+            continue;
+        }
+        if is_string(token) {
+            finish(&mut head, &mut description, &mut items);
+            description = Some(token.to_string());
+        } else if token == "{" {
+            let end = skip_block(tokens, index);
+            let kind = graphql_body_kind(&head);
+            items.push(GraphqlItem {
+                description: description.take(),
+                head: std::mem::take(&mut head),
+                body: Some(graphql_items(&tokens[index..end - 1], kind)),
+            });
+            index = end;
+        } else {
+            let continues_extend = head.last().is_some_and(|last| last == "extend");
+            if GRAPHQL_DEFINITIONS.contains(&token) && !continues_extend {
+                finish(&mut head, &mut description, &mut items);
+            }
+            head.push(token.to_string());
+        }
+    }
+    finish(&mut head, &mut description, &mut items);
+
+    let mut lines = Vec::new();
+    render_graphql_items(&items, 1, max_depth, &mut lines);
+    lines
+}
+
+fn graphql_body_kind(head: &[String]) -> GraphqlBody {
+    let keyword = head
+        .iter()
+        .find(|token| *token != "extend")
+        .map_or("", String::as_str);
+    match keyword {
+        "enum" => GraphqlBody::EnumValues,
+        "type" | "interface" | "input" | "schema" => GraphqlBody::Fields,
+        _ => GraphqlBody::Selections,
+    }
+}
+
+/// Splits the inside of a block into its items.
+fn graphql_items(tokens: &[String], kind: GraphqlBody) -> Vec<GraphqlItem> {
+    let mut items: Vec<GraphqlItem> = Vec::new();
+    let mut description = None;
+    let mut nesting = 0usize;
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = tokens[index].as_str();
+        let previous = index.checked_sub(1).map(|i| tokens[i].as_str());
+        let next = tokens.get(index + 1).map(String::as_str);
+        index += 1;
+        if nesting == 0 {
+            if is_string(token) {
+                description = Some(token.to_string());
+                continue;
+            }
+            let starts_item = match kind {
+                GraphqlBody::Fields => {
+                    is_word(token) && previous != Some("@") && matches!(next, Some(":" | "("))
+                }
+                GraphqlBody::EnumValues => is_word(token) && previous != Some("@"),
+                GraphqlBody::Selections => {
+                    token == "..."
+                        || is_word(token)
+                            && !matches!(previous, Some(":" | "@" | "..." | "on" | "$"))
+                }
+            };
+            if starts_item || items.is_empty() {
+                items.push(GraphqlItem {
+                    description: description.take(),
+                    head: Vec::new(),
+                    body: None,
+                });
+            }
+            if token == "{" && kind == GraphqlBody::Selections {
+                let end = skip_block(tokens, index);
+                if let Some(item) = items.last_mut() {
+                    item.body = Some(graphql_items(&tokens[index..end - 1], kind));
+                }
+                index = end;
+                continue;
+            }
+        }
+        match token {
+            "(" | "{" | "[" => nesting += 1,
+            ")" | "}" | "]" => nesting = nesting.saturating_sub(1),
+            _ => {}
+        }
+        if let Some(item) = items.last_mut() {
+            item.head.push(token.to_string());
+        }
+    }
+    items
+}
+
+fn render_graphql_items(
+    items: &[GraphqlItem],
+    level: usize,
+    max_depth: usize,
+    lines: &mut Vec<String>,
+) {
+    let indent = "  ".repeat(level - 1);
+    for item in items {
+        if let Some(description) = &item.description {
+            lines.push(format!("{}# {}", indent, trim_description(description)));
+        }
+        let head = join_tokens(&graphql_signature(&item.head));
+        match &item.body {
+            Some(body) => push_block(lines, head, level, max_depth, |lines| {
+                render_graphql_items(body, level + 1, max_depth, lines)
+            }),
+            None => lines.push(format!("{}{}", indent, head)),
+        }
+    }
+}
+
+/// Tidies a field or definition head for display: drops the descriptions
+/// of arguments (strings followed by `name:`), which would otherwise sit in
+/// the middle of the signature, and separates arguments with commas (which
+/// GraphQL doesn't require, so the tokens don't keep them).
+fn graphql_signature(head: &[String]) -> Vec<String> {
+    let mut signature: Vec<String> = Vec::new();
+    let mut nesting = 0usize;
+    for (index, token) in head.iter().enumerate() {
+        let starts_argument =
+            head.get(index + 1).is_some_and(|next| next == ":") && is_word(token) && nesting > 0;
+        match token.as_str() {
+            "(" => nesting += 1,
+            ")" => nesting = nesting.saturating_sub(1),
+            _ if is_string(token)
+                && head.get(index + 1).is_some_and(|next| is_word(next))
+                && head.get(index + 2).is_some_and(|next| next == ":") =>
+            {
+                continue;
+            }
+            _ => {}
+        }
+        if starts_argument && signature.last().is_some_and(|last| last != "(") {
+            signature.push(",".to_string());
+        }
+        signature.push(token.clone());
+    }
+    signature
+}
+
+/// The first line of a description, without its quotes, marked with `...` if
+/// anything was cut.
+fn trim_description(description: &str) -> String {
+    let text = description
+        .trim_start_matches("\"\"\"")
+        .trim_end_matches("\"\"\"")
+        .trim_matches('"')
+        .trim();
+    let mut text_lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let first = text_lines.next().unwrap_or("");
+    if text_lines.next().is_some() {
+        format!("{} ...", first)
+    } else {
+        first.to_string()
+    }
+}
+
+// --- OpenAPI ---
+
+/// A YAML or JSON value, keeping mapping keys in source order.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Null,
+    Scalar(String),
+    Sequence(Vec<Node>),
+    Mapping(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn get(&self, key: &str) -> Option<&Node> {
+        match self {
+            Node::Mapping(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Node::Scalar(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn entries(&self) -> &[(String, Node)] {
+        match self {
+            Node::Mapping(entries) => entries,
+            _ => &[],
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeVisitor;
+
+        impl<'de> Visitor<'de> for NodeVisitor {
+            type Value = Node;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a JSON value")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Node, E> {
+                Ok(Node::Null)
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(Node::Sequence(items))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Node::Mapping(entries))
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+/// Reads the block-style YAML that OpenAPI specs are written in: nested
+/// mappings and `- ` sequences, plain and quoted scalars, and `|`/`>` block
+/// scalars. Flow collections (`[a, b]`) are kept as plain text, and anchors
+/// and tags aren't resolved; the outline doesn't need them.
+fn parse_yaml(source: &str) -> Node {
+    let mut lines: Vec<(usize, String)> = source
+        .lines()
+        .filter_map(|line| {
+            let content = strip_yaml_comment(line.trim_start()).trim_end();
+            let is_marker = matches!(content, "---" | "...");
+            (!content.is_empty() && !is_marker)
+                .then(|| (line.len() - line.trim_start().len(), content.to_string()))
+        })
+        .collect();
+    let mut position = 0;
+    yaml_node(&mut lines, &mut position, 0)
+}
+
+/// Cuts a ` # comment` off a line, outside quotes.
+fn strip_yaml_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if previous == ' ' || previous == ':' => quote = Some(c),
+            (None, '#') if previous.is_whitespace() => return &line[..index],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Splits `key: value` (the value possibly empty), with the key unquoted.
+fn split_yaml_key(text: &str) -> Option<(String, &str)> {
+    if let Some(quote) = text.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        let end = text[1..].find(quote)? + 1;
+        let rest = text[end + 1..].strip_prefix(':')?;
+        return Some((text[1..end].to_string(), rest.trim()));
+    }
+    if text.starts_with(['{', '[']) {
+        return None;
+    }
+    let end = text
+        .find(": ")
+        .or_else(|| text.ends_with(':').then(|| text.len() - 1))?;
+    Some((text[..end].to_string(), text[end + 1..].trim()))
+}
+
+fn unquote(text: &str) -> String {
+    let quoted = text.len() >= 2
+        && (text.starts_with('"') && text.ends_with('"')
+            || text.starts_with('\'') && text.ends_with('\''));
+    if quoted {
+        text[1..text.len() - 1].to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Reads the node starting at `position`, if it's indented at least `indent`.
+fn yaml_node(lines: &mut [(usize, String)], position: &mut usize, indent: usize) -> Node {
+    match lines.get(*position) {
+        Some((line_indent, _)) if *line_indent >= indent => {
+            let indent = *line_indent;
+            if is_sequence_item(&lines[*position].1) {
+                yaml_sequence(lines, position, indent)
+            } else {
+                yaml_mapping(lines, position, indent)
+            }
+        }
+        _ => Node::Null,
+    }
+}
+
+fn yaml_sequence(lines: &mut [(usize, String)], position: &mut usize, indent: usize) -> Node {
+    let mut items = Vec::new();
+    while let Some((line_indent, text)) = lines.get(*position) {
+        if *line_indent != indent || !is_sequence_item(text) {
+            break;
+        }
+        let rest = text[1..].trim_start();
+        if rest.is_empty() {
+            *position += 1;
+            items.push(yaml_node(lines, position, indent + 1));
+        } else if split_yaml_key(rest).is_some() {
+            // `- key: value` starts a mapping indented to where `key` is.
+            let column = indent + (text.len() - rest.len());
+            lines[*position] = (column, rest.to_string());
+            items.push(yaml_mapping(lines, position, column));
+        } else {
+            items.push(Node::Scalar(unquote(rest)));
+            *position += 1;
+        }
+    }
+    Node::Sequence(items)
+}
+
+fn yaml_mapping(lines: &mut [(usize, String)], position: &mut usize, indent: usize) -> Node {
+    let mut entries = Vec::new();
+    while let Some((line_indent, text)) = lines.get(*position) {
+        if *line_indent < indent || *line_indent == indent && is_sequence_item(text) {
+            break;
+        }
+        *position += 1;
+        let Some((key, value)) = split_yaml_key(text).filter(|_| *line_indent == indent) else {
+            continue;
+        };
+        let value = if value.starts_with(['|', '>']) {
+            let mut text_lines = Vec::new();
+            while let Some((next_indent, next)) = lines.get(*position) {
+                if *next_indent <= indent {
+                    break;
+                }
+                text_lines.push(next.clone());
+                *position += 1;
+            }
+            Node::Scalar(text_lines.join(" "))
+        } else if value.is_empty() || value.starts_with(['&', '!']) {
+            match lines.get(*position) {
+                Some((next_indent, _)) if *next_indent > indent => {
+                    yaml_node(lines, position, indent + 1)
+                }
+                Some((next_indent, next)) if *next_indent == indent && is_sequence_item(next) => {
+                    yaml_sequence(lines, position, indent)
+                }
+                _ => Node::Null,
+            }
+        } else {
+            Node::Scalar(unquote(value))
+        };
+        entries.push((key, value));
+    }
+    Node::Mapping(entries)
+}
+
+/// The operations a path item can have, in the order the spec lists them.
+const HTTP_METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Outlines an OpenAPI (or Swagger 2) spec, or returns `None` if `document`
+/// isn't one. Depth 1 lists paths and schema names; depth 2 adds each path's
+/// methods with their `operationId`s and each schema's property names; depth
+/// 3 adds operation summaries and property types.
+fn openapi_outline(document: &Node, max_depth: usize) -> Option<Vec<String>> {
+    let (version_key, version) = ["openapi", "swagger"]
+        .iter()
+        .find_map(|key| Some((*key, document.get(key)?.as_str()?)))?;
+    let mut lines = vec![format!("{}: {}", version_key, version)];
+    if let Some(info) = document.get("info") {
+        let title = [info.get("title"), info.get("version")]
+            .into_iter()
+            .flatten()
+            .filter_map(Node::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !title.is_empty() {
+            lines.push(format!("info: {}", title));
+        }
+    }
+
+    if let Some(paths) = document
+        .get("paths")
+        .filter(|paths| !paths.entries().is_empty())
+    {
+        lines.push("paths:".to_string());
+        for (path, item) in paths.entries() {
+            if max_depth < 2 {
+                lines.push(format!("  {}", path));
+                continue;
+            }
+            lines.push(format!("  {}:", path));
+            for (method, operation) in item.entries() {
+                if !HTTP_METHODS.contains(&method.as_str()) {
+                    continue;
+                }
+                let mut line = format!("    {}", method);
+                if let Some(id) = operation.get("operationId").and_then(Node::as_str) {
+                    line.push_str(&format!(" {}", id));
+                }
+                if max_depth >= 3
+                    && let Some(summary) = operation.get("summary").and_then(Node::as_str)
+                {
+                    line.push_str(&format!(" - {}", summary));
+                }
+                lines.push(line);
+            }
+        }
+    }
+
+    let schemas = match document.get("components").and_then(|c| c.get("schemas")) {
+        Some(schemas) => Some(("components.schemas", schemas)),
+        None => document
+            .get("definitions")
+            .map(|schemas| ("definitions", schemas)),
+    };
+    if let Some((label, schemas)) = schemas.filter(|(_, schemas)| !schemas.entries().is_empty()) {
+        lines.push(format!("{}:", label));
+        for (name, schema) in schemas.entries() {
+            let properties = schema
+                .get("properties")
+                .map_or(&[][..], |properties| properties.entries());
+            match max_depth {
+                1 => lines.push(format!("  {}", name)),
+                2 if !properties.is_empty() => lines.push(format!(
+                    "  {}: {}",
+                    name,
+                    properties
+                        .iter()
+                        .map(|(property, _)| property.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+                _ if !properties.is_empty() => {
+                    lines.push(format!("  {}:", name));
+                    for (property, property_schema) in properties {
+                        lines.push(format!(
+                            "    {}: {}",
+                            property,
+                            schema_type(property_schema)
+                        ));
+                    }
+                }
+                _ => lines.push(format!("  {}", name)),
+            }
+        }
+    }
+    Some(lines)
+}
+
+/// A short type for a property schema: `string`, `Pet` for a `$ref`, `[Pet]`
+/// for an array.
+fn schema_type(schema: &Node) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(Node::as_str) {
+        return reference
+            .rsplit('/')
+            .next()
+            .unwrap_or(reference)
+            .to_string();
+    }
+    match schema.get("type").and_then(Node::as_str) {
+        Some("array") => format!(
+            "[{}]",
+            schema.get("items").map_or("?".to_string(), schema_type)
+        ),
+        Some(kind) => kind.to_string(),
+        None => "object".to_string(),
+    }
+}
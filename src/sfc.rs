@@ -0,0 +1,171 @@
+// src/sfc.rs
+
+//! Vue and Svelte single-file components, which mix markup with `<script>`
+//! and `<style>` blocks. Their scripts are skeletonized with the TypeScript
+//! grammar, and their markup is reduced to the components it uses.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+static SCRIPT_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)(<script\b[^>]*>)(.*?)</script\s*>").unwrap());
+static STYLE_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)(<style\b[^>]*>).*?</style\s*>").unwrap());
+
+/// HTML elements that never have a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Returns true if a language key names a single-file component format.
+pub fn is_single_file_component(file_extension: &str) -> bool {
+    matches!(file_extension, "vue" | "svelte")
+}
+
+/// A `<script>` block of a component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptBlock<'a> {
+    /// The opening tag, e.g. `<script setup lang="ts">`.
+    pub open_tag: &'a str,
+    /// The code between the tags.
+    pub content: &'a str,
+    /// The line of the file `content` starts on, 1-based.
+    pub first_line: usize,
+}
+
+/// A component split into its blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component<'a> {
+    /// The `<script>` blocks, in source order.
+    pub scripts: Vec<ScriptBlock<'a>>,
+    /// The opening tag of each `<style>` block, in source order.
+    pub styles: Vec<&'a str>,
+    /// Everything else: a Vue `<template>` block, or Svelte's markup.
+    pub markup: String,
+}
+
+/// Splits a component's source into its script and style blocks and the
+/// markup around them.
+pub fn split(source: &str) -> Component<'_> {
+    let mut blocks: Vec<(usize, usize)> = Vec::new();
+    let mut scripts = Vec::new();
+    for captures in SCRIPT_BLOCK.captures_iter(source) {
+        let (whole, open_tag, content) = (
+            captures.get(0).unwrap(),
+            captures.get(1).unwrap(),
+            captures.get(2).unwrap(),
+        );
+        blocks.push((whole.start(), whole.end()));
+        scripts.push(ScriptBlock {
+            open_tag: open_tag.as_str(),
+            content: content.as_str(),
+            first_line: source[..content.start()].matches('\n').count() + 1,
+        });
+    }
+    let mut styles = Vec::new();
+    for captures in STYLE_BLOCK.captures_iter(source) {
+        let whole = captures.get(0).unwrap();
+        // A `<style>` inside a script's string literal belongs to the script.
+        if blocks
+            .iter()
+            .any(|&(start, end)| whole.start() >= start && whole.start() < end)
+        {
+            continue;
+        }
+        blocks.push((whole.start(), whole.end()));
+        styles.push(captures.get(1).unwrap().as_str());
+    }
+
+    blocks.sort_unstable();
+    let mut markup = String::new();
+    let mut position = 0;
+    for (start, end) in blocks {
+        if start >= position {
+            markup.push_str(&source[position..start]);
+            position = end;
+        }
+    }
+    markup.push_str(&source[position..]);
+
+    Component {
+        scripts,
+        styles,
+        markup,
+    }
+}
+
+/// Returns true if a tag names a component rather than an HTML element:
+/// `TodoList`, `router-view`, `svelte:window` or `Form.Field`.
+fn is_component(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase()) || name.contains(['-', ':', '.'])
+}
+
+/// Outlines the components used in `markup`, one per line, indented two
+/// spaces per level of nesting among components (HTML elements between them
+/// don't count). Components more than `max_depth` levels deep are left out.
+pub fn component_outline(markup: &str, max_depth: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    // The open elements, each with whether it's a component.
+    let mut open: Vec<(&str, bool)> = Vec::new();
+    let mut rest = markup;
+    while let Some(index) = rest.find('<') {
+        rest = &rest[index + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let closing = rest.starts_with('/');
+        let tag = rest.strip_prefix('/').unwrap_or(rest);
+        let name_len = tag
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | ':' | '.' | '_')))
+            .unwrap_or(tag.len());
+        let name = &tag[..name_len];
+        // `a < b` in an expression, or a doctype.
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        let (tag_len, self_closing) = tag_end(&tag[name_len..]);
+        rest = &tag[name_len + tag_len..];
+
+        if closing {
+            if let Some(position) = open.iter().rposition(|(open_name, _)| *open_name == name) {
+                open.truncate(position);
+            }
+            continue;
+        }
+        let component = is_component(name);
+        if component {
+            let level = open.iter().filter(|(_, component)| *component).count() + 1;
+            if level <= max_depth {
+                lines.push(format!("{}{}", "  ".repeat(level), name));
+            }
+        }
+        if !self_closing && !VOID_ELEMENTS.contains(&name.to_ascii_lowercase().as_str()) {
+            open.push((name, component));
+        }
+    }
+    lines
+}
+
+/// Finds the `>` ending a tag's attributes, skipping over quoted values and
+/// `{...}` expressions. Returns the length up to and including it, and whether
+/// the tag closes itself (`/>`).
+fn tag_end(attributes: &str) -> (usize, bool) {
+    let mut quote = None;
+    let mut braces = 0usize;
+    let mut previous = ' ';
+    for (index, c) in attributes.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => braces += 1,
+            (None, '}') => braces = braces.saturating_sub(1),
+            (None, '>') if braces == 0 => return (index + 1, previous == '/'),
+            _ => {}
+        }
+        previous = c;
+    }
+    (attributes.len(), false)
+}
@@ -0,0 +1,173 @@
+// src/stats.rs
+
+use crate::language;
+use crate::manifest;
+use crate::symbol_extractor::{self, SkeletonOptions};
+use crate::types::ResolvedFile;
+use std::collections::BTreeMap;
+
+/// How many of the largest files `--stats` lists.
+const LARGEST_FILE_COUNT: usize = 10;
+
+/// How many files per language are skeletonized to estimate a depth's savings.
+const SAMPLES_PER_LANGUAGE: usize = 3;
+
+/// The group for files with no recognized language.
+const OTHER_LANGUAGE: &str = "(other)";
+
+/// File count and size for one language (usually an extension).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LanguageStats {
+    pub language: String,
+    pub files: usize,
+    pub lines: usize,
+    pub estimated_tokens: usize,
+}
+
+/// One file's size, for the largest-files list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSize {
+    pub path: String,
+    pub lines: usize,
+    pub estimated_tokens: usize,
+}
+
+/// How much skeletonizing a sample of one language's files shrank them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthEstimate {
+    pub language: String,
+    pub sampled_files: usize,
+    /// Skeleton size as a fraction of full size, over the sampled files.
+    pub ratio: f64,
+    /// The language's total tokens, scaled by `ratio`.
+    pub estimated_tokens: usize,
+}
+
+/// The census printed by `--stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub total: LanguageStats,
+    /// Sorted by estimated tokens, largest first.
+    pub by_language: Vec<LanguageStats>,
+    pub largest_files: Vec<FileSize>,
+    /// The depth asked about and the estimate for each skeletonizable language.
+    pub depth_estimates: Option<(usize, Vec<DepthEstimate>)>,
+    /// Files that couldn't be read, and why.
+    pub unreadable: Vec<(String, String)>,
+}
+
+impl Stats {
+    /// Reads `files` and tallies them. With a `depth`, a few files of each
+    /// language with skeleton support are skeletonized to estimate savings.
+    pub fn collect(
+        files: &[ResolvedFile],
+        depth: Option<usize>,
+        skeleton_options: &SkeletonOptions,
+    ) -> Self {
+        let mut by_language: BTreeMap<String, LanguageStats> = BTreeMap::new();
+        let mut samples: BTreeMap<String, (usize, usize, usize)> = BTreeMap::new();
+        let mut sizes = Vec::new();
+        let mut unreadable = Vec::new();
+
+        for file in files {
+            let path = file.display_label();
+            let content = match crate::read_file_content(file) {
+                Ok(content) => content,
+                Err(e) => {
+                    unreadable.push((path, e));
+                    continue;
+                }
+            };
+            let key = language::language_for(
+                file.display_path(),
+                &content,
+                &skeleton_options.language_overrides,
+            );
+            let lines = content.lines().count();
+            let estimated_tokens = manifest::estimate_tokens(&content);
+
+            let entry = by_language
+                .entry(key.clone().unwrap_or_else(|| OTHER_LANGUAGE.to_string()))
+                .or_default();
+            entry.files += 1;
+            entry.lines += lines;
+            entry.estimated_tokens += estimated_tokens;
+            sizes.push(FileSize {
+                path,
+                lines,
+                estimated_tokens,
+            });
+
+            // (files sampled, full characters, skeleton characters)
+            if let (Some(depth), Some(key)) = (depth, key)
+                && symbol_extractor::is_supported_extension(&key)
+            {
+                let sample = samples.entry(key.clone()).or_default();
+                if sample.0 < SAMPLES_PER_LANGUAGE
+                    && let Ok(skeleton) = symbol_extractor::create_skeleton_by_depth(
+                        &content,
+                        &key,
+                        depth,
+                        skeleton_options,
+                    )
+                {
+                    sample.0 += 1;
+                    sample.1 += content.chars().count();
+                    sample.2 += skeleton.chars().count();
+                }
+            }
+        }
+
+        let mut total = LanguageStats {
+            language: "total".to_string(),
+            ..LanguageStats::default()
+        };
+        let mut by_language: Vec<LanguageStats> = by_language
+            .into_iter()
+            .map(|(language, mut stats)| {
+                total.files += stats.files;
+                total.lines += stats.lines;
+                total.estimated_tokens += stats.estimated_tokens;
+                stats.language = language;
+                stats
+            })
+            .collect();
+        by_language.sort_by_key(|stats| std::cmp::Reverse(stats.estimated_tokens));
+
+        sizes.sort_by(|a, b| {
+            b.estimated_tokens
+                .cmp(&a.estimated_tokens)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        sizes.truncate(LARGEST_FILE_COUNT);
+
+        let depth_estimates = depth.map(|depth| {
+            let estimates = samples
+                .into_iter()
+                .filter(|(_, (sampled, full_chars, _))| *sampled > 0 && *full_chars > 0)
+                .map(|(language, (sampled_files, full_chars, skeleton_chars))| {
+                    let ratio = skeleton_chars as f64 / full_chars as f64;
+                    let language_tokens = by_language
+                        .iter()
+                        .find(|stats| stats.language == language)
+                        .map_or(0, |stats| stats.estimated_tokens);
+                    DepthEstimate {
+                        language,
+                        sampled_files,
+                        ratio,
+                        estimated_tokens: (language_tokens as f64 * ratio).round() as usize,
+                    }
+                })
+                .collect();
+            (depth, estimates)
+        });
+
+        Self {
+            total,
+            by_language,
+            largest_files: sizes,
+            depth_estimates,
+            unreadable,
+        }
+    }
+}
@@ -1,30 +1,375 @@
 // src/symbol_extractor.rs
 
-use tree_sitter::{Language, Node, Parser};
+use crate::documents;
+use crate::grammars;
+use crate::language::LanguageOverride;
+use crate::schemas;
+use crate::sfc;
+use crate::types::{ImportStatement, LineRange, Tag};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{LazyLock, Mutex};
+use tree_sitter::{Language, Node, Parser, Tree};
 
-/// Creates a code "skeleton" by walking the CST up to a specified depth.
-///
-/// This function walks the Concrete Syntax Tree of the source code down to the
-/// `max_depth`. It collects the text of all terminal nodes (leaves) it finds
-/// within that depth, and then joins them with spaces to create a flattened,
-/// high-level representation of the code's structure.
-pub fn create_skeleton_by_depth(
-    source_code: &str,
-    file_extension: &str,
-    max_depth: usize,
-) -> Result<String, String> {
-    // --- Language loading ---
-    let language: Language = match file_extension {
-        "rs" => tree_sitter_rust::LANGUAGE.into(),
-        "py" => tree_sitter_python::LANGUAGE.into(),
-        "ts" => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
-        _ => {
-            return Err(format!(
-                "Language support not configured for file extension: '{}'",
-                file_extension
-            ));
+/// The separator placed between top-level symbols by default: one per line.
+pub const DEFAULT_SYMBOL_SEPARATOR: &str = "\n";
+
+/// Rust doc comments longer than this many lines are cut to their first
+/// paragraph in skeletons by default.
+pub const DEFAULT_DOC_COMMENT_MAX_LINES: usize = 4;
+
+/// The order of top-level symbols in a skeleton (`--symbol-order`).
+#[derive(clap::ValueEnum, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolOrder {
+    /// As they appear in the file.
+    #[default]
+    Source,
+    /// Alphabetically by name, ignoring case.
+    Name,
+    /// Grouped by kind (function, struct, class, ...), in source order within each.
+    Kind,
+}
+
+impl SymbolOrder {
+    /// Returns true for the default, source order.
+    pub fn is_source(&self) -> bool {
+        *self == Self::Source
+    }
+
+    /// Sorts `tags` into this order. Sorting is stable, so ties keep their
+    /// source order.
+    pub fn sort_tags(self, tags: &mut [Tag]) {
+        match self {
+            Self::Source => tags.sort(),
+            Self::Name => tags.sort_by_cached_key(|tag| tag.name.to_lowercase()),
+            Self::Kind => tags.sort_by(|a, b| a.kind.cmp(&b.kind)),
         }
-    };
+    }
+}
+
+/// Options controlling how a skeleton is rendered.
+#[derive(Debug, Clone)]
+pub struct SkeletonOptions {
+    /// Placed between top-level symbols (tokens within a symbol are space-joined).
+    pub symbol_separator: String,
+    /// Collapse Rust `macro_rules!` bodies to `{ ... }`.
+    pub elide_macro_bodies: bool,
+    /// Cut Rust `///` and `//!` doc comments longer than this many lines to
+    /// their first paragraph, marked with `(...)`. `None` keeps them whole.
+    pub doc_comment_max_lines: Option<usize>,
+    /// Keep only the first N top-level symbols of each file (comments don't
+    /// count), followed by an `... and X more symbols` marker.
+    pub max_symbols: Option<usize>,
+    /// When extraction fails, use the full content (with an error banner)
+    /// instead of leaving the file out.
+    pub full_content_fallback: bool,
+    /// Annotate each top-level symbol with its most recent git change, giving
+    /// ages relative to this Unix time (`--blame-hints`). `None` disables.
+    pub blame_hints: Option<i64>,
+    /// Languages forced on files matching a glob (`--lang-override`), used
+    /// for both the skeleton grammar and the fence hint.
+    pub language_overrides: Vec<LanguageOverride>,
+    /// The order of top-level symbols (`--symbol-order`).
+    pub symbol_order: SymbolOrder,
+    /// Remove the indentation shared by the lines of a line range or symbol
+    /// read in full, noting it in the header (disabled by `--keep-indent`).
+    pub dedent_slices: bool,
+    /// Give zero-length files a context too, rather than reporting them as
+    /// empty (`--include-empty-files`).
+    pub include_empty_files: bool,
+}
+
+impl Default for SkeletonOptions {
+    fn default() -> Self {
+        Self {
+            symbol_separator: DEFAULT_SYMBOL_SEPARATOR.to_string(),
+            elide_macro_bodies: true,
+            doc_comment_max_lines: Some(DEFAULT_DOC_COMMENT_MAX_LINES),
+            max_symbols: None,
+            full_content_fallback: true,
+            blame_hints: None,
+            language_overrides: Vec::new(),
+            symbol_order: SymbolOrder::Source,
+            dedent_slices: true,
+            include_empty_files: false,
+        }
+    }
+}
+
+/// Rust-specific parts of the tree to leave out of a skeleton.
+#[derive(Default)]
+struct Elisions {
+    /// Render `macro_definition` nodes as their name plus `{ ... }`.
+    macro_bodies: bool,
+    /// Nodes (doc comment lines past the cut) to skip entirely.
+    skipped: HashSet<usize>,
+    /// Nodes after which a `(...)` marker is placed, where a doc comment was cut.
+    cut_after: HashSet<usize>,
+}
+
+impl Elisions {
+    fn for_tree(
+        tree: &Tree,
+        file_extension: &str,
+        source: &[u8],
+        options: &SkeletonOptions,
+    ) -> Self {
+        let mut elisions = Self::default();
+        if file_extension != "rs" {
+            return elisions;
+        }
+        elisions.macro_bodies = options.elide_macro_bodies;
+        if let Some(max_lines) = options.doc_comment_max_lines {
+            let mut stack = vec![tree.root_node()];
+            while let Some(node) = stack.pop() {
+                let mut cursor = node.walk();
+                let children: Vec<Node> = node.children(&mut cursor).collect();
+                for run in doc_comment_runs(&children) {
+                    elisions.cut_doc_comment(run, max_lines, source);
+                }
+                stack.extend(children);
+            }
+        }
+        elisions
+    }
+
+    /// Keeps the first paragraph of a doc comment run (at most `max_lines`
+    /// lines) if the run is longer than `max_lines`.
+    fn cut_doc_comment(&mut self, run: &[Node], max_lines: usize, source: &[u8]) {
+        if run.len() <= max_lines {
+            return;
+        }
+        let paragraph_len = run
+            .iter()
+            .position(|line| doc_comment_text(*line, source).is_empty())
+            .unwrap_or(run.len());
+        let kept = paragraph_len.clamp(1, max_lines);
+        self.cut_after.insert(run[kept - 1].id());
+        self.skipped.extend(run[kept..].iter().map(Node::id));
+    }
+}
+
+/// Returns true if `node` is a `///` or `//!` comment line.
+fn is_line_doc_comment(node: &Node) -> bool {
+    if node.kind() != "line_comment" {
+        return false;
+    }
+    let mut cursor = node.walk();
+    node.children(&mut cursor).any(|child| {
+        matches!(
+            child.kind(),
+            "outer_doc_comment_marker" | "inner_doc_comment_marker"
+        )
+    })
+}
+
+/// Splits `siblings` into runs of doc comment lines on consecutive rows.
+fn doc_comment_runs<'a, 't>(siblings: &'a [Node<'t>]) -> Vec<&'a [Node<'t>]> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    while start < siblings.len() {
+        if !is_line_doc_comment(&siblings[start]) {
+            start += 1;
+            continue;
+        }
+        let mut end = start + 1;
+        while end < siblings.len()
+            && is_line_doc_comment(&siblings[end])
+            && siblings[end].start_position().row == siblings[end - 1].start_position().row + 1
+        {
+            end += 1;
+        }
+        runs.push(&siblings[start..end]);
+        start = end;
+    }
+    runs
+}
+
+/// The text of one doc comment line, without its `///` or `//!`.
+fn doc_comment_text<'s>(node: Node, source: &'s [u8]) -> &'s str {
+    node.utf8_text(source)
+        .unwrap_or("")
+        .trim_start_matches("///")
+        .trim_start_matches("//!")
+        .trim()
+}
+
+/// Returns the tree-sitter language for a language key (usually a file
+/// extension; see `language::detect_language`), if one is configured.
+fn language_for_extension(file_extension: &str) -> Option<Language> {
+    match file_extension {
+        #[cfg(feature = "lang-rust")]
+        "rs" => Some(tree_sitter_rust::LANGUAGE.into()),
+        #[cfg(feature = "lang-python")]
+        "py" => Some(tree_sitter_python::LANGUAGE.into()),
+        #[cfg(feature = "lang-typescript")]
+        "ts" => Some(tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()),
+        #[cfg(feature = "lang-make")]
+        "makefile" | "mk" => Some(tree_sitter_make::LANGUAGE.into()),
+        #[cfg(feature = "lang-dockerfile")]
+        "dockerfile" => Some(tree_sitter_containerfile::LANGUAGE.into()),
+        #[cfg(feature = "lang-lua")]
+        "lua" => Some(tree_sitter_lua::LANGUAGE.into()),
+        #[cfg(feature = "lang-vim")]
+        "vim" => Some(tree_sitter_vim::language()),
+        #[cfg(feature = "lang-elixir")]
+        "ex" | "exs" => Some(tree_sitter_elixir::LANGUAGE.into()),
+        #[cfg(feature = "lang-erlang")]
+        "erl" | "hrl" => Some(tree_sitter_erlang::LANGUAGE.into()),
+        #[cfg(feature = "lang-zig")]
+        "zig" => Some(tree_sitter_zig::LANGUAGE.into()),
+        #[cfg(feature = "lang-nim")]
+        "nim" => Some(tree_sitter_nim::LANGUAGE.into()),
+        #[cfg(feature = "lang-hcl")]
+        "tf" | "hcl" => Some(tree_sitter_hcl::LANGUAGE.into()),
+        _ => None,
+    }
+}
+
+/// Every language key with a grammar, and the cargo feature that compiles
+/// the grammar in.
+const LANGUAGE_FEATURES: &[(&str, &str)] = &[
+    ("rs", "lang-rust"),
+    ("py", "lang-python"),
+    ("ts", "lang-typescript"),
+    ("makefile", "lang-make"),
+    ("mk", "lang-make"),
+    ("dockerfile", "lang-dockerfile"),
+    ("lua", "lang-lua"),
+    ("vim", "lang-vim"),
+    ("ex", "lang-elixir"),
+    ("exs", "lang-elixir"),
+    ("erl", "lang-erlang"),
+    ("hrl", "lang-erlang"),
+    ("zig", "lang-zig"),
+    ("nim", "lang-nim"),
+    ("tf", "lang-hcl"),
+    ("hcl", "lang-hcl"),
+];
+
+/// The language keys whose grammar is compiled into this build.
+pub fn supported_languages() -> Vec<&'static str> {
+    LANGUAGE_FEATURES
+        .iter()
+        .map(|(key, _)| *key)
+        .filter(|key| is_supported_extension(key))
+        .collect()
+}
+
+/// The `lang-*` features this build was compiled without, in order.
+pub fn missing_language_features() -> Vec<&'static str> {
+    let mut missing: Vec<&'static str> = Vec::new();
+    for (key, feature) in LANGUAGE_FEATURES {
+        if !is_supported_extension(key) && !missing.contains(feature) {
+            missing.push(feature);
+        }
+    }
+    missing
+}
+
+/// The error for a language key without a grammar, naming the feature to
+/// rebuild with if the grammar exists but was left out of this build.
+fn unsupported_language_error(file_extension: &str) -> String {
+    match LANGUAGE_FEATURES
+        .iter()
+        .find(|(key, _)| *key == file_extension)
+    {
+        Some((_, feature)) => format!(
+            "Built without support for '{}' files; rebuild with --features {}",
+            file_extension, feature
+        ),
+        None => format!(
+            "Language support not configured for file extension: '{}'",
+            file_extension
+        ),
+    }
+}
+
+/// The grammar for a language key: the built-in one, or else one loaded from
+/// a library listed in the user's config (see `grammars`).
+fn grammar(file_extension: &str) -> Result<Language, String> {
+    match language_for_extension(file_extension) {
+        Some(language) => Ok(language),
+        None => grammars::language(file_extension)
+            .unwrap_or_else(|| Err(unsupported_language_error(file_extension))),
+    }
+}
+
+/// Checks that the grammar for a language key loads into a parser, which
+/// fails if it was built for an incompatible tree-sitter version.
+pub fn check_grammar(file_extension: &str) -> Result<(), String> {
+    let language = grammar(file_extension)?;
+    Parser::new()
+        .set_language(&language)
+        .map_err(|e| e.to_string())
+}
+
+/// Template languages (Phoenix `.heex`, EEx) that embed code in markup. They
+/// have no grammar of their own, so they're always included in full rather
+/// than reported as a failed skeleton.
+const TEMPLATE_LANGUAGES: &[&str] = &["heex", "eex", "leex", "sface"];
+
+/// Returns true if a language key names a template language, which is
+/// included in full even where a skeleton was asked for.
+pub fn is_template_language(file_extension: &str) -> bool {
+    TEMPLATE_LANGUAGES.contains(&file_extension)
+}
+
+/// Returns true if files with this language key are included in full even
+/// where a skeleton was asked for: templates, and Terraform variable files
+/// (`.tfvars`), whose values are the whole point of them.
+pub fn is_always_full(file_extension: &str) -> bool {
+    is_template_language(file_extension) || file_extension == "tfvars"
+}
+
+/// Returns true if skeleton extraction is supported for the given file
+/// extension, by a built-in grammar or one from the user's config.
+pub fn is_supported_extension(file_extension: &str) -> bool {
+    if sfc::is_single_file_component(file_extension) {
+        return language_for_extension("ts").is_some();
+    }
+    schemas::is_schema_language(file_extension)
+        || documents::is_document_language(file_extension)
+        || language_for_extension(file_extension).is_some()
+        || grammars::language(file_extension).is_some_and(|language| language.is_ok())
+}
+
+/// Parse trees of the files in which symbol search found a definition, keyed
+/// by language key and a hash of the source, so those files aren't parsed
+/// again when their skeletons are built. Each tree is removed when reused,
+/// and whatever is left (files shown in full, or cut by an exclude) is
+/// dropped by `clear_parse_cache` once the contexts are built.
+static PARSE_CACHE: LazyLock<Mutex<HashMap<(String, u64), Tree>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn parse_cache_key(source_code: &str, file_extension: &str) -> (String, u64) {
+    let mut hasher = DefaultHasher::new();
+    source_code.hash(&mut hasher);
+    (file_extension.to_string(), hasher.finish())
+}
+
+/// Drops the trees symbol search kept that no skeleton has used.
+pub fn clear_parse_cache() {
+    if let Ok(mut cache) = PARSE_CACHE.lock() {
+        cache.clear();
+    }
+}
+
+/// Parses `source_code` with the grammar for `file_extension`, taking the
+/// tree symbol search kept for it instead if there is one.
+fn parse(source_code: &str, file_extension: &str) -> Result<Tree, String> {
+    let language = grammar(file_extension)?;
+
+    let key = parse_cache_key(source_code, file_extension);
+    if let Some(tree) = PARSE_CACHE
+        .lock()
+        .ok()
+        .and_then(|mut cache| cache.remove(&key))
+    {
+        return Ok(tree);
+    }
 
     let mut parser = Parser::new();
     parser
@@ -34,27 +379,647 @@ pub fn create_skeleton_by_depth(
     let tree = parser
         .parse(source_code, None)
         .ok_or("Internal error: Failed to parse source code.")?;
+    Ok(tree)
+}
+
+/// A parse tree from symbol search, which can be kept for building the
+/// file's skeleton if the file turns out to be wanted.
+pub struct SearchedSource {
+    key: (String, u64),
+    tree: Tree,
+}
+
+impl SearchedSource {
+    /// Keeps the tree for the next `parse` of the same source.
+    pub fn keep(self) {
+        if let Ok(mut cache) = PARSE_CACHE.lock() {
+            cache.insert(self.key, self.tree);
+        }
+    }
+}
+
+/// Like `extract_tags`, for symbol search: also returns the parse tree, for
+/// the caller to keep if the file has a definition it's looking for.
+pub fn search_tags(
+    source_code: &str,
+    file_extension: &str,
+) -> Result<(Vec<Tag>, SearchedSource), String> {
+    let tree = parse(source_code, file_extension)?;
+    let tags = tags_in(&tree, source_code, file_extension);
+    let key = parse_cache_key(source_code, file_extension);
+    Ok((tags, SearchedSource { key, tree }))
+}
+
+/// The CST node kinds that define a named symbol in each language, and the
+/// tag kind reported for them.
+fn definition_kinds(file_extension: &str) -> &'static [(&'static str, &'static str)] {
+    match file_extension {
+        "rs" => &[
+            ("function_item", "function"),
+            ("function_signature_item", "function"),
+            ("struct_item", "struct"),
+            ("enum_item", "enum"),
+            ("union_item", "union"),
+            ("trait_item", "trait"),
+            ("type_item", "type"),
+            ("const_item", "const"),
+            ("static_item", "static"),
+            ("mod_item", "module"),
+            ("macro_definition", "macro"),
+        ],
+        "py" => &[
+            ("function_definition", "function"),
+            ("class_definition", "class"),
+        ],
+        "ts" => &[
+            ("function_declaration", "function"),
+            ("generator_function_declaration", "function"),
+            ("class_declaration", "class"),
+            ("abstract_class_declaration", "class"),
+            ("method_definition", "method"),
+            ("interface_declaration", "interface"),
+            ("type_alias_declaration", "type"),
+            ("enum_declaration", "enum"),
+            ("variable_declarator", "variable"),
+        ],
+        "lua" | "vim" | "zig" => &[("function_declaration", "function")],
+        "nim" => &[
+            ("proc_declaration", "function"),
+            ("func_declaration", "function"),
+        ],
+        _ => &[],
+    }
+}
+
+/// The CST node kinds of import statements in each language.
+fn import_kinds(file_extension: &str) -> &'static [&'static str] {
+    match file_extension {
+        "rs" => &["use_declaration", "extern_crate_declaration"],
+        "py" => &["import_statement", "import_from_statement"],
+        "ts" => &["import_statement"],
+        _ => &[],
+    }
+}
+
+/// Returns true if `extract_imports` knows the import syntax of a language.
+pub fn supports_imports(file_extension: &str) -> bool {
+    !import_kinds(file_extension).is_empty()
+}
+
+/// Extracts the top-level import statements in a file that could be moved
+/// elsewhere without changing what the rest of it means: each must sit on
+/// lines of its own, and Rust `pub use` re-exports and imports under an
+/// attribute (e.g. `#[cfg(test)]`) are left out.
+pub fn extract_imports(
+    source_code: &str,
+    file_extension: &str,
+) -> Result<Vec<ImportStatement>, String> {
+    let tree = parse(source_code, file_extension)?;
+    let kinds = import_kinds(file_extension);
+    let source = source_code.as_bytes();
+
+    let mut imports = Vec::new();
+    let root_node = tree.root_node();
+    let mut cursor = root_node.walk();
+    for node in root_node.children(&mut cursor) {
+        if !kinds.contains(&node.kind())
+            || node
+                .prev_named_sibling()
+                .is_some_and(|sibling| sibling.kind() == "attribute_item")
+            || node
+                .child(0)
+                .is_some_and(|child| child.kind() == "visibility_modifier")
+        {
+            continue;
+        }
+        let line_start = source_code[..node.start_byte()]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let line_end = source_code[node.end_byte()..]
+            .find('\n')
+            .map_or(source.len(), |newline| node.end_byte() + newline + 1);
+        let alone_on_its_lines = source_code[line_start..node.start_byte()].trim().is_empty()
+            && source_code[node.end_byte()..line_end].trim().is_empty();
+        if alone_on_its_lines && let Ok(text) = node.utf8_text(source) {
+            imports.push(ImportStatement {
+                text: text.to_string(),
+                lines: line_start..line_end,
+            });
+        }
+    }
+    Ok(imports)
+}
+
+/// Extracts the named definitions (functions, types, classes, ...) in a file,
+/// sorted by their position in the source.
+///
+/// Names are taken verbatim from the grammar's `name` field, so qualified
+/// definitions keep their qualifier (e.g. `M.setup` in Lua, `s:helper` in Vimscript).
+pub fn extract_tags(source_code: &str, file_extension: &str) -> Result<Vec<Tag>, String> {
+    let tree = parse(source_code, file_extension)?;
+    Ok(tags_in(&tree, source_code, file_extension))
+}
+
+fn tags_in(tree: &Tree, source_code: &str, file_extension: &str) -> Vec<Tag> {
+    let kinds = definition_kinds(file_extension);
+    let source_bytes = source_code.as_bytes();
+
+    let mut tags = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if let Some((_, kind)) = kinds
+            .iter()
+            .find(|(node_kind, _)| *node_kind == node.kind())
+            && let Some(name) = node
+                .child_by_field_name("name")
+                .and_then(|name_node| name_node.utf8_text(source_bytes).ok())
+        {
+            let line_start = source_code[..node.start_byte()]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
+            let line_text = source_code[line_start..].lines().next().unwrap_or("");
+            tags.push(Tag {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                start_byte: node.start_byte(),
+                line: node.start_position().row + 1,
+                end_line: node.end_position().row + 1,
+                line_text: line_text.trim_end().to_string(),
+                doc_string: None,
+            });
+        }
+
+        let mut cursor = node.walk();
+        stack.extend(node.children(&mut cursor));
+    }
+
+    tags.sort();
+    tags
+}
+
+/// Creates a code "skeleton" by walking the CST up to a specified depth.
+///
+/// This function walks the Concrete Syntax Tree of the source code down to the
+/// `max_depth`. It collects the text of all terminal nodes (leaves) it finds
+/// within that depth, joining the tokens of each top-level symbol with spaces
+/// and separating symbols with `options.symbol_separator`, to create a
+/// flattened, high-level representation of the code's structure.
+pub fn create_skeleton_by_depth(
+    source_code: &str,
+    file_extension: &str,
+    max_depth: usize,
+    options: &SkeletonOptions,
+) -> Result<String, String> {
+    create_skeleton_with_hints(source_code, file_extension, max_depth, options, |_| None)
+}
+
+/// Like `create_skeleton_by_depth`, but appends `hint(lines)` (if any) to each
+/// top-level symbol other than comments, where `lines` is the symbol's line
+/// range in `source_code`.
+pub fn create_skeleton_with_hints(
+    source_code: &str,
+    file_extension: &str,
+    max_depth: usize,
+    options: &SkeletonOptions,
+    hint: impl Fn(LineRange) -> Option<String>,
+) -> Result<String, String> {
+    if let Some(skeleton) = schemas::skeleton(source_code, file_extension, max_depth)
+        .or_else(|| documents::skeleton(source_code, file_extension, max_depth))
+    {
+        return skeleton;
+    }
+    if sfc::is_single_file_component(file_extension) {
+        return component_skeleton(source_code, file_extension, max_depth, options, &hint);
+    }
+    let tree = parse(source_code, file_extension)?;
+    let elisions = Elisions::for_tree(&tree, file_extension, source_code.as_bytes(), options);
 
     // --- Core Logic: Depth-Limited Walk ---
 
-    let mut tokens: Vec<String> = Vec::new();
+    // Each symbol is paired with whether it's a comment, for `max_symbols`.
+    let mut symbols: Vec<(String, bool)> = Vec::new();
     let root_node = tree.root_node();
 
-    // Start the recursive walk from the root node (depth 0).
-    collect_tokens_at_depth(
-        root_node,
-        0, // current_depth
-        max_depth + 1,
-        &mut tokens,
-        source_code.as_bytes(),
+    if matches!(file_extension, "ex" | "exs") {
+        // Elixir definitions are all macro calls several CST levels apart,
+        // so depth counts module levels instead (see `elixir_outline`).
+        symbols = elixir_outline(root_node, source_code.as_bytes(), max_depth, &hint);
+    } else if matches!(file_extension, "tf" | "hcl") {
+        // Block headers sit several CST levels down, below a `body` node.
+        symbols = hcl_outline(root_node, source_code.as_bytes(), max_depth, &hint);
+    } else {
+        // Walk each top-level node (depth 1, below the root) separately, so every
+        // top-level symbol becomes its own entry in the output.
+        let mut sort_keys: Vec<String> = Vec::new();
+        let mut cursor = root_node.walk();
+        for top_level_node in root_node.children(&mut cursor) {
+            let mut tokens: Vec<String> = Vec::new();
+            collect_tokens_at_depth(
+                top_level_node,
+                1, // current_depth
+                max_depth + 1,
+                &mut tokens,
+                source_code.as_bytes(),
+                &elisions,
+            );
+            if !tokens.is_empty() {
+                // Join the collected tokens with a space (likely breaks syntactic validity; should be fine for LLMs)
+                let mut symbol = tokens.join(" ");
+                let is_comment = top_level_node.kind().contains("comment");
+                if !is_comment
+                    && let Some(hint) = hint(LineRange {
+                        start: top_level_node.start_position().row + 1,
+                        end: top_level_node.end_position().row + 1,
+                    })
+                {
+                    symbol.push_str(&format!("  [{}]", hint));
+                }
+                sort_keys.push(match options.symbol_order {
+                    SymbolOrder::Source => String::new(),
+                    SymbolOrder::Name => top_level_node
+                        .child_by_field_name("name")
+                        .and_then(|name| name.utf8_text(source_code.as_bytes()).ok())
+                        .unwrap_or(&symbol)
+                        .to_lowercase(),
+                    SymbolOrder::Kind => top_level_node.kind().to_string(),
+                });
+                symbols.push((symbol, is_comment));
+            }
+        }
+        if !options.symbol_order.is_source() {
+            symbols = order_symbols(symbols, sort_keys);
+        }
+    }
+
+    if symbols.is_empty() {
+        return Ok("(No structure found)".to_string());
+    }
+
+    let omitted = options
+        .max_symbols
+        .map_or(0, |max_symbols| truncate_symbols(&mut symbols, max_symbols));
+    let mut lines: Vec<String> = symbols.into_iter().map(|(symbol, _)| symbol).collect();
+    if omitted > 0 {
+        lines.push(format!(
+            "... and {} more symbol{}",
+            omitted,
+            if omitted == 1 { "" } else { "s" }
+        ));
+    }
+    Ok(lines.join(&options.symbol_separator))
+}
+
+/// Returns the line of the first syntax error the parser finds in
+/// `source_code` (an `ERROR` or missing node), if any. A skeleton of such a
+/// file, often one that's half-edited, may be missing symbols.
+pub fn first_syntax_error(source_code: &str, file_extension: &str) -> Option<usize> {
+    if sfc::is_single_file_component(file_extension) {
+        return sfc::split(source_code).scripts.iter().find_map(|script| {
+            first_syntax_error(script.content, "ts").map(|line| line + script.first_line - 1)
+        });
+    }
+    let tree = parse(source_code, file_extension).ok()?;
+    let root_node = tree.root_node();
+    if !root_node.has_error() {
+        return None;
+    }
+    let mut cursor = root_node.walk();
+    loop {
+        let node = cursor.node();
+        if node.is_error() || node.is_missing() {
+            return Some(node.start_position().row + 1);
+        }
+        // Only descend into subtrees that contain the error.
+        if !(node.has_error() && cursor.goto_first_child()) {
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Builds the skeleton of a Vue or Svelte single-file component: each
+/// `<script>` block's TypeScript skeleton between its tags, then the
+/// components its markup uses (see `sfc::component_outline`), then the
+/// opening tag of each `<style>` block.
+fn component_skeleton(
+    source_code: &str,
+    file_extension: &str,
+    max_depth: usize,
+    options: &SkeletonOptions,
+    hint: &dyn Fn(LineRange) -> Option<String>,
+) -> Result<String, String> {
+    let component = sfc::split(source_code);
+    let mut sections = Vec::new();
+    for script in &component.scripts {
+        let offset = script.first_line - 1;
+        let shifted = |lines: LineRange| {
+            hint(LineRange {
+                start: lines.start + offset,
+                end: lines.end + offset,
+            })
+        };
+        let shifted: &dyn Fn(LineRange) -> Option<String> = &shifted;
+        let skeleton =
+            create_skeleton_with_hints(script.content, "ts", max_depth, options, shifted)?;
+        sections.push(format!("{}\n{}\n</script>", script.open_tag, skeleton));
+    }
+    let outline = sfc::component_outline(&component.markup, max_depth);
+    if !outline.is_empty() {
+        sections.push(if file_extension == "vue" {
+            format!("<template>\n{}\n</template>", outline.join("\n"))
+        } else {
+            format!("<!-- markup -->\n{}", outline.join("\n"))
+        });
+    }
+    sections.extend(
+        component
+            .styles
+            .iter()
+            .map(|tag| format!("{} ... </style>", tag)),
     );
 
-    if tokens.is_empty() {
+    if sections.is_empty() {
         return Ok("(No structure found)".to_string());
     }
+    Ok(sections.join(&options.symbol_separator))
+}
+
+/// Sorts `symbols` by `sort_keys` (one per symbol), keeping comments with the
+/// symbol they lead into. Comments after the last symbol stay at the end.
+fn order_symbols(symbols: Vec<(String, bool)>, sort_keys: Vec<String>) -> Vec<(String, bool)> {
+    let mut groups: Vec<(String, Vec<(String, bool)>)> = Vec::new();
+    let mut leading: Vec<(String, bool)> = Vec::new();
+    for (symbol, key) in symbols.into_iter().zip(sort_keys) {
+        let is_comment = symbol.1;
+        leading.push(symbol);
+        if !is_comment {
+            groups.push((key, std::mem::take(&mut leading)));
+        }
+    }
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+    groups
+        .into_iter()
+        .flat_map(|(_, group)| group)
+        .chain(leading)
+        .collect()
+}
+
+/// Cuts `symbols` after the first `max_symbols` non-comment symbols, also
+/// dropping the comments that lead into the first omitted one. Returns how
+/// many non-comment symbols were omitted.
+fn truncate_symbols(symbols: &mut Vec<(String, bool)>, max_symbols: usize) -> usize {
+    let Some(first_omitted) = symbols
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, is_comment))| !is_comment)
+        .nth(max_symbols)
+        .map(|(index, _)| index)
+    else {
+        return 0;
+    };
+    let cut = symbols[..first_omitted]
+        .iter()
+        .rposition(|(_, is_comment)| !is_comment)
+        .map_or(0, |index| index + 1);
+    let omitted = symbols[first_omitted..]
+        .iter()
+        .filter(|(_, is_comment)| !is_comment)
+        .count();
+    symbols.truncate(cut);
+    omitted
+}
+
+/// The Elixir macros that define a function; only their heads are outlined.
+const ELIXIR_DEFINITIONS: &[&str] = &[
+    "def",
+    "defp",
+    "defmacro",
+    "defmacrop",
+    "defguard",
+    "defguardp",
+    "defdelegate",
+    "defn",
+    "defnp",
+];
+
+/// Builds an Elixir skeleton as an indented outline, one symbol per top-level
+/// expression. Depth 1 shows the top-level forms (`defmodule App do ... end`),
+/// and each further level opens one more level of `do` blocks (modules, and
+/// block macros such as `schema` or `describe`), listing what they contain a
+/// line each: function heads, module attributes in full (so `@doc` stays
+/// with its function) and the first line of anything else. Function bodies
+/// are never shown.
+fn elixir_outline(
+    root_node: Node,
+    source_bytes: &[u8],
+    max_depth: usize,
+    hint: &impl Fn(LineRange) -> Option<String>,
+) -> Vec<(String, bool)> {
+    let mut symbols = Vec::new();
+    let mut cursor = root_node.walk();
+    for node in root_node.named_children(&mut cursor) {
+        let mut lines = Vec::new();
+        outline_elixir_node(node, 1, max_depth, source_bytes, hint, &mut lines);
+        if !lines.is_empty() {
+            symbols.push((lines.join("\n"), node.kind() == "comment"));
+        }
+    }
+    symbols
+}
+
+/// Appends the outline of one Elixir expression, `level` blocks deep, to `lines`.
+fn outline_elixir_node(
+    node: Node,
+    level: usize,
+    max_depth: usize,
+    source_bytes: &[u8],
+    hint: &impl Fn(LineRange) -> Option<String>,
+    lines: &mut Vec<String>,
+) {
+    let indent = "  ".repeat(level - 1);
+    let text = node.utf8_text(source_bytes).unwrap_or("").trim();
+    let with_hint = |line: String| match hint(LineRange {
+        start: node.start_position().row + 1,
+        end: node.end_position().row + 1,
+    }) {
+        Some(hint) => format!("{}  [{}]", line, hint),
+        None => line,
+    };
+    let first_line = || {
+        let mut text_lines = text.lines();
+        let first = text_lines.next().unwrap_or("").trim_end();
+        if text_lines.next().is_some() {
+            format!("{}{} ...", indent, first)
+        } else {
+            format!("{}{}", indent, first)
+        }
+    };
 
-    // Join the collected tokens with a space (likely breaks syntactic validity; should be fine for LLMs)
-    Ok(tokens.join(" "))
+    match node.kind() {
+        // Comments inside modules are left out; top-level ones are kept, as
+        // in other languages.
+        "comment" => {
+            if level == 1 {
+                lines.push(text.to_string());
+            }
+        }
+        "unary_operator" if text.starts_with('@') => {
+            lines.extend(
+                text.lines()
+                    .map(|line| format!("{}{}", indent, line.trim())),
+            );
+        }
+        "call" => {
+            let target = node
+                .child_by_field_name("target")
+                .and_then(|target| target.utf8_text(source_bytes).ok())
+                .unwrap_or("");
+            let mut cursor = node.walk();
+            let children: Vec<Node> = node.named_children(&mut cursor).collect();
+            let arguments = children.iter().find(|child| child.kind() == "arguments");
+            let do_block = children.iter().find(|child| child.kind() == "do_block");
+
+            if ELIXIR_DEFINITIONS.contains(&target) {
+                let head = arguments
+                    .and_then(|arguments| arguments.named_child(0))
+                    .and_then(|head| head.utf8_text(source_bytes).ok())
+                    .unwrap_or("");
+                lines.push(with_hint(format!(
+                    "{}{} {}",
+                    indent,
+                    target,
+                    head.split_whitespace().collect::<Vec<_>>().join(" ")
+                )));
+            } else if let Some(do_block) = do_block {
+                let head = String::from_utf8_lossy(
+                    &source_bytes[node.start_byte()..do_block.start_byte()],
+                )
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+                let mut cursor = do_block.walk();
+                let body: Vec<Node> = do_block
+                    .named_children(&mut cursor)
+                    .filter(|child| child.kind() != "comment")
+                    .collect();
+                if level < max_depth && !body.is_empty() {
+                    lines.push(with_hint(format!("{}{} do", indent, head)));
+                    for child in body {
+                        outline_elixir_node(child, level + 1, max_depth, source_bytes, hint, lines);
+                    }
+                    lines.push(format!("{}end", indent));
+                } else {
+                    lines.push(with_hint(format!("{}{} do ... end", indent, head)));
+                }
+            } else {
+                lines.push(first_line());
+            }
+        }
+        _ => lines.push(first_line()),
+    }
+}
+
+/// Builds an HCL (Terraform) skeleton as an indented outline, one symbol per
+/// top-level block or attribute. Depths 1 and 2 show block headers with their
+/// bodies elided (`resource "aws_s3_bucket" "logs" { ... }`), depth 3 adds
+/// the attribute names and nested block headers inside each block, and each
+/// further level opens one more level of nested blocks. Attribute values are
+/// never shown.
+fn hcl_outline(
+    root_node: Node,
+    source_bytes: &[u8],
+    max_depth: usize,
+    hint: &impl Fn(LineRange) -> Option<String>,
+) -> Vec<(String, bool)> {
+    let mut symbols = Vec::new();
+    let mut cursor = root_node.walk();
+    for node in root_node.named_children(&mut cursor) {
+        if node.kind() == "comment" {
+            let text = node.utf8_text(source_bytes).unwrap_or("").trim();
+            symbols.push((text.to_string(), true));
+            continue;
+        }
+        let mut body_cursor = node.walk();
+        for child in node.named_children(&mut body_cursor) {
+            if child.kind() == "comment" {
+                let text = child.utf8_text(source_bytes).unwrap_or("").trim();
+                symbols.push((text.to_string(), true));
+                continue;
+            }
+            let mut lines = Vec::new();
+            outline_hcl_node(child, 1, max_depth, source_bytes, &mut lines);
+            if let Some(first) = lines.first_mut()
+                && let Some(hint) = hint(LineRange {
+                    start: child.start_position().row + 1,
+                    end: child.end_position().row + 1,
+                })
+            {
+                first.push_str(&format!("  [{}]", hint));
+            }
+            if !lines.is_empty() {
+                symbols.push((lines.join("\n"), false));
+            }
+        }
+    }
+    symbols
+}
+
+/// Appends the outline of one HCL block or attribute, `level` blocks deep,
+/// to `lines`.
+fn outline_hcl_node(
+    node: Node,
+    level: usize,
+    max_depth: usize,
+    source_bytes: &[u8],
+    lines: &mut Vec<String>,
+) {
+    let indent = "  ".repeat(level - 1);
+    let mut cursor = node.walk();
+    let children: Vec<Node> = node.named_children(&mut cursor).collect();
+    match node.kind() {
+        "attribute" => {
+            let name = children
+                .first()
+                .and_then(|name| name.utf8_text(source_bytes).ok())
+                .unwrap_or("");
+            lines.push(format!("{}{}", indent, name));
+        }
+        "block" => {
+            let header_end = children
+                .iter()
+                .find(|child| child.kind() == "block_start")
+                .map_or(node.end_byte(), |start| start.start_byte());
+            let header = String::from_utf8_lossy(&source_bytes[node.start_byte()..header_end])
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let body: Vec<Node> = children
+                .iter()
+                .find(|child| child.kind() == "body")
+                .map(|body| {
+                    let mut cursor = body.walk();
+                    body.named_children(&mut cursor)
+                        .filter(|child| child.kind() != "comment")
+                        .collect()
+                })
+                .unwrap_or_default();
+            if body.is_empty() {
+                lines.push(format!("{}{} {{}}", indent, header));
+            } else if level + 2 <= max_depth {
+                lines.push(format!("{}{} {{", indent, header));
+                for child in body {
+                    outline_hcl_node(child, level + 1, max_depth, source_bytes, lines);
+                }
+                lines.push(format!("{}}}", indent));
+            } else {
+                lines.push(format!("{}{} {{ ... }}", indent, header));
+            }
+        }
+        _ => {}
+    }
 }
 
 /// A recursive helper function to walk the tree to a max depth.
@@ -64,9 +1029,33 @@ fn collect_tokens_at_depth(
     max_depth: usize,
     tokens: &mut Vec<String>,
     source_bytes: &[u8],
+    elisions: &Elisions,
 ) {
     // Base Case: If we've exceeded the max depth, stop recursing.
-    if current_depth > max_depth {
+    if current_depth > max_depth || elisions.skipped.contains(&node.id()) {
+        return;
+    }
+
+    // A macro keeps what precedes its body (`macro_rules! name`); the body
+    // itself, if deep enough to show, becomes `{ ... }`.
+    if elisions.macro_bodies && node.kind() == "macro_definition" {
+        let mut cursor = node.walk();
+        for child_node in node.children(&mut cursor) {
+            if matches!(child_node.kind(), "{" | "(" | "[") {
+                if current_depth < max_depth {
+                    tokens.push("{ ... }".to_string());
+                }
+                break;
+            }
+            collect_tokens_at_depth(
+                child_node,
+                current_depth + 1,
+                max_depth,
+                tokens,
+                source_bytes,
+                elisions,
+            );
+        }
         return;
     }
 
@@ -92,6 +1081,10 @@ fn collect_tokens_at_depth(
             max_depth,
             tokens,
             source_bytes,
+            elisions,
         );
     }
+    if elisions.cut_after.contains(&node.id()) {
+        tokens.push("(...)".to_string());
+    }
 }
@@ -0,0 +1,3967 @@
+// src/test_harness.rs
+
+//! Integration test harness for the full resolution + rendering pipeline.
+//!
+//! A `Fixture` builds a throwaway directory tree from a declarative list of
+//! files, `run_pipeline` runs it through `pipeline::run`, as `main` does,
+//! with a fixed `Config`, and `assert_snapshot` compares the rendered result
+//! with a checked-in snapshot under `src/snapshots/`.
+//!
+//! Set `CTX_PICK_UPDATE_SNAPSHOTS=1` to (re)write snapshots instead of
+//! comparing against them.
+
+use crate::config::Config;
+use crate::display::DisplayManager;
+use crate::file_resolver;
+use crate::pipeline;
+use crate::types::{InputResolution, ResolutionReport};
+use clap::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A temporary directory tree, removed again when dropped.
+pub struct Fixture {
+    root: PathBuf,
+}
+
+impl Fixture {
+    /// Creates a fixture containing the given `(relative_path, contents)` files.
+    /// Parent directories are created as needed.
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        let fixture = Self::empty();
+        for (relative_path, contents) in files {
+            fixture.write(relative_path, contents.as_bytes());
+        }
+        fixture
+    }
+
+    /// Creates an empty fixture directory with a unique name.
+    pub fn empty() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let unique = format!(
+            "ctx-pick-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        );
+        let root = std::env::temp_dir().join(unique);
+        fs::create_dir_all(&root).expect("failed to create fixture root");
+        // Canonicalize so display paths match what the resolver produces
+        // (e.g. /tmp is a symlink to /private/tmp on macOS).
+        let root = fs::canonicalize(&root).expect("failed to canonicalize fixture root");
+        Self { root }
+    }
+
+    /// Writes raw bytes to a file inside the fixture.
+    pub fn write(&self, relative_path: &str, contents: &[u8]) -> &Self {
+        let path = self.root.join(relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("failed to create fixture directory");
+        }
+        fs::write(&path, contents).expect("failed to write fixture file");
+        self
+    }
+
+    /// Runs `git` inside the fixture, panicking if it fails.
+    pub fn git(&self, args: &[&str]) -> &Self {
+        let status = std::process::Command::new("git")
+            .args([
+                "-c",
+                "user.name=ctx-pick",
+                "-c",
+                "user.email=ctx-pick@example.com",
+            ])
+            .args(args)
+            .current_dir(&self.root)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .expect("failed to run git");
+        assert!(status.success(), "git {:?} failed", args);
+        self
+    }
+
+    /// Returns the fixture's root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns a `Config` rooted at the fixture directory.
+    pub fn config(&self) -> Config {
+        Config {
+            working_dir: self.root.clone(),
+            invocation_dir: self.root.clone(),
+            search_roots: Vec::new(),
+            max_files_per_input: Some(crate::config::DEFAULT_MAX_FILES_PER_INPUT),
+            search_entry_limit: Some(crate::config::DEFAULT_SEARCH_ENTRY_LIMIT),
+            search_time_limit: Some(crate::config::DEFAULT_SEARCH_TIME_LIMIT),
+            dir_depth: None,
+            expand_inputs: true,
+            balance_warning_threshold: Some(crate::config::DEFAULT_BALANCE_WARNING_THRESHOLD),
+            git_tree: None,
+            archive: None,
+            default_excludes: true,
+            hidden: false,
+            allow_outside: false,
+            follow_symlinks: true,
+        }
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Options for a pipeline run, mirroring the relevant CLI flags.
+#[derive(Default)]
+pub struct RunOptions {
+    pub depth: Option<usize>,
+    pub overview: bool,
+    /// Overrides the default per-input file limit when set.
+    pub max_files_per_input: Option<usize>,
+    /// Mirrors `--search-entry-limit N`.
+    pub search_entry_limit: Option<usize>,
+    /// Mirrors `--dir-depth N`.
+    pub dir_depth: Option<usize>,
+    /// Mirrors `--no-expand`.
+    pub no_expand: bool,
+    /// Mirrors `--no-default-excludes`.
+    pub no_default_excludes: bool,
+    /// Mirrors `--at REF`.
+    pub at: Option<&'static str>,
+    /// Mirrors `--archive PATH`, with PATH relative to the fixture root.
+    pub archive: Option<&'static str>,
+    /// Mirrors `--regex PATTERN`, once per pattern.
+    pub regex: Vec<&'static str>,
+    /// Mirrors `--exclude-from FILE`, with FILE relative to the fixture root.
+    pub exclude_from: Option<&'static str>,
+    /// Runs from this subdirectory of the fixture instead of its root.
+    pub working_subdir: Option<&'static str>,
+    /// Resolves from the fixture root (or `working_subdir`) as if invoked
+    /// from this subdirectory of the fixture.
+    pub invocation_subdir: Option<&'static str>,
+    /// Mirrors `--allow-outside`.
+    pub allow_outside: bool,
+    /// Mirrors `--hidden`.
+    pub hidden: bool,
+    /// Mirrors `--no-follow-symlinks`.
+    pub no_follow_symlinks: bool,
+    /// Mirrors `--scope-to-inputs`.
+    pub scope_to_inputs: bool,
+    /// Mirrors `--compact`.
+    pub compact: bool,
+    /// Mirrors `--format`.
+    pub format: crate::output::OutputFormat,
+    /// Mirrors `--max-symbols N`.
+    pub max_symbols: Option<usize>,
+    /// Mirrors `--symbol-order`.
+    pub symbol_order: crate::symbol_extractor::SymbolOrder,
+    /// Mirrors `--keep-indent`.
+    pub keep_indent: bool,
+    /// Mirrors `--wrap N`.
+    pub wrap: Option<usize>,
+    /// Mirrors `--max-line-length N`.
+    pub max_line_length: Option<usize>,
+    /// Mirrors `--compare REF --compare-format FORMAT`.
+    pub compare: Option<(&'static str, crate::compare::CompareFormat)>,
+    /// Mirrors `--changed-only=REF --context-lines N`.
+    pub changed_only: Option<(&'static str, usize)>,
+    /// Mirrors `--grep PATTERN`.
+    pub grep: Option<&'static str>,
+    /// Mirrors `--only-matching-lines --context-lines N`.
+    pub only_matching_lines: Option<usize>,
+    /// Mirrors `--blame-hints`, with ages measured back from this Unix time.
+    pub blame_hints: Option<i64>,
+    /// Mirrors `--dedupe-imports`.
+    pub dedupe_imports: bool,
+    /// Mirrors `--dedup-skeletons`.
+    pub dedup_skeletons: bool,
+    /// Mirrors `--toc`.
+    pub toc: bool,
+    /// Mirrors `--anchors`.
+    pub anchors: bool,
+    /// Appends the `--manifest-out` JSON as its own section.
+    pub manifest: bool,
+    /// Mirrors `--diff-last=MANIFEST`, with MANIFEST relative to the fixture
+    /// root; the report is appended after the manifest.
+    pub diff_last: Option<&'static str>,
+    /// Mirrors `--on-missing skip`: unresolved inputs are warnings, not errors.
+    pub skip_missing: bool,
+    /// Mirrors `--lang-override GLOB=LANG`, once per override.
+    pub lang_override: Vec<&'static str>,
+}
+
+/// Runs `inputs` inside `fixture` through the same pipeline as `main`,
+/// returning a deterministic text rendering suitable for snapshotting.
+///
+/// The rendering has a section listing each input's resolution, followed by
+/// either the error report (if the run stopped short) or any warnings and
+/// the rendered output.
+pub fn run_pipeline(fixture: &Fixture, inputs: &[&str], options: &RunOptions) -> String {
+    let mut config = fixture.config();
+    if let Some(limit) = options.max_files_per_input {
+        config.max_files_per_input = Some(limit);
+    }
+    if let Some(limit) = options.search_entry_limit {
+        config.search_entry_limit = Some(limit);
+    }
+    config.dir_depth = options.dir_depth;
+    config.expand_inputs = !options.no_expand;
+    config.default_excludes = !options.no_default_excludes;
+    config.allow_outside = options.allow_outside;
+    config.hidden = options.hidden;
+    config.follow_symlinks = !options.no_follow_symlinks;
+    if let Some(subdir) = options.working_subdir {
+        config.working_dir = config.working_dir.join(subdir);
+        config.invocation_dir = config.working_dir.clone();
+    }
+    if let Some(subdir) = options.invocation_subdir {
+        config.invocation_dir = fixture.root().join(subdir);
+    }
+    if let Some(revision) = options.at {
+        config.git_tree = Some(
+            crate::git::GitTree::load(&config.working_dir, revision)
+                .expect("fixture revision should load"),
+        );
+    }
+    if let Some(archive) = options.archive {
+        config.archive = Some(
+            crate::archive::Archive::load(&fixture.root().join(archive))
+                .expect("fixture archive should load"),
+        );
+    }
+    if options.scope_to_inputs {
+        config.search_roots = file_resolver::directory_inputs(inputs, &config);
+    }
+
+    let cli = command_line(fixture, inputs, options);
+    let compare_tree = options.compare.map(|(revision, _)| {
+        crate::git::GitTree::load(&config.working_dir, revision)
+            .expect("fixture revision should load")
+    });
+    let changed_lines = options.changed_only.map(|(revision, _)| {
+        crate::git::ChangedLines::load(&config.working_dir, revision)
+            .expect("fixture revision should load")
+    });
+    let revisions = pipeline::Revisions {
+        compare_tree: compare_tree.as_ref(),
+        changed_lines: changed_lines.as_ref(),
+    };
+    let skeleton_options = crate::symbol_extractor::SkeletonOptions {
+        blame_hints: options.blame_hints,
+        ..crate::skeleton_options(&cli)
+    };
+    console::set_colors_enabled_stderr(false);
+    console::set_colors_enabled(false);
+    let mut messages: Vec<u8> = Vec::new();
+    let run = pipeline::run(
+        &cli,
+        &[],
+        &config,
+        &revisions,
+        skeleton_options,
+        &DisplayManager::new(),
+        &mut messages,
+    );
+
+    let mut rendered = String::from("== resolutions ==\n");
+    for resolution in &run.resolutions {
+        rendered.push_str(&describe_resolution(resolution));
+        rendered.push('\n');
+    }
+    let messages = String::from_utf8_lossy(&messages);
+    let output = match run.outcome {
+        Ok(pipeline::Outcome::Rendered(output)) => output,
+        Ok(pipeline::Outcome::Selected(_)) => unreachable!("the harness always renders"),
+        Err(_) => {
+            rendered.push_str("\n== error report ==\n");
+            rendered.push_str(&messages);
+            return normalize(&rendered, fixture.root());
+        }
+    };
+    if !messages.is_empty() {
+        rendered.push_str("\n== warnings ==\n");
+        rendered.push_str(&messages);
+    }
+    rendered.push_str(match options.format {
+        crate::output::OutputFormat::Markdown => "\n== markdown ==\n",
+        crate::output::OutputFormat::Chat => "\n== chat ==\n",
+        crate::output::OutputFormat::Cxml => "\n== cxml ==\n",
+        crate::output::OutputFormat::SymbolsJson => "\n== symbols-json ==\n",
+    });
+    rendered.push_str(&output.output);
+    if options.manifest {
+        let manifest = crate::manifest::Manifest::new(
+            crate::manifest_options(&cli),
+            &output.contexts,
+            &output.output,
+        );
+        rendered.push_str("== manifest ==\n");
+        rendered.push_str(&serde_json::to_string_pretty(&manifest).expect("manifest serializes"));
+        rendered.push('\n');
+        if let Some(previous) = options.diff_last {
+            let recorded = crate::manifest::RecordedManifest::read(&fixture.root.join(previous))
+                .expect("earlier manifest reads");
+            let mut report = Vec::new();
+            DisplayManager::new()
+                .write_file_set_diff(
+                    &mut report,
+                    Path::new(previous),
+                    &manifest.diff_against(&recorded),
+                )
+                .expect("writing to a Vec cannot fail");
+            rendered.push_str("== diff-last ==\n");
+            rendered.push_str(&String::from_utf8_lossy(&report));
+        }
+    }
+
+    normalize(&rendered, fixture.root())
+}
+
+/// The command line `options` stand for, parsed as `main` would parse it.
+fn command_line(fixture: &Fixture, inputs: &[&str], options: &RunOptions) -> crate::Cli {
+    fn value<T: clap::ValueEnum>(value: T) -> String {
+        value
+            .to_possible_value()
+            .expect("no value is skipped")
+            .get_name()
+            .to_string()
+    }
+    let mut args = vec!["ctx-pick".to_string(), "--to-stdout".to_string()];
+    let mut flag = |name: &str, given: bool| {
+        if given {
+            args.push(format!("--{}", name));
+        }
+    };
+    flag("overview", options.overview);
+    flag("compact", options.compact);
+    flag("keep-indent", options.keep_indent);
+    flag("only-matching-lines", options.only_matching_lines.is_some());
+    flag("blame-hints", options.blame_hints.is_some());
+    flag("dedupe-imports", options.dedupe_imports);
+    flag("dedup-skeletons", options.dedup_skeletons);
+    flag("toc", options.toc);
+    flag("anchors", options.anchors);
+    let mut option = |name: &str, value: Option<String>| {
+        if let Some(value) = value {
+            args.push(format!("--{}={}", name, value));
+        }
+    };
+    option("depth", options.depth.map(|depth| depth.to_string()));
+    option("format", Some(value(options.format)));
+    option(
+        "max-symbols",
+        options.max_symbols.map(|max| max.to_string()),
+    );
+    option("symbol-order", Some(value(options.symbol_order)));
+    option("wrap", options.wrap.map(|width| width.to_string()));
+    option(
+        "max-line-length",
+        options.max_line_length.map(|max| max.to_string()),
+    );
+    option(
+        "exclude-from",
+        options
+            .exclude_from
+            .map(|list| fixture.root().join(list).display().to_string()),
+    );
+    if let Some((revision, format)) = options.compare {
+        option("compare", Some(revision.to_string()));
+        option("compare-format", Some(value(format)));
+    }
+    if let Some((revision, context_lines)) = options.changed_only {
+        option("changed-only", Some(revision.to_string()));
+        option("context-lines", Some(context_lines.to_string()));
+    }
+    option("grep", options.grep.map(str::to_string));
+    option(
+        "context-lines",
+        options.only_matching_lines.map(|lines| lines.to_string()),
+    );
+    if options.skip_missing {
+        option("on-missing", Some("skip".to_string()));
+    }
+    for pattern in &options.regex {
+        option("regex", Some(pattern.to_string()));
+    }
+    for value in &options.lang_override {
+        option("lang-override", Some(value.to_string()));
+    }
+    args.push("--".to_string());
+    args.extend(inputs.iter().map(|input| input.to_string()));
+    crate::Cli::try_parse_from(args).expect("fixture options should parse")
+}
+
+/// Summarizes one resolution on a single line, with display paths only.
+fn describe_resolution(resolution: &InputResolution) -> String {
+    let join = |paths: &mut dyn Iterator<Item = &Path>| {
+        paths
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match resolution {
+        InputResolution::Success(files) => format!(
+            "Success [{}]",
+            files
+                .iter()
+                .map(|f| f.display_label())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        InputResolution::Ambiguous {
+            input_string,
+            candidates,
+        } => format!(
+            "Ambiguous '{}' [{}]",
+            input_string,
+            join(
+                &mut candidates
+                    .iter()
+                    .map(|candidate| candidate.display_path.as_path())
+            )
+        ),
+        InputResolution::InvalidGlobPattern {
+            input_string,
+            error,
+        } => format!("InvalidGlobPattern '{}' ({})", input_string, error),
+        InputResolution::OutsideWorkingDir {
+            input_string,
+            file_count,
+            ..
+        } => format!(
+            "OutsideWorkingDir '{}' ({} files)",
+            input_string, file_count
+        ),
+        InputResolution::InvalidRegex {
+            input_string,
+            error,
+        } => format!("InvalidRegex '{}' ({})", input_string, error),
+        InputResolution::TooManyFiles {
+            input_string,
+            file_count,
+            limit,
+            ..
+        } => format!(
+            "TooManyFiles '{}' ({} > {})",
+            input_string, file_count, limit
+        ),
+        InputResolution::Excluded {
+            input_string,
+            file_count,
+            ..
+        } => format!("Excluded '{}' ({} files)", input_string, file_count),
+        InputResolution::SearchStopped {
+            input_string,
+            entries,
+            reason,
+        } => format!(
+            "SearchStopped '{}' ({} entries; {:?})",
+            input_string, entries, reason
+        ),
+        InputResolution::NotFound { input_string } => format!("NotFound '{}'", input_string),
+        InputResolution::PathDoesNotExist {
+            input_string,
+            path_tried,
+        } => format!(
+            "PathDoesNotExist '{}' ({})",
+            input_string,
+            path_tried.display()
+        ),
+    }
+}
+
+/// Makes output independent of the machine it ran on: the fixture root is
+/// replaced with `<root>`, Windows separators become `/`, and line endings
+/// are normalized to `\n`.
+pub fn normalize(text: &str, root: &Path) -> String {
+    let root_str = root.to_string_lossy();
+    // `{:?}`-formatted paths escape backslashes, so replace that form first.
+    let debug_root_str = root_str.replace('\\', "\\\\");
+    let normalized = text
+        .replace("\r\n", "\n")
+        .replace(&debug_root_str, "<root>")
+        .replace(root_str.as_ref(), "<root>");
+    if cfg!(windows) {
+        normalized.replace("\\\\", "/").replace('\\', "/")
+    } else {
+        normalized
+    }
+}
+
+/// Compares `actual` against `src/snapshots/<name>.snap`, or writes it when
+/// `CTX_PICK_UPDATE_SNAPSHOTS` is set.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src")
+        .join("snapshots")
+        .join(format!("{}.snap", name));
+
+    if std::env::var_os("CTX_PICK_UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).expect("failed to create snapshot dir");
+        fs::write(&path, actual).expect("failed to write snapshot");
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {:?}; rerun with CTX_PICK_UPDATE_SNAPSHOTS=1 to create it.\n\nactual:\n{}",
+            path, actual
+        )
+    });
+    let expected = expected.replace("\r\n", "\n");
+    if expected != actual {
+        panic!(
+            "snapshot {:?} does not match; rerun with CTX_PICK_UPDATE_SNAPSHOTS=1 to accept.\n\n--- expected ---\n{}\n--- actual ---\n{}",
+            path, expected, actual
+        );
+    }
+}
+
+mod scenarios {
+    use super::*;
+
+    const MAIN_RS: &str = "fn main() {\n    println!(\"hi\");\n}\n";
+    const LIB_RS: &str = "/// Adds.\npub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n";
+
+    fn sample_tree() -> Fixture {
+        Fixture::new(&[
+            ("src/main.rs", MAIN_RS),
+            ("src/lib.rs", LIB_RS),
+            (
+                "src/util/strings.py",
+                "def shout(s):\n    return s.upper()\n",
+            ),
+            ("docs/notes.txt", "some notes\n"),
+        ])
+    }
+
+    #[test]
+    fn direct_file() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "direct_file",
+            &run_pipeline(&fx, &["src/main.rs"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn directory_expansion() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "directory_expansion",
+            &run_pipeline(&fx, &["src/util"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn glob_match() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "glob_match",
+            &run_pipeline(&fx, &["src/*.rs"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn glob_without_matches() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "glob_without_matches",
+            &run_pipeline(&fx, &["*.go"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn glob_under_missing_directory() {
+        let fx = sample_tree();
+        let config = fx.config();
+        file_resolver::take_skipped_walks();
+        let resolution = file_resolver::resolve_input_string("missing/**/*.rs", &config);
+        assert!(matches!(resolution, InputResolution::NotFound { .. }));
+        assert!(file_resolver::take_skipped_walks().is_empty());
+    }
+
+    #[test]
+    fn invalid_glob() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "invalid_glob",
+            &run_pipeline(&fx, &["src/[*.rs"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn fuzzy_unique() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "fuzzy_unique",
+            &run_pipeline(&fx, &["strings"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn fuzzy_ambiguous() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "fuzzy_ambiguous",
+            &run_pipeline(&fx, &[".rs"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn binary_files_are_skipped() {
+        let fx = Fixture::new(&[
+            ("assets/logo.png", "\u{89}PNG\r\n\u{1a}\n\0\0\0\rIHDR"),
+            ("assets/notes.txt", "Logo is 64x64.\n"),
+        ]);
+        assert!(crate::file_reader::is_binary(&fx.root().join("assets/logo.png")).unwrap());
+        assert!(!crate::file_reader::is_binary(&fx.root().join("assets/notes.txt")).unwrap());
+        assert_eq!(
+            run_pipeline(&fx, &["assets/*"], &RunOptions::default()),
+            "== resolutions ==\nSuccess [assets/logo.png, assets/notes.txt]\n\n== warnings ==\nWarning: Skipping binary file \"assets/logo.png\".\n\n== markdown ==\nassets/notes.txt\n```txt\nLogo is 64x64.\n```\n\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn colliding_headers_are_disambiguated() {
+        use crate::types::ResolvedFile;
+
+        let fx = Fixture::new(&[
+            ("app/pkg/lib.rs", "pub fn app() {}\n"),
+            ("app/main.rs", "fn main() {}\n"),
+            ("vendor/pkg/lib.rs", "pub fn vendored() {}\n"),
+        ]);
+        // Two roots, one reached through a symlink, each holding a `pkg/lib.rs`.
+        std::os::unix::fs::symlink(fx.root().join("vendor"), fx.root().join("deps"))
+            .expect("symlink should be created");
+        let resolve = |root: &str, path: &str| {
+            let root = fx.root().join(root);
+            let canonical = fs::canonicalize(root.join(path)).expect("fixture file exists");
+            let display = pathdiff::diff_paths(&canonical, fs::canonicalize(&root).unwrap());
+            (ResolvedFile::new(display.unwrap(), canonical), None)
+        };
+        let files = [
+            resolve("app", "pkg/lib.rs"),
+            resolve("app", "main.rs"),
+            resolve("deps", "pkg/lib.rs"),
+        ];
+        let mut contexts = crate::generate_file_contexts(&files, &Default::default());
+        let collisions = crate::disambiguate_headers(&files, &mut contexts);
+        let headers: Vec<_> = contexts.iter().map(|c| c.display_path.as_str()).collect();
+        assert_eq!(headers, ["app/pkg/lib.rs", "main.rs", "vendor/pkg/lib.rs"]);
+
+        console::set_colors_enabled(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .with_header_collisions(collisions)
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        assert!(String::from_utf8_lossy(&buffer).contains(
+            "Same header for different files: pkg/lib.rs (shown as app/pkg/lib.rs, vendor/pkg/lib.rs)"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn identical_skeletons_are_collapsed() {
+        use crate::types::FileContext;
+
+        let generated = |name: &str| {
+            format!(
+                "// Generated; do not edit.\npub struct Message {{\n    pub id: u64,\n}}\n\npub fn decode(bytes: &[u8]) -> Message {{\n    Message {{ id: bytes.len() as u64 /* {} */ }}\n}}\n",
+                name
+            )
+        };
+        let (a, b, c) = (generated("a"), generated("b"), generated("c"));
+        let fx = Fixture::new(&[
+            ("gen/a.rs", &a),
+            ("gen/b.rs", &b),
+            ("gen/c.rs", &c),
+            ("src/lib.rs", "pub fn handwritten() {}\n"),
+        ]);
+        assert_snapshot(
+            "identical_skeletons_are_collapsed",
+            &run_pipeline(
+                &fx,
+                &["gen/a.rs", "src/lib.rs", "gen/b.rs", "gen/c.rs"],
+                &RunOptions {
+                    depth: Some(2),
+                    dedup_skeletons: true,
+                    ..RunOptions::default()
+                },
+            ),
+        );
+
+        let skeleton = |path: &str, content: &str| {
+            FileContext::new(path.to_string(), content.to_string(), None, Some(1))
+        };
+        let contexts = crate::dedup_skeletons(
+            vec![
+                skeleton("a.rs", "fn a"),
+                skeleton("b.rs", "fn a"),
+                skeleton("c.rs", "fn c"),
+                skeleton("d.rs", "fn c"),
+                skeleton("e.rs", "fn c"),
+                FileContext::new("f.rs".to_string(), "fn a".to_string(), None, None),
+            ],
+            false,
+        );
+        let paths: Vec<_> = contexts
+            .iter()
+            .map(|context| (context.display_path.as_str(), context.file_count))
+            .collect();
+        assert_eq!(
+            paths,
+            [("a.rs, b.rs", 2), ("c.rs, d.rs, e.rs", 3), ("f.rs", 1)]
+        );
+
+        // Copies that differ only in trailing whitespace and line endings
+        // are collapsed only when asked.
+        let copies = || {
+            vec![
+                skeleton("vendor/a/x.rs", "fn x() {}\nfn y() {}\n"),
+                skeleton("vendor/b/x.rs", "fn x() {}  \r\nfn y() {}\r\n\n"),
+                skeleton("vendor/c/x.rs", "fn x()  {}\nfn y() {}\n"),
+            ]
+        };
+        let paths = |contexts: Vec<FileContext>| -> Vec<String> {
+            contexts
+                .into_iter()
+                .map(|context| context.display_path)
+                .collect()
+        };
+        assert_eq!(
+            paths(crate::dedup_skeletons(copies(), false)),
+            ["vendor/a/x.rs", "vendor/b/x.rs", "vendor/c/x.rs"]
+        );
+        let collapsed = crate::dedup_skeletons(copies(), true);
+        assert_eq!(collapsed[0].content, "fn x() {}\nfn y() {}\n");
+        assert_eq!(
+            paths(collapsed),
+            ["vendor/a/x.rs, vendor/b/x.rs", "vendor/c/x.rs"]
+        );
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        let summary = String::from_utf8_lossy(&buffer);
+        assert!(
+            summary.contains("Identical skeletons: 5 files collapsed into 2 blocks\n"),
+            "{}",
+            summary
+        );
+    }
+
+    #[test]
+    fn resolve_only_writes_terminated_paths() {
+        let fx = Fixture::new(&[("src/a.rs", "fn a() {}\n"), ("src/b.rs", "fn b() {}\n")]);
+        let config = fx.config();
+        let files = match file_resolver::resolve_input_string("src/*.rs", &config) {
+            InputResolution::Success(files) => files,
+            other => panic!("glob should resolve: {:?}", other),
+        };
+        let written = |style, terminator| {
+            let mut buffer: Vec<u8> = Vec::new();
+            crate::write_paths(&mut buffer, &files, style, terminator)
+                .expect("writing to a Vec cannot fail");
+            String::from_utf8(buffer).expect("fixture paths are UTF-8")
+        };
+        assert_eq!(
+            written(crate::PathStyle::Display, b'\0'),
+            "src/a.rs\0src/b.rs\0"
+        );
+        assert_eq!(
+            written(crate::PathStyle::Display, b'\n'),
+            "src/a.rs\nsrc/b.rs\n"
+        );
+        let root = fx.root().canonicalize().expect("fixture root exists");
+        assert_eq!(
+            written(crate::PathStyle::Canonical, b'\n'),
+            format!(
+                "{}\n{}\n",
+                root.join("src/a.rs").display(),
+                root.join("src/b.rs").display()
+            )
+        );
+    }
+
+    #[test]
+    fn copy_paths_only_reports_the_copy_or_the_fallback() {
+        console::set_colors_enabled(false);
+        let written = |result: &Result<(), arboard::Error>| {
+            let mut buffer: Vec<u8> = Vec::new();
+            DisplayManager::new()
+                .write_paths_copied(&mut buffer, 2, result)
+                .expect("writing to a Vec cannot fail");
+            String::from_utf8(buffer).expect("summary is UTF-8")
+        };
+        assert_eq!(written(&Ok(())), "✅ Copied 2 file paths to clipboard.\n");
+        let failed = written(&Err(arboard::Error::ClipboardNotSupported));
+        assert!(
+            failed.starts_with("⚠️ Failed to copy to clipboard.\n"),
+            "{}",
+            failed
+        );
+        assert!(
+            failed.ends_with("The paths will be printed to stdout as a fallback.\n"),
+            "{}",
+            failed
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn slices_are_dedented() {
+        let fx = Fixture::new(&[
+            (
+                "src/shapes.rs",
+                "mod shapes {\n    impl Circle {\n        fn area(&self) -> f64 {\n            3.14 * self.r * self.r\n\n        }\n    }\n}\n",
+            ),
+            (
+                "src/tabs.go",
+                "func main() {\n\tif ok {\n\t\trun()\n\t}\n}\n",
+            ),
+            (
+                "src/mixed.py",
+                "class A:\n\t    def f(self):\n\t    \treturn 1\n",
+            ),
+        ]);
+        let inputs = [
+            "shapes.rs:3-6",
+            "shapes.rs:2-7",
+            "shapes.rs",
+            "tabs.go:3-3",
+            "mixed.py:2-3",
+        ];
+        assert_snapshot(
+            "slices_dedented",
+            &format!(
+                "{}\n== --keep-indent ==\n{}",
+                run_pipeline(&fx, &inputs, &RunOptions::default()),
+                run_pipeline(
+                    &fx,
+                    &inputs[..1],
+                    &RunOptions {
+                        keep_indent: true,
+                        ..RunOptions::default()
+                    }
+                )
+            ),
+        );
+
+        // `--context-around-symbol` reads its definition as a line range.
+        let config = fx.config();
+        let files = match file_resolver::resolve_symbol_context("area", 0, None, &config) {
+            InputResolution::Success(files) => files,
+            other => panic!("area should resolve: {:?}", other),
+        };
+        let files: Vec<_> = files.into_iter().map(|file| (file, None)).collect();
+        let contexts = crate::generate_file_contexts(
+            &files,
+            &crate::symbol_extractor::SkeletonOptions::default(),
+        );
+        assert_eq!(
+            contexts[0].display_path,
+            "src/shapes.rs:3-6 (indentation reduced by 8 spaces)"
+        );
+        assert!(
+            contexts[0].content.starts_with("fn area"),
+            "{}",
+            contexts[0].content
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn context_around_symbol_selects_the_padded_definition() {
+        let fx = Fixture::new(&[
+            (
+                "src/math.rs",
+                "use std::fmt;\n\n/// Adds.\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn sub(a: i32, b: i32) -> i32 {\n    a - b\n}\n",
+            ),
+            ("src/other.rs", "fn sub() {}\n"),
+            ("lib/parse.py", "def parse(text):\n    return text\n"),
+        ]);
+        let config = fx.config();
+        let resolve = |symbol, context_lines, scope: Option<&str>| {
+            let scope =
+                scope.map(
+                    |input| match file_resolver::resolve_input_string(input, &config) {
+                        InputResolution::Success(files) => files,
+                        other => panic!("{} should resolve: {:?}", input, other),
+                    },
+                );
+            match file_resolver::resolve_symbol_context(
+                symbol,
+                context_lines,
+                scope.as_deref(),
+                &config,
+            ) {
+                InputResolution::Success(files) => files
+                    .iter()
+                    .map(|file| crate::display_path_for(file, None))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                InputResolution::Ambiguous { candidates, .. } => candidates
+                    .iter()
+                    .map(|candidate| candidate.display_path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                other => format!("{:?}", other),
+            }
+        };
+        // The definition spans lines 4-6; padding stops at the file's ends.
+        assert_eq!(resolve("add", 0, None), "src/math.rs:4-6");
+        assert_eq!(resolve("add", 2, None), "src/math.rs:2-8");
+        assert_eq!(resolve("add", 10, None), "src/math.rs:1-10");
+        assert_eq!(resolve("parse", 1, None), "lib/parse.py:1-2");
+        // A name defined twice is ambiguous, unless the inputs narrow it down.
+        assert_eq!(resolve("sub", 1, None), "src/math.rs:8, src/other.rs:1");
+        assert_eq!(resolve("sub", 1, Some("src/math.rs")), "src/math.rs:7-10");
+    }
+
+    #[test]
+    fn ambiguous_candidates_are_ordered_by_proximity() {
+        let fx = Fixture::new(&[
+            ("crates/app/src/lib.rs", "fn app() {}\n"),
+            ("crates/resolver/lib.rs", "fn shallow() {}\n"),
+            ("crates/resolver/src/lib.rs", "fn resolver() {}\n"),
+            ("crates/zeta/lib.rs", "fn zeta() {}\n"),
+            ("lib.rs", "fn root() {}\n"),
+            ("tools/a/lib.rs", "fn tool() {}\n"),
+        ]);
+        let candidates = |invocation_subdir| {
+            let rendered = run_pipeline(
+                &fx,
+                &["lib"],
+                &RunOptions {
+                    invocation_subdir,
+                    ..RunOptions::default()
+                },
+            );
+            rendered.lines().nth(1).unwrap_or_default().to_string()
+        };
+        // Under the invocation directory first, then its siblings, then the
+        // rest; shallower first within each.
+        assert_eq!(
+            candidates(Some("crates/resolver")),
+            "Ambiguous 'lib' [crates/resolver/lib.rs, crates/resolver/src/lib.rs, crates/zeta/lib.rs, crates/app/src/lib.rs, lib.rs, tools/a/lib.rs]"
+        );
+        // From the root, that's just shallowest first.
+        assert_eq!(
+            candidates(None),
+            "Ambiguous 'lib' [lib.rs, crates/resolver/lib.rs, crates/zeta/lib.rs, tools/a/lib.rs, crates/app/src/lib.rs, crates/resolver/src/lib.rs]"
+        );
+    }
+
+    #[test]
+    fn fuzzy_inputs_can_be_scoped_to_directory_inputs() {
+        let fx = Fixture::new(&[
+            ("legacy/invoice.rs", "fn old_invoice() {}\n"),
+            ("other/payments.rs", "fn other() {}\n"),
+            ("services/auth/payments.rs", "fn auth() {}\n"),
+            ("services/billing/invoice.rs", "fn invoice() {}\n"),
+            ("services/billing/payments.rs", "fn billing() {}\n"),
+        ]);
+        fx.git(&["init", "-q"])
+            .git(&["add", "."])
+            .git(&["commit", "-q", "-m", "init"]);
+        let inputs = [
+            "services/billing",
+            "services/auth",
+            "invoice.rs",
+            "payments.rs",
+        ];
+        let run = |scope_to_inputs, at| {
+            run_pipeline(
+                &fx,
+                &inputs,
+                &RunOptions {
+                    scope_to_inputs,
+                    at,
+                    ..RunOptions::default()
+                },
+            )
+        };
+        // Unscoped, both fuzzy inputs find matches outside the named directories.
+        let unscoped = run(false, None);
+        assert!(unscoped.contains("Ambiguous 'invoice.rs' [legacy/invoice.rs,"));
+        assert!(unscoped.contains("other/payments.rs"));
+        // Scoped, the remaining ambiguity is listed per directory, in input order.
+        let scoped = run(true, None);
+        assert_snapshot("scope_to_inputs", &scoped);
+        // A revision's files are scoped the same way.
+        let resolutions = |rendered: &str| rendered.split("\n\n").next().unwrap().to_string();
+        assert_eq!(
+            resolutions(&run(true, Some("HEAD"))).replace(" @ HEAD", ""),
+            resolutions(&scoped)
+        );
+    }
+
+    #[test]
+    fn vanished_working_directory_is_reported() {
+        use crate::error::AppError;
+        use std::io;
+
+        let gone = || Err(io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(
+            Config::with_current_dir(None, gone()),
+            Err(AppError::WorkingDirectoryMissing(_))
+        ));
+
+        // An absolute `--cwd` doesn't need the current directory at all.
+        let fx = Fixture::new(&[("src/lib.rs", "pub fn lib() {}\n")]);
+        let config = Config::with_current_dir(Some(fx.root()), gone()).expect("absolute --cwd");
+        assert_eq!(config.working_dir, fx.root());
+        assert!(matches!(
+            file_resolver::resolve_input_string("lib.rs", &config),
+            InputResolution::Success(_)
+        ));
+        assert!(matches!(
+            Config::with_current_dir(Some(Path::new("relative")), gone()),
+            Err(AppError::WorkingDirectoryMissing(_))
+        ));
+
+        // Deleted after the run started, it's named rather than each file failing.
+        fs::remove_dir_all(fx.root()).expect("fixture should be removable");
+        let error = config.ensure_working_dir_exists().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "The working directory {:?} no longer exists; it may have been deleted. Change to an existing directory, or pass --cwd with an absolute path.",
+                fx.root()
+            )
+        );
+    }
+
+    #[test]
+    fn ambiguous_candidates_are_capped() {
+        let fx = sample_tree();
+        let config = fx.config();
+        let resolutions = [file_resolver::resolve_input_string(".rs", &config)];
+        let report = ResolutionReport::from_resolutions(&resolutions);
+
+        console::set_colors_enabled(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .with_max_ambiguous(1)
+            .write_resolution_errors(&mut buffer, &report)
+            .expect("writing to a Vec cannot fail");
+        let rendered = String::from_utf8_lossy(&buffer);
+        assert!(
+            rendered.contains("→ src/lib.rs  (58 B, modified "),
+            "{}",
+            rendered
+        );
+        assert!(rendered.contains("1 more match."), "{}", rendered);
+        assert!(!rendered.contains("main.rs"), "{}", rendered);
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn symbol_search() {
+        let fx = Fixture::new(&[
+            ("src/lib.rs", LIB_RS),
+            ("src/main.rs", "fn main() {}\nfn shout() {}\n"),
+            (
+                "src/util/strings.py",
+                "class Loud:\n    def shout(self, s):\n        return s.upper()\n",
+            ),
+        ]);
+        assert_snapshot(
+            "symbol_search",
+            &run_pipeline(
+                &fx,
+                &["fn:add", "sym:shout", "fn:missing"],
+                &RunOptions::default(),
+            ),
+        );
+    }
+
+    #[test]
+    fn skipped_missing_inputs() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "skipped_missing_inputs",
+            &run_pipeline(
+                &fx,
+                &["src/main.rs", "nothing_like_this", "src/nope.rs"],
+                &RunOptions {
+                    skip_missing: true,
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn not_found() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "not_found",
+            &run_pipeline(&fx, &["nonexistent"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn path_does_not_exist() {
+        let fx = sample_tree();
+        let input = format!("missing{}file.rs", std::path::MAIN_SEPARATOR);
+        assert_snapshot(
+            "path_does_not_exist",
+            &run_pipeline(&fx, &[&input], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn dedup_across_inputs() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "dedup_across_inputs",
+            &run_pipeline(
+                &fx,
+                &["src/main.rs", "src/*.rs", "main"],
+                &RunOptions::default(),
+            ),
+        );
+    }
+
+    #[test]
+    fn unreadable_file() {
+        let fx = sample_tree();
+        fx.write("data.bin", &[0xff, 0xfe, 0x80]);
+        assert_snapshot(
+            "unreadable_file",
+            &run_pipeline(&fx, &["data.bin"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn skeleton_of_supported_language() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "skeleton_of_supported_language",
+            &run_pipeline(
+                &fx,
+                &["src/lib.rs"],
+                &RunOptions {
+                    depth: Some(2),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-lua")]
+    fn lua_module_skeleton() {
+        let fx = Fixture::new(&[(
+            "lua/plugin.lua",
+            "local M = {}\n\nlocal function helper(x)\n  return x * 2\nend\n\nfunction M.setup(opts)\n  vim.g.scale = helper(opts.n)\nend\n\nM.keys = {\n  normal = function() print(\"n\") end,\n}\n\nreturn M\n",
+        )]);
+        assert_snapshot(
+            "lua_module_skeleton",
+            &run_pipeline(
+                &fx,
+                &["plugin.lua"],
+                &RunOptions {
+                    depth: Some(3),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-elixir", feature = "lang-erlang"))]
+    fn elixir_and_erlang_skeletons() {
+        let fx = Fixture::new(&[
+            (
+                "lib/greeter.ex",
+                "defmodule MyApp.Greeter do\n  @moduledoc \"Greets people.\"\n\n  alias MyApp.Repo\n\n  @doc \"Says hello.\"\n  def hello(name) when is_binary(name) do\n    \"Hello, #{name}\"\n  end\n\n  defp shout(text), do: String.upcase(text)\n\n  defmodule Inner do\n    def nested, do: :ok\n  end\nend\n",
+            ),
+            (
+                "src/util.erl",
+                "-module(util).\n-export([add/2]).\n\n%% @doc Adds two numbers.\nadd(A, B) ->\n    A + B.\n",
+            ),
+            (
+                "lib/greeter.html.heex",
+                "<p class=\"greeting\"><%= @name %></p>\n",
+            ),
+        ]);
+        for depth in [1, 2] {
+            assert_snapshot(
+                &format!("elixir_and_erlang_skeletons_depth_{}", depth),
+                &run_pipeline(
+                    &fx,
+                    &["greeter.ex", "util.erl", "greeter.html.heex"],
+                    &RunOptions {
+                        depth: Some(depth),
+                        ..RunOptions::default()
+                    },
+                ),
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-zig", feature = "lang-nim"))]
+    fn zig_and_nim_skeletons() {
+        let fx = Fixture::new(&[
+            (
+                "src/math.zig",
+                "const std = @import(\"std\");\n\n/// A 2D point.\npub const Point = struct {\n    x: f32,\n    y: f32,\n\n    pub fn len(self: Point) f32 {\n        return @sqrt(self.x * self.x + self.y * self.y);\n    }\n};\n\npub fn add(a: i32, b: i32) i32 {\n    return a + b;\n}\n",
+            ),
+            (
+                "src/shapes.nim",
+                "type\n  Shape* = object\n    width*: float\n\nproc area*(s: Shape): float =\n  ## Area of the shape.\n  result = s.width * s.width\n\nfunc double(x: int): int = x * 2\n",
+            ),
+        ]);
+        assert_snapshot(
+            "zig_and_nim_skeletons",
+            &run_pipeline(
+                &fx,
+                &["math.zig", "shapes.nim"],
+                &RunOptions {
+                    depth: Some(3),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-typescript")]
+    fn vue_and_svelte_components() {
+        let fx = Fixture::new(&[
+            (
+                "src/TodoList.vue",
+                "<template>\n  <div class=\"todos\">\n    <TodoHeader :count=\"items.length\" />\n    <ul>\n      <TodoItem v-for=\"item in items\" :key=\"item.id\" :item=\"item\">\n        <template #actions><IconButton icon=\"x\" /></template>\n      </TodoItem>\n    </ul>\n    <p v-if=\"items.length > 3\">Busy!</p>\n  </div>\n</template>\n\n<script>\nimport TodoItem from './TodoItem.vue';\n\nexport default {\n  name: 'TodoList',\n  props: { items: Array },\n  methods: {\n    clear() {\n      this.$emit('clear');\n    },\n  },\n};\n</script>\n\n<style scoped>\n.todos { margin: 0; }\n</style>\n",
+            ),
+            (
+                "src/TodoItem.vue",
+                "<script setup lang=\"ts\">\nimport { computed } from 'vue';\n\nconst props = defineProps<{ item: { title: string; done: boolean } }>();\nconst label = computed(() => props.item.title.toUpperCase());\n\nfunction toggle(): void {\n  props.item.done = !props.item.done;\n}\n</script>\n\n<template>\n  <li @click=\"toggle\">{{ label }}<slot name=\"actions\" /></li>\n</template>\n",
+            ),
+            (
+                "src/Counter.svelte",
+                "<script lang=\"ts\">\n  import Button from './Button.svelte';\n  export let start = 0;\n  let count = start;\n  function increment() {\n    count += 1;\n  }\n</script>\n\n<svelte:window on:keydown={increment} />\n<main>\n  {#if count < 10}\n    <Button on:click={increment}>Clicked {count} times</Button>\n  {/if}\n</main>\n\n<style>\n  main { padding: 1em; }\n</style>\n",
+            ),
+        ]);
+        for depth in [1, 2] {
+            assert_snapshot(
+                &format!("vue_and_svelte_components_depth_{}", depth),
+                &run_pipeline(
+                    &fx,
+                    &["TodoList.vue", "TodoItem.vue", "Counter.svelte"],
+                    &RunOptions {
+                        depth: Some(depth),
+                        ..RunOptions::default()
+                    },
+                ),
+            );
+        }
+        assert_snapshot(
+            "vue_component_in_full",
+            &run_pipeline(&fx, &["TodoItem.vue"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn interface_definition_skeletons() {
+        let fx = Fixture::new(&[
+            (
+                "api/shop.proto",
+                "syntax = \"proto3\";\n\npackage shop.v1;\n\nimport \"google/protobuf/timestamp.proto\";\n\noption go_package = \"example.com/shop/v1;shopv1\";\n\n// An order and its items.\nmessage Order {\n  option (validate.disabled) = true;\n  string id = 1;\n  repeated Item items = 2 [(validate.rules).repeated.min_items = 1];\n  map<string, string> labels = 3;\n  google.protobuf.Timestamp created_at = 4 [deprecated = true];\n\n  message Item {\n    string sku = 1;\n    int32 quantity = 2;\n  }\n\n  oneof payment {\n    string card_token = 5;\n    string invoice_id = 6;\n  }\n}\n\nenum Status {\n  STATUS_UNSPECIFIED = 0;\n  STATUS_OPEN = 1;\n}\n\nservice Orders {\n  option (google.api.default_host) = \"shop.example.com\";\n\n  rpc GetOrder(GetOrderRequest) returns (Order) {\n    option (google.api.http) = { get: \"/v1/orders/{id}\" };\n  }\n  rpc WatchOrders(WatchRequest) returns (stream Order);\n}\n",
+            ),
+            (
+                "api/schema.graphql",
+                "\"\"\"\nA user of the shop.\nCan place orders.\n\"\"\"\ntype User implements Node & Entity @key(fields: \"id\") {\n  id: ID!\n  \"The display name.\"\n  name: String\n  orders(\n    \"How many to return.\"\n    first: Int = 10\n    after: String\n  ): [Order!]! @deprecated(reason: \"Use ordersConnection.\")\n}\n\nenum Role {\n  ADMIN\n  \"Can only read.\"\n  VIEWER @deprecated\n}\n\nunion SearchResult = User | Order\n\nscalar DateTime\n\ninput OrderFilter {\n  status: Status = OPEN\n  since: DateTime\n}\n\n# Queries\nquery GetUser($id: ID!) {\n  user(id: $id) {\n    name\n    avatar: profilePic(size: 64)\n    ... on Admin { permissions }\n  }\n}\n",
+            ),
+            (
+                "api/petstore.yaml",
+                "openapi: 3.0.3\ninfo:\n  title: Petstore\n  version: 1.0.0\n  description: |\n    A sample API.\n    With a long description: really.\npaths:\n  /pets:\n    get:\n      operationId: listPets\n      summary: List all pets\n      parameters:\n        - name: limit\n          in: query\n          schema:\n            type: integer\n    post:\n      operationId: createPet\n      summary: Create a pet\n  \"/pets/{petId}\":\n    parameters:\n      - $ref: '#/components/parameters/PetId'\n    get:\n      operationId: showPetById # the main one\ncomponents:\n  schemas:\n    Pet:\n      type: object\n      required: [id, name]\n      properties:\n        id:\n          type: integer\n        name:\n          type: string\n        owner:\n          $ref: '#/components/schemas/User'\n        tags:\n          type: array\n          items:\n            type: string\n    Error:\n      allOf:\n        - $ref: '#/components/schemas/Base'\n",
+            ),
+            (
+                "api/legacy.json",
+                "{\"swagger\": \"2.0\", \"info\": {\"title\": \"Legacy\", \"version\": \"0.1\"}, \"paths\": {\"/users\": {\"get\": {\"operationId\": \"listUsers\"}, \"delete\": {\"operationId\": \"purge\"}}}, \"definitions\": {\"User\": {\"properties\": {\"name\": {\"type\": \"string\"}, \"age\": {\"type\": \"integer\"}}}}}\n",
+            ),
+            ("config.yaml", "name: not an api\n"),
+        ]);
+        for depth in [1, 2, 3] {
+            assert_snapshot(
+                &format!("interface_definition_skeletons_depth_{}", depth),
+                &run_pipeline(
+                    &fx,
+                    &[
+                        "shop.proto",
+                        "schema.graphql",
+                        "petstore.yaml",
+                        "legacy.json",
+                        "config.yaml",
+                    ],
+                    &RunOptions {
+                        depth: Some(depth),
+                        ..RunOptions::default()
+                    },
+                ),
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "lang-hcl")]
+    fn terraform_skeletons() {
+        let fx = Fixture::new(&[
+            (
+                "infra/main.tf",
+                "# Log storage\nresource \"aws_s3_bucket\" \"logs\" {\n  bucket = \"my-logs\"\n  tags   = { env = \"prod\" }\n\n  lifecycle {\n    prevent_destroy = true\n  }\n\n  server_side_encryption_configuration {\n    rule {\n      apply_server_side_encryption_by_default {\n        sse_algorithm = \"AES256\"\n      }\n    }\n  }\n}\n\nmodule \"vpc\" {\n  source = \"terraform-aws-modules/vpc/aws\"\n  cidr   = var.cidr\n}\n\nvariable \"cidr\" {\n  type    = string\n  default = \"10.0.0.0/16\"\n}\n\nterraform {}\n",
+            ),
+            (
+                "infra/prod.tfvars",
+                "cidr = \"10.1.0.0/16\"\ndb_password = \"hunter2\"\n",
+            ),
+        ]);
+        for depth in [2, 3, 4] {
+            assert_snapshot(
+                &format!("terraform_skeletons_depth_{}", depth),
+                &run_pipeline(
+                    &fx,
+                    &["main.tf", "prod.tfvars"],
+                    &RunOptions {
+                        depth: Some(depth),
+                        ..RunOptions::default()
+                    },
+                ),
+            );
+        }
+    }
+
+    #[test]
+    fn latex_and_rst_outlines() {
+        let fx = Fixture::new(&[
+            (
+                "docs/paper.tex",
+                "\\documentclass{article}\n\\title{Fast   Widgets}\n\\begin{document}\n\\maketitle\n\n\\section{Introduction}\n\\label{sec:intro}\nWidgets are everywhere. They are also slow, which this paper fixes.\n\n\\subsection{Background}\nPrior work (see \\cite{smith}) made widgets e.g. faster\nby 2\\% only. % TODO: check number\nLater work did more.\n\n\\subsubsection*{A detail}\nDetails matter.\n\n\\section[Results]{Experimental   Results}\n\\begin{figure}[t]\n\\centering\n\\includegraphics{plot.pdf}\n\\end{figure}\nWe measured everything twice? Yes.\n\n\\end{document}\n",
+            ),
+            (
+                "docs/guide.rst",
+                "==========\nUser Guide\n==========\n\nThis guide covers installation and usage. It is long.\n\nInstallation\n============\n\n.. code-block:: console\n\n   $ pip install widgets\n\nInstall the package\nwith pip.\n\nConfiguration\n-------------\n\nSettings live in ``widgets.toml``.\n\n.. autoclass:: widgets.Config\n\nUsage\n=====\n\nCall ``widgets.run()``.\n",
+            ),
+        ]);
+        for depth in [1, 2, 3] {
+            assert_snapshot(
+                &format!("latex_and_rst_outlines_depth_{}", depth),
+                &run_pipeline(
+                    &fx,
+                    &["paper.tex", "guide.rst"],
+                    &RunOptions {
+                        depth: Some(depth),
+                        ..RunOptions::default()
+                    },
+                ),
+            );
+        }
+        let full = run_pipeline(&fx, &["paper.tex", "guide.rst"], &RunOptions::default());
+        assert!(full.contains("docs/paper.tex\n```latex\n"), "{}", full);
+        assert!(full.contains("docs/guide.rst\n```rst\n"), "{}", full);
+    }
+
+    #[test]
+    fn only_matching_lines_are_extracted() {
+        let lines: Vec<String> = (1..=20)
+            .map(|n| match n {
+                4 | 6 | 15 => format!("line {} // TODO", n),
+                _ => format!("line {}", n),
+            })
+            .collect();
+        let fx = Fixture::new(&[
+            ("src/tasks.rs", &(lines.join("\n") + "\n")),
+            ("src/done.rs", "fn done() {}\n"),
+        ]);
+        assert_snapshot(
+            "only_matching_lines_are_extracted",
+            &run_pipeline(
+                &fx,
+                &["src"],
+                &RunOptions {
+                    grep: Some("TODO"),
+                    only_matching_lines: Some(1),
+                    manifest: true,
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn language_dispatch_follows_features() {
+        // Holds for any feature set, e.g. `cargo test --no-default-features
+        // --features lang-rust language_dispatch`, as does the rest of the
+        // suite: scenarios that need a grammar are gated on its feature.
+        let expected = [
+            ("rs", "lang-rust", cfg!(feature = "lang-rust")),
+            ("py", "lang-python", cfg!(feature = "lang-python")),
+            ("ts", "lang-typescript", cfg!(feature = "lang-typescript")),
+            ("makefile", "lang-make", cfg!(feature = "lang-make")),
+            ("mk", "lang-make", cfg!(feature = "lang-make")),
+            (
+                "dockerfile",
+                "lang-dockerfile",
+                cfg!(feature = "lang-dockerfile"),
+            ),
+            ("lua", "lang-lua", cfg!(feature = "lang-lua")),
+            ("vim", "lang-vim", cfg!(feature = "lang-vim")),
+            ("ex", "lang-elixir", cfg!(feature = "lang-elixir")),
+            ("exs", "lang-elixir", cfg!(feature = "lang-elixir")),
+            ("erl", "lang-erlang", cfg!(feature = "lang-erlang")),
+            ("hrl", "lang-erlang", cfg!(feature = "lang-erlang")),
+            ("zig", "lang-zig", cfg!(feature = "lang-zig")),
+            ("nim", "lang-nim", cfg!(feature = "lang-nim")),
+            ("tf", "lang-hcl", cfg!(feature = "lang-hcl")),
+            ("hcl", "lang-hcl", cfg!(feature = "lang-hcl")),
+        ];
+        for (key, feature, enabled) in expected {
+            match crate::symbol_extractor::check_grammar(key) {
+                Ok(()) => assert!(enabled, "{} loaded without {}", key, feature),
+                Err(e) => {
+                    assert!(!enabled, "{} failed to load: {}", key, e);
+                    assert!(
+                        e.ends_with(&format!("rebuild with --features {}", feature)),
+                        "{}",
+                        e
+                    );
+                }
+            }
+            assert_eq!(
+                crate::symbol_extractor::supported_languages().contains(&key),
+                enabled
+            );
+        }
+        assert_eq!(
+            crate::symbol_extractor::check_grammar("cobol"),
+            Err("Language support not configured for file extension: 'cobol'".to_string())
+        );
+    }
+
+    #[test]
+    fn exclude_from_drops_listed_files() {
+        let fx = Fixture::new(&[
+            ("src/a.rs", "fn a() {}\n"),
+            ("src/b.rs", "fn b() {}\n"),
+            ("src/c.rs", "fn c() {}\n"),
+        ]);
+        // Listed files match by canonical path however they're written.
+        fs::write(
+            fx.root.join("open-buffers.txt"),
+            format!(
+                "src/./a.rs\n\n{}\nsrc/gone.rs\n",
+                fx.root.join("src/c.rs").display()
+            ),
+        )
+        .expect("write list");
+        assert_snapshot(
+            "exclude_from_drops_listed_files",
+            &run_pipeline(
+                &fx,
+                &["src"],
+                &RunOptions {
+                    exclude_from: Some("open-buffers.txt"),
+                    manifest: true,
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn archives_are_read_without_unpacking() {
+        let files = [
+            (
+                "proj-main/src/main.rs",
+                "fn main() {\n    util::helper();\n}\n",
+            ),
+            ("proj-main/src/util.rs", "pub fn helper() {}\n"),
+            ("proj-main/README.md", "# Proj\n"),
+            ("proj-main/logo.png", "\u{0}PNG"),
+        ];
+        let fx = Fixture::empty();
+
+        let tar_gz = fs::File::create(fx.root().join("src.tar.gz")).unwrap();
+        let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+            tar_gz,
+            flate2::Compression::default(),
+        ));
+        let mut zip = zip::ZipWriter::new(fs::File::create(fx.root().join("src.zip")).unwrap());
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            tar.append_data(&mut header, format!("./{}", path), content.as_bytes())
+                .unwrap();
+            zip.start_file(path, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut zip, content.as_bytes()).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+        zip.finish().unwrap();
+
+        let mut rendered = String::new();
+        for archive in ["src.tar.gz", "src.zip"] {
+            rendered.push_str(&format!("=== {} ===\n", archive));
+            rendered.push_str(&run_pipeline(
+                &fx,
+                &["proj-main/src", "*/*.md", "util", "main.rs:2-2", "*/*.png"],
+                &RunOptions {
+                    archive: Some(archive),
+                    ..RunOptions::default()
+                },
+            ));
+        }
+        assert_snapshot("archives_are_read_without_unpacking", &rendered);
+    }
+
+    #[test]
+    fn diff_last_reports_added_removed_and_modified_files() {
+        console::set_colors_enabled(false);
+        let fx = Fixture::new(&[
+            ("src/a.rs", "fn a() {}\n"),
+            ("src/b.rs", "fn b() { 2 }\n"),
+            ("src/new.rs", "fn new() {}\n"),
+        ]);
+        let previous = serde_json::json!({
+            "version": 1,
+            "files": [
+                { "path": "src/a.rs", "sha256": crate::manifest::sha256_hex("fn a() {}\n") },
+                { "path": "src/b.rs", "sha256": crate::manifest::sha256_hex("fn b() { 1 }\n") },
+                { "path": "src/gone.rs", "sha256": crate::manifest::sha256_hex("") },
+            ],
+        });
+        std::fs::write(fx.root.join("last.json"), previous.to_string()).expect("write manifest");
+        assert_snapshot(
+            "diff_last_reports_added_removed_and_modified_files",
+            &run_pipeline(
+                &fx,
+                &["src"],
+                &RunOptions {
+                    manifest: true,
+                    diff_last: Some("last.json"),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn working_dir_input_respects_ignore_files() {
+        let fx = Fixture::new(&[
+            (".gitignore", "target/\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("target/debug/build.log", "compiled\n"),
+            (".cache/state", "x\n"),
+        ]);
+        assert_snapshot(
+            "working_dir_input_respects_ignore_files",
+            &run_pipeline(&fx, &["."], &RunOptions::default()),
+        );
+        // Any other directory is still taken whole.
+        assert_snapshot(
+            "ignored_directory_named_directly",
+            &run_pipeline(&fx, &["target"], &RunOptions::default()),
+        );
+    }
+
+    #[test]
+    fn dir_depth_limits_directory_expansion() {
+        let fx = Fixture::new(&[
+            ("top.rs", "fn top() {}\n"),
+            ("src/lib.rs", "fn lib() {}\n"),
+            ("src/util/mod.rs", "fn util() {}\n"),
+            ("src/util/deep/leaf.rs", "fn leaf() {}\n"),
+        ]);
+        fx.git(&["init", "-q"])
+            .git(&["add", "."])
+            .git(&["commit", "-q", "-m", "init"]);
+        let resolutions = |inputs: &[&str], dir_depth, at| {
+            let rendered = run_pipeline(
+                &fx,
+                inputs,
+                &RunOptions {
+                    dir_depth,
+                    at,
+                    ..RunOptions::default()
+                },
+            );
+            rendered
+                .split("\n== markdown ==")
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        };
+        assert_eq!(
+            resolutions(&["src"], Some(1), None),
+            "== resolutions ==\nSuccess [src/lib.rs]\n"
+        );
+        assert_eq!(
+            resolutions(&["src"], Some(2), None),
+            "== resolutions ==\nSuccess [src/lib.rs, src/util/mod.rs]\n"
+        );
+        assert_eq!(
+            resolutions(&["src"], None, None),
+            "== resolutions ==\nSuccess [src/lib.rs, src/util/deep/leaf.rs, src/util/mod.rs]\n"
+        );
+        assert_eq!(
+            resolutions(&["."], Some(1), None),
+            "== resolutions ==\nSuccess [top.rs]\n"
+        );
+        assert_eq!(
+            resolutions(&["src"], Some(2), Some("HEAD")),
+            "== resolutions ==\nSuccess [src/lib.rs @ HEAD, src/util/mod.rs @ HEAD]\n"
+        );
+        // Globs aren't directory expansions, so they're unaffected.
+        assert_eq!(
+            resolutions(&["src/**/*.rs"], Some(1), None),
+            "== resolutions ==\nSuccess [src/lib.rs, src/util/deep/leaf.rs, src/util/mod.rs]\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn globs_and_directories_follow_symlinks_alike() {
+        let fx = Fixture::new(&[
+            ("src/main.rs", "fn main() {}\n"),
+            ("shared/util.rs", "fn util() {}\n"),
+            ("shared/deep/leaf.rs", "fn leaf() {}\n"),
+        ]);
+        let link = |target: &str, link: &str| {
+            std::os::unix::fs::symlink(target, fx.root().join(link))
+                .expect("symlink should be created");
+        };
+        link("../shared", "src/linked");
+        // A loop back to its own parent is skipped, not walked until the
+        // search budget runs out.
+        link(".", "src/loop");
+        let resolutions = |inputs: &[&str], no_follow_symlinks| {
+            let rendered = run_pipeline(
+                &fx,
+                inputs,
+                &RunOptions {
+                    no_follow_symlinks,
+                    ..RunOptions::default()
+                },
+            );
+            rendered
+                .split("\n== ")
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        };
+        let followed =
+            "== resolutions ==\nSuccess [shared/deep/leaf.rs, shared/util.rs, src/main.rs]\n";
+        assert_eq!(resolutions(&["src/**/*.rs"], false), followed);
+        assert_eq!(resolutions(&["src"], false), followed);
+        assert_eq!(
+            resolutions(&["src/*/*.rs"], false),
+            "== resolutions ==\nSuccess [shared/util.rs]\n"
+        );
+
+        let unfollowed = "== resolutions ==\nSuccess [src/main.rs]\n";
+        assert_eq!(resolutions(&["src/**/*.rs"], true), unfollowed);
+        assert_eq!(resolutions(&["src"], true), unfollowed);
+        // A symlink named in the input itself is still followed.
+        assert_eq!(
+            resolutions(&["src/linked/*.rs"], true),
+            "== resolutions ==\nSuccess [shared/util.rs]\n"
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn symbols_json_lists_each_files_tags() {
+        let fx = Fixture::new(&[
+            (
+                "src/shapes.rs",
+                "pub struct Circle {\n    r: f64,\n}\n\nimpl Circle {\n    pub fn area(&self) -> f64 {\n        3.14 * self.r * self.r\n    }\n}\n",
+            ),
+            (
+                "tools/run.py",
+                "class Runner:\n    def start(self):\n        pass\n",
+            ),
+        ]);
+        assert_snapshot(
+            "symbols_json",
+            &run_pipeline(
+                &fx,
+                &["src/shapes.rs", "tools/run.py:2-3"],
+                &RunOptions {
+                    depth: Some(2),
+                    format: crate::output::OutputFormat::SymbolsJson,
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn lang_override_forces_fence_and_skeleton_language() {
+        let fx = Fixture::new(&[
+            (
+                "scripts/deploy.inc",
+                "def deploy(target):\n    return target\n",
+            ),
+            ("lib/helper.inc", "pub fn helper() -> u32 {\n    1\n}\n"),
+            ("main.py", "def main():\n    pass\n"),
+        ]);
+        assert_snapshot(
+            "lang_override_depth_1",
+            &run_pipeline(
+                &fx,
+                &["scripts", "lib", "main.py"],
+                &RunOptions {
+                    depth: Some(1),
+                    lang_override: vec!["*.inc=python", "lib/*=rust"],
+                    ..RunOptions::default()
+                },
+            ),
+        );
+        assert_snapshot(
+            "lang_override_in_full",
+            &run_pipeline(
+                &fx,
+                &["scripts/deploy.inc"],
+                &RunOptions {
+                    lang_override: vec!["*.inc=py"],
+                    ..RunOptions::default()
+                },
+            ),
+        );
+        assert_eq!(
+            crate::language::parse_override("*.inc=cobol").unwrap_err(),
+            "'cobol' is not a known language"
+        );
+        assert_eq!(
+            crate::language::parse_override("python").unwrap_err(),
+            "expected GLOB=LANG, got 'python'"
+        );
+    }
+
+    #[test]
+    fn lang_override_accepts_languages_without_a_grammar() {
+        let fx = Fixture::new(&[
+            ("notes/data.txt", "{\"retries\": 3}\n"),
+            ("web/page.inc", "<?php\nfunction page() { return 1; }\n"),
+        ]);
+        assert_snapshot(
+            "lang_override_without_grammar",
+            &run_pipeline(
+                &fx,
+                &["notes/data.txt", "web/page.inc"],
+                &RunOptions {
+                    depth: Some(1),
+                    lang_override: vec!["*.txt=json", "*.inc=php"],
+                    ..RunOptions::default()
+                },
+            ),
+        );
+        assert_snapshot(
+            "lang_override_without_grammar_in_full",
+            &run_pipeline(
+                &fx,
+                &["notes/data.txt"],
+                &RunOptions {
+                    lang_override: vec!["*.txt=json"],
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-python")]
+    fn extensionless_files_are_detected_from_content() {
+        let fx = Fixture::new(&[
+            (
+                "bin/deploy",
+                "set -euo pipefail\nif [ -z \"$1\" ]; then\n  exit 1\nfi\n",
+            ),
+            (
+                "bin/report",
+                "from pathlib import Path\n\ndef main():\n    print(Path.cwd())\n",
+            ),
+            ("bin/page", "<?php\necho 'hi';\n"),
+            ("bin/tool", "print('hi')\n# vim: set ft=python:\n"),
+            ("bin/notes", "Remember to run this before every release.\n"),
+            ("bin/mixed", "def main():\n    pass\nexport PATH=/bin\nfi\n"),
+        ]);
+        let inputs = [
+            "bin/deploy",
+            "bin/report",
+            "bin/page",
+            "bin/tool",
+            "bin/notes",
+            "bin/mixed",
+        ];
+        assert_snapshot(
+            "extensionless_languages",
+            &format!(
+                "{}\n== --depth 1 ==\n{}",
+                run_pipeline(&fx, &inputs, &RunOptions::default()),
+                run_pipeline(
+                    &fx,
+                    &["bin/report"],
+                    &RunOptions {
+                        depth: Some(1),
+                        ..RunOptions::default()
+                    }
+                )
+            ),
+        );
+    }
+
+    #[test]
+    fn hidden_files_are_included_only_when_asked_for() {
+        let fx = Fixture::new(&[
+            (".env", "TOKEN=abc\n"),
+            (".github/workflows/ci.yml", "on: push\n"),
+            (".config/app/settings.toml", "x = 1\n"),
+            (".tools/visible.rs", "fn visible() {}\n"),
+            ("src/.local.rs", "fn local() {}\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ]);
+        fx.git(&["init", "-q"])
+            .git(&["add", "."])
+            .git(&["commit", "-q", "-m", "init"]);
+        let resolution = |input: &str, hidden, at| {
+            let rendered = run_pipeline(
+                &fx,
+                &[input],
+                &RunOptions {
+                    hidden,
+                    at,
+                    ..RunOptions::default()
+                },
+            );
+            rendered.lines().nth(1).unwrap_or_default().to_string()
+        };
+        for at in [None, Some("HEAD")] {
+            let suffix = if at.is_some() { " @ HEAD" } else { "" };
+            let success = |paths: &[&str]| {
+                let paths: Vec<String> = paths
+                    .iter()
+                    .map(|path| format!("{}{}", path, suffix))
+                    .collect();
+                format!("Success [{}]", paths.join(", "))
+            };
+            // Named or globbed explicitly, hidden paths always resolve.
+            assert_eq!(
+                resolution(".github/workflows/ci.yml", false, at),
+                success(&[".github/workflows/ci.yml"])
+            );
+            assert_eq!(
+                resolution(".config/**/*.toml", false, at),
+                success(&[".config/app/settings.toml"])
+            );
+            // A directory input skips hidden entries inside it...
+            assert_eq!(resolution("src", false, at), success(&["src/main.rs"]));
+            assert_eq!(
+                resolution("src", true, at),
+                success(&["src/.local.rs", "src/main.rs"])
+            );
+            // ...unless it's hidden itself, which takes everything inside.
+            assert_eq!(
+                resolution(".tools", false, at),
+                success(&[".tools/visible.rs"])
+            );
+            // Fuzzy search skips anything under a hidden directory...
+            assert_eq!(resolution("ci.yml", false, at), "NotFound 'ci.yml'");
+            assert_eq!(resolution("visible", false, at), "NotFound 'visible'");
+            assert_eq!(
+                resolution("visible", true, at),
+                success(&[".tools/visible.rs"])
+            );
+            // ...unless the query starts with a hidden component.
+            assert_eq!(
+                resolution(".tools/vis", false, at),
+                success(&[".tools/visible.rs"])
+            );
+        }
+        // The working directory itself, walked like `--overview`.
+        assert_eq!(resolution(".", false, None), "Success [src/main.rs]");
+        assert_eq!(
+            resolution(".", true, None),
+            "Success [.config/app/settings.toml, .env, .github/workflows/ci.yml, .tools/visible.rs, src/.local.rs, src/main.rs]"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn rust_skeleton_elides_macros_and_long_docs() {
+        let fx = Fixture::new(&[(
+            "src/math.rs",
+            "//! Math helpers.\n\n/// Adds two numbers.\n///\n/// ```\n/// assert_eq!(add(1, 2), 3);\n/// ```\npub fn add(a: i64, b: i64) -> i64 {\n    a + b\n}\n\n/// Squares.\nmacro_rules! square {\n    ($x:expr) => {\n        $x * $x\n    };\n}\n",
+        )]);
+        assert_snapshot(
+            "rust_skeleton_elides_macros_and_long_docs",
+            &run_pipeline(
+                &fx,
+                &["math.rs"],
+                &RunOptions {
+                    depth: Some(6),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn crlf_line_endings_are_normalized() {
+        // `run_pipeline` output is itself CRLF-normalized for snapshots, so
+        // this checks the contexts directly.
+        let fx = Fixture::new(&[("notes.txt", "one\r\ntwo\r\n")]);
+        let config = fx.config();
+        let InputResolution::Success(files) =
+            file_resolver::resolve_input_string("notes.txt", &config)
+        else {
+            panic!("notes.txt should resolve");
+        };
+        let included: Vec<_> = files.into_iter().map(|file| (file, None)).collect();
+        let contexts = crate::generate_file_contexts(
+            &included,
+            &crate::symbol_extractor::SkeletonOptions::default(),
+        );
+        assert_eq!(contexts[0].content, "one\r\ntwo\r\n");
+        assert_eq!(crate::normalize_newlines(contexts)[0].content, "one\ntwo\n");
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn skeleton_symbols_are_capped() {
+        let fx = Fixture::new(&[(
+            "src/ops.rs",
+            "// Operations.\nfn one() {}\nfn two() {}\n/// Third.\nfn three() {}\nfn four() {}\n",
+        )]);
+        assert_snapshot(
+            "skeleton_symbols_are_capped",
+            &run_pipeline(
+                &fx,
+                &["ops.rs"],
+                &RunOptions {
+                    depth: Some(2),
+                    max_symbols: Some(2),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn skeleton_symbols_can_be_reordered() {
+        use crate::symbol_extractor::SymbolOrder;
+
+        let fx = Fixture::new(&[(
+            "src/ops.rs",
+            "use std::fmt;\n\nfn zeta() {}\n/// Builds one.\nstruct Beta;\nfn Alpha() {}\nstruct gamma;\n// Trailing note.\n",
+        )]);
+        let run = |symbol_order, format| {
+            run_pipeline(
+                &fx,
+                &["src/ops.rs"],
+                &RunOptions {
+                    depth: Some(1),
+                    symbol_order,
+                    format,
+                    ..RunOptions::default()
+                },
+            )
+        };
+        let markdown = crate::output::OutputFormat::Markdown;
+        let by_name = run(SymbolOrder::Name, markdown);
+        let by_kind = run(SymbolOrder::Kind, markdown);
+        let json = run(SymbolOrder::Name, crate::output::OutputFormat::SymbolsJson);
+        assert_snapshot(
+            "skeleton_symbols_reordered",
+            &format!("{}\n{}\n{}", by_name, by_kind, json),
+        );
+    }
+
+    /// `files_to_prompt.cxml` was written to match `files-to-prompt --cxml
+    /// src/main.rs docs/notes.txt src/util/strings.py` on the sample tree.
+    #[test]
+    fn cxml_matches_files_to_prompt() {
+        let fx = sample_tree();
+        let rendered = run_pipeline(
+            &fx,
+            &["src/main.rs", "docs/notes.txt", "src/util/strings.py"],
+            &RunOptions {
+                format: crate::output::OutputFormat::Cxml,
+                ..RunOptions::default()
+            },
+        );
+        let cxml = rendered.split_once("== cxml ==\n").expect("cxml section").1;
+        let golden = fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/snapshots/files_to_prompt.cxml"),
+        )
+        .expect("golden file");
+        assert_eq!(cxml, golden);
+    }
+
+    #[test]
+    fn chat_format_bolds_names_and_lengthens_fences() {
+        let fx = Fixture::new(&[
+            ("pkg/__init__.py", "from .core import run\n"),
+            ("docs/usage.md", "# Usage\n\n```sh\nctx-pick src\n```\n"),
+        ]);
+        assert_snapshot(
+            "chat_format",
+            &run_pipeline(
+                &fx,
+                &["pkg/__init__.py", "docs/usage.md"],
+                &RunOptions {
+                    format: crate::output::OutputFormat::Chat,
+                    anchors: true,
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn operation_summary_breaks_down_modes() {
+        use crate::types::FileContext;
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let long_file = "line\n".repeat(1204);
+        let contexts = vec![
+            FileContext::new("src/big.rs".to_string(), long_file, None, None),
+            FileContext::new("src/lib.rs".to_string(), "a\nb\n".to_string(), None, None),
+            FileContext::new(
+                "src/main.rs".to_string(),
+                "fn main ( ) { }".to_string(),
+                None,
+                Some(2),
+            ),
+        ];
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        assert_snapshot(
+            "operation_summary_breaks_down_modes",
+            &String::from_utf8_lossy(&buffer),
+        );
+    }
+
+    #[test]
+    fn summary_lists_skeleton_depths_by_language() {
+        use crate::types::FileContext;
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let skeleton = |path: &str, language: &str, depth| {
+            FileContext::new(
+                path.to_string(),
+                "fn f ( )".to_string(),
+                Some(language.to_string()),
+                Some(depth),
+            )
+        };
+        let contexts = vec![
+            skeleton("src/a.rs", "rs", 2),
+            skeleton("src/b.rs", "rs", 4),
+            skeleton("lib/c.ex", "ex", 3),
+            FileContext::new("README.md".to_string(), "hi\n".to_string(), None, None),
+        ];
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        let summary = String::from_utf8_lossy(&buffer);
+        assert!(
+            summary.contains("Skeleton depth: ex=3, rs=2/4\n"),
+            "{}",
+            summary
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn files_without_a_skeleton_are_omitted() {
+        let fx = Fixture::new(&[
+            ("src/lib.rs", "fn lib() {\n    1\n}\n"),
+            ("notes.txt", &"a long note\n".repeat(500)),
+        ]);
+        let config = fx.config();
+        let files: Vec<_> = ["src/lib.rs", "notes.txt"]
+            .iter()
+            .map(
+                |input| match file_resolver::resolve_input_string(input, &config) {
+                    InputResolution::Success(files) => (files[0].clone(), Some(1)),
+                    other => panic!("{} should resolve: {:?}", input, other),
+                },
+            )
+            .collect();
+        let skeleton_options = crate::symbol_extractor::SkeletonOptions {
+            full_content_fallback: false,
+            ..crate::symbol_extractor::SkeletonOptions::default()
+        };
+
+        let mut contexts = Vec::new();
+        let mut omitted = Vec::new();
+        for result in crate::read_file_contexts(&files, &skeleton_options) {
+            match result {
+                Ok(context) => contexts.push(context),
+                Err(crate::ContextFailure::NoSkeleton(path, error)) => omitted.push((path, error)),
+                Err(
+                    crate::ContextFailure::Unreadable(path, _)
+                    | crate::ContextFailure::Crashed(path, _)
+                    | crate::ContextFailure::Removed(path)
+                    | crate::ContextFailure::Empty(path),
+                ) => panic!("{} is readable and non-empty", path),
+            }
+        }
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &omitted, &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        assert_snapshot(
+            "files_without_a_skeleton_are_omitted",
+            &String::from_utf8_lossy(&buffer),
+        );
+    }
+
+    #[test]
+    fn a_panic_costs_only_the_file_that_caused_it() {
+        let fx = Fixture::new(&[
+            ("src/a.rs", "fn a() {}\n"),
+            ("src/bad.rs", "fn bad() {}\n"),
+            ("src/c.rs", "fn c() {}\n"),
+        ]);
+        let config = fx.config();
+        let InputResolution::Success(files) = file_resolver::resolve_input_string("src", &config)
+        else {
+            panic!("src should resolve");
+        };
+        let files: Vec<_> = files.into_iter().map(|file| (file, Some(1))).collect();
+
+        let mut contexts = Vec::new();
+        let mut failures = Vec::new();
+        // Stands in for a grammar that panics on malformed input.
+        let options = Default::default();
+        let read = |file: &crate::types::ResolvedFile, depth| {
+            if file.display_path().ends_with("bad.rs") {
+                panic!("the grammar gave up");
+            }
+            crate::read_file_context(file, depth, &options)
+        };
+        for result in crate::read_file_contexts_with(&files, read) {
+            match result {
+                Ok(context) => contexts.push(context.display_path),
+                Err(crate::ContextFailure::Crashed(path, error)) => failures.push((path, error)),
+                Err(other) => panic!("unexpected failure: {:?}", other),
+            }
+        }
+        assert_eq!(contexts, ["src/a.rs", "src/c.rs"]);
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_read_errors(&mut buffer, &failures, true)
+            .expect("writing to a Vec cannot fail");
+        assert_eq!(
+            String::from_utf8_lossy(&buffer),
+            "⚠️ Skipped 1 file that could not be read or processed:\n  \
+             • src/bad.rs (ctx-pick crashed while processing it: the grammar gave up)\n\n"
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-typescript"))]
+    fn skeletons_of_files_with_syntax_errors_are_flagged() {
+        let fx = Fixture::new(&[
+            ("src/ok.rs", "fn ok() {}\n"),
+            (
+                "src/half.rs",
+                "fn done() {}\n\nfn half(x: u32 {\n    x +\n}\n\nfn after() {}\n",
+            ),
+            (
+                "src/Broken.vue",
+                "<template>\n  <div />\n</template>\n<script setup>\nconst a = ;\n</script>\n",
+            ),
+        ]);
+        let config = fx.config();
+        let files: Vec<_> = ["src/ok.rs", "src/half.rs", "src/Broken.vue"]
+            .iter()
+            .map(
+                |input| match file_resolver::resolve_input_string(input, &config) {
+                    InputResolution::Success(files) => (files[0].clone(), Some(1)),
+                    other => panic!("{} should resolve: {:?}", input, other),
+                },
+            )
+            .collect();
+        let contexts: Vec<_> = crate::read_file_contexts(&files, &Default::default())
+            .into_iter()
+            .map(|result| result.expect("each file has a skeleton"))
+            .collect();
+        let error_lines: Vec<_> = contexts
+            .iter()
+            .map(|context| context.syntax_error_line)
+            .collect();
+        assert_eq!(error_lines, [None, Some(3), Some(5)]);
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        assert_snapshot(
+            "skeletons_of_files_with_syntax_errors_are_flagged",
+            &String::from_utf8_lossy(&buffer),
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_are_shown_in_summary_and_porcelain() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fx = Fixture::new(&[
+            ("deploy.sh", "#!/bin/sh\necho deploying\n"),
+            ("README.md", "# Deploy\n"),
+        ]);
+        for (path, mode) in [("deploy.sh", 0o755), ("README.md", 0o444)] {
+            std::fs::set_permissions(fx.root().join(path), std::fs::Permissions::from_mode(mode))
+                .expect("fixture permissions should be settable");
+        }
+        let config = fx.config();
+        let files: Vec<_> = ["deploy.sh", "README.md"]
+            .iter()
+            .map(
+                |input| match file_resolver::resolve_input_string(input, &config) {
+                    InputResolution::Success(files) => (files[0].clone(), None),
+                    other => panic!("{} should resolve: {:?}", input, other),
+                },
+            )
+            .collect();
+        let contexts: Vec<_> = crate::read_file_contexts(&files, &Default::default())
+            .into_iter()
+            .map(|result| result.expect("each file is readable"))
+            .collect();
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .with_permissions(true)
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        let summary = String::from_utf8_lossy(&buffer);
+        assert!(
+            summary.contains("deploy.sh\n    📄 2 lines\n    🔐 rwxr-xr-x (executable)\n"),
+            "{}",
+            summary
+        );
+        assert!(
+            summary.contains("README.md\n    📄 1 lines\n    🔐 r--r--r--\n"),
+            "{}",
+            summary
+        );
+
+        let porcelain = crate::manifest::RunSummary::new(2, "stdout", "")
+            .with_permissions(&contexts)
+            .to_json_line();
+        assert!(
+            porcelain.ends_with(
+                "\"permissions\":[{\"path\":\"deploy.sh\",\"mode\":\"0755\",\"executable\":true,\"readonly\":false},{\"path\":\"README.md\",\"mode\":\"0444\",\"executable\":false,\"readonly\":true}]}"
+            ),
+            "{}",
+            porcelain
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn summary_annotates_detected_languages() {
+        let fx = Fixture::new(&[
+            ("src/lib.rs", "pub fn lib() {}\n"),
+            ("data.json", "{}\n"),
+            ("page.heex", "<div></div>\n"),
+            ("NOTES", "todo\n"),
+        ]);
+        let config = fx.config();
+        let files: Vec<_> = ["src/lib.rs", "data.json", "page.heex", "NOTES"]
+            .iter()
+            .map(
+                |input| match file_resolver::resolve_input_string(input, &config) {
+                    InputResolution::Success(files) => (files[0].clone(), None),
+                    other => panic!("{} should resolve: {:?}", input, other),
+                },
+            )
+            .collect();
+        let contexts: Vec<_> = crate::read_file_contexts(&files, &Default::default())
+            .into_iter()
+            .map(|result| result.expect("each file is readable"))
+            .collect();
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .with_languages(true)
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        let summary = String::from_utf8_lossy(&buffer);
+        for expected in [
+            "src/lib.rs\n    📄 1 lines\n    🔤 rs (skeleton supported)\n",
+            "data.json\n    📄 1 lines\n    🔤 json (no skeleton; included in full)\n",
+            "page.heex\n    📄 1 lines\n    🔤 heex (always included in full)\n",
+            "NOTES\n    📄 1 lines\n    🔤 unknown language (no skeleton; included in full)\n",
+        ] {
+            assert!(summary.contains(expected), "{}", summary);
+        }
+    }
+
+    #[test]
+    fn footer_records_the_run_and_counts_in_the_totals() {
+        use crate::output::{Footer, OutputFormat};
+        use crate::types::FileContext;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let footer = Footer {
+            command: "ctx-pick --depth 2 'src/**/*.rs' '$HOME/notes.md'".to_string(),
+            working_dir: PathBuf::from("/work/proj"),
+            dir_configs: vec![PathBuf::from("src/.ctx-pick.toml")],
+            timestamp: UNIX_EPOCH + Duration::from_secs(1_709_251_199),
+        };
+        let version = env!("CARGO_PKG_VERSION");
+        let markdown = footer.render(OutputFormat::Markdown);
+        assert_eq!(
+            markdown,
+            format!(
+                "---\nGenerated by ctx-pick {} at 2024-02-29T23:59:59Z\nCommand: ctx-pick --depth 2 'src/**/*.rs' '$HOME/notes.md'\nWorking directory: /work/proj\nDirectory configs: src/.ctx-pick.toml\n",
+                version
+            )
+        );
+        assert!(
+            footer
+                .render(OutputFormat::Cxml)
+                .starts_with(&format!("<!--\nGenerated by ctx-pick {} at", version))
+        );
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let contexts = [FileContext::new(
+            "src/lib.rs".to_string(),
+            "fn lib() {}\n".to_string(),
+            Some("rs".to_string()),
+            None,
+        )];
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .with_footer(Some(&markdown))
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        let summary = String::from_utf8_lossy(&buffer);
+        let footer_chars = markdown.chars().count();
+        assert!(
+            summary.contains(&format!(
+                "1 file full (1 lines); 6 lines, {} characters in total with the footer\n",
+                contexts[0].char_count + footer_chars
+            )),
+            "{}",
+            summary
+        );
+        assert!(
+            summary.contains("src/lib.rs\n    📄 1 lines\n"),
+            "{}",
+            summary
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn editor_artifacts_warn_of_unsaved_edits() {
+        use crate::unsaved;
+        use std::time::{Duration, SystemTime};
+
+        let fx = Fixture::new(&[
+            ("src/lib.rs", "pub fn lib() {}\n"),
+            ("src/.lib.rs.swp", "b0VIM 9.0"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/#main.rs#", "fn main() { todo!() }\n"),
+            ("src/stale.rs", "fn stale() {}\n"),
+            ("src/stale.rs~", "fn older() {}\n"),
+            ("src/fresh.rs", "fn fresh() {}\n"),
+            ("src/fresh.rs~", "fn newer() {}\n"),
+            ("src/clean.rs", "fn clean() {}\n"),
+        ]);
+        // Emacs locks are symlinks to a target that doesn't exist.
+        std::os::unix::fs::symlink("user@host.1234:1700000000", fx.root().join("src/.#lib.rs"))
+            .expect("symlink should be created");
+        let set_modified = |path: &str, seconds_ago: u64| {
+            fs::File::options()
+                .write(true)
+                .open(fx.root().join(path))
+                .and_then(|file| {
+                    file.set_modified(SystemTime::now() - Duration::from_secs(seconds_ago))
+                })
+                .expect("fixture mtime should be settable");
+        };
+        // A backup older than its file is left over from the last save.
+        set_modified("src/stale.rs~", 600);
+        set_modified("src/fresh.rs", 600);
+
+        let found = |path: &str| -> Vec<String> {
+            unsaved::artifacts_for(&fx.root().join(path))
+                .iter()
+                .map(unsaved::EditorArtifact::describe)
+                .collect()
+        };
+        assert_eq!(
+            found("src/lib.rs"),
+            ["Vim swap file .lib.rs.swp", "Emacs lock file .#lib.rs"]
+        );
+        assert_eq!(found("src/main.rs"), ["Emacs auto-save file #main.rs#"]);
+        assert_eq!(found("src/fresh.rs"), ["an editor backup fresh.rs~"]);
+        assert!(found("src/stale.rs").is_empty());
+        assert!(found("src/clean.rs").is_empty());
+
+        let config = fx.config();
+        let files = match file_resolver::resolve_input_string("src/*.rs", &config) {
+            InputResolution::Success(files) => files,
+            other => panic!("glob should resolve: {:?}", other),
+        };
+        let files: Vec<_> = files.into_iter().map(|file| (file, None)).collect();
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .with_unsaved_edits(crate::unsaved_edits(&files))
+            .write_unsaved_edits(&mut buffer)
+            .expect("writing to a Vec cannot fail");
+        assert_eq!(
+            String::from_utf8_lossy(&buffer),
+            "⚠️ 3 files may have unsaved edits in an editor, so what was included may be out of date:\n  \
+             • src/fresh.rs (an editor backup fresh.rs~)\n  \
+             • src/lib.rs (Vim swap file .lib.rs.swp, Emacs lock file .#lib.rs)\n  \
+             • src/main.rs (Emacs auto-save file #main.rs#)\n"
+        );
+
+        let parse = |text| {
+            crate::user_config::parse(text, Path::new("/")).map(|config| config.warn_unsaved_edits)
+        };
+        assert_eq!(parse(""), Ok(true));
+        assert_eq!(
+            parse("warn_unsaved_edits = false\n[grammars.sol]\nlibrary = \"x.so\"\n"),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn max_total_lines_stops_at_the_first_file_over_budget() {
+        use crate::types::FileContext;
+
+        let context = |path: &str, lines: usize| {
+            FileContext::new(path.to_string(), "line\n".repeat(lines), None, None)
+        };
+        let contexts = [
+            context("a.rs", 4),
+            context("b.rs", 3),
+            context("c.rs", 5),
+            context("d.rs", 1),
+        ];
+        assert_eq!(crate::within_line_budget(&contexts, 100), 4);
+        assert_eq!(crate::within_line_budget(&contexts, 7), 2);
+        // A later file that would still fit isn't taken once one hasn't.
+        assert_eq!(crate::within_line_budget(&contexts, 8), 2);
+        assert_eq!(crate::within_line_budget(&contexts, 3), 0);
+
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .with_line_budget(
+                Some(8),
+                vec![("c.rs".to_string(), 5), ("d.rs".to_string(), 1)],
+            )
+            .write_operation_summary_and_preview(&mut buffer, &contexts[..2], &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        let summary = String::from_utf8_lossy(&buffer);
+        assert!(
+            summary.contains(
+                "✂️ Left out 2 files to stay within --max-total-lines 8:\n  • c.rs (5 lines)\n  • d.rs (1 lines)\n"
+            ),
+            "{}",
+            summary
+        );
+    }
+
+    #[test]
+    fn modes_fill_in_only_what_the_command_line_leaves_unset() {
+        use clap::{CommandFactory, FromArgMatches};
+        let parse = |args: &[&str]| {
+            let matches = crate::Cli::command()
+                .try_get_matches_from(std::iter::once("ctx-pick").chain(args.iter().copied()))
+                .unwrap();
+            let mut cli = crate::Cli::from_arg_matches(&matches).unwrap();
+            cli.mode.unwrap().apply(&mut cli, &matches);
+            cli
+        };
+
+        let skim = parse(&["--mode", "skim", "a.rs"]);
+        assert_eq!((skim.depth, skim.compact), (Some(3), true));
+        let skim = parse(&["--mode", "skim", "--depth", "0", "a.rs"]);
+        assert_eq!((skim.depth, skim.compact), (Some(0), true));
+        let skim = parse(&["--mode", "skim", "--no-compact", "a.rs"]);
+        assert_eq!((skim.depth, skim.compact), (Some(3), false));
+        let skim = parse(&["--mode", "skim", "--no-compact", "--compact", "a.rs"]);
+        assert!(skim.compact);
+
+        let review = parse(&["--mode", "review"]);
+        assert_eq!(review.changed_only.as_deref(), Some("HEAD"));
+        let review = parse(&["--mode", "review", "--changed-only=main"]);
+        assert_eq!(review.changed_only.as_deref(), Some("main"));
+        // A flag that can't go with --changed-only wins over the mode.
+        let review = parse(&["--mode", "review", "--depth", "2", "a.rs"]);
+        assert_eq!((review.changed_only, review.depth), (None, Some(2)));
+
+        let full = parse(&["--mode", "full", "a.rs"]);
+        assert_eq!(
+            (full.depth, full.compact, full.changed_only),
+            (None, false, None)
+        );
+    }
+
+    #[test]
+    fn input_file_lines_carry_their_own_options() {
+        use crate::input_file;
+
+        let fx = Fixture::new(&[
+            ("src/core.rs", "fn core() {}\n"),
+            ("src/view.ts", "export const view = 1;\n"),
+            ("src/view.test.ts", "test('view', () => {});\n"),
+        ]);
+        let list = Path::new("selection.txt");
+        let text = "# recurring selection\n\nsrc/core.rs --depth 0\n'src/**/*.ts' --exclude '*.test.ts' --full\n";
+        let entries = input_file::parse(text, list).expect("input file should parse");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            (
+                entries[0].inputs.as_slice(),
+                entries[0].depth,
+                entries[0].location()
+            ),
+            (
+                &["src/core.rs".to_string()][..],
+                Some(Some(0)),
+                "selection.txt:3".to_string()
+            )
+        );
+        assert_eq!(entries[1].depth, Some(None));
+
+        let config = fx.config();
+        let crate::types::InputResolution::Success(files) =
+            file_resolver::resolve_input_string(&entries[1].inputs[0], &config)
+        else {
+            panic!("glob should resolve");
+        };
+        let kept: Vec<_> = files
+            .iter()
+            .filter(|file| !entries[1].excludes(file))
+            .map(|file| file.display_path().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(kept, ["src/view.ts"]);
+
+        // Each line is checked like a command line, naming where it went wrong.
+        let error = input_file::parse("src/core.rs --depth 1 --full\n", list).unwrap_err();
+        assert!(error.starts_with("selection.txt:1: "), "{}", error);
+        let error = input_file::parse("--depth 2\n", list).unwrap_err();
+        assert!(error.starts_with("selection.txt:1: "), "{}", error);
+        let error = input_file::parse("'src/core.rs\n", list).unwrap_err();
+        assert!(error.contains("unbalanced quotes"), "{}", error);
+    }
+
+    #[test]
+    fn clear_after_durations_parse_with_units() {
+        use crate::clipboard::parse_duration;
+        use std::time::Duration;
+
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration(" 45 "), Ok(Duration::from_secs(45)));
+        assert_eq!(
+            parse_duration("soon"),
+            Err("invalid duration 'soon' (expected e.g. 30s, 5m, 1h)".to_string())
+        );
+        assert_eq!(
+            parse_duration("9999999999999999h"),
+            Err("invalid duration '9999999999999999h' (expected e.g. 30s, 5m, 1h)".to_string())
+        );
+    }
+
+    #[test]
+    fn clear_watch_clears_only_the_text_it_copied() {
+        use crate::clipboard::{content_hash, still_holds};
+
+        let copied = content_hash("## src/main.rs\n```rs\nfn main() {}\n```");
+        assert!(still_holds(
+            Some("## src/main.rs\n```rs\nfn main() {}\n```"),
+            copied
+        ));
+        assert!(!still_holds(Some("copied since"), copied));
+        assert!(!still_holds(None, copied));
+    }
+
+    #[test]
+    fn why_traces_each_resolution_phase() {
+        let fx = Fixture::new(&[
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/display.rs", "pub fn show() {}\n"),
+            ("src/util/strings.rs", "pub fn trim() {}\n"),
+        ]);
+        let config = fx.config();
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        for input in ["src/main.rs", "src/*.rs", "strin"] {
+            let (resolution, trace) = file_resolver::trace_input_string(input, &config);
+            let resolution =
+                crate::dir_config::DirConfigs::new(fx.root()).check_input(input, resolution);
+            DisplayManager::new()
+                .write_resolution_trace(&mut buffer, input, &trace, &resolution)
+                .expect("writing to a Vec cannot fail");
+            buffer.push(b'\n');
+        }
+        assert_snapshot(
+            "why_traces_each_resolution_phase",
+            &normalize(&String::from_utf8_lossy(&buffer), fx.root()),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn stdin_content_is_skeletonized_in_the_given_language() {
+        use clap::Parser;
+
+        let cli = crate::Cli::try_parse_from([
+            "ctx-pick",
+            "--stdin-content",
+            "--force-language",
+            "rust",
+            "--depth",
+            "4",
+        ])
+        .unwrap();
+        let source = "pub struct Point {\n    x: i32,\n}\n\nimpl Point {\n    pub fn norm(&self) -> i32 {\n        self.x.abs()\n    }\n}\n";
+        assert_snapshot(
+            "stdin_content_skeleton",
+            &crate::stdin_skeleton(source, &cli).expect("rust should skeletonize"),
+        );
+    }
+
+    #[test]
+    fn stdin_content_needs_a_language_it_can_skeletonize() {
+        use clap::Parser;
+
+        // Without --force-language there's no path to detect a language from.
+        let error = crate::Cli::try_parse_from(["ctx-pick", "--stdin-content", "--depth", "2"])
+            .unwrap_err();
+        assert_eq!(
+            error.kind(),
+            clap::error::ErrorKind::MissingRequiredArgument
+        );
+        assert!(error.to_string().contains("--force-language"), "{}", error);
+
+        let cli = crate::Cli::try_parse_from([
+            "ctx-pick",
+            "--stdin-content",
+            "--force-language",
+            "cobol",
+            "--depth",
+            "2",
+        ])
+        .unwrap();
+        assert_eq!(
+            crate::stdin_skeleton("IDENTIFICATION DIVISION.\n", &cli)
+                .unwrap_err()
+                .to_string(),
+            "Could not extract skeleton: Language support not configured for file extension: 'cobol'"
+        );
+    }
+
+    #[test]
+    fn a_dominant_file_is_warned_about_unless_opted_out() {
+        use clap::Parser;
+
+        let fx = Fixture::new(&[
+            ("src/big.txt", &"line\n".repeat(40)),
+            ("src/small.txt", "one\ntwo\n"),
+        ]);
+        let config = fx.config();
+        let InputResolution::Success(files) = file_resolver::resolve_input_string("src", &config)
+        else {
+            panic!("src should resolve");
+        };
+        let files: Vec<_> = files.into_iter().map(|file| (file, None)).collect();
+        let contexts: Vec<_> = crate::read_file_contexts(&files, &Default::default())
+            .into_iter()
+            .map(|result| result.expect("fixture files are readable"))
+            .collect();
+        let threshold = |args: &[&str]| {
+            let cli = crate::Cli::try_parse_from(
+                ["ctx-pick"].iter().chain(args).chain(&["src"]).copied(),
+            )
+            .unwrap();
+            crate::balance_warning_threshold(&cli)
+        };
+
+        // 40 of 42 lines is over the default 60%.
+        let (dominant, share) =
+            crate::find_dominant_file(&contexts, threshold(&[])).expect("big.txt should dominate");
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_balance_warning(&mut buffer, dominant, share, false)
+            .expect("writing to a Vec cannot fail");
+        assert_eq!(
+            String::from_utf8_lossy(&buffer),
+            "⚠️ src/big.txt makes up 95% of the context (40 lines).\n    \
+             Consider leaving it out, or using --depth to include only its skeleton. \
+             (Silence with --no-balance-warning.)\n"
+        );
+
+        assert!(
+            crate::find_dominant_file(&contexts, threshold(&["--balance-threshold", "96"]))
+                .is_none()
+        );
+        assert!(
+            crate::find_dominant_file(&contexts, threshold(&["--no-balance-warning"])).is_none()
+        );
+
+        // A skeleton among full files is measured in characters: one long
+        // line outweighs the 40 short ones.
+        let mixed = vec![
+            crate::types::FileContext::new(
+                "src/api.rs".to_string(),
+                "x".repeat(1000),
+                Some("rust".to_string()),
+                Some(2),
+            ),
+            crate::types::FileContext::new(
+                "src/big.txt".to_string(),
+                "line\n".repeat(40),
+                None,
+                None,
+            ),
+        ];
+        let (dominant, share) = crate::find_dominant_file(&mixed, threshold(&[]))
+            .expect("the skeleton should dominate");
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_balance_warning(
+                &mut buffer,
+                dominant,
+                share,
+                crate::measured_in_characters(&mixed),
+            )
+            .expect("writing to a Vec cannot fail");
+        assert_eq!(
+            String::from_utf8_lossy(&buffer),
+            "⚠️ src/api.rs makes up 83% of the context (1000 characters).\n    \
+             Consider leaving it out or narrowing your inputs. \
+             (Silence with --no-balance-warning.)\n"
+        );
+
+        let configured = |text| {
+            crate::user_config::parse(text, Path::new("/")).map(|config| config.balance_threshold)
+        };
+        assert_eq!(configured("balance_threshold = 80\n"), Ok(Some(80)));
+        assert_eq!(
+            configured("balance_threshold = 0\n"),
+            Err("balance_threshold must be between 1 and 100, not 0".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn walks_warn_about_unreadable_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fx = Fixture::new(&[
+            ("src/a.rs", "fn a() {}\n"),
+            ("src/locked/b.rs", "fn b() {}\n"),
+        ]);
+        let locked = fx.root().join("src/locked");
+        let set_mode = |mode| {
+            fs::set_permissions(&locked, fs::Permissions::from_mode(mode))
+                .expect("fixture permissions should be settable");
+        };
+        set_mode(0o000);
+        // Permissions don't stop root, so there's nothing to skip.
+        if fs::read_dir(&locked).is_ok() {
+            set_mode(0o755);
+            return;
+        }
+
+        let config = fx.config();
+        file_resolver::take_skipped_walks();
+        let resolution = file_resolver::resolve_input_string("src", &config);
+        let skipped = file_resolver::take_skipped_walks();
+        set_mode(0o755);
+
+        let InputResolution::Success(files) = resolution else {
+            panic!("src should resolve");
+        };
+        assert_eq!(files.len(), 1);
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_skipped_walks(&mut buffer, &skipped)
+            .expect("writing to a Vec cannot fail");
+        assert_eq!(
+            String::from_utf8_lossy(&buffer),
+            "⚠️ 1 directory skipped due to permissions while resolving 'src'; results may be incomplete.\n"
+        );
+    }
+
+    #[test]
+    fn doctor_checks_config_files() {
+        use crate::doctor::{self, Check, CheckStatus};
+
+        let fx = Fixture::new(&[
+            (".ctx-pick.toml", "depth = 2\n"),
+            ("vendor/.ctx-pick.toml", "exclude = [\"[\"]\n"),
+            ("docs/.ctx-pick.toml", "depth = \"deep\"\n"),
+        ]);
+        let check = doctor::check_config_files(fx.root());
+        assert_eq!(check.status, CheckStatus::Warn);
+
+        let empty = Fixture::new(&[("src/lib.rs", LIB_RS)]);
+        let checks = vec![
+            check,
+            doctor::check_config_files(empty.root()),
+            Check {
+                name: "Clipboard",
+                status: CheckStatus::Fail,
+                detail: "X11: could not open clipboard".to_string(),
+            },
+        ];
+        console::set_colors_enabled(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_doctor_report(&mut buffer, &checks)
+            .expect("writing to a Vec cannot fail");
+        assert_snapshot("doctor_report", &String::from_utf8_lossy(&buffer));
+    }
+
+    #[test]
+    fn clipboard_confirmation_prompt() {
+        use crate::clipboard::ClipboardContents;
+
+        console::set_colors_enabled_stderr(false);
+        console::set_colors_enabled(false);
+        let output = "src/lib.rs\n```rs\nfn lib() {}\n```\n";
+        let mut buffer: Vec<u8> = Vec::new();
+        for current in [
+            ClipboardContents::Text {
+                chars: 12_345,
+                lines: 210,
+            },
+            ClipboardContents::Image {
+                width: 1280,
+                height: 720,
+            },
+            ClipboardContents::Unreadable,
+        ] {
+            DisplayManager::new()
+                .write_clipboard_confirmation_prompt(&mut buffer, 1, output, &current, false)
+                .expect("writing to a Vec cannot fail");
+            buffer.push(b'\n');
+        }
+        assert_snapshot(
+            "clipboard_confirmation_prompt",
+            &String::from_utf8_lossy(&buffer),
+        );
+    }
+
+    #[test]
+    fn clipboard_confirmation_can_edit_the_selection() {
+        use crate::clipboard::ClipboardContents;
+        use crate::types::FileContext;
+
+        console::set_colors_enabled_stderr(false);
+        console::set_colors_enabled(false);
+        let contexts: Vec<FileContext> = [
+            ("src/lib.rs", "pub mod a;\npub mod b;\n"),
+            ("src/a.rs", "pub fn a() {}\n"),
+            ("notes/todo list.md", "- one\n- two\n- three\n"),
+        ]
+        .iter()
+        .map(|(path, content)| FileContext::new(path.to_string(), content.to_string(), None, None))
+        .collect();
+        let display = DisplayManager::new();
+        let mut buffer: Vec<u8> = Vec::new();
+        display
+            .write_clipboard_confirmation_prompt(
+                &mut buffer,
+                3,
+                "",
+                &ClipboardContents::Text { chars: 5, lines: 1 },
+                true,
+            )
+            .expect("writing to a Vec cannot fail");
+        buffer.push(b'\n');
+        display
+            .write_exclusion_menu(&mut buffer, &contexts)
+            .expect("writing to a Vec cannot fail");
+        buffer.push(b'\n');
+        display
+            .write_exclude_hint(
+                &mut buffer,
+                &["src/a.rs".to_string(), "notes/todo list.md".to_string()],
+            )
+            .expect("writing to a Vec cannot fail");
+        assert_snapshot(
+            "clipboard_selection_edit",
+            &String::from_utf8_lossy(&buffer),
+        );
+
+        assert_eq!(
+            crate::parse_selection("3, 1-2", 3),
+            Ok([1, 2, 3].into_iter().collect())
+        );
+        assert_eq!(crate::parse_selection("  ", 3), Ok(Default::default()));
+        assert!(crate::parse_selection("4", 3).is_err());
+        assert!(crate::parse_selection("0", 3).is_err());
+        assert!(crate::parse_selection("2-1", 3).is_err());
+        assert!(crate::parse_selection("two", 3).is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn stats_report() {
+        let fx = sample_tree();
+        let config = fx.config();
+        let InputResolution::Success(files) = file_resolver::resolve_input_string(".", &config)
+        else {
+            panic!("the fixture root should resolve");
+        };
+        let stats = crate::stats::Stats::collect(
+            &files,
+            Some(2),
+            &crate::symbol_extractor::SkeletonOptions::default(),
+        );
+        console::set_colors_enabled(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_stats(&mut buffer, &stats)
+            .expect("writing to a Vec cannot fail");
+        assert_snapshot(
+            "stats_report",
+            &normalize(&String::from_utf8_lossy(&buffer), fx.root()),
+        );
+    }
+
+    #[test]
+    fn regex_inputs() {
+        let fx = Fixture::new(&[
+            ("src/api/user_handler.rs", "pub fn user() {}\n"),
+            ("src/api/order_handler.rs", "pub fn order() {}\n"),
+            ("src/api/handler_utils.rs", "pub fn util() {}\n"),
+        ]);
+        let matched = run_pipeline(
+            &fx,
+            &[],
+            &RunOptions {
+                regex: vec![r"src/.*_handler\.rs$"],
+                ..RunOptions::default()
+            },
+        );
+        let invalid = run_pipeline(
+            &fx,
+            &[],
+            &RunOptions {
+                regex: vec!["src/(api"],
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot("regex_inputs", &format!("{}\n{}", matched, invalid));
+    }
+
+    #[test]
+    fn compact_strips_blank_lines_and_common_indent() {
+        let fx = Fixture::new(&[(
+            "snippet.rs",
+            "    fn a() {\n\n        1\n    }\n   \n    fn b() {}\n",
+        )]);
+        assert_snapshot(
+            "compact_strips_blank_lines_and_common_indent",
+            &run_pipeline(
+                &fx,
+                &["snippet.rs"],
+                &RunOptions {
+                    compact: true,
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn expansions_stay_inside_working_dir() {
+        let fx = Fixture::new(&[
+            ("project/src/lib.rs", "pub fn lib() {}\n"),
+            ("keys/server.pem", "secret\n"),
+        ]);
+        let run = |inputs: &[&str], allow_outside: bool| {
+            run_pipeline(
+                &fx,
+                inputs,
+                &RunOptions {
+                    working_subdir: Some("project"),
+                    allow_outside,
+                    ..RunOptions::default()
+                },
+            )
+        };
+        let refused = run(&["src/lib.rs", "../**/*.pem"], false);
+        let explicit = run(&["../keys/server.pem"], false);
+        let allowed = run(&["../**/*.pem"], true);
+        assert_snapshot(
+            "expansions_stay_inside_working_dir",
+            &format!("{}\n{}\n{}", refused, explicit, allowed),
+        );
+    }
+
+    #[cfg(all(unix, feature = "lang-rust"))]
+    #[test]
+    fn project_walks_stay_inside_working_dir() {
+        let fx = Fixture::new(&[
+            ("project/src/lib.rs", "pub fn lib() {}\n"),
+            ("shared/keys.rs", "pub fn signing_key() {}\n"),
+        ]);
+        std::os::unix::fs::symlink("../shared", fx.root().join("project/vendor"))
+            .expect("symlink should be created");
+        let mut config = fx.config();
+        config.working_dir = fx.root().join("project");
+
+        assert_eq!(
+            file_resolver::project_files(&config),
+            vec![PathBuf::from("src/lib.rs")]
+        );
+        assert!(matches!(
+            file_resolver::resolve_input_string("sym:signing_key", &config),
+            InputResolution::OutsideWorkingDir { file_count: 1, .. }
+        ));
+        assert!(matches!(
+            file_resolver::resolve_symbol_context("signing_key", 2, None, &config),
+            InputResolution::OutsideWorkingDir { file_count: 1, .. }
+        ));
+
+        config.allow_outside = true;
+        assert_eq!(
+            file_resolver::project_files(&config),
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("vendor/keys.rs")]
+        );
+        assert!(matches!(
+            file_resolver::resolve_input_string("sym:signing_key", &config),
+            InputResolution::Success(_)
+        ));
+    }
+
+    #[test]
+    fn table_of_contents() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "table_of_contents",
+            &run_pipeline(
+                &fx,
+                &["src/main.rs", "lib.rs", "docs"],
+                &RunOptions {
+                    toc: true,
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn anchors_number_blocks_and_index_them() {
+        let fx = Fixture::new(&[
+            (
+                "src/display.rs",
+                "//! Terminal output for the summary.\n\npub fn show() {}\n",
+            ),
+            (
+                "tools/run.py",
+                "import sys\n\ndef main():\n    \"\"\"Runs the tool.\"\"\"\n",
+            ),
+            (
+                "notes.txt",
+                "\n\nRemember to update the changelog before every release, including patch releases and hotfixes.\n",
+            ),
+        ]);
+        let inputs = ["src/display.rs", "tools/run.py", "notes.txt"];
+        let run = |format| {
+            run_pipeline(
+                &fx,
+                &inputs,
+                &RunOptions {
+                    anchors: true,
+                    format,
+                    ..RunOptions::default()
+                },
+            )
+        };
+        assert_snapshot(
+            "anchors",
+            &format!(
+                "{}\n{}",
+                run(crate::output::OutputFormat::Markdown),
+                run(crate::output::OutputFormat::Cxml)
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn per_directory_depth_and_excludes() {
+        let fx = Fixture::new(&[
+            ("src/main.rs", MAIN_RS),
+            (
+                "src/deep/.ctx-pick.toml",
+                "depth = 1\nexclude = [\"gen_*.rs\"]\n",
+            ),
+            ("src/deep/lib.rs", LIB_RS),
+            ("src/deep/gen_bindings.rs", "fn generated() {}\n"),
+            ("src/deep/inner/.ctx-pick.toml", "depth = 3\n"),
+            ("src/deep/inner/mod.rs", LIB_RS),
+        ]);
+        assert_snapshot(
+            "per_directory_depth_and_excludes",
+            &run_pipeline(
+                &fx,
+                &["src/main.rs", "src/deep/**/*.rs"],
+                &RunOptions::default(),
+            ),
+        );
+        // `--depth` overrides every directory config, but not their excludes.
+        assert_snapshot(
+            "per_directory_depth_overridden_by_cli",
+            &run_pipeline(
+                &fx,
+                &["src/deep/**/*.rs"],
+                &RunOptions {
+                    depth: Some(2),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn per_directory_language_overrides() {
+        let fx = Fixture::new(&[
+            (".ctx-pick.toml", "lang_override = [\"*.inc=python\"]\n"),
+            (
+                "pkg/.ctx-pick.toml",
+                "depth = 1\nlang_override = [\"*.inc=rust\"]\n",
+            ),
+            ("pkg/sub/helper.inc", "pub fn helper() -> u32 {\n    1\n}\n"),
+            (
+                "scripts/deploy.inc",
+                "def deploy(target):\n    return target\n",
+            ),
+        ]);
+        // The nearest config's override applies to each file.
+        assert_snapshot(
+            "per_directory_language_overrides",
+            &run_pipeline(&fx, &["pkg", "scripts"], &RunOptions::default()),
+        );
+        // `--lang-override` applies over every directory config.
+        let rendered = run_pipeline(
+            &fx,
+            &["scripts"],
+            &RunOptions {
+                lang_override: vec!["*.inc=rust"],
+                ..RunOptions::default()
+            },
+        );
+        assert!(rendered.contains("```rs\n"));
+    }
+
+    #[test]
+    fn manifest_is_deterministic() {
+        let fx = sample_tree();
+        let options = RunOptions {
+            manifest: true,
+            ..RunOptions::default()
+        };
+        let first = run_pipeline(&fx, &["src", "docs/notes.txt"], &options);
+        assert_eq!(
+            first,
+            run_pipeline(&fx, &["src", "docs/notes.txt"], &options)
+        );
+        assert_snapshot("manifest_is_deterministic", &first);
+    }
+
+    #[test]
+    fn line_ranges() {
+        let numbered: String = (1..=12).map(|n| format!("line {}\n", n)).collect();
+        // Large enough to be memory-mapped rather than read.
+        let big: String = (1..=200_000).map(|n| format!("row {}\n", n)).collect();
+        let fx = Fixture::new(&[
+            ("logs/app.log", numbered.as_str()),
+            ("logs/big.log", big.as_str()),
+            ("src/lib.rs", LIB_RS),
+        ]);
+        assert_snapshot(
+            "line_ranges",
+            &run_pipeline(
+                &fx,
+                &[
+                    "logs/app.log:3-5",
+                    "app.log:11-20",
+                    "lib.rs:2-3",
+                    "missing.rs:1-2",
+                ],
+                &RunOptions::default(),
+            ),
+        );
+        assert_snapshot(
+            "line_ranges_resolved",
+            &run_pipeline(
+                &fx,
+                &[
+                    "logs/app.log:3-5",
+                    "app.log:11-20",
+                    "lib.rs:2-3",
+                    "big.log:199999-200005",
+                ],
+                &RunOptions::default(),
+            ),
+        );
+    }
+
+    #[test]
+    fn files_at_git_revision() {
+        let fx = sample_tree();
+        fx.git(&["init", "-q"])
+            .git(&["add", "."])
+            .git(&["commit", "-q", "-m", "v1", "--no-gpg-sign"])
+            .git(&["tag", "v1"]);
+        // Change, add, and delete files after the tag; `--at v1` shouldn't see any of it.
+        fx.write("src/lib.rs", b"pub fn add_v2() {}\n")
+            .write("src/new.rs", b"fn new() {}\n");
+        fs::remove_dir_all(fx.root().join("src/util")).expect("failed to remove fixture dir");
+
+        assert_snapshot(
+            "files_at_git_revision",
+            &run_pipeline(
+                &fx,
+                &["src/lib.rs", "src/util", "*.txt", "docs/*.txt", "new.rs"],
+                &RunOptions {
+                    at: Some("v1"),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+        assert_snapshot(
+            "files_at_git_revision_content",
+            &run_pipeline(
+                &fx,
+                &["src/lib.rs", "src/util", "docs/*.txt", "main"],
+                &RunOptions {
+                    at: Some("v1"),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn default_excludes_apply_to_expansions() {
+        let fx = Fixture::new(&[
+            ("Cargo.lock", "# lock\n"),
+            ("web/app.js", "run();\n"),
+            ("web/app.min.js", "run();\n"),
+            ("web/api.generated.ts", "export {};\n"),
+            ("web/package-lock.json", "{}\n"),
+        ]);
+        let defaults = run_pipeline(&fx, &["web", "*.lock"], &RunOptions::default());
+        let named = run_pipeline(
+            &fx,
+            &["Cargo.lock", "web/app.min.js"],
+            &RunOptions::default(),
+        );
+        let disabled = run_pipeline(
+            &fx,
+            &["web"],
+            &RunOptions {
+                no_default_excludes: true,
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot(
+            "default_excludes_apply_to_expansions",
+            &format!("{}\n{}\n{}", defaults, named, disabled),
+        );
+    }
+
+    #[test]
+    fn filtered_inputs_name_the_rule() {
+        let fx = Fixture::new(&[
+            ("src/lib.rs", "pub fn lib() {}\n"),
+            ("src/gen/.ctx-pick.toml", "exclude = [\"*.rs\"]\n"),
+            ("src/gen/a.rs", "pub fn a() {}\n"),
+            ("src/gen/b.rs", "pub fn b() {}\n"),
+            ("yarn.lock", "# lock\n"),
+        ]);
+        let rendered = run_pipeline(
+            &fx,
+            &["src/lib.rs", "src/gen/*.rs", "*.lock"],
+            &RunOptions {
+                skip_missing: true,
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot("filtered_inputs_name_the_rule", &rendered);
+    }
+
+    #[test]
+    fn porcelain_summary_is_one_json_line() {
+        let summary = crate::manifest::RunSummary::new(2, "clipboard", "héllo\nworld\n");
+        assert_eq!(
+            summary.to_json_line(),
+            "{\"files\":2,\"destination\":\"clipboard\",\"characters\":12,\"sha256\":\"4920c098f87034386739f1ff95b0c743717e8b16b1e9b1e39bdc56adf4b76baf\",\"bytes\":13,\"lines\":2,\"estimated_tokens\":3}"
+        );
+    }
+
+    #[test]
+    fn stable_hash_is_the_porcelain_hash_and_repeats() {
+        assert_eq!(
+            crate::manifest::sha256_hex("héllo\nworld\n"),
+            "4920c098f87034386739f1ff95b0c743717e8b16b1e9b1e39bdc56adf4b76baf"
+        );
+        let fx = sample_tree();
+        let options = RunOptions {
+            depth: Some(2),
+            ..RunOptions::default()
+        };
+        assert_eq!(
+            crate::manifest::sha256_hex(&run_pipeline(&fx, &["src", "*.md"], &options)),
+            crate::manifest::sha256_hex(&run_pipeline(&fx, &["src", "*.md"], &options))
+        );
+    }
+
+    #[test]
+    fn output_file_is_gzipped_for_gz_paths() {
+        use std::io::Read;
+
+        let fx = Fixture::empty();
+        let output = "src/lib.rs\n```rs\nfn lib() {}\n```\n".repeat(200);
+
+        let plain = fx.root().join("context.md");
+        let bytes = crate::output::write_to_file(&plain, &output).expect("write plain output");
+        assert_eq!(bytes, output.len() as u64);
+        assert_eq!(fs::read_to_string(&plain).unwrap(), output);
+
+        let gzipped = fx.root().join("context.md.gz");
+        let bytes = crate::output::write_to_file(&gzipped, &output).expect("write gzip output");
+        assert!(bytes < output.len() as u64 / 10, "{} bytes", bytes);
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(fs::File::open(&gzipped).unwrap())
+            .read_to_string(&mut decompressed)
+            .expect("output should be valid gzip");
+        assert_eq!(decompressed, output);
+
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_output_written(&mut buffer, Path::new("context.md.gz"), &output, 1234)
+            .expect("writing to a Vec cannot fail");
+        assert_eq!(
+            String::from_utf8_lossy(&buffer),
+            "✅ Context written to context.md.gz: 800 lines, 6,600 characters; gzip-compressed to 1,234 bytes\n"
+        );
+    }
+
+    #[test]
+    fn prompt_templates_wrap_the_context() {
+        let review = crate::prompts::parse_template("review").expect("review is built in");
+        let wrapped = review.wrap("src/lib.rs\n```rs\nfn lib() {}\n```\n");
+        assert!(wrapped.starts_with("Review the code below"), "{}", wrapped);
+        assert!(
+            wrapped.ends_with(".\n\nsrc/lib.rs\n```rs\nfn lib() {}\n```\n"),
+            "{}",
+            wrapped
+        );
+        assert_eq!(
+            crate::prompts::parse_template("summarize"),
+            Err(
+                "no built-in template named 'summarize' (available: review, explain, test-gen)"
+                    .to_string()
+            )
+        );
+        assert_snapshot("prompt_template_list", &crate::prompts::list());
+    }
+
+    #[test]
+    fn user_grammars_are_parsed_and_failures_name_the_library() {
+        let grammars = crate::user_config::parse(
+            "[grammars.Sol]\nlibrary = \"lib/libtree-sitter-solidity.so\"\n\n[grammars.gleam]\nlibrary = \"/opt/gleam.so\"\nsymbol = \"tree_sitter_gleam\"\n",
+            Path::new("/home/me/.config/ctx-pick"),
+        )
+        .expect("config should parse")
+        .grammars;
+        let solidity = &grammars["sol"];
+        assert_eq!(
+            solidity.library,
+            Path::new("/home/me/.config/ctx-pick/lib/libtree-sitter-solidity.so")
+        );
+        assert_eq!(solidity.symbol, None);
+        assert_eq!(grammars["gleam"].library, Path::new("/opt/gleam.so"));
+
+        let error = crate::grammars::load("sol", solidity).expect_err("library doesn't exist");
+        assert!(
+            error.starts_with(
+                "Could not load grammar library \"/home/me/.config/ctx-pick/lib/libtree-sitter-solidity.so\""
+            ),
+            "{}",
+            error
+        );
+
+        assert_eq!(
+            crate::user_config::parse("[grammars.sol]\nlib = \"x.so\"\n", Path::new("/"))
+                .map(|config| config.grammars),
+            Err("line 2: unknown field `lib`, expected `library` or `symbol`".to_string())
+        );
+    }
+
+    #[test]
+    fn working_dir_inside_default_excluded_directory() {
+        let fx = Fixture::new(&[("api.generated.d/client.ts", "export {};\n")]);
+        let inside = fx.root.join("api.generated.d");
+        assert_eq!(
+            crate::config::default_exclude_covering(&inside),
+            Some(("api.generated.d".to_string(), "*.generated.*"))
+        );
+        assert_eq!(crate::config::default_exclude_covering(&fx.root), None);
+    }
+
+    #[test]
+    fn compare_with_git_revision() {
+        use crate::compare::CompareFormat;
+
+        let fx = sample_tree();
+        fx.git(&["init", "-q"])
+            .git(&["add", "."])
+            .git(&["commit", "-q", "-m", "v1", "--no-gpg-sign"])
+            .git(&["tag", "v1"]);
+        fx.write(
+            "src/lib.rs",
+            b"/// Adds.\npub fn add(a: i64, b: i64) -> i64 {\n    a + b\n}\n",
+        )
+        .write("src/new.rs", b"fn new() {}\n");
+
+        let inputs = ["src/main.rs", "src/lib.rs", "src/new.rs"];
+        let blocks = run_pipeline(
+            &fx,
+            &inputs,
+            &RunOptions {
+                compare: Some(("v1", CompareFormat::Blocks)),
+                ..RunOptions::default()
+            },
+        );
+        let diff = run_pipeline(
+            &fx,
+            &inputs,
+            &RunOptions {
+                compare: Some(("v1", CompareFormat::Diff)),
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot(
+            "compare_with_git_revision",
+            &format!("{}\n{}", blocks, diff),
+        );
+    }
+
+    #[test]
+    fn changed_lines_with_context() {
+        let original: String = (1..=30).map(|n| format!("line {}\n", n)).collect();
+        let fx = Fixture::new(&[
+            ("notes.txt", original.as_str()),
+            ("untouched.txt", "same\n"),
+        ]);
+        fx.git(&["init", "-q"]).git(&["add", "."]).git(&[
+            "commit",
+            "-q",
+            "-m",
+            "v1",
+            "--no-gpg-sign",
+        ]);
+        let edited = original
+            .replace("line 5\n", "line five\n")
+            .replace("line 8\n", "")
+            .replace("line 25\n", "line 25\nline 25.5\n");
+        fx.write("notes.txt", edited.as_bytes());
+
+        let rendered = run_pipeline(
+            &fx,
+            &["notes.txt", "untouched.txt"],
+            &RunOptions {
+                changed_only: Some(("HEAD", 2)),
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot("changed_lines_with_context", &rendered);
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn skeleton_blame_hints() {
+        let fx = Fixture::new(&[(
+            "src/lib.rs",
+            "// Helpers.\nfn old() {}\n\nfn edited() {\n    1\n}\n",
+        )]);
+        fx.git(&["init", "-q"]).git(&["add", "."]).git(&[
+            "commit",
+            "-q",
+            "-m",
+            "v1",
+            "--no-gpg-sign",
+            "--date=2025-01-01T00:00:00Z",
+        ]);
+        fx.write(
+            "src/lib.rs",
+            b"// Helpers.\nfn old() {}\n\nfn edited() {\n    2\n}\n",
+        )
+        .git(&["add", "."])
+        .git(&[
+            "-c",
+            "user.name=Ada",
+            "commit",
+            "-q",
+            "-m",
+            "v2",
+            "--no-gpg-sign",
+            "--date=2026-02-20T00:00:00Z",
+        ]);
+        fx.write(
+            "src/lib.rs",
+            b"// Helpers.\nfn old() {}\n\nfn edited() {\n    2\n}\n\nfn added() {}\n",
+        );
+
+        // 2026-03-01T00:00:00Z
+        let rendered = run_pipeline(
+            &fx,
+            &["src/lib.rs"],
+            &RunOptions {
+                depth: Some(3),
+                blame_hints: Some(1_772_323_200),
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot("skeleton_blame_hints", &rendered);
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn shared_imports_are_deduplicated() {
+        let fx = Fixture::new(&[
+            (
+                "src/a.rs",
+                "use std::fs;\nuse std::io;\nuse crate::b;\n\nfn a() {}\n",
+            ),
+            (
+                "src/b.rs",
+                "use std::io;\nuse  std::fs;\npub use std::fmt;\n#[cfg(test)]\nuse std::env;\n\nfn b() {}\n",
+            ),
+            ("src/c.rs", "use std::io;\nuse std::env;\n\nfn c() {}\n"),
+            ("tools/x.py", "import os\nfrom pathlib import Path\n"),
+            ("tools/y.py", "import os\n\nprint(os.sep)\n"),
+        ]);
+        let rendered = run_pipeline(
+            &fx,
+            &[
+                "src/a.rs",
+                "src/b.rs",
+                "src/c.rs",
+                "tools/x.py",
+                "tools/y.py",
+            ],
+            &RunOptions {
+                dedupe_imports: true,
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot("shared_imports_are_deduplicated", &rendered);
+    }
+
+    #[test]
+    fn files_removed_after_resolution() {
+        let fx = Fixture::new(&[("src/a.rs", "fn a() {}\n"), ("src/b.rs", "fn b() {}\n")]);
+        let config = fx.config();
+        let InputResolution::Success(files) = file_resolver::resolve_input_string("src", &config)
+        else {
+            panic!("src should resolve");
+        };
+        fs::remove_file(fx.root().join("src/b.rs")).unwrap();
+
+        let files: Vec<_> = files.into_iter().map(|file| (file, None)).collect();
+        let results =
+            crate::read_file_contexts(&files, &crate::symbol_extractor::SkeletonOptions::default());
+        assert_eq!(
+            results[0].as_ref().map(|c| c.display_path.as_str()),
+            Ok("src/a.rs")
+        );
+        let Err(crate::ContextFailure::Removed(path)) = &results[1] else {
+            panic!("src/b.rs was removed and should be reported as such");
+        };
+        assert_eq!(path, "src/b.rs");
+        let contexts = crate::generate_file_contexts(
+            &files,
+            &crate::symbol_extractor::SkeletonOptions::default(),
+        );
+        assert_eq!(contexts.len(), 1);
+
+        console::set_colors_enabled(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .with_removed_files(vec![path.clone()])
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        let summary = String::from_utf8_lossy(&buffer);
+        assert!(
+            summary.contains("Skipped 1 file removed during run:\n  • src/b.rs\n"),
+            "{}",
+            summary
+        );
+    }
+
+    #[test]
+    fn empty_files_are_skipped_unless_included() {
+        let fx = Fixture::new(&[
+            ("pkg/__init__.py", ""),
+            ("pkg/core.py", "def run():\n    pass\n"),
+        ]);
+        let config = fx.config();
+        let InputResolution::Success(files) = file_resolver::resolve_input_string("pkg", &config)
+        else {
+            panic!("pkg should resolve");
+        };
+        let files: Vec<_> = files.into_iter().map(|file| (file, None)).collect();
+
+        let results =
+            crate::read_file_contexts(&files, &crate::symbol_extractor::SkeletonOptions::default());
+        let Err(crate::ContextFailure::Empty(path)) = &results[0] else {
+            panic!("pkg/__init__.py is empty and should be reported as such");
+        };
+        assert_eq!(path, "pkg/__init__.py");
+        assert!(results[1].is_ok());
+
+        let included = crate::generate_file_contexts(
+            &files,
+            &crate::symbol_extractor::SkeletonOptions {
+                include_empty_files: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(included[0].display_path, "pkg/__init__.py");
+        assert_eq!(included[0].content, "");
+
+        console::set_colors_enabled(false);
+        let contexts = crate::generate_file_contexts(&files, &Default::default());
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .with_empty_files(vec![path.clone()])
+            .write_operation_summary_and_preview(&mut buffer, &contexts, &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        let summary = String::from_utf8_lossy(&buffer);
+        assert!(
+            summary.contains(
+                "Skipped 1 empty file (pass --include-empty-files to keep them):\n  • pkg/__init__.py\n"
+            ),
+            "{}",
+            summary
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn long_lines_are_wrapped() {
+        let long_line = format!("var x=\"{}\";", "ab".repeat(14));
+        let fx = Fixture::new(&[
+            (
+                "dist/app.min.js",
+                format!("{}\nshort();\n", long_line).as_str(),
+            ),
+            ("src/lib.rs", LIB_RS),
+        ]);
+        let full = run_pipeline(
+            &fx,
+            &["dist/app.min.js"],
+            &RunOptions {
+                wrap: Some(16),
+                ..RunOptions::default()
+            },
+        );
+        let skeleton = run_pipeline(
+            &fx,
+            &["src/lib.rs"],
+            &RunOptions {
+                wrap: Some(16),
+                depth: Some(3),
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot("long_lines_are_wrapped", &format!("{}\n{}", full, skeleton));
+    }
+
+    #[test]
+    fn long_lines_are_truncated() {
+        let table = format!("const TABLE = \"{}\";", "é".repeat(30));
+        // A cut through a run of backticks leaves the line as it started.
+        let fence_like = format!("// {}", "`".repeat(40));
+        let fx = Fixture::new(&[
+            (
+                "src/table.js",
+                format!("{}\n{}\r\nshort();\n", table, fence_like).as_str(),
+            ),
+            ("src/lib.rs", LIB_RS),
+        ]);
+        let rendered = run_pipeline(
+            &fx,
+            &["src/table.js", "src/lib.rs"],
+            &RunOptions {
+                max_line_length: Some(20),
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot("long_lines_are_truncated", &rendered);
+
+        let (content, cut) = crate::output::truncate_long_lines("abcdef\nabc\r\nabcd\r\n", 3);
+        assert_eq!(content, "abc⏎…\nabc\r\nabc⏎…\r\n");
+        assert_eq!(cut, 2);
+        assert_eq!(
+            crate::user_config::parse("max_line_length = 400\n", Path::new("/"))
+                .map(|config| config.max_line_length),
+            Ok(Some(400))
+        );
+
+        let context = crate::types::FileContext {
+            truncated_lines: 2,
+            ..crate::types::FileContext::new("src/table.js".to_string(), content, None, None)
+        };
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+        let mut buffer: Vec<u8> = Vec::new();
+        DisplayManager::new()
+            .write_operation_summary_and_preview(&mut buffer, &[context], &[], &Ok(()))
+            .expect("writing to a Vec cannot fail");
+        let summary = String::from_utf8_lossy(&buffer);
+        assert!(
+            summary.contains(
+                "src/table.js\n    📄 3 lines\n    ⏎ 2 long lines truncated (--max-line-length)\n"
+            ),
+            "{}",
+            summary
+        );
+    }
+
+    #[test]
+    fn unsupported_language_with_depth() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "unsupported_language_with_depth",
+            &run_pipeline(
+                &fx,
+                &["docs/notes.txt"],
+                &RunOptions {
+                    depth: Some(3),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn error_report_rendering() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "error_report_rendering",
+            &run_pipeline(
+                &fx,
+                &["src/main.rs", "nonexistent", ".rs", "src/[*.rs"],
+                &RunOptions::default(),
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-rust", feature = "lang-python"))]
+    fn overview_respects_ignore_files() {
+        let fx = sample_tree();
+        fx.write(".gitignore", b"generated/\n");
+        fx.write("generated/out.rs", b"fn generated() {}\n");
+        fx.write(".hidden/secret.py", b"x = 1\n");
+        assert_snapshot(
+            "overview_respects_ignore_files",
+            &run_pipeline(
+                &fx,
+                &[],
+                &RunOptions {
+                    depth: Some(1),
+                    overview: true,
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn overview_is_limited_like_directory_expansion() {
+        let fx = Fixture::new(&[
+            ("main.rs", "fn main() {}\n"),
+            ("src/lib.rs", "pub fn lib() {}\n"),
+            ("src/schema.generated.rs", "pub struct Row;\n"),
+        ]);
+        let overview = |config: &Config| -> Vec<String> {
+            match file_resolver::resolve_overview(config) {
+                InputResolution::Success(files) => files
+                    .iter()
+                    .map(|file| file.display_path().to_string_lossy().into_owned())
+                    .collect(),
+                other => panic!("overview should resolve, got {:?}", other),
+            }
+        };
+        let mut config = fx.config();
+        assert_eq!(overview(&config), vec!["main.rs", "src/lib.rs"]);
+
+        config.dir_depth = Some(1);
+        assert_eq!(overview(&config), vec!["main.rs"]);
+
+        config.dir_depth = None;
+        config.max_files_per_input = Some(1);
+        assert!(matches!(
+            file_resolver::resolve_overview(&config),
+            InputResolution::TooManyFiles { file_count: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn expansion_over_per_input_limit() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "expansion_over_per_input_limit",
+            &run_pipeline(
+                &fx,
+                &["src", "src/main.rs"],
+                &RunOptions {
+                    max_files_per_input: Some(2),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn searches_over_the_entry_limit_are_stopped() {
+        let fx = sample_tree();
+        assert_snapshot(
+            "searches_over_the_entry_limit",
+            &run_pipeline(
+                &fx,
+                &["**/**/*", "main", "src/main.rs"],
+                &RunOptions {
+                    search_entry_limit: Some(2),
+                    ..RunOptions::default()
+                },
+            ),
+        );
+    }
+
+    // Harness inputs never pass through a shell, just like inputs read from
+    // an @-file or stdin list, so these exercise ctx-pick's own expansion.
+    #[test]
+    fn env_vars_are_expanded_without_a_shell() {
+        let fx = sample_tree();
+        // SAFETY: the variable name is unique to this test.
+        unsafe { std::env::set_var("CTX_PICK_TEST_EXPAND_ROOT", fx.root()) };
+        assert_snapshot(
+            "env_vars_are_expanded_without_a_shell",
+            &run_pipeline(
+                &fx,
+                &[
+                    "$CTX_PICK_TEST_EXPAND_ROOT/src/main.rs",
+                    "${CTX_PICK_TEST_EXPAND_ROOT}/src/lib.rs",
+                ],
+                &RunOptions::default(),
+            ),
+        );
+    }
+
+    #[test]
+    fn unknown_env_vars_and_no_expand_stay_literal() {
+        let fx = sample_tree();
+        fx.write("$CTX_PICK_TEST_UNSET.txt", b"literal\n");
+        fx.write("$HOME.txt", b"also literal\n");
+        let unknown = run_pipeline(&fx, &["$CTX_PICK_TEST_UNSET.txt"], &RunOptions::default());
+        let no_expand = run_pipeline(
+            &fx,
+            &["$HOME.txt"],
+            &RunOptions {
+                no_expand: true,
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot(
+            "unknown_env_vars_and_no_expand_stay_literal",
+            &format!("{}\n{}", unknown, no_expand),
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "lang-make", feature = "lang-dockerfile"))]
+    fn special_filenames() {
+        let fx = Fixture::new(&[
+            ("Makefile", "build:\n\tcargo build\n"),
+            ("Dockerfile.dev", "FROM rust:1\nRUN cargo build\n"),
+            ("LICENSE", "MIT\n"),
+        ]);
+        let full = run_pipeline(
+            &fx,
+            &["Makefile", "Dockerfile.dev", "LICENSE"],
+            &RunOptions::default(),
+        );
+        let skeleton = run_pipeline(
+            &fx,
+            &["Makefile", "Dockerfile.dev"],
+            &RunOptions {
+                depth: Some(2),
+                ..RunOptions::default()
+            },
+        );
+        assert_snapshot("special_filenames", &format!("{}\n{}", full, skeleton));
+    }
+}
@@ -1,8 +1,195 @@
+use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 
 pub struct FileContext {
     pub display_path: String,
     pub content: String,
+    /// The detected language key (usually the file extension), if any.
+    pub language: Option<String>,
+    /// The skeleton depth `content` was extracted at, or `None` for full content.
+    pub depth: Option<usize>,
+    /// Number of lines in `content`.
+    pub line_count: usize,
+    /// Number of characters in `content`.
+    pub char_count: usize,
+    /// For a skeleton, the line of the first syntax error in the file, if
+    /// the parser found any (the skeleton may then be incomplete).
+    pub syntax_error_line: Option<usize>,
+    /// The permissions of the file on disk, if it was read from the working tree.
+    pub permissions: Option<FilePermissions>,
+    /// How many files this context stands for: more than one when identical
+    /// skeletons were collapsed into it (`--dedup-skeletons`).
+    pub file_count: usize,
+    /// How many lines `--max-line-length` cut short.
+    pub truncated_lines: usize,
+}
+
+impl FileContext {
+    /// Creates a context, computing its size metrics from `content`.
+    pub fn new(
+        display_path: String,
+        content: String,
+        language: Option<String>,
+        depth: Option<usize>,
+    ) -> Self {
+        let line_count = content.lines().count();
+        let char_count = content.chars().count();
+        Self {
+            display_path,
+            content,
+            language,
+            depth,
+            line_count,
+            char_count,
+            syntax_error_line: None,
+            permissions: None,
+            file_count: 1,
+            truncated_lines: 0,
+        }
+    }
+
+    /// Replaces `content`, recomputing the size metrics and keeping the rest.
+    pub fn with_content(self, content: String) -> Self {
+        Self {
+            line_count: content.lines().count(),
+            char_count: content.chars().count(),
+            content,
+            ..self
+        }
+    }
+}
+
+impl FileContext {
+    /// Whether `content` is the file itself or a skeleton of it.
+    pub fn mode(&self) -> ContextMode {
+        if self.depth.is_some() {
+            ContextMode::Skeleton
+        } else {
+            ContextMode::Full
+        }
+    }
+}
+
+/// A file's permissions, as shown by `--show-permissions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilePermissions {
+    /// The Unix permission bits (e.g. `0o755`). `None` on other platforms.
+    pub mode: Option<u32>,
+    pub readonly: bool,
+    /// Executable by anyone (Unix), or an executable extension (elsewhere).
+    pub executable: bool,
+}
+
+impl FilePermissions {
+    /// Reads the permissions from the metadata of the file at `path`.
+    pub fn of(path: &Path, metadata: &std::fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            Some(metadata.permissions().mode() & 0o7777)
+        };
+        #[cfg(not(unix))]
+        let mode = None;
+        let executable = match mode {
+            Some(mode) => mode & 0o111 != 0,
+            None => path.extension().is_some_and(|ext| {
+                ["exe", "bat", "cmd", "com", "ps1"]
+                    .iter()
+                    .any(|executable| ext.eq_ignore_ascii_case(executable))
+            }),
+        };
+        Self {
+            mode,
+            readonly: metadata.permissions().readonly(),
+            executable,
+        }
+    }
+
+    /// The permission bits as `ls -l` shows them (e.g. `rwxr-xr-x`), or
+    /// `read-only`/`read-write` where there are none.
+    pub fn symbolic(&self) -> String {
+        let Some(mode) = self.mode else {
+            return if self.readonly {
+                "read-only"
+            } else {
+                "read-write"
+            }
+            .to_string();
+        };
+        (0..9)
+            .map(|bit| {
+                if mode & (0o400 >> bit) == 0 {
+                    '-'
+                } else {
+                    ['r', 'w', 'x'][bit % 3]
+                }
+            })
+            .collect()
+    }
+}
+
+/// How a context's content was produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextMode {
+    /// The file's content (or a line range of it).
+    Full,
+    /// A skeleton extracted at some depth.
+    Skeleton,
+}
+
+/// File, line and character counts for a group of contexts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextCounts {
+    pub files: usize,
+    pub lines: usize,
+    pub chars: usize,
+}
+
+impl ContextCounts {
+    fn add(&mut self, context: &FileContext) {
+        self.files += 1;
+        self.lines += context.line_count;
+        self.chars += context.char_count;
+    }
+}
+
+/// Size totals for a set of contexts, overall and per mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextTotals {
+    pub total: ContextCounts,
+    pub full: ContextCounts,
+    pub skeleton: ContextCounts,
+}
+
+impl ContextTotals {
+    pub fn of(contexts: &[FileContext]) -> Self {
+        let mut totals = Self::default();
+        for context in contexts {
+            totals.total.add(context);
+            match context.mode() {
+                ContextMode::Full => totals.full.add(context),
+                ContextMode::Skeleton => totals.skeleton.add(context),
+            }
+        }
+        totals
+    }
+}
+
+/// An inclusive, 1-based range of lines to include from a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    /// The first `lines` lines of a file.
+    pub fn head(lines: usize) -> Self {
+        Self {
+            start: 1,
+            end: lines,
+        }
+    }
 }
 
 /// Represents a successfully resolved file, ready for inclusion.
@@ -15,6 +202,13 @@ pub struct ResolvedFile {
     pub(crate) display_path: PathBuf,
     // Absolute, canonicalized path for uniqueness checks and reading the file.
     pub(crate) canonical_path: PathBuf,
+    // The lines to include, from a `path:START-END` input. `None` means the whole file.
+    pub(crate) line_range: Option<LineRange>,
+    // The git revision to read the file from (`--at`). `None` means the working tree.
+    pub(crate) revision: Option<String>,
+    // The canonical path of the archive holding the file (`--archive`), whose
+    // path inside it is `canonical_path` relative to this.
+    pub(crate) archive: Option<PathBuf>,
 }
 
 impl ResolvedFile {
@@ -24,6 +218,41 @@ impl ResolvedFile {
         Self {
             display_path,
             canonical_path,
+            line_range: None,
+            revision: None,
+            archive: None,
+        }
+    }
+
+    /// Returns this file as of a git revision rather than the working tree.
+    pub(crate) fn at_revision(self, revision: &str) -> Self {
+        Self {
+            revision: Some(revision.to_string()),
+            ..self
+        }
+    }
+
+    /// Returns this file as an entry of the archive at canonical path `archive`.
+    pub(crate) fn in_archive(self, archive: &Path) -> Self {
+        Self {
+            archive: Some(archive.to_path_buf()),
+            ..self
+        }
+    }
+
+    /// Returns this file restricted to `line_range`.
+    pub(crate) fn with_line_range(self, line_range: LineRange) -> Self {
+        Self {
+            line_range: Some(line_range),
+            ..self
+        }
+    }
+
+    /// Returns this file as a whole, dropping any line range.
+    pub(crate) fn without_line_range(self) -> Self {
+        Self {
+            line_range: None,
+            ..self
         }
     }
 
@@ -36,12 +265,46 @@ impl ResolvedFile {
     pub fn canonical_path(&self) -> &Path {
         &self.canonical_path
     }
+
+    /// Returns the requested line range, if only part of the file is wanted.
+    pub fn line_range(&self) -> Option<LineRange> {
+        self.line_range
+    }
+
+    /// Returns the git revision the file is read from, if not the working tree.
+    pub fn revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+
+    /// Returns the canonical path of the archive the file is read from, if any.
+    pub fn archive(&self) -> Option<&Path> {
+        self.archive.as_deref()
+    }
+
+    /// Returns true if the file is read from disk, rather than from a git
+    /// revision or an archive.
+    pub fn is_on_disk(&self) -> bool {
+        self.revision.is_none() && self.archive.is_none()
+    }
+
+    /// Returns the display path, with a `:START-END` suffix if a line range was
+    /// requested and an `@ REV` suffix if the file comes from a git revision.
+    pub fn display_label(&self) -> String {
+        let mut label = self.display_path.display().to_string();
+        if let Some(range) = self.line_range {
+            label.push_str(&format!(":{}-{}", range.start, range.end));
+        }
+        if let Some(revision) = &self.revision {
+            label.push_str(&format!(" @ {}", revision));
+        }
+        label
+    }
 }
 
 /// Represents a single, tagged symbol extracted from a source file.
 /// This structure is designed to mirror the kind of information provided
 /// by the `tree-sitter tags` CLI command.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize)]
 pub struct Tag {
     /// The name of the symbol (e.g., the function or struct name).
     pub name: String,
@@ -49,6 +312,10 @@ pub struct Tag {
     pub kind: String,
     /// The byte offset where the symbol's definition starts. Used for sorting.
     pub start_byte: usize,
+    /// The 1-based line number where the symbol's definition starts.
+    pub line: usize,
+    /// The 1-based line number where the symbol's definition ends.
+    pub end_line: usize,
     /// The full first line of the symbol's definition.
     pub line_text: String,
     /// An optional docstring associated with the symbol.
@@ -68,6 +335,15 @@ impl PartialOrd for Tag {
     }
 }
 
+/// A top-level import statement that sits on lines of its own.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ImportStatement {
+    /// The statement's source text.
+    pub text: String,
+    /// The byte range of the whole lines it occupies, including the final newline.
+    pub lines: std::ops::Range<usize>,
+}
+
 /// Represents the outcome of processing a single user input string.
 /// It is generic over a lifetime `'a` to borrow the input string, avoiding allocations.
 #[derive(Debug, Clone)]
@@ -79,8 +355,8 @@ pub enum InputResolution<'a> {
     /// The input string led to multiple conflicting matches, making it ambiguous.
     Ambiguous {
         input_string: &'a str,
-        /// Paths (typically relative to PWD for display) that caused the ambiguity.
-        conflicting_paths: Vec<PathBuf>,
+        /// The matches that caused the ambiguity, in path order.
+        candidates: Vec<AmbiguousMatch>,
     },
 
     /// The input string was treated as a glob pattern, but the pattern was malformed.
@@ -90,6 +366,51 @@ pub enum InputResolution<'a> {
         error: String,
     },
 
+    /// A `--regex` pattern failed to compile.
+    InvalidRegex {
+        input_string: &'a str,
+        /// The regex crate's description of the problem.
+        error: String,
+    },
+
+    /// A directory or glob input expanded to more files than the per-input limit allows.
+    TooManyFiles {
+        input_string: &'a str,
+        /// How many files the input expanded to.
+        file_count: usize,
+        /// The limit that was exceeded.
+        limit: usize,
+        /// A few of the matched display paths, to help narrow the input.
+        sample_paths: Vec<PathBuf>,
+    },
+
+    /// The input matched files, but an exclude rule filtered out every one of them.
+    Excluded {
+        input_string: &'a str,
+        /// How many files the input matched before exclusion.
+        file_count: usize,
+        /// A description of the rule that excluded them, with how to override it.
+        rule: String,
+    },
+
+    /// The input matched files outside the working directory (e.g. `../**/*.pem`,
+    /// or via a symlink), which is refused unless `--allow-outside` is given.
+    OutsideWorkingDir {
+        input_string: &'a str,
+        /// How many matched files lie outside.
+        file_count: usize,
+        /// A few of them, to show where the input reached.
+        sample_paths: Vec<PathBuf>,
+    },
+
+    /// A glob or fuzzy search was stopped before it finished, having visited
+    /// `entries` files and directories.
+    SearchStopped {
+        input_string: &'a str,
+        entries: usize,
+        reason: SearchStop,
+    },
+
     /// The input string could not be found after searching.
     NotFound { input_string: &'a str },
 
@@ -104,3 +425,230 @@ pub enum InputResolution<'a> {
     // encountered when trying to resolve a specific file that wasn't a general WalkDir error.
     // For V1, the above should cover the main scenarios.
 }
+
+impl<'a> InputResolution<'a> {
+    /// Returns this resolution reported against `input_string` instead. Used
+    /// when an input was resolved via a derived form (e.g. with its line range
+    /// stripped), so errors still quote what the user typed.
+    pub fn with_input_string(self, input_string: &'a str) -> Self {
+        match self {
+            InputResolution::Success(files) => InputResolution::Success(files),
+            InputResolution::Ambiguous { candidates, .. } => InputResolution::Ambiguous {
+                input_string,
+                candidates,
+            },
+            InputResolution::InvalidGlobPattern { error, .. } => {
+                InputResolution::InvalidGlobPattern {
+                    input_string,
+                    error,
+                }
+            }
+            InputResolution::InvalidRegex { error, .. } => InputResolution::InvalidRegex {
+                input_string,
+                error,
+            },
+            InputResolution::TooManyFiles {
+                file_count,
+                limit,
+                sample_paths,
+                ..
+            } => InputResolution::TooManyFiles {
+                input_string,
+                file_count,
+                limit,
+                sample_paths,
+            },
+            InputResolution::Excluded {
+                file_count, rule, ..
+            } => InputResolution::Excluded {
+                input_string,
+                file_count,
+                rule,
+            },
+            InputResolution::OutsideWorkingDir {
+                file_count,
+                sample_paths,
+                ..
+            } => InputResolution::OutsideWorkingDir {
+                input_string,
+                file_count,
+                sample_paths,
+            },
+            InputResolution::SearchStopped {
+                entries, reason, ..
+            } => InputResolution::SearchStopped {
+                input_string,
+                entries,
+                reason,
+            },
+            InputResolution::NotFound { .. } => InputResolution::NotFound { input_string },
+            InputResolution::PathDoesNotExist { path_tried, .. } => {
+                InputResolution::PathDoesNotExist {
+                    input_string,
+                    path_tried,
+                }
+            }
+        }
+    }
+}
+
+/// The outcome of resolving every user input, with successes merged into a
+/// single deduplicated file list and failures bucketed by kind for reporting.
+#[derive(Debug)]
+pub struct ResolutionReport<'r, 'a> {
+    /// All successfully resolved files, in input order, without duplicates.
+    pub files: Vec<ResolvedFile>,
+    pub path_does_not_exist_errors: Vec<&'r InputResolution<'a>>,
+    pub not_founds: Vec<&'r InputResolution<'a>>,
+    pub ambiguities: Vec<&'r InputResolution<'a>>,
+    pub invalid_globs: Vec<&'r InputResolution<'a>>,
+    pub invalid_regexes: Vec<&'r InputResolution<'a>>,
+    pub too_many_files: Vec<&'r InputResolution<'a>>,
+    pub excluded: Vec<&'r InputResolution<'a>>,
+    pub outside_working_dir: Vec<&'r InputResolution<'a>>,
+    pub stopped_searches: Vec<&'r InputResolution<'a>>,
+}
+
+impl<'r, 'a> ResolutionReport<'r, 'a> {
+    /// Buckets resolutions into successes and the various error types.
+    /// Files reached by more than one input are only included once (different
+    /// line ranges of the same file count as different files).
+    pub fn from_resolutions(resolutions: &'r [InputResolution<'a>]) -> Self {
+        let mut report = Self {
+            files: Vec::new(),
+            path_does_not_exist_errors: Vec::new(),
+            not_founds: Vec::new(),
+            ambiguities: Vec::new(),
+            invalid_globs: Vec::new(),
+            invalid_regexes: Vec::new(),
+            too_many_files: Vec::new(),
+            excluded: Vec::new(),
+            outside_working_dir: Vec::new(),
+            stopped_searches: Vec::new(),
+        };
+        let mut seen: BTreeSet<(PathBuf, Option<LineRange>)> = BTreeSet::new();
+
+        for resolution in resolutions {
+            match resolution {
+                InputResolution::Success(resolved_files_for_input) => {
+                    for resolved_file in resolved_files_for_input {
+                        let key = (
+                            resolved_file.canonical_path().to_path_buf(),
+                            resolved_file.line_range(),
+                        );
+                        if seen.insert(key) {
+                            report.files.push(resolved_file.clone());
+                        }
+                    }
+                }
+                InputResolution::Ambiguous { .. } => report.ambiguities.push(resolution),
+                InputResolution::NotFound { .. } => report.not_founds.push(resolution),
+                InputResolution::PathDoesNotExist { .. } => {
+                    report.path_does_not_exist_errors.push(resolution)
+                }
+                InputResolution::InvalidGlobPattern { .. } => report.invalid_globs.push(resolution),
+                InputResolution::InvalidRegex { .. } => report.invalid_regexes.push(resolution),
+                InputResolution::TooManyFiles { .. } => report.too_many_files.push(resolution),
+                InputResolution::Excluded { .. } => report.excluded.push(resolution),
+                InputResolution::OutsideWorkingDir { .. } => {
+                    report.outside_working_dir.push(resolution)
+                }
+                InputResolution::SearchStopped { .. } => report.stopped_searches.push(resolution),
+            }
+        }
+        report
+    }
+
+    /// Returns true if any input failed to resolve.
+    pub fn has_errors(&self) -> bool {
+        !self.path_does_not_exist_errors.is_empty()
+            || !self.not_founds.is_empty()
+            || !self.ambiguities.is_empty()
+            || !self.invalid_globs.is_empty()
+            || !self.invalid_regexes.is_empty()
+            || !self.too_many_files.is_empty()
+            || !self.excluded.is_empty()
+            || !self.outside_working_dir.is_empty()
+            || !self.stopped_searches.is_empty()
+    }
+}
+
+/// Why a glob or fuzzy search was stopped before it finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchStop {
+    /// It visited more entries than `--search-entry-limit` allows.
+    EntryLimit(usize),
+    /// It ran longer than `--search-time-limit`.
+    TimeLimit(std::time::Duration),
+    /// Ctrl-C was pressed.
+    Interrupted,
+}
+
+/// How the direct-path phase of resolution matched an input, if it did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirectMatch {
+    /// The input named an existing file.
+    File,
+    /// The input named an existing directory, which expanded to `file_count` files.
+    Directory { file_count: usize },
+}
+
+/// One of several matches for an ambiguous input, with enough detail to tell
+/// the candidates apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmbiguousMatch {
+    /// The candidate's path relative to the working directory (with a `:LINE`
+    /// suffix for symbol definitions).
+    pub display_path: PathBuf,
+    /// Byte range of the input within `display_path`, if it appears there.
+    pub matched: Option<std::ops::Range<usize>>,
+    /// With `--scope-to-inputs`, the directory input the candidate was found
+    /// under, relative to the working directory.
+    pub scope: Option<PathBuf>,
+    /// The file's size in bytes, if it's on disk.
+    pub size: Option<u64>,
+    /// When the file was last modified, if it's on disk.
+    pub modified: Option<std::time::SystemTime>,
+}
+
+/// A single file matched by the fuzzy search phase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyCandidate {
+    /// The candidate's path relative to the working directory.
+    pub display_path: PathBuf,
+    /// Byte offset of the first occurrence of the input within `display_path`.
+    pub match_start: usize,
+    /// Byte length of the matched input.
+    pub match_len: usize,
+}
+
+/// A record of how each resolution phase behaved for one input.
+/// Populated only when tracing is requested (e.g. by `--why`).
+#[derive(Debug, Clone, Default)]
+pub struct ResolutionTrace {
+    /// The literal path checked in the direct-match phase.
+    pub direct_path_checked: PathBuf,
+    /// The direct-match outcome, or `None` if the path didn't exist.
+    pub direct_match: Option<DirectMatch>,
+    /// Whether the input contained glob metacharacters.
+    pub treated_as_glob: bool,
+    /// How many files a valid glob pattern matched.
+    pub glob_match_count: Option<usize>,
+    /// Whether the fuzzy search phase ran at all.
+    pub fuzzy_searched: bool,
+    /// All fuzzy candidates, sorted by path.
+    pub fuzzy_candidates: Vec<FuzzyCandidate>,
+    /// For `fn:`/`sym:` inputs, how many definitions of the symbol were found.
+    /// Symbol searches skip the three path-based phases entirely.
+    pub symbol_definition_count: Option<usize>,
+}
+
+impl ResolutionTrace {
+    /// Creates an empty trace for an input whose literal path is `direct_path_checked`.
+    pub fn new(direct_path_checked: PathBuf) -> Self {
+        Self {
+            direct_path_checked,
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// src/unsaved.rs
+
+//! Best-effort detection of edits an editor hasn't saved yet, from the swap,
+//! lock and auto-save files editors keep next to the files they have open.
+//! A hit means the copied content may be stale, so it's only ever a warning.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A file an editor keeps next to the file it's editing, named after it.
+struct ArtifactPattern {
+    editor: &'static str,
+    kind: &'static str,
+    prefix: &'static str,
+    suffix: &'static str,
+    /// Only counts when modified after the file itself. A backup is written
+    /// on every save, so an older one says nothing about unsaved edits.
+    only_if_newer: bool,
+}
+
+const PATTERNS: &[ArtifactPattern] = &[
+    ArtifactPattern {
+        editor: "Vim",
+        kind: "swap file",
+        prefix: ".",
+        suffix: ".swp",
+        only_if_newer: false,
+    },
+    ArtifactPattern {
+        editor: "Vim",
+        kind: "swap file",
+        prefix: ".",
+        suffix: ".swo",
+        only_if_newer: false,
+    },
+    ArtifactPattern {
+        editor: "Vim",
+        kind: "swap file",
+        prefix: ".",
+        suffix: ".swn",
+        only_if_newer: false,
+    },
+    ArtifactPattern {
+        editor: "Emacs",
+        kind: "lock file",
+        prefix: ".#",
+        suffix: "",
+        only_if_newer: false,
+    },
+    ArtifactPattern {
+        editor: "Emacs",
+        kind: "auto-save file",
+        prefix: "#",
+        suffix: "#",
+        only_if_newer: false,
+    },
+    ArtifactPattern {
+        editor: "an editor",
+        kind: "backup",
+        prefix: "",
+        suffix: "~",
+        only_if_newer: true,
+    },
+];
+
+/// An editor artifact found next to an included file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorArtifact {
+    pub editor: &'static str,
+    pub kind: &'static str,
+    pub path: PathBuf,
+}
+
+impl EditorArtifact {
+    /// Describes the artifact by its file name, e.g. `Vim swap file .lib.rs.swp`.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.editor,
+            self.kind,
+            self.path.file_name().unwrap_or_default().to_string_lossy()
+        )
+    }
+}
+
+/// Finds the editor artifacts next to the file at `path`.
+pub fn artifacts_for(path: &Path) -> Vec<EditorArtifact> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy();
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    PATTERNS
+        .iter()
+        .filter_map(|pattern| {
+            let artifact = dir.join(format!("{}{}{}", pattern.prefix, name, pattern.suffix));
+            // Emacs lock files are symlinks to nowhere, so they're not followed.
+            let metadata = fs::symlink_metadata(&artifact).ok()?;
+            if pattern.only_if_newer && metadata.modified().ok()? <= modified? {
+                return None;
+            }
+            Some(EditorArtifact {
+                editor: pattern.editor,
+                kind: pattern.kind,
+                path: artifact,
+            })
+        })
+        .collect()
+}
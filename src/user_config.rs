@@ -0,0 +1,109 @@
+// src/user_config.rs
+
+//! The user's own config file (`~/.config/ctx-pick/config.toml`), read once
+//! per run. Unlike `.ctx-pick.toml` files, which a project can ship, it's
+//! trusted: it can load grammar libraries and changes defaults everywhere.
+
+use crate::dir_config;
+use crate::grammars::UserGrammar;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Overrides where the user config file is read from.
+pub const CONFIG_PATH_ENV: &str = "CTX_PICK_CONFIG";
+
+/// The settings the user config file can hold.
+#[derive(Debug, Deserialize)]
+pub struct UserConfig {
+    /// Grammar libraries for languages without a built-in grammar, keyed by
+    /// lowercased language key (`[grammars.<key>]`).
+    #[serde(default)]
+    pub grammars: BTreeMap<String, UserGrammar>,
+    /// Whether to warn about included files with unsaved edits in an editor
+    /// (on unless `warn_unsaved_edits = false`).
+    #[serde(default = "warn_by_default")]
+    pub warn_unsaved_edits: bool,
+    /// The `--max-line-length` to use when none is given.
+    pub max_line_length: Option<usize>,
+    /// The `--balance-threshold` to use when none is given, in percent.
+    pub balance_threshold: Option<u8>,
+}
+
+fn warn_by_default() -> bool {
+    true
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            grammars: BTreeMap::new(),
+            warn_unsaved_edits: warn_by_default(),
+            max_line_length: None,
+            balance_threshold: None,
+        }
+    }
+}
+
+/// The user config file: `$CTX_PICK_CONFIG` if set, otherwise
+/// `ctx-pick/config.toml` in `$XDG_CONFIG_HOME` (by default `~/.config`).
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("ctx-pick").join("config.toml"))
+}
+
+/// Parses the text of a user config. Grammar keys are lowercased, and
+/// relative library paths are taken from `config_dir`.
+pub fn parse(text: &str, config_dir: &Path) -> Result<UserConfig, String> {
+    let mut config: UserConfig =
+        toml::from_str(text).map_err(|e| dir_config::describe_toml_error(text, &e))?;
+    if let Some(percent) = config.balance_threshold
+        && !(1..=100).contains(&percent)
+    {
+        return Err(format!(
+            "balance_threshold must be between 1 and 100, not {}",
+            percent
+        ));
+    }
+    config.grammars = config
+        .grammars
+        .into_iter()
+        .map(|(key, grammar)| {
+            let grammar = UserGrammar {
+                library: config_dir.join(&grammar.library),
+                ..grammar
+            };
+            (key.trim_start_matches('.').to_ascii_lowercase(), grammar)
+        })
+        .collect();
+    Ok(config)
+}
+
+/// The user config, read once. A missing config means the defaults; an
+/// invalid one is reported and then treated the same way.
+static USER_CONFIG: LazyLock<UserConfig> = LazyLock::new(|| {
+    let Some(path) = config_path() else {
+        return UserConfig::default();
+    };
+    let Ok(text) = fs::read_to_string(&path) else {
+        return UserConfig::default();
+    };
+    let config_dir = path.parent().unwrap_or(Path::new(""));
+    parse(&text, config_dir).unwrap_or_else(|e| {
+        eprintln!("Warning: Ignoring invalid {:?}: {}", path, e);
+        UserConfig::default()
+    })
+});
+
+/// The user config for this run.
+pub fn get() -> &'static UserConfig {
+    &USER_CONFIG
+}